@@ -24,29 +24,40 @@
 use std::sync::Arc;
 
 use codec::{Decode, Encode};
-use futures::{channel::mpsc::UnboundedSender, task::SpawnError, SinkExt};
+use futures::{channel::mpsc::UnboundedSender, task::SpawnError, FutureExt, SinkExt};
 use jsonrpsee::{
 	core::{async_trait, Error as JsonRpseeError, RpcResult},
 	proc_macros::rpc,
-	types::{error::CallError, ErrorObject},
+	types::{error::CallError, ErrorObject, SubscriptionResult},
+	SubscriptionSink,
 };
 use log::{error, info, warn};
 use memory_db::{HashKey, MemoryDB};
-use orderbook::{snapshot::SnapshotStore, DbRef};
+use orderbook::{
+	notification::{
+		ObActionNotification, ObActionStream, ObSnapshotNotification, ObSnapshotStream,
+	},
+	snapshot::{generate_balance_proof, read_open_orders, read_snapshot_store, SnapshotError},
+	DbRef,
+};
 use orderbook_primitives::{
+	proof::BalanceProof,
 	recovery::ObRecoveryState,
-	types::{AccountAsset, ObMessage},
-	ObApi, ORDERBOOK_STATE_CHUNK_PREFIX,
+	types::{AccountAsset, AccountInfo, ObMessage, TradingPair},
+	ObApi, SnapshotSummary, ORDERBOOK_STATE_CHUNK_PREFIX,
 };
 use parking_lot::RwLock;
+use polkadex_primitives::{AccountId, AssetId};
 use reference_trie::{ExtensionLayout, RefHasher};
 use rust_decimal::Decimal;
+use sc_rpc::SubscriptionTaskExecutor;
+use serde::{Deserialize, Serialize};
 use sp_api::ProvideRuntimeApi;
 use sp_arithmetic::traits::SaturatedConversion;
 use sp_blockchain::HeaderBackend;
-use sp_core::offchain::OffchainStorage;
+use sp_core::{offchain::OffchainStorage, Bytes, H256};
 use sp_runtime::{generic::BlockId, traits::Block as BlockT};
-use trie_db::{TrieDBMut, TrieDBMutBuilder, TrieMut};
+use trie_db::{proof::generate_proof, Trie, TrieDBBuilder, TrieDBMut, TrieDBMutBuilder, TrieMut};
 
 /// Top-level error type for the RPC handler.
 #[derive(Debug, thiserror::Error)]
@@ -88,6 +99,30 @@ impl From<Error> for JsonRpseeError {
 	}
 }
 
+/// Balances of an account asset read from the working state.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BalanceResponse {
+	/// Free balance.
+	pub free: Decimal,
+	/// Balance reserved by open orders.
+	pub reserved: Decimal,
+	/// Working state root the proof is generated against.
+	pub state_root: H256,
+	/// Merkle proof of the free and reserved balance entries, in the `trie_db` compact format.
+	pub proof: Vec<Bytes>,
+}
+
+/// Account information read from the working state.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountInfoResponse {
+	/// Account information, `None` if the main account is not registered.
+	pub account: Option<AccountInfo>,
+	/// Working state root the proof is generated against.
+	pub state_root: H256,
+	/// Merkle proof of the account entry, in the `trie_db` compact format.
+	pub proof: Vec<Bytes>,
+}
+
 /// RPC abstraction for interacting with Orderbook.
 #[rpc(client, server)]
 pub trait OrderbookApi {
@@ -123,6 +158,75 @@ pub trait OrderbookApi {
 		&self,
 		snapshot_id: u64,
 	) -> RpcResult<String>;
+
+	/// Returns the free and reserved balances of an account asset in the working state.
+	///
+	/// # Parameters
+	///
+	/// * `main`: Main account.
+	/// * `asset`: Asset id.
+	///
+	/// # Returns
+	///
+	/// * `RpcResult<BalanceResponse>`: Balances along with their proof against the working state
+	///   root.
+	#[method(name = "ob_getBalance")]
+	async fn get_balance(&self, main: AccountId, asset: AssetId) -> RpcResult<BalanceResponse>;
+
+	/// Returns the account information, including the proxies, of a main account in the working
+	/// state.
+	///
+	/// # Parameters
+	///
+	/// * `main`: Main account.
+	///
+	/// # Returns
+	///
+	/// * `RpcResult<AccountInfoResponse>`: Account information along with its proof against the
+	///   working state root.
+	#[method(name = "ob_getAccountInfo")]
+	async fn get_account_info(&self, main: AccountId) -> RpcResult<AccountInfoResponse>;
+
+	/// Returns the working state root.
+	#[method(name = "ob_getStateRoot")]
+	async fn get_state_root(&self) -> RpcResult<H256>;
+
+	/// Generates the proof of the balances of an account asset at a snapshot, verifiable against
+	/// the signed state root of the snapshot summary.
+	///
+	/// # Parameters
+	///
+	/// * `snapshot_id`: Snapshot id.
+	/// * `main`: Main account.
+	/// * `asset`: Asset id.
+	///
+	/// # Returns
+	///
+	/// * `RpcResult<BalanceProof>`: Balances at the snapshot along with their proof.
+	#[method(name = "ob_generateBalanceProof")]
+	async fn generate_balance_proof(
+		&self,
+		snapshot_id: u64,
+		main: AccountId,
+		asset: AssetId,
+	) -> RpcResult<BalanceProof>;
+
+	/// Subscribes to the actions applied by the worker, each notification carries the worker
+	/// nonce and the working state root after the action is applied.
+	#[subscription(
+		name = "ob_subscribeActions" => "ob_action",
+		unsubscribe = "ob_unsubscribeActions",
+		item = ObActionNotification,
+	)]
+	fn subscribe_actions(&self);
+
+	/// Subscribes to the snapshots generated by the worker.
+	#[subscription(
+		name = "ob_subscribeSnapshots" => "ob_snapshot",
+		unsubscribe = "ob_unsubscribeSnapshots",
+		item = ObSnapshotNotification,
+	)]
+	fn subscribe_snapshots(&self);
 }
 
 #[async_trait]
@@ -151,6 +255,45 @@ where
 	) -> RpcResult<String> {
 		self.get_orderbook_recovery_state_from_storage_inner(snapshot_id).await
 	}
+
+	async fn get_balance(&self, main: AccountId, asset: AssetId) -> RpcResult<BalanceResponse> {
+		self.get_balance_inner(main, asset)
+	}
+
+	async fn get_account_info(&self, main: AccountId) -> RpcResult<AccountInfoResponse> {
+		self.get_account_info_inner(main)
+	}
+
+	async fn get_state_root(&self) -> RpcResult<H256> {
+		Ok((*self.working_state_root.read()).into())
+	}
+
+	async fn generate_balance_proof(
+		&self,
+		snapshot_id: u64,
+		main: AccountId,
+		asset: AssetId,
+	) -> RpcResult<BalanceProof> {
+		self.generate_balance_proof_inner(snapshot_id, main, asset)
+	}
+
+	fn subscribe_actions(&self, mut sink: SubscriptionSink) -> SubscriptionResult {
+		let stream = self.action_stream.subscribe(100_000);
+		let fut = async move {
+			sink.pipe_from_stream(stream).await;
+		};
+		self.executor.spawn("orderbook-rpc-subscription", Some("rpc"), fut.boxed());
+		Ok(())
+	}
+
+	fn subscribe_snapshots(&self, mut sink: SubscriptionSink) -> SubscriptionResult {
+		let stream = self.snapshot_stream.subscribe(100_000);
+		let fut = async move {
+			sink.pipe_from_stream(stream).await;
+		};
+		self.executor.spawn("orderbook-rpc-subscription", Some("rpc"), fut.boxed());
+		Ok(())
+	}
 }
 
 /// Orderbook specific RPC dependencies
@@ -168,6 +311,12 @@ pub struct OrderbookDeps<Backend, Client, Runtime> {
 	pub memory_db: Arc<RwLock<MemoryDB<RefHasher, HashKey<RefHasher>, Vec<u8>>>>,
 	/// working_state_root
 	pub working_state_root: Arc<RwLock<[u8; 32]>>,
+	/// Receives notifications about the actions applied by the worker
+	pub action_stream: ObActionStream,
+	/// Receives notifications about the snapshots generated by the worker
+	pub snapshot_stream: ObSnapshotStream,
+	/// Executor to drive the subscriptions
+	pub subscription_executor: SubscriptionTaskExecutor,
 }
 
 /// Implements the OrderbookApi RPC trait for interacting with Orderbook.
@@ -178,6 +327,9 @@ pub struct OrderbookRpc<Block, Client, Backend, Runtime> {
 	runtime: Arc<Runtime>,
 	client: Arc<Client>,
 	backend: Arc<Backend>,
+	action_stream: ObActionStream,
+	snapshot_stream: ObSnapshotStream,
+	executor: SubscriptionTaskExecutor,
 	_marker: std::marker::PhantomData<Block>,
 }
 
@@ -207,10 +359,88 @@ where
 			runtime: deps.runtime.clone(),
 			client: deps.client.clone(),
 			backend: deps.backend,
+			action_stream: deps.action_stream,
+			snapshot_stream: deps.snapshot_stream,
+			executor: deps.subscription_executor,
 			_marker: Default::default(),
 		}
 	}
 
+	/// Returns the balances of an account asset in the working state with their proof.
+	///
+	/// # Parameters
+	///
+	/// * `main`: Main account.
+	/// * `asset`: Asset id.
+	pub fn get_balance_inner(&self, main: AccountId, asset: AssetId) -> RpcResult<BalanceResponse> {
+		let account_asset = AccountAsset::new(main, asset);
+		let keys = [account_asset.encode(), account_asset.reserved_balance_key()];
+		let (values, state_root, proof) = self.read_with_proof(&keys)?;
+		let decode_balance = |value: Option<Vec<u8>>| match value {
+			Some(data) => Decimal::decode(&mut &data[..]).map_err(|err| {
+				JsonRpseeError::Custom(err.to_string() + "failed to decode decimal")
+			}),
+			// Account may not have balance for asset
+			None => Ok(Decimal::ZERO),
+		};
+		let mut values = values.into_iter();
+		let free = decode_balance(values.next().flatten())?;
+		let reserved = decode_balance(values.next().flatten())?;
+		Ok(BalanceResponse { free, reserved, state_root, proof })
+	}
+
+	/// Returns the account information of a main account in the working state with its proof.
+	///
+	/// # Parameters
+	///
+	/// * `main`: Main account.
+	pub fn get_account_info_inner(&self, main: AccountId) -> RpcResult<AccountInfoResponse> {
+		let (values, state_root, proof) = self.read_with_proof(&[main.encode()])?;
+		let account = values
+			.into_iter()
+			.next()
+			.flatten()
+			.map(|data| AccountInfo::decode(&mut &data[..]))
+			.transpose()
+			.map_err(|err| {
+				JsonRpseeError::Custom(err.to_string() + "failed to decode account info")
+			})?;
+		Ok(AccountInfoResponse { account, state_root, proof })
+	}
+
+	/// Reads keys from the working trie along with a proof of their values against the working
+	/// state root.
+	///
+	/// # Parameters
+	///
+	/// * `keys`: Trie keys to read.
+	///
+	/// # Returns
+	///
+	/// * `RpcResult<(Vec<Option<Vec<u8>>>, H256, Vec<Bytes>)>`: Values of the keys, working state
+	///   root and the compact proof.
+	#[allow(clippy::type_complexity)]
+	fn read_with_proof(
+		&self,
+		keys: &[Vec<u8>],
+	) -> RpcResult<(Vec<Option<Vec<u8>>>, H256, Vec<Bytes>)> {
+		// Same locking order as the worker
+		let memory_db = self.memory_db.read();
+		let working_state_root = *self.working_state_root.read();
+		let trie = TrieDBBuilder::<ExtensionLayout>::new(&*memory_db, &working_state_root).build();
+		let values = keys
+			.iter()
+			.map(|key| trie.get(key))
+			.collect::<Result<Vec<_>, _>>()
+			.map_err(|err| JsonRpseeError::Custom(format!("Error accessing trie: {err:?}")))?;
+		let proof =
+			generate_proof::<_, ExtensionLayout, _, _>(&*memory_db, &working_state_root, keys)
+				.map_err(|err| {
+					JsonRpseeError::Custom(format!("Error generating proof: {err:?}"))
+				})?;
+		Ok((values, working_state_root.into(), proof.into_iter().map(Bytes).collect()))
+	}
+
 	/// Returns the serialized offchain state based on the last finalized snapshot summary.
 	pub async fn get_orderbook_recovery_state_inner(&self) -> RpcResult<String> {
 		// get snapshot summary
@@ -250,6 +480,9 @@ where
 			})?;
 		info!(target:"orderbook-rpc","Getting allowlisted asset ids: {:?}", allowlisted_asset_ids);
 		// Create existing DB, it will fail if root does not exist
+		let trading_pairs = self.get_trading_pairs(&BlockId::number(
+			last_snapshot_summary.last_processed_blk.saturated_into(),
+		))?;
 		let trie: TrieDBMut<ExtensionLayout> =
 			TrieDBMutBuilder::from_existing(&mut memory_db, &mut worker_state_root).build();
 
@@ -262,8 +495,16 @@ where
 				let account_asset = AccountAsset::new(user_main_account.clone(), asset);
 				self.insert_balance(&trie, &mut ob_recovery_state, &account_asset)?;
 			}
+			self.insert_proxy_permissions(&trie, &mut ob_recovery_state, &user_main_account)?;
 			ob_recovery_state.account_ids.insert(user_main_account, list_of_proxy_accounts);
 		}
+		drop(trie);
+		self.insert_open_orders(
+			&memory_db,
+			&worker_state_root,
+			&mut ob_recovery_state,
+			&trading_pairs,
+		)?;
 
 		ob_recovery_state.snapshot_id = last_snapshot_summary.snapshot_id;
 		ob_recovery_state.state_change_id = last_snapshot_summary.state_change_id;
@@ -277,10 +518,21 @@ where
 		Ok(serialize_ob_recovery_state)
 	}
 
-	async fn get_orderbook_recovery_state_from_storage_inner(
+	/// Rebuilds the memory db of a snapshot from the offchain storage.
+	///
+	/// # Parameters
+	///
+	/// * `snapshot_id`: Id of the snapshot to load.
+	///
+	/// # Returns
+	///
+	/// * `RpcResult<(SnapshotSummary<AccountId>, MemoryDB<...>)>`: Snapshot summary and its memory
+	///   db.
+	#[allow(clippy::type_complexity)]
+	fn load_snapshot_memory_db(
 		&self,
 		snapshot_id: u64,
-	) -> RpcResult<String> {
+	) -> RpcResult<(SnapshotSummary<AccountId>, MemoryDB<RefHasher, HashKey<RefHasher>, Vec<u8>>)> {
 		let offchain_storage = self
 			.backend
 			.offchain_storage()
@@ -297,22 +549,53 @@ where
 
 		info!(target:"orderbook-rpc","Summary Loaded: {:?}",summary);
 
-		let mut data = Vec::new();
-
-		for chunk in summary.state_chunk_hashes {
-			let mut chunk_data = offchain_storage
-				.get(ORDERBOOK_STATE_CHUNK_PREFIX, chunk.0.as_ref())
-				.ok_or(JsonRpseeError::Custom(format!("Chunk not found: {chunk:?}")))?;
-			info!(target:"orderbook-rpc","Chunk Loaded: {:?}",chunk);
-			data.append(&mut chunk_data);
-		}
-
-		let mut worker_state_root = summary.state_root.0;
+		let at = BlockId::number(self.client.info().finalized_number);
+		let store = read_snapshot_store(
+			&summary,
+			|chunk_hash| offchain_storage.get(ORDERBOOK_STATE_CHUNK_PREFIX, chunk_hash.0.as_ref()),
+			|snapshot_id| {
+				self.runtime
+					.runtime_api()
+					.get_snapshot_by_id(&at, snapshot_id)
+					.map_err(|err| SnapshotError::Runtime(err.to_string()))
+			},
+		)
+		.map_err(|err| JsonRpseeError::Custom(format!("Error reading snapshot: {err:?}")))?;
 
 		let mut memory_db: MemoryDB<RefHasher, HashKey<RefHasher>, Vec<u8>> = Default::default();
-
-		let store: SnapshotStore = serde_json::from_slice(&data)?;
 		memory_db.load_from(store.convert_to_hashmap());
+		Ok((summary, memory_db))
+	}
+
+	/// Generates the proof of the balances of an account asset at a snapshot.
+	///
+	/// # Parameters
+	///
+	/// * `snapshot_id`: Snapshot id.
+	/// * `main`: Main account.
+	/// * `asset`: Asset id.
+	pub fn generate_balance_proof_inner(
+		&self,
+		snapshot_id: u64,
+		main: AccountId,
+		asset: AssetId,
+	) -> RpcResult<BalanceProof> {
+		let (summary, memory_db) = self.load_snapshot_memory_db(snapshot_id)?;
+		generate_balance_proof(
+			&memory_db,
+			&summary.state_root.0,
+			summary.snapshot_id,
+			AccountAsset::new(main, asset),
+		)
+		.map_err(|err| JsonRpseeError::Custom(format!("Error generating balance proof: {err:?}")))
+	}
+
+	async fn get_orderbook_recovery_state_from_storage_inner(
+		&self,
+		snapshot_id: u64,
+	) -> RpcResult<String> {
+		let (summary, mut memory_db) = self.load_snapshot_memory_db(snapshot_id)?;
+		let mut worker_state_root = summary.state_root.0;
 
 		// get all accounts
 		let all_register_accounts = self
@@ -333,6 +616,9 @@ where
 			})?;
 		info!(target:"orderbook-rpc","Getting allowlisted asset ids: {:?}", allowlisted_asset_ids);
 
+		let trading_pairs =
+			self.get_trading_pairs(&BlockId::number(self.client.info().finalized_number))?;
+
 		// Create existing DB, it will fail if root does not exist
 		let mut trie: TrieDBMut<ExtensionLayout> =
 			TrieDBMutBuilder::from_existing(&mut memory_db, &mut worker_state_root).build();
@@ -353,11 +639,19 @@ where
 				.contains(&user_main_account.encode())
 				.map_err(|err| JsonRpseeError::Custom(format!("Error accessing trie: {err:?}")))?
 			{
+				self.insert_proxy_permissions(&trie, &mut ob_recovery_state, &user_main_account)?;
 				ob_recovery_state.account_ids.insert(user_main_account, list_of_proxy_accounts);
 			} else {
 				warn!(target:"orderbook-rpc","Main account not found: {:?}",user_main_account);
 			}
 		}
+		drop(trie);
+		self.insert_open_orders(
+			&memory_db,
+			&worker_state_root,
+			&mut ob_recovery_state,
+			&trading_pairs,
+		)?;
 
 		ob_recovery_state.snapshot_id = summary.snapshot_id;
 		ob_recovery_state.state_change_id = summary.state_change_id;
@@ -395,6 +689,73 @@ where
 				&account_asset.main, &account_asset.asset
 			)))
 		}
+		let data = trie
+			.get(&account_asset.reserved_balance_key())
+			.map_err(|err| JsonRpseeError::Custom(format!("Error accessing trie: {err:?}")))?;
+		// Ignored none case as account may not have reserved balance for asset
+		if let Some(data) = data {
+			let reserved_balance = Decimal::decode(&mut &data[..]).map_err(|err| {
+				JsonRpseeError::Custom(err.to_string() + "failed to decode decimal")
+			})?;
+			ob_recovery_state
+				.reserved_balances
+				.insert(account_asset.clone(), reserved_balance);
+		}
+		Ok(())
+	}
+
+	/// Inserts the scoped proxy permissions of a main account from the trie
+	pub fn insert_proxy_permissions(
+		&self,
+		trie: &TrieDBMut<ExtensionLayout>,
+		ob_recovery_state: &mut ObRecoveryState,
+		main: &AccountId,
+	) -> RpcResult<()> {
+		let data = trie
+			.get(&main.encode())
+			.map_err(|err| JsonRpseeError::Custom(format!("Error accessing trie: {err:?}")))?;
+		// Ignored none case as the account may not be registered in the trie yet
+		if let Some(data) = data {
+			let account_info = AccountInfo::decode(&mut &data[..]).map_err(|err| {
+				JsonRpseeError::Custom(err.to_string() + "failed to decode account info")
+			})?;
+			if !account_info.permissions.is_empty() {
+				ob_recovery_state
+					.proxy_permissions
+					.insert(main.clone(), account_info.permissions);
+			}
+		}
 		Ok(())
 	}
+
+	/// Inserts the open orders of the trading pairs from the state
+	pub fn insert_open_orders(
+		&self,
+		memory_db: &MemoryDB<RefHasher, HashKey<RefHasher>, Vec<u8>>,
+		state_root: &[u8; 32],
+		ob_recovery_state: &mut ObRecoveryState,
+		trading_pairs: &[TradingPair],
+	) -> RpcResult<()> {
+		info!(target:"orderbook-rpc","Loading open orders from trie to result...");
+		for pair in trading_pairs {
+			let orders = read_open_orders(memory_db, state_root, pair).map_err(|err| {
+				JsonRpseeError::Custom(err.to_string() + "failed to read open orders")
+			})?;
+			ob_recovery_state.open_orders.extend(orders);
+		}
+		Ok(())
+	}
+
+	/// Returns the trading pairs registered in runtime at the given block
+	fn get_trading_pairs(&self, at: &BlockId<Block>) -> RpcResult<Vec<TradingPair>> {
+		let trading_pairs = self
+			.runtime
+			.runtime_api()
+			.read_trading_pair_configs(at)
+			.map_err(|err| JsonRpseeError::Custom(err.to_string() + "failed to get trading pairs"))?
+			.into_iter()
+			.map(|(pair, _)| pair)
+			.collect();
+		Ok(trading_pairs)
+	}
 }
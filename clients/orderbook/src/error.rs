@@ -20,9 +20,10 @@
 //!
 //! Used for Orderbook gadget internal error handling only.
 
+use crate::snapshot::SnapshotError;
 use hash_db::MaybeDebug;
 use orderbook_primitives::types::AccountAsset;
-use polkadex_primitives::BlockNumber;
+use polkadex_primitives::{ocex::ProxyPermissions, AccountId, BlockNumber};
 use sp_api::ApiError;
 use std::fmt::Debug;
 use tokio::task::JoinError;
@@ -62,6 +63,8 @@ pub enum Error {
 	ProxyAccountNotFound,
 	#[error("Proxy not associated with main")]
 	ProxyNotAssociatedWithMain,
+	#[error("Proxy {0:?} is not allowed to sign the action, required permissions: {1:?}")]
+	ProxyPermissionDenied(AccountId, ProxyPermissions),
 	#[error("Failed to submit snapshot to runtime")]
 	FailedToSubmitSnapshotToRuntime,
 	#[error("Offchain storage not available")]
@@ -74,6 +77,22 @@ pub enum Error {
 	TradingPairConfigNotFound,
 	#[error("Error during BLS operation: {0}")]
 	BLSError(String),
+	#[error("Unable to convert balance to decimal: {0}")]
+	BalanceConversionFailed(u128),
+	#[error("Exchange is not operational")]
+	ExchangeNotOperational,
+	#[error("Invalid order found")]
+	InvalidOrder,
+	#[error("Matching engine error: {0}")]
+	MatchingEngine(String),
+	#[error("Order is already open")]
+	OrderAlreadyOpen,
+	#[error("Not enough reserved balance in account")]
+	InsufficientReservedBalance,
+	#[error("Worker nonce gap: expected {0}, found {1}")]
+	WorkerNonceGap(u64, u64),
+	#[error("Invalid replay input: {0}")]
+	ReplayInput(String),
 }
 
 impl<T: MaybeDebug, E: MaybeDebug> From<Box<TrieError<T, E>>> for Error {
@@ -82,6 +101,17 @@ impl<T: MaybeDebug, E: MaybeDebug> From<Box<TrieError<T, E>>> for Error {
 	}
 }
 
+impl From<SnapshotError> for Error {
+	fn from(value: SnapshotError) -> Self {
+		match value {
+			SnapshotError::ChunkNotFound(_) | SnapshotError::SummaryNotFound(_) =>
+				Self::SnapshotNotFound,
+			SnapshotError::ChunkHashMismatch { .. } => Self::StateHashMisMatch,
+			err => Self::Backend(format!("Error reading snapshot: {err:?}")),
+		}
+	}
+}
+
 impl From<parity_scale_codec::Error> for Error {
 	fn from(value: parity_scale_codec::Error) -> Self {
 		Self::CodecError(value)
@@ -18,17 +18,22 @@
 
 //! Contains messages exchange specific logic related to the messages validation.
 
+use futures::channel::mpsc::UnboundedSender;
 use log::{info, trace};
-use orderbook_primitives::{types::GossipMessage, SnapshotSummary};
+use orderbook_primitives::{
+	types::{GossipMessage, ObMessage},
+	SnapshotSummary,
+};
 use parity_scale_codec::{Decode, Encode};
 use parking_lot::RwLock;
 use polkadex_primitives::AccountId;
-use sc_network::PeerId;
+use sc_network::{PeerId, ReputationChange};
 use sc_network_common::protocol::role::ObservedRole;
 use sc_network_gossip::{MessageIntent, ValidationResult, Validator, ValidatorContext};
+use sp_core::ecdsa;
 use sp_runtime::traits::{Block, Hash, Header};
 use std::{
-	collections::{BTreeSet, HashMap},
+	collections::{BTreeSet, HashMap, VecDeque},
 	ops::Sub,
 	sync::Arc,
 };
@@ -41,6 +46,123 @@ pub const REBROADCAST_INTERVAL: Duration = Duration::from_secs(3);
 /// worker).
 pub const WANT_REBROADCAST_INTERVAL: Duration = Duration::from_secs(3);
 
+/// Maximum number of `ObMessage`s held until the operator keys are read from the runtime.
+pub const MAX_HELD_MESSAGES: usize = 1024;
+
+/// Maximum number of expired operator keys remembered.
+pub const MAX_EXPIRED_OPERATOR_KEYS: usize = 8;
+
+/// Reputation costs of the peers relaying invalid data.
+pub(crate) mod cost {
+	use sc_network::ReputationChange as Rep;
+
+	/// Message could not be decoded.
+	pub(crate) const UNDECODABLE_MESSAGE: Rep = Rep::new(-200, "Orderbook: Undecodable message");
+	/// `ObMessage` not signed by the orderbook operator.
+	pub(crate) const INVALID_SIGNATURE: Rep =
+		Rep::new(-1000, "Orderbook: Invalid operator signature");
+	/// `ObMessage` signed with an operator key which is no longer accepted, the peer is most
+	/// likely relaying a message signed before the key rotation.
+	pub(crate) const EXPIRED_KEY_SIGNATURE: Rep =
+		Rep::new(-50, "Orderbook: Signature of an expired operator key");
+	/// Snapshot chunk whose hash is not in the snapshot summary.
+	pub(crate) const INVALID_CHUNK: Rep = Rep::new(-500, "Orderbook: Invalid snapshot chunk");
+}
+
+/// Reputation change of a peer, reported to the network by the worker.
+#[derive(Debug, PartialEq)]
+pub(crate) struct PeerReport {
+	/// Reported peer.
+	pub who: PeerId,
+	/// Reputation change of the peer.
+	pub cost_benefit: ReputationChange,
+}
+
+/// Result of the verification of an `ObMessage` signature.
+#[derive(Debug, PartialEq)]
+pub(crate) enum SignatureCheck {
+	/// Signed with an accepted operator key.
+	Valid,
+	/// Signed with an operator key which is no longer accepted.
+	ExpiredKey,
+	/// Not signed by the operator.
+	Invalid,
+	/// The operator keys are not read from the runtime yet.
+	KeysNotLoaded,
+}
+
+/// Orderbook operator keys shared by the worker and the gossip validator.
+#[derive(Default)]
+pub(crate) struct OperatorKeys {
+	/// Keys the `ObMessage`s are accepted from, empty until read from the runtime.
+	current: Vec<ecdsa::Public>,
+	/// Keys which were accepted before, most recently expired last.
+	expired: Vec<ecdsa::Public>,
+	/// Messages received before the keys were read from the runtime and their senders.
+	held: VecDeque<(Option<PeerId>, ObMessage)>,
+}
+
+impl OperatorKeys {
+	/// Replaces the accepted keys with the ones read from the runtime, the removed keys are
+	/// remembered as expired.
+	///
+	/// # Parameters
+	///
+	/// * `keys`: Keys read from the runtime.
+	///
+	/// # Returns
+	///
+	/// * `Vec<(Option<PeerId>, ObMessage)>`: Held messages to verify now that the keys are read.
+	pub fn update(&mut self, keys: Vec<ecdsa::Public>) -> Vec<(Option<PeerId>, ObMessage)> {
+		for key in self.current.iter().filter(|key| !keys.contains(key)) {
+			self.expired.push(*key);
+		}
+		self.expired.retain(|key| !keys.contains(key));
+		let excess = self.expired.len().saturating_sub(MAX_EXPIRED_OPERATOR_KEYS);
+		self.expired.drain(..excess);
+		self.current = keys;
+		if self.current.is_empty() {
+			return vec![]
+		}
+		self.held.drain(..).collect()
+	}
+
+	/// Returns the keys the `ObMessage`s are accepted from.
+	pub fn current(&self) -> &[ecdsa::Public] {
+		&self.current
+	}
+
+	/// Verifies the signature of an `ObMessage`.
+	///
+	/// # Parameters
+	///
+	/// * `message`: Message to verify.
+	pub fn check(&self, message: &ObMessage) -> SignatureCheck {
+		if self.current.is_empty() {
+			SignatureCheck::KeysNotLoaded
+		} else if message.verify_with_any(&self.current) {
+			SignatureCheck::Valid
+		} else if message.verify_with_any(&self.expired) {
+			SignatureCheck::ExpiredKey
+		} else {
+			SignatureCheck::Invalid
+		}
+	}
+
+	/// Holds a message until the keys are read from the runtime, the message is dropped once
+	/// [`MAX_HELD_MESSAGES`] are held.
+	///
+	/// # Parameters
+	///
+	/// * `sender`: Peer the message is received from.
+	/// * `message`: Message to hold.
+	pub fn hold(&mut self, sender: Option<PeerId>, message: ObMessage) {
+		if self.held.len() < MAX_HELD_MESSAGES {
+			self.held.push_back((sender, message));
+		}
+	}
+}
+
 /// Gossip engine messages topic
 pub fn topic<B: Block>() -> B::Hash
 where
@@ -68,6 +190,10 @@ where
 	pub(crate) fullnodes: Arc<RwLock<BTreeSet<PeerId>>>,
 	pub(crate) message_cache: Arc<RwLock<HashMap<([u8; 16], PeerId), Instant>>>,
 	pub state_version: Arc<RwLock<u16>>,
+	/// Orderbook operator keys the `ObMessage`s are verified against.
+	operator_keys: Arc<RwLock<OperatorKeys>>,
+	/// Reports misbehaving peers to the worker.
+	report_sender: UnboundedSender<PeerReport>,
 }
 
 impl<B> GossipValidator<B>
@@ -82,12 +208,17 @@ where
 	/// * `fullnodes`: Fullnodes.
 	/// * `is_validator`: Defines if peer is validator.
 	/// * `last_snapshot`: Latest snapshot summary.
+	/// * `state_version`: Version of the current state.
+	/// * `operator_keys`: Orderbook operator keys.
+	/// * `report_sender`: Channel for reporting misbehaving peers.
 	pub fn new(
 		latest_worker_nonce: Arc<RwLock<u64>>,
 		fullnodes: Arc<RwLock<BTreeSet<PeerId>>>,
 		is_validator: bool,
 		last_snapshot: Arc<RwLock<SnapshotSummary<AccountId>>>,
 		state_version: Arc<RwLock<u16>>,
+		operator_keys: Arc<RwLock<OperatorKeys>>,
+		report_sender: UnboundedSender<PeerReport>,
 	) -> GossipValidator<B> {
 		GossipValidator {
 			topic: topic::<B>(),
@@ -97,9 +228,21 @@ where
 			last_snapshot,
 			message_cache: Arc::new(RwLock::new(HashMap::new())),
 			state_version,
+			operator_keys,
+			report_sender,
 		}
 	}
 
+	/// Reports the reputation change of a peer.
+	///
+	/// # Parameters
+	///
+	/// * `who`: Reported peer.
+	/// * `cost_benefit`: Reputation change of the peer.
+	pub(crate) fn report(&self, who: PeerId, cost_benefit: ReputationChange) {
+		let _ = self.report_sender.unbounded_send(PeerReport { who, cost_benefit });
+	}
+
 	/// Validates provided message.
 	///
 	/// # Parameters
@@ -120,8 +263,25 @@ where
 					msg.version == *self.state_version.read()) ||
 					msg.reset
 				{
-					// It's a new message so we process it and keep it in our pool
-					ValidationResult::ProcessAndKeep(self.topic)
+					// Only messages of the operator are processed and propagated
+					let mut operator_keys = self.operator_keys.write();
+					match operator_keys.check(msg) {
+						// It's a new message so we process it and keep it in our pool
+						SignatureCheck::Valid => ValidationResult::ProcessAndKeep(self.topic),
+						SignatureCheck::KeysNotLoaded => {
+							// The signature is verified by the worker once the keys are read
+							operator_keys.hold(Some(peerid), (**msg).clone());
+							ValidationResult::Discard
+						},
+						SignatureCheck::ExpiredKey => {
+							self.report(peerid, cost::EXPIRED_KEY_SIGNATURE);
+							ValidationResult::Discard
+						},
+						SignatureCheck::Invalid => {
+							self.report(peerid, cost::INVALID_SIGNATURE);
+							ValidationResult::Discard
+						},
+					}
 				} else {
 					// We already saw this message, so discarding.
 					ValidationResult::Discard
@@ -269,6 +429,7 @@ where
 			}
 			return result
 		}
+		self.report(*sender, cost::UNDECODABLE_MESSAGE);
 		ValidationResult::Discard
 	}
 
@@ -304,13 +465,16 @@ where
 
 #[cfg(test)]
 mod tests {
-	use crate::gossip::GossipValidator;
+	use crate::gossip::{cost, GossipValidator, OperatorKeys, PeerReport, SignatureCheck};
 	use orderbook_primitives::{
 		types::{GossipMessage, ObMessage, UserActions},
 		SnapshotSummary,
 	};
 	use parking_lot::RwLock;
 	use polkadex_primitives::{AccountId, Block};
+	use sc_network::PeerId;
+	use sc_network_gossip::ValidationResult;
+	use sp_core::Pair;
 	use std::sync::Arc;
 
 	#[test]
@@ -327,6 +491,8 @@ mod tests {
 			false,
 			last_snapshot,
 			state_version,
+			Arc::new(RwLock::new(OperatorKeys::default())),
+			futures::channel::mpsc::unbounded().0,
 		);
 
 		let gossip = GossipMessage::ObMessage(Box::from(ObMessage {
@@ -349,4 +515,107 @@ mod tests {
 		}));
 		assert!(!validator.message_expired_check(&gossip));
 	}
+
+	#[test]
+	pub fn test_ob_message_signature_is_verified() {
+		let (operator, _) = sp_core::ecdsa::Pair::generate();
+		let (previous_operator, _) = sp_core::ecdsa::Pair::generate();
+		let (forger, _) = sp_core::ecdsa::Pair::generate();
+		let (report_sender, mut report_receiver) = futures::channel::mpsc::unbounded();
+		let operator_keys = Arc::new(RwLock::new(OperatorKeys::default()));
+		operator_keys
+			.write()
+			.update(vec![operator.public(), previous_operator.public()]);
+		let validator: GossipValidator<Block> = GossipValidator::new(
+			Arc::new(RwLock::new(0)),
+			Arc::new(RwLock::new(Default::default())),
+			false,
+			Arc::new(RwLock::new(Default::default())),
+			Arc::new(RwLock::new(0)),
+			operator_keys.clone(),
+			report_sender,
+		);
+		let peer = PeerId::random();
+		let mut message = ObMessage {
+			stid: 1,
+			worker_nonce: 1,
+			action: UserActions::BlockImport(1),
+			signature: Default::default(),
+			reset: false,
+			version: 0,
+		};
+
+		message.signature = operator.sign_prehashed(&message.sign_data());
+		let gossip = GossipMessage::ObMessage(Box::new(message.clone()));
+		assert!(matches!(
+			validator.validate_message(&gossip, peer),
+			ValidationResult::ProcessAndKeep(_)
+		));
+		// The previous operator key is still accepted during the rotation grace period
+		message.signature = previous_operator.sign_prehashed(&message.sign_data());
+		let gossip = GossipMessage::ObMessage(Box::new(message.clone()));
+		assert!(matches!(
+			validator.validate_message(&gossip, peer),
+			ValidationResult::ProcessAndKeep(_)
+		));
+		assert!(report_receiver.try_next().is_err());
+
+		// Forged messages are neither processed nor propagated, the relaying peer is reported
+		message.signature = forger.sign_prehashed(&message.sign_data());
+		let gossip = GossipMessage::ObMessage(Box::new(message.clone()));
+		assert!(matches!(validator.validate_message(&gossip, peer), ValidationResult::Discard));
+		assert_eq!(
+			report_receiver.try_next().unwrap(),
+			Some(PeerReport { who: peer, cost_benefit: cost::INVALID_SIGNATURE })
+		);
+
+		// Once the grace period is over, the previous key is only slightly penalized
+		operator_keys.write().update(vec![operator.public()]);
+		message.signature = previous_operator.sign_prehashed(&message.sign_data());
+		let gossip = GossipMessage::ObMessage(Box::new(message));
+		assert!(matches!(validator.validate_message(&gossip, peer), ValidationResult::Discard));
+		assert_eq!(
+			report_receiver.try_next().unwrap(),
+			Some(PeerReport { who: peer, cost_benefit: cost::EXPIRED_KEY_SIGNATURE })
+		);
+	}
+
+	#[test]
+	pub fn test_ob_messages_are_held_until_the_operator_keys_are_read() {
+		let (operator, _) = sp_core::ecdsa::Pair::generate();
+		let (report_sender, mut report_receiver) = futures::channel::mpsc::unbounded();
+		let operator_keys = Arc::new(RwLock::new(OperatorKeys::default()));
+		let validator: GossipValidator<Block> = GossipValidator::new(
+			Arc::new(RwLock::new(0)),
+			Arc::new(RwLock::new(Default::default())),
+			false,
+			Arc::new(RwLock::new(Default::default())),
+			Arc::new(RwLock::new(0)),
+			operator_keys.clone(),
+			report_sender,
+		);
+		let peer = PeerId::random();
+		let mut message = ObMessage {
+			stid: 1,
+			worker_nonce: 1,
+			action: UserActions::BlockImport(1),
+			signature: Default::default(),
+			reset: false,
+			version: 0,
+		};
+		message.signature = operator.sign_prehashed(&message.sign_data());
+		let gossip = GossipMessage::ObMessage(Box::new(message.clone()));
+
+		// Nobody is reported while the signature cannot be verified
+		assert!(matches!(validator.validate_message(&gossip, peer), ValidationResult::Discard));
+		assert!(report_receiver.try_next().is_err());
+		assert!(operator_keys.write().update(vec![]).is_empty());
+
+		let held = operator_keys.write().update(vec![operator.public()]);
+		assert_eq!(held.len(), 1);
+		assert_eq!(held[0].0, Some(peer));
+		assert_eq!(held[0].1.worker_nonce, message.worker_nonce);
+		assert_eq!(operator_keys.read().check(&held[0].1), SignatureCheck::Valid);
+		assert!(operator_keys.write().update(vec![operator.public()]).is_empty());
+	}
 }
@@ -41,7 +41,11 @@ use std::{marker::PhantomData, sync::Arc};
 mod error;
 mod gossip;
 mod keystore;
+pub mod matching;
+mod message_log;
 mod metrics;
+pub mod notification;
+pub mod replay;
 pub mod snapshot;
 mod utils;
 mod worker;
@@ -151,6 +155,12 @@ where
 	pub memory_db: DbRef,
 	/// Working state root.
 	pub working_state_root: Arc<RwLock<[u8; 32]>>,
+	/// Enables the matching engine verifying the trades submitted by the operator.
+	pub enable_matching_engine: bool,
+	/// Notifies the RPC subscribers about applied actions.
+	pub action_sender: notification::ObActionSender,
+	/// Notifies the RPC subscribers about generated snapshots.
+	pub snapshot_sender: notification::ObSnapshotSender,
 }
 
 /// Start the Orderbook gadget.
@@ -178,6 +188,9 @@ where
 		marker: _,
 		memory_db,
 		working_state_root,
+		enable_matching_engine,
+		action_sender,
+		snapshot_sender,
 	} = ob_params;
 
 	let sync_oracle = network.clone();
@@ -210,6 +223,9 @@ where
 		memory_db,
 		working_state_root,
 		keystore,
+		enable_matching_engine,
+		action_sender,
+		snapshot_sender,
 	};
 
 	let worker = worker::ObWorker::<_, _, _, _, _, _>::new(worker_params);
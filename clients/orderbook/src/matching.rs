@@ -0,0 +1,657 @@
+// This file is part of Polkadex.
+//
+// Copyright (c) 2023 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Deterministic price-time priority matching engine.
+//!
+//! Validators feed the orders placed by the operator into the engine and compare the trades it
+//! produces with the trades submitted by the operator.
+//!
+//! STOP_LIMIT orders wait outside of the book until the last trade price reaches their trigger
+//! price, POST_ONLY orders are rejected if they would take liquidity, IOC orders discard their
+//! unfilled part and FOK orders are rejected unless they can be entirely filled at once.
+
+use std::collections::BTreeMap;
+
+use orderbook_primitives::types::{
+	Order, OrderId, OrderSide, OrderStatus, OrderType, TimeInForce, Trade, TradingPair,
+};
+use polkadex_primitives::ocex::TradingPairConfig;
+use rust_decimal::{prelude::Zero, Decimal};
+
+use crate::error::Error;
+
+/// Priority of a resting order: (signed price, timestamp, order id).
+///
+/// Bid prices are negated so that both sides are iterated from the best price, and orders at the
+/// same price are iterated from the oldest one. The order id makes the key unique.
+type PriorityKey = (Decimal, i64, OrderId);
+
+/// Resting orders of a single trading pair.
+#[derive(Clone, Debug, Default)]
+pub struct OrderBook {
+	/// Resting bids in priority order.
+	bids: BTreeMap<PriorityKey, Order>,
+	/// Resting asks in priority order.
+	asks: BTreeMap<PriorityKey, Order>,
+	/// Side and priority of the resting orders by their identifier.
+	index: BTreeMap<OrderId, (OrderSide, PriorityKey)>,
+	/// STOP_LIMIT orders waiting for their trigger price by their identifier.
+	stops: BTreeMap<OrderId, Order>,
+	/// Price of the last trade.
+	last_price: Option<Decimal>,
+}
+
+impl OrderBook {
+	fn priority_key(order: &Order) -> PriorityKey {
+		let price = match order.side {
+			OrderSide::Bid => -order.price,
+			OrderSide::Ask => order.price,
+		};
+		(price, order.timestamp, order.id)
+	}
+
+	fn side_mut(&mut self, side: OrderSide) -> &mut BTreeMap<PriorityKey, Order> {
+		match side {
+			OrderSide::Bid => &mut self.bids,
+			OrderSide::Ask => &mut self.asks,
+		}
+	}
+
+	/// Returns the best resting order of the given side.
+	///
+	/// # Parameters
+	///
+	/// * `side`: Side of the book.
+	pub fn best(&self, side: OrderSide) -> Option<&Order> {
+		let orders = match side {
+			OrderSide::Bid => &self.bids,
+			OrderSide::Ask => &self.asks,
+		};
+		orders.values().next()
+	}
+
+	/// Returns a resting or a waiting STOP_LIMIT order by its identifier.
+	///
+	/// # Parameters
+	///
+	/// * `id`: Order identifier.
+	pub fn get(&self, id: &OrderId) -> Option<&Order> {
+		match self.index.get(id) {
+			Some((OrderSide::Bid, key)) => self.bids.get(key),
+			Some((OrderSide::Ask, key)) => self.asks.get(key),
+			None => self.stops.get(id),
+		}
+	}
+
+	fn insert(&mut self, order: Order) {
+		let key = Self::priority_key(&order);
+		self.index.insert(order.id, (order.side, key));
+		self.side_mut(order.side).insert(key, order);
+	}
+
+	fn remove(&mut self, id: &OrderId) -> Option<Order> {
+		match self.index.remove(id) {
+			Some((side, key)) => self.side_mut(side).remove(&key),
+			None => self.stops.remove(id),
+		}
+	}
+
+	/// Returns the price of the last trade, if any.
+	pub fn last_price(&self) -> Option<Decimal> {
+		self.last_price
+	}
+
+	/// Checks if the last trade price reached the trigger price of a STOP_LIMIT order.
+	fn is_triggered(&self, order: &Order) -> bool {
+		match self.last_price {
+			Some(price) => match order.side {
+				OrderSide::Bid => price >= order.trigger_price,
+				OrderSide::Ask => price <= order.trigger_price,
+			},
+			None => false,
+		}
+	}
+
+	/// Removes the oldest triggered STOP_LIMIT order.
+	fn pop_triggered_stop(&mut self) -> Option<Order> {
+		let id = self
+			.stops
+			.values()
+			.filter(|order| self.is_triggered(order))
+			.min_by_key(|order| (order.timestamp, order.id))?
+			.id;
+		self.stops.remove(&id)
+	}
+
+	/// Executes an active order against the book.
+	///
+	/// # Parameters
+	///
+	/// * `order`: Incoming taker order.
+	/// * `config`: Trading pair configuration used to round MARKET bid quantities.
+	fn execute(
+		&mut self,
+		mut order: Order,
+		config: &TradingPairConfig,
+	) -> Result<Vec<Trade>, Error> {
+		let opposite_side = order.side.get_opposite();
+		if order.order_type == OrderType::POST_ONLY &&
+			self.best(opposite_side).map_or(false, |maker| crosses(&order, maker.price))
+		{
+			return Ok(Vec::new())
+		}
+		if order.time_in_force == TimeInForce::FOK {
+			// Match against a copy of the book which is discarded if the order is not filled
+			let mut book = self.clone();
+			let (trades, filled) = book.match_order(&mut order, config)?;
+			if filled {
+				*self = book;
+				return Ok(trades)
+			}
+			return Ok(Vec::new())
+		}
+		let (trades, _) = self.match_order(&mut order, config)?;
+		if order.is_resting() && order.filled_quantity < order.qty {
+			self.insert(order);
+		}
+		Ok(trades)
+	}
+
+	/// Matches the order against the resting orders of the opposite side.
+	///
+	/// # Parameters
+	///
+	/// * `order`: Incoming taker order, its filled quantity is updated.
+	/// * `config`: Trading pair configuration used to round MARKET bid quantities.
+	///
+	/// # Returns
+	///
+	/// Trades produced by the order, in execution order, and whether the order was entirely
+	/// filled.
+	fn match_order(
+		&mut self,
+		order: &mut Order,
+		config: &TradingPairConfig,
+	) -> Result<(Vec<Trade>, bool), Error> {
+		let opposite_side = order.side.get_opposite();
+		let mut quote_spent = Decimal::zero();
+		let mut trades = Vec::new();
+
+		while let Some(maker) = self.best(opposite_side) {
+			if order.order_type != OrderType::MARKET && !crosses(order, maker.price) {
+				break
+			}
+			let taker_remaining =
+				if order.order_type == OrderType::MARKET && order.side == OrderSide::Bid {
+					let qty = order
+						.quote_order_qty
+						.saturating_sub(quote_spent)
+						.checked_div(maker.price)
+						.ok_or_else(|| {
+							Error::MatchingEngine(format!("order {:?} has a zero price", maker.id))
+						})?;
+					round_down(qty, config.qty_step_size)
+				} else {
+					order.qty.saturating_sub(order.filled_quantity)
+				};
+			if taker_remaining <= Decimal::zero() {
+				return Ok((trades, true))
+			}
+			let maker_remaining = maker.qty.saturating_sub(maker.filled_quantity);
+			let amount = taker_remaining.min(maker_remaining);
+
+			let maker_id = maker.id;
+			let mut maker = self.remove(&maker_id).ok_or_else(|| {
+				Error::MatchingEngine(format!("order {maker_id:?} is not indexed in the book"))
+			})?;
+			let price = maker.price;
+			maker.update_avg_price_and_filled_qty(price, amount);
+			order.update_avg_price_and_filled_qty(price, amount);
+			quote_spent = quote_spent.saturating_add(price.saturating_mul(amount));
+			if maker.filled_quantity >= maker.qty {
+				maker.status = OrderStatus::CLOSED;
+			}
+			self.last_price = Some(price);
+			trades.push(Trade {
+				maker: maker.clone(),
+				taker: order.clone(),
+				price,
+				amount,
+				time: order.timestamp,
+			});
+			if maker.status == OrderStatus::OPEN {
+				self.insert(maker);
+			}
+		}
+		// MARKET bids are sized in quote asset
+		let filled = if order.order_type == OrderType::MARKET && order.side == OrderSide::Bid {
+			quote_spent >= order.quote_order_qty
+		} else {
+			order.filled_quantity >= order.qty
+		};
+		Ok((trades, filled))
+	}
+}
+
+/// Matching engine holding an order book per trading pair.
+#[derive(Clone, Debug, Default)]
+pub struct MatchingEngine {
+	books: BTreeMap<TradingPair, OrderBook>,
+}
+
+impl MatchingEngine {
+	/// Returns the order book of the trading pair, if any order was placed on it.
+	///
+	/// # Parameters
+	///
+	/// * `pair`: Trading pair.
+	pub fn book(&self, pair: &TradingPair) -> Option<&OrderBook> {
+		self.books.get(pair)
+	}
+
+	/// Matches the order against the resting orders of the opposite side.
+	///
+	/// Resting orders are matched from the best price and, at the same price, from the oldest
+	/// one. Trades are executed at the resting order price. The unfilled part of a good till
+	/// cancelled order rests in the book while the unfilled part of a MARKET or IOC order is
+	/// discarded. STOP_LIMIT orders whose trigger price is reached by the trades are executed in
+	/// turn, from the oldest one.
+	///
+	/// # Parameters
+	///
+	/// * `order`: Incoming taker order.
+	/// * `config`: Trading pair configuration used to round MARKET bid quantities.
+	///
+	/// # Returns
+	///
+	/// Trades produced by the order, in execution order, or an error if the book is
+	/// inconsistent.
+	pub fn place_order(
+		&mut self,
+		order: Order,
+		config: &TradingPairConfig,
+	) -> Result<Vec<Trade>, Error> {
+		let book = self.books.entry(order.pair).or_default();
+		if order.order_type == OrderType::STOP_LIMIT && !book.is_triggered(&order) {
+			book.stops.insert(order.id, order);
+			return Ok(Vec::new())
+		}
+		let mut trades = book.execute(order, config)?;
+		while let Some(stop) = book.pop_triggered_stop() {
+			trades.extend(book.execute(stop, config)?);
+		}
+		Ok(trades)
+	}
+
+	/// Removes a resting or a waiting STOP_LIMIT order from the book.
+	///
+	/// # Parameters
+	///
+	/// * `pair`: Trading pair of the order.
+	/// * `id`: Order identifier.
+	///
+	/// # Returns
+	///
+	/// The cancelled order, or `None` if it was not resting in the book.
+	pub fn cancel_order(&mut self, pair: &TradingPair, id: &OrderId) -> Option<Order> {
+		let mut order = self.books.get_mut(pair)?.remove(id)?;
+		order.status = OrderStatus::CANCELLED;
+		Some(order)
+	}
+
+	/// Adds an order to the book without matching it, used to rebuild the books from the open
+	/// orders of the state.
+	///
+	/// A STOP_LIMIT order is considered triggered if it was partially filled or if the last trade
+	/// price reached its trigger price, the last trade price has to be restored first.
+	///
+	/// # Parameters
+	///
+	/// * `order`: Open order.
+	pub fn restore_order(&mut self, order: Order) {
+		let book = self.books.entry(order.pair).or_default();
+		if order.order_type == OrderType::STOP_LIMIT &&
+			order.filled_quantity.is_zero() &&
+			!book.is_triggered(&order)
+		{
+			book.stops.insert(order.id, order);
+		} else {
+			book.insert(order);
+		}
+	}
+
+	/// Restores the price of the last trade of a trading pair.
+	///
+	/// # Parameters
+	///
+	/// * `pair`: Trading pair.
+	/// * `price`: Price of the last trade.
+	pub fn restore_last_price(&mut self, pair: TradingPair, price: Decimal) {
+		self.books.entry(pair).or_default().last_price = Some(price);
+	}
+
+	/// Removes every resting order.
+	pub fn clear(&mut self) {
+		self.books.clear();
+	}
+}
+
+/// Checks if two trades match the same orders with the same price and amount.
+///
+/// The state of the orders embedded in the trades and the trade time are not compared as they
+/// are informational.
+///
+/// # Parameters
+///
+/// * `expected`: Trade produced by the matching engine.
+/// * `actual`: Trade submitted by the operator.
+pub fn is_same_trade(expected: &Trade, actual: &Trade) -> bool {
+	expected.maker.id == actual.maker.id &&
+		expected.taker.id == actual.taker.id &&
+		expected.price == actual.price &&
+		expected.amount == actual.amount
+}
+
+fn crosses(taker: &Order, maker_price: Decimal) -> bool {
+	match taker.side {
+		OrderSide::Bid => taker.price >= maker_price,
+		OrderSide::Ask => taker.price <= maker_price,
+	}
+}
+
+fn round_down(qty: Decimal, step: Decimal) -> Decimal {
+	if step.is_zero() {
+		return qty
+	}
+	(qty / step).floor().saturating_mul(step)
+}
+
+#[cfg(test)]
+mod tests {
+	use orderbook_primitives::types::{
+		Order, OrderSide, OrderStatus, OrderType, TimeInForce, Trade, TradingPair,
+	};
+	use polkadex_primitives::{ocex::TradingPairConfig, AssetId};
+	use rust_decimal::Decimal;
+	use sp_core::H256;
+
+	use crate::{
+		error::Error,
+		matching::{is_same_trade, MatchingEngine},
+	};
+
+	fn pair() -> TradingPair {
+		TradingPair { base: AssetId::Polkadex, quote: AssetId::Asset(1) }
+	}
+
+	fn config() -> TradingPairConfig {
+		TradingPairConfig::default(AssetId::Polkadex, AssetId::Asset(1))
+	}
+
+	fn limit_order(id: u8, side: OrderSide, price: i64, qty: i64, timestamp: i64) -> Order {
+		let mut order = Order::random_order_for_testing(pair(), side, OrderType::LIMIT);
+		order.id = H256([id; 32]);
+		order.price = Decimal::from(price);
+		order.qty = Decimal::from(qty);
+		order.timestamp = timestamp;
+		order
+	}
+
+	#[test]
+	pub fn test_limit_orders_are_matched_in_price_time_priority() {
+		let mut engine = MatchingEngine::default();
+		assert!(engine
+			.place_order(limit_order(1, OrderSide::Ask, 11, 1, 1), &config())
+			.unwrap()
+			.is_empty());
+		assert!(engine
+			.place_order(limit_order(2, OrderSide::Ask, 10, 1, 2), &config())
+			.unwrap()
+			.is_empty());
+		assert!(engine
+			.place_order(limit_order(3, OrderSide::Ask, 10, 1, 3), &config())
+			.unwrap()
+			.is_empty());
+
+		let trades =
+			engine.place_order(limit_order(4, OrderSide::Bid, 11, 3, 4), &config()).unwrap();
+		let matched: Vec<_> =
+			trades.iter().map(|trade| (trade.maker.id, trade.price, trade.amount)).collect();
+		assert_eq!(
+			matched,
+			vec![
+				(H256([2; 32]), Decimal::from(10), Decimal::ONE),
+				(H256([3; 32]), Decimal::from(10), Decimal::ONE),
+				(H256([1; 32]), Decimal::from(11), Decimal::ONE),
+			]
+		);
+		assert!(trades.iter().all(|trade| trade.maker.status == OrderStatus::CLOSED));
+		assert!(engine.book(&pair()).unwrap().best(OrderSide::Ask).is_none());
+		assert!(engine.book(&pair()).unwrap().best(OrderSide::Bid).is_none());
+	}
+
+	#[test]
+	pub fn test_partially_filled_limit_order_rests_in_book() {
+		let mut engine = MatchingEngine::default();
+		assert!(engine
+			.place_order(limit_order(1, OrderSide::Bid, 10, 2, 1), &config())
+			.unwrap()
+			.is_empty());
+
+		let trades =
+			engine.place_order(limit_order(2, OrderSide::Ask, 9, 5, 2), &config()).unwrap();
+		assert_eq!(trades.len(), 1);
+		assert_eq!(trades[0].price, Decimal::from(10));
+		assert_eq!(trades[0].amount, Decimal::from(2));
+
+		let book = engine.book(&pair()).unwrap();
+		assert!(book.best(OrderSide::Bid).is_none());
+		let resting = book.best(OrderSide::Ask).unwrap();
+		assert_eq!(resting.id, H256([2; 32]));
+		assert_eq!(resting.filled_quantity, Decimal::from(2));
+
+		// A non crossing bid does not trade and rests in the book
+		assert!(engine
+			.place_order(limit_order(3, OrderSide::Bid, 8, 1, 3), &config())
+			.unwrap()
+			.is_empty());
+		assert_eq!(engine.book(&pair()).unwrap().best(OrderSide::Bid).unwrap().id, H256([3; 32]));
+	}
+
+	#[test]
+	pub fn test_market_bid_is_limited_by_quote_order_qty() {
+		let mut engine = MatchingEngine::default();
+		assert!(engine
+			.place_order(limit_order(1, OrderSide::Ask, 2, 10, 1), &config())
+			.unwrap()
+			.is_empty());
+
+		let mut market_bid =
+			Order::random_order_for_testing(pair(), OrderSide::Bid, OrderType::MARKET);
+		market_bid.id = H256([2; 32]);
+		market_bid.qty = Decimal::ZERO;
+		market_bid.quote_order_qty = Decimal::from(7);
+		let trades = engine.place_order(market_bid, &config()).unwrap();
+		assert_eq!(trades.len(), 1);
+		assert_eq!(trades[0].amount, Decimal::new(35, 1));
+		// The market order remainder is not kept in the book
+		assert!(engine.book(&pair()).unwrap().best(OrderSide::Bid).is_none());
+	}
+
+	#[test]
+	pub fn test_cancel_order_removes_it_from_book() {
+		let mut engine = MatchingEngine::default();
+		let ask = limit_order(1, OrderSide::Ask, 10, 1, 1);
+		assert!(engine.place_order(ask.clone(), &config()).unwrap().is_empty());
+
+		let cancelled = engine.cancel_order(&pair(), &ask.id).unwrap();
+		assert_eq!(cancelled.status, OrderStatus::CANCELLED);
+		assert!(engine.cancel_order(&pair(), &ask.id).is_none());
+		assert!(engine
+			.place_order(limit_order(2, OrderSide::Bid, 10, 1, 2), &config())
+			.unwrap()
+			.is_empty());
+	}
+
+	#[test]
+	pub fn test_stop_limit_order_is_executed_once_triggered() {
+		let mut engine = MatchingEngine::default();
+		assert!(engine
+			.place_order(limit_order(1, OrderSide::Ask, 12, 1, 1), &config())
+			.unwrap()
+			.is_empty());
+		let mut stop_bid = limit_order(2, OrderSide::Bid, 12, 1, 2);
+		stop_bid.order_type = OrderType::STOP_LIMIT;
+		stop_bid.trigger_price = Decimal::from(10);
+		assert!(engine.place_order(stop_bid, &config()).unwrap().is_empty());
+		assert!(engine.book(&pair()).unwrap().best(OrderSide::Bid).is_none());
+		assert!(engine.book(&pair()).unwrap().get(&H256([2; 32])).is_some());
+
+		// A trade at the trigger price executes the stop order against the remaining ask
+		assert!(engine
+			.place_order(limit_order(3, OrderSide::Ask, 10, 1, 3), &config())
+			.unwrap()
+			.is_empty());
+		let trades =
+			engine.place_order(limit_order(4, OrderSide::Bid, 10, 1, 4), &config()).unwrap();
+		let matched: Vec<_> = trades
+			.iter()
+			.map(|trade| (trade.taker.id, trade.maker.id, trade.price))
+			.collect();
+		assert_eq!(
+			matched,
+			vec![
+				(H256([4; 32]), H256([3; 32]), Decimal::from(10)),
+				(H256([2; 32]), H256([1; 32]), Decimal::from(12)),
+			]
+		);
+		assert_eq!(engine.book(&pair()).unwrap().last_price(), Some(Decimal::from(12)));
+	}
+
+	#[test]
+	pub fn test_post_only_order_is_rejected_if_it_takes_liquidity() {
+		let mut engine = MatchingEngine::default();
+		assert!(engine
+			.place_order(limit_order(1, OrderSide::Ask, 10, 1, 1), &config())
+			.unwrap()
+			.is_empty());
+		let mut crossing_bid = limit_order(2, OrderSide::Bid, 10, 1, 2);
+		crossing_bid.order_type = OrderType::POST_ONLY;
+		assert!(engine.place_order(crossing_bid, &config()).unwrap().is_empty());
+		assert!(engine.book(&pair()).unwrap().get(&H256([2; 32])).is_none());
+
+		let mut resting_bid = limit_order(3, OrderSide::Bid, 9, 1, 3);
+		resting_bid.order_type = OrderType::POST_ONLY;
+		assert!(engine.place_order(resting_bid, &config()).unwrap().is_empty());
+		assert_eq!(engine.book(&pair()).unwrap().best(OrderSide::Bid).unwrap().id, H256([3; 32]));
+	}
+
+	#[test]
+	pub fn test_ioc_and_fok_orders_do_not_rest_in_book() {
+		let mut engine = MatchingEngine::default();
+		assert!(engine
+			.place_order(limit_order(1, OrderSide::Ask, 10, 2, 1), &config())
+			.unwrap()
+			.is_empty());
+
+		// FOK order can't be entirely filled, nothing is matched
+		let mut fok_bid = limit_order(2, OrderSide::Bid, 10, 3, 2);
+		fok_bid.time_in_force = TimeInForce::FOK;
+		assert!(engine.place_order(fok_bid, &config()).unwrap().is_empty());
+		assert_eq!(
+			engine.book(&pair()).unwrap().best(OrderSide::Ask).unwrap().filled_quantity,
+			Decimal::ZERO
+		);
+
+		// IOC order is partially filled and its remainder is discarded
+		let mut ioc_bid = limit_order(3, OrderSide::Bid, 10, 3, 3);
+		ioc_bid.time_in_force = TimeInForce::IOC;
+		let trades = engine.place_order(ioc_bid, &config()).unwrap();
+		assert_eq!(trades.len(), 1);
+		assert_eq!(trades[0].amount, Decimal::from(2));
+		let book = engine.book(&pair()).unwrap();
+		assert!(book.best(OrderSide::Ask).is_none());
+		assert!(book.best(OrderSide::Bid).is_none());
+	}
+
+	#[test]
+	pub fn test_fok_market_bid_is_filled_up_to_quote_order_qty() {
+		let mut engine = MatchingEngine::default();
+		assert!(engine
+			.place_order(limit_order(1, OrderSide::Ask, 2, 3, 1), &config())
+			.unwrap()
+			.is_empty());
+
+		// The book only offers 6 units of quote asset, nothing is matched
+		let mut market_bid =
+			Order::random_order_for_testing(pair(), OrderSide::Bid, OrderType::MARKET);
+		market_bid.id = H256([2; 32]);
+		market_bid.qty = Decimal::ZERO;
+		market_bid.quote_order_qty = Decimal::from(7);
+		market_bid.time_in_force = TimeInForce::FOK;
+		assert!(engine.place_order(market_bid.clone(), &config()).unwrap().is_empty());
+		assert_eq!(
+			engine.book(&pair()).unwrap().best(OrderSide::Ask).unwrap().filled_quantity,
+			Decimal::ZERO
+		);
+
+		// The whole quote order quantity is spent
+		market_bid.id = H256([3; 32]);
+		market_bid.quote_order_qty = Decimal::from(5);
+		let trades = engine.place_order(market_bid, &config()).unwrap();
+		assert_eq!(trades.len(), 1);
+		assert_eq!(trades[0].amount, Decimal::new(25, 1));
+	}
+
+	#[test]
+	pub fn test_operator_trades_are_compared_with_engine_trades() {
+		let first_ask = limit_order(1, OrderSide::Ask, 10, 2, 1);
+		let second_ask = limit_order(2, OrderSide::Ask, 10, 2, 2);
+		let bid = limit_order(3, OrderSide::Bid, 10, 3, 3);
+		let mut engine = MatchingEngine::default();
+		assert!(engine.place_order(first_ask.clone(), &config()).unwrap().is_empty());
+		assert!(engine.place_order(second_ask.clone(), &config()).unwrap().is_empty());
+		let trades = engine.place_order(bid.clone(), &config()).unwrap();
+
+		// Trades the operator submits for the bid, the oldest ask is matched first
+		let operator_trades = vec![
+			Trade::new(first_ask.clone(), bid.clone(), Decimal::from(10), Decimal::from(2)),
+			Trade::new(second_ask.clone(), bid.clone(), Decimal::from(10), Decimal::ONE),
+		];
+		assert_eq!(trades.len(), operator_trades.len());
+		assert!(trades.iter().zip(operator_trades.iter()).all(|(e, a)| is_same_trade(e, a)));
+
+		// Trades skipping the time priority or with another amount don't match
+		let skipping_priority =
+			Trade::new(second_ask, bid.clone(), Decimal::from(10), Decimal::from(2));
+		assert!(!is_same_trade(&trades[0], &skipping_priority));
+		let other_amount = Trade::new(first_ask, bid, Decimal::from(10), Decimal::ONE);
+		assert!(!is_same_trade(&trades[0], &other_amount));
+	}
+
+	#[test]
+	pub fn test_market_bid_against_zero_price_order_returns_error() {
+		let mut engine = MatchingEngine::default();
+		engine.restore_order(limit_order(1, OrderSide::Ask, 0, 1, 1));
+
+		let mut market_bid =
+			Order::random_order_for_testing(pair(), OrderSide::Bid, OrderType::MARKET);
+		market_bid.quote_order_qty = Decimal::from(7);
+		assert!(matches!(engine.place_order(market_bid, &config()), Err(Error::MatchingEngine(_))));
+	}
+}
@@ -0,0 +1,290 @@
+// This file is part of Polkadex.
+//
+// Copyright (c) 2023 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Persistent log of the accepted `ObMessage`s.
+//!
+//! Messages are kept in the offchain storage until a snapshot including them is accepted by the
+//! runtime, so that worker nonce gaps can be filled after a restart even if the peers have pruned
+//! the messages.
+
+use log::{info, warn};
+use orderbook_primitives::types::ObMessage;
+use parity_scale_codec::{Decode, Encode};
+use sp_core::{ecdsa, offchain::OffchainStorage};
+use std::collections::BTreeMap;
+
+/// Orderbook message log prefix used as an offchain storage item prefix.
+pub const ORDERBOOK_MESSAGE_LOG_PREFIX: &[u8; 19] = b"OrderbookMessageLog";
+
+/// Key of the range of worker nonces stored in the message log.
+const MESSAGE_LOG_BOUNDS_KEY: &[u8; 6] = b"bounds";
+
+/// Key of the operator keys by the first worker nonce they are accepted at.
+const MESSAGE_LOG_OPERATOR_KEYS_KEY: &[u8; 13] = b"operator_keys";
+
+/// Append-only log of the accepted `ObMessage`s, indexed by worker nonce.
+pub struct MessageLog<S> {
+	storage: S,
+}
+
+impl<S: OffchainStorage> MessageLog<S> {
+	/// Creates a message log over the offchain storage.
+	///
+	/// # Parameters
+	///
+	/// * `storage`: Offchain storage the messages are persisted in.
+	pub fn new(storage: S) -> Self {
+		Self { storage }
+	}
+
+	/// Returns the first and last worker nonces stored in the log, `None` if the log is empty.
+	pub fn bounds(&self) -> Option<(u64, u64)> {
+		self.storage
+			.get(ORDERBOOK_MESSAGE_LOG_PREFIX, MESSAGE_LOG_BOUNDS_KEY)
+			.and_then(|data| Option::<(u64, u64)>::decode(&mut &data[..]).ok())
+			.flatten()
+	}
+
+	fn set_bounds(&mut self, bounds: Option<(u64, u64)>) {
+		self.storage
+			.set(ORDERBOOK_MESSAGE_LOG_PREFIX, MESSAGE_LOG_BOUNDS_KEY, &bounds.encode());
+	}
+
+	/// Appends an accepted message to the log.
+	///
+	/// A reset message starts a new message stream from the last snapshot, so the messages of the
+	/// previous stream are removed.
+	///
+	/// # Parameters
+	///
+	/// * `message`: Accepted message.
+	pub fn append(&mut self, message: &ObMessage) {
+		let bounds = if message.reset {
+			self.clear();
+			(message.worker_nonce, message.worker_nonce)
+		} else {
+			match self.bounds() {
+				None => (message.worker_nonce, message.worker_nonce),
+				Some((first, last)) =>
+					(first.min(message.worker_nonce), last.max(message.worker_nonce)),
+			}
+		};
+		self.storage.set(
+			ORDERBOOK_MESSAGE_LOG_PREFIX,
+			&message.worker_nonce.encode(),
+			&message.encode(),
+		);
+		self.set_bounds(Some(bounds));
+	}
+
+	/// Returns the logged message of a worker nonce.
+	///
+	/// # Parameters
+	///
+	/// * `worker_nonce`: Worker nonce of the message.
+	pub fn get(&self, worker_nonce: u64) -> Option<ObMessage> {
+		let data = self.storage.get(ORDERBOOK_MESSAGE_LOG_PREFIX, &worker_nonce.encode())?;
+		match ObMessage::decode(&mut &data[..]) {
+			Ok(message) => Some(message),
+			Err(err) => {
+				warn!(target:"orderbook","📒 Unable to decode logged message {:?}: {:?}", worker_nonce, err);
+				None
+			},
+		}
+	}
+
+	/// Returns the logged messages after a worker nonce, in worker nonce order.
+	///
+	/// # Parameters
+	///
+	/// * `worker_nonce`: Worker nonce the messages are returned after, usually the one of the last
+	///   snapshot.
+	pub fn messages_after(&self, worker_nonce: u64) -> Vec<ObMessage> {
+		match self.bounds() {
+			Some((first, last)) if last > worker_nonce =>
+				(first.max(worker_nonce.saturating_add(1))..=last)
+					.filter_map(|worker_nonce| self.get(worker_nonce))
+					.collect(),
+			_ => vec![],
+		}
+	}
+
+	fn operator_keys(&self) -> BTreeMap<u64, Vec<ecdsa::Public>> {
+		self.storage
+			.get(ORDERBOOK_MESSAGE_LOG_PREFIX, MESSAGE_LOG_OPERATOR_KEYS_KEY)
+			.and_then(|data| BTreeMap::decode(&mut &data[..]).ok())
+			.unwrap_or_default()
+	}
+
+	fn set_operator_keys(&mut self, operator_keys: &BTreeMap<u64, Vec<ecdsa::Public>>) {
+		self.storage.set(
+			ORDERBOOK_MESSAGE_LOG_PREFIX,
+			MESSAGE_LOG_OPERATOR_KEYS_KEY,
+			&operator_keys.encode(),
+		);
+	}
+
+	/// Records the operator keys the messages are accepted from, starting at a worker nonce.
+	///
+	/// # Parameters
+	///
+	/// * `worker_nonce`: First worker nonce the keys are accepted at.
+	/// * `keys`: Accepted operator keys.
+	pub fn record_operator_keys(&mut self, worker_nonce: u64, keys: &[ecdsa::Public]) {
+		let mut operator_keys = self.operator_keys();
+		if operator_keys.values().next_back().map_or(false, |last| last.as_slice() == keys) {
+			return
+		}
+		operator_keys.insert(worker_nonce, keys.to_vec());
+		self.set_operator_keys(&operator_keys);
+	}
+
+	/// Returns the operator keys accepted at a worker nonce, `None` if no keys are recorded
+	/// before it.
+	///
+	/// # Parameters
+	///
+	/// * `worker_nonce`: Worker nonce of a message.
+	pub fn operator_keys_at(&self, worker_nonce: u64) -> Option<Vec<ecdsa::Public>> {
+		self.operator_keys()
+			.range(..=worker_nonce)
+			.next_back()
+			.map(|(_, keys)| keys.clone())
+	}
+
+	/// Removes the messages up to a worker nonce, once they are included in an accepted
+	/// snapshot.
+	///
+	/// # Parameters
+	///
+	/// * `worker_nonce`: Last worker nonce to remove.
+	pub fn prune(&mut self, worker_nonce: u64) {
+		// Keeps the keys accepted after the worker nonce
+		let mut operator_keys = self.operator_keys();
+		if let Some(&since) = operator_keys
+			.range(..=worker_nonce.saturating_add(1))
+			.next_back()
+			.map(|(since, _)| since)
+		{
+			if operator_keys.keys().next().map_or(false, |first| *first < since) {
+				operator_keys = operator_keys.split_off(&since);
+				self.set_operator_keys(&operator_keys);
+			}
+		}
+		let (first, last) = match self.bounds() {
+			Some((first, last)) if first <= worker_nonce => (first, last),
+			_ => return,
+		};
+		for nonce in first..=worker_nonce.min(last) {
+			self.storage.remove(ORDERBOOK_MESSAGE_LOG_PREFIX, &nonce.encode());
+		}
+		let bounds = (worker_nonce < last).then_some((worker_nonce.saturating_add(1), last));
+		self.set_bounds(bounds);
+		info!(target:"orderbook","📒 Message log pruned up to worker nonce: {:?}", worker_nonce);
+	}
+
+	/// Removes all the messages.
+	pub fn clear(&mut self) {
+		if let Some((first, last)) = self.bounds() {
+			for nonce in first..=last {
+				self.storage.remove(ORDERBOOK_MESSAGE_LOG_PREFIX, &nonce.encode());
+			}
+		}
+		self.set_bounds(None);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::message_log::MessageLog;
+	use orderbook_primitives::types::{ObMessage, UserActions};
+	use sp_core::offchain::storage::InMemOffchainStorage;
+
+	fn message(worker_nonce: u64, reset: bool) -> ObMessage {
+		ObMessage {
+			stid: worker_nonce,
+			worker_nonce,
+			action: if reset { UserActions::Reset } else { UserActions::BlockImport(1) },
+			signature: Default::default(),
+			reset,
+			version: 0,
+		}
+	}
+
+	fn worker_nonces(messages: Vec<ObMessage>) -> Vec<u64> {
+		messages.iter().map(|message| message.worker_nonce).collect()
+	}
+
+	#[test]
+	pub fn test_message_log_is_pruned_and_replayed() {
+		let mut log = MessageLog::new(InMemOffchainStorage::default());
+		assert!(log.messages_after(0).is_empty());
+		for worker_nonce in 1..=5 {
+			log.append(&message(worker_nonce, false));
+		}
+		assert_eq!(log.bounds(), Some((1, 5)));
+		assert_eq!(worker_nonces(log.messages_after(2)), vec![3, 4, 5]);
+
+		log.prune(3);
+		assert_eq!(log.bounds(), Some((4, 5)));
+		assert!(log.get(3).is_none());
+		assert_eq!(worker_nonces(log.messages_after(0)), vec![4, 5]);
+
+		log.prune(5);
+		assert_eq!(log.bounds(), None);
+		assert!(log.get(5).is_none());
+	}
+
+	#[test]
+	pub fn test_reset_message_replaces_the_message_stream() {
+		let mut log = MessageLog::new(InMemOffchainStorage::default());
+		for worker_nonce in 1..=5 {
+			log.append(&message(worker_nonce, false));
+		}
+		log.append(&message(3, true));
+		log.append(&message(4, false));
+		assert_eq!(log.bounds(), Some((3, 4)));
+		assert!(log.get(5).is_none());
+		let messages = log.messages_after(0);
+		assert_eq!(worker_nonces(messages.clone()), vec![3, 4]);
+		assert!(messages[0].reset);
+	}
+
+	#[test]
+	pub fn test_operator_keys_are_recorded_by_worker_nonce() {
+		use sp_core::Pair;
+		let (previous_operator, _) = sp_core::ecdsa::Pair::generate();
+		let (operator, _) = sp_core::ecdsa::Pair::generate();
+		let mut log = MessageLog::new(InMemOffchainStorage::default());
+		assert!(log.operator_keys_at(1).is_none());
+
+		log.record_operator_keys(1, &[previous_operator.public()]);
+		log.record_operator_keys(3, &[previous_operator.public()]);
+		log.record_operator_keys(5, &[operator.public()]);
+		assert!(log.operator_keys_at(0).is_none());
+		assert_eq!(log.operator_keys_at(4), Some(vec![previous_operator.public()]));
+		assert_eq!(log.operator_keys_at(5), Some(vec![operator.public()]));
+
+		// The keys accepted after the pruned worker nonce are kept
+		log.prune(3);
+		assert_eq!(log.operator_keys_at(4), Some(vec![previous_operator.public()]));
+		log.prune(4);
+		assert!(log.operator_keys_at(4).is_none());
+		assert_eq!(log.operator_keys_at(6), Some(vec![operator.public()]));
+	}
+}
@@ -18,7 +18,10 @@
 
 //! Orderbook Prometheus metrics definition.
 
-use prometheus::{register, Counter, Gauge, PrometheusError, Registry, U64};
+use prometheus::{
+	exponential_buckets, register, Counter, CounterVec, Gauge, GaugeVec, Histogram, HistogramOpts,
+	HistogramVec, Opts, PrometheusError, Registry, U64,
+};
 
 /// Orderbook metrics exposed through Prometheus.
 pub struct Metrics {
@@ -34,6 +37,22 @@ pub struct Metrics {
 	pub ob_data_sent: Gauge<U64>,
 	/// Total data recv by ob worker.
 	pub ob_data_recv: Gauge<U64>,
+	/// Time taken to apply an action to the working state, labelled by action.
+	pub ob_handle_action_duration: HistogramVec,
+	/// Total number of trades processed, labelled by trading pair.
+	pub ob_trades_processed: CounterVec<U64>,
+	/// Number of withdrawals waiting to be included in a snapshot.
+	pub ob_pending_withdrawals: Gauge<U64>,
+	/// Time taken to generate and store a snapshot.
+	pub ob_snapshot_generation_duration: Histogram,
+	/// Size in bytes of the last stored snapshot, labelled by kind (full or delta).
+	pub ob_snapshot_size: GaugeVec<U64>,
+	/// Number of state chunks of the snapshot being synced, labelled by sync status.
+	pub ob_sync_chunks: GaugeVec<U64>,
+	/// Number of known messages not yet pruned by an accepted snapshot.
+	pub ob_known_messages: Gauge<U64>,
+	/// Seconds elapsed since the last snapshot was accepted by the runtime.
+	pub ob_last_snapshot_age: Gauge<U64>,
 }
 
 impl Metrics {
@@ -71,6 +90,78 @@ impl Metrics {
 				Gauge::new("polkadex_ob_data_recv", "Total Data received by orderbook worker")?,
 				registry,
 			)?,
+			ob_handle_action_duration: register(
+				HistogramVec::new(
+					HistogramOpts::new(
+						"polkadex_ob_handle_action_duration_seconds",
+						"Time taken to apply an action to the working state by Orderbook",
+					)
+					.buckets(exponential_buckets(0.0001, 2.0, 16)?),
+					&["action"],
+				)?,
+				registry,
+			)?,
+			ob_trades_processed: register(
+				CounterVec::new(
+					Opts::new(
+						"polkadex_ob_trades_processed",
+						"Number of trades processed by Orderbook",
+					),
+					&["pair"],
+				)?,
+				registry,
+			)?,
+			ob_pending_withdrawals: register(
+				Gauge::new(
+					"polkadex_ob_pending_withdrawals",
+					"Number of withdrawals waiting to be included in a snapshot",
+				)?,
+				registry,
+			)?,
+			ob_snapshot_generation_duration: register(
+				Histogram::with_opts(
+					HistogramOpts::new(
+						"polkadex_ob_snapshot_generation_duration_seconds",
+						"Time taken to generate and store a snapshot by Orderbook",
+					)
+					.buckets(exponential_buckets(0.01, 2.0, 12)?),
+				)?,
+				registry,
+			)?,
+			ob_snapshot_size: register(
+				GaugeVec::new(
+					Opts::new(
+						"polkadex_ob_snapshot_size_bytes",
+						"Size of the last snapshot stored by Orderbook",
+					),
+					&["kind"],
+				)?,
+				registry,
+			)?,
+			ob_sync_chunks: register(
+				GaugeVec::new(
+					Opts::new(
+						"polkadex_ob_sync_chunks",
+						"Number of state chunks of the snapshot being synced by Orderbook",
+					),
+					&["status"],
+				)?,
+				registry,
+			)?,
+			ob_known_messages: register(
+				Gauge::new(
+					"polkadex_ob_known_messages",
+					"Number of known messages not yet pruned by an accepted snapshot",
+				)?,
+				registry,
+			)?,
+			ob_last_snapshot_age: register(
+				Gauge::new(
+					"polkadex_ob_last_snapshot_age_seconds",
+					"Seconds elapsed since the last snapshot was accepted",
+				)?,
+				registry,
+			)?,
 		})
 	}
 }
@@ -86,6 +177,13 @@ macro_rules! metric_set {
 			metrics.$m.set(val);
 		}
 	}};
+	($self:ident, $m:ident, $labels:expr, $v:expr) => {{
+		let val: u64 = format!("{}", $v).parse().unwrap();
+
+		if let Some(metrics) = $self.metrics.as_ref() {
+			metrics.$m.with_label_values($labels).set(val);
+		}
+	}};
 }
 
 #[macro_export]
@@ -95,6 +193,25 @@ macro_rules! metric_inc {
 			metrics.$m.inc();
 		}
 	}};
+	($self:ident, $m:ident, $labels:expr) => {{
+		if let Some(metrics) = $self.metrics.as_ref() {
+			metrics.$m.with_label_values($labels).inc();
+		}
+	}};
+}
+
+#[macro_export]
+macro_rules! metric_observe {
+	($self:ident, $m:ident, $v:expr) => {{
+		if let Some(metrics) = $self.metrics.as_ref() {
+			metrics.$m.observe($v);
+		}
+	}};
+	($self:ident, $m:ident, $labels:expr, $v:expr) => {{
+		if let Some(metrics) = $self.metrics.as_ref() {
+			metrics.$m.with_label_values($labels).observe($v);
+		}
+	}};
 }
 
 #[cfg(test)]
@@ -0,0 +1,95 @@
+// This file is part of Polkadex.
+//
+// Copyright (c) 2023 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Notifications sent by the worker to the RPC subscribers.
+
+use orderbook_primitives::{types::ObMessage, SnapshotSummary, H128};
+use polkadex_primitives::{withdrawal::Withdrawal, AccountId, BlockNumber};
+use sc_utils::notification::{NotificationSender, NotificationStream, TracingKeyStr};
+use serde::{Deserialize, Serialize};
+use sp_core::H256;
+
+/// Action applied by the worker to the working state.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ObActionNotification {
+	/// Applied action.
+	pub action: ObMessage,
+	/// Worker nonce after the action is applied.
+	pub worker_nonce: u64,
+	/// Working state root after the action is applied.
+	pub state_root: H256,
+}
+
+/// Snapshot generated by the worker.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ObSnapshotNotification {
+	/// Snapshot identifier.
+	pub snapshot_id: u64,
+	/// Worker nonce of the last action included in the snapshot.
+	pub worker_nonce: u64,
+	/// State change identifier.
+	pub state_change_id: u64,
+	/// Latest processed block number.
+	pub last_processed_blk: BlockNumber,
+	/// State root of the snapshot.
+	pub state_root: H256,
+	/// State chunk hashes.
+	pub state_chunk_hashes: Vec<H128>,
+	/// Withdrawals processed in the snapshot.
+	pub withdrawals: Vec<Withdrawal<AccountId>>,
+}
+
+impl From<&SnapshotSummary<AccountId>> for ObSnapshotNotification {
+	fn from(summary: &SnapshotSummary<AccountId>) -> Self {
+		Self {
+			snapshot_id: summary.snapshot_id,
+			worker_nonce: summary.worker_nonce,
+			state_change_id: summary.state_change_id,
+			last_processed_blk: summary.last_processed_blk,
+			state_root: summary.state_root,
+			state_chunk_hashes: summary.state_chunk_hashes.clone(),
+			withdrawals: summary.withdrawals.clone(),
+		}
+	}
+}
+
+/// Sending endpoint for notifying about applied actions.
+pub type ObActionSender = NotificationSender<ObActionNotification>;
+
+/// Receiving endpoint for applied action notifications.
+pub type ObActionStream = NotificationStream<ObActionNotification, ObActionTracingKey>;
+
+/// Sending endpoint for notifying about generated snapshots.
+pub type ObSnapshotSender = NotificationSender<ObSnapshotNotification>;
+
+/// Receiving endpoint for generated snapshot notifications.
+pub type ObSnapshotStream = NotificationStream<ObSnapshotNotification, ObSnapshotTracingKey>;
+
+/// Provides tracing key for applied action stream.
+#[derive(Clone)]
+pub struct ObActionTracingKey;
+impl TracingKeyStr for ObActionTracingKey {
+	const TRACING_KEY: &'static str = "mpsc_ob_action_notification_stream";
+}
+
+/// Provides tracing key for generated snapshot stream.
+#[derive(Clone)]
+pub struct ObSnapshotTracingKey;
+impl TracingKeyStr for ObSnapshotTracingKey {
+	const TRACING_KEY: &'static str = "mpsc_ob_snapshot_notification_stream";
+}
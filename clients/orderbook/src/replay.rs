@@ -0,0 +1,681 @@
+// This file is part of Polkadex.
+//
+// Copyright (c) 2023 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Offline replay of the orderbook messages on top of a snapshot.
+//!
+//! The messages are applied with the same state transition functions as the worker, so the
+//! operator can be audited without running a validator.
+
+use std::{
+	collections::{BTreeMap, BTreeSet},
+	fs,
+	path::{Path, PathBuf},
+};
+
+use log::{debug, info};
+use memory_db::{HashKey, MemoryDB};
+use orderbook_primitives::{
+	recovery::ObRecoveryState,
+	types::{AccountAsset, AccountInfo, ObMessage, Order, TradingPair, UserActions},
+	SnapshotSummary,
+};
+use parity_scale_codec::{Decode, Encode};
+use polkadex_primitives::{
+	ingress::IngressMessages, ocex::TradingPairConfig, withdrawal::Withdrawal, AccountId, AssetId,
+	BlockNumber,
+};
+use reference_trie::{ExtensionLayout, RefHasher};
+use rust_decimal::Decimal;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sp_core::{ecdsa, H256};
+use trie_db::{TrieDBMut, TrieDBMutBuilder, TrieMut};
+
+use crate::{
+	error::Error,
+	snapshot::{migrate_account_infos, SnapshotStore},
+	worker::{
+		cancel_open_order, get_exchange_state, open_order, order_reservation,
+		process_ingress_messages, process_trade, process_withdrawal_request, take_collected_fees,
+	},
+};
+
+/// State the messages are replayed on top of.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum ReplayBase {
+	/// Raw memory db of a snapshot, the replayed state root can be compared with the snapshots.
+	Store(SnapshotStore),
+	/// Exported recovery state of a snapshot. Only the balances, accounts and open orders are
+	/// restored, so the replayed state root differs from the one of the snapshots.
+	Recovery(ObRecoveryState),
+}
+
+/// Files read by an offline replay.
+#[derive(Clone, Debug)]
+pub struct ReplayFiles {
+	/// JSON `SnapshotStore` or `ObRecoveryState` the messages are replayed on top of.
+	pub snapshot: PathBuf,
+	/// JSON array of the `ObMessage`s to replay.
+	pub messages: PathBuf,
+	/// JSON array of the `TradingPairConfig`s open at the snapshot.
+	pub trading_pairs: Option<PathBuf>,
+	/// JSON map of block number to the `IngressMessages` of the block. Blocks missing from the
+	/// map are imported without ingress messages.
+	pub ingress_messages: Option<PathBuf>,
+	/// Hex encoded ecdsa public keys of the operator, the message signatures are not verified if
+	/// none is provided.
+	pub operator_public_keys: Vec<String>,
+}
+
+/// Free and reserved balances of an account asset.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct AccountBalance {
+	/// Main account.
+	pub main: AccountId,
+	/// Asset identifier.
+	pub asset: AssetId,
+	/// Free balance.
+	pub free: Decimal,
+	/// Reserved balance.
+	pub reserved: Decimal,
+}
+
+/// Result of an offline replay.
+#[derive(Clone, Debug, Serialize)]
+pub struct ReplayReport {
+	/// State root after the last replayed message.
+	pub state_root: H256,
+	/// Worker nonce of the last replayed message.
+	pub worker_nonce: u64,
+	/// State change id of the last replayed message.
+	pub state_change_id: u64,
+	/// Last imported block.
+	pub last_processed_blk: BlockNumber,
+	/// Balances of the accounts known by the replay.
+	pub balances: Vec<AccountBalance>,
+	/// Withdrawals queued since the snapshot.
+	pub withdrawals: Vec<Withdrawal<AccountId>>,
+	/// Trading fees collected since the snapshot.
+	pub trading_fees: Vec<(AssetId, Decimal)>,
+}
+
+/// Field of a `ReplayReport` which differs from the target snapshot summary.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ReplayMismatch {
+	/// Name of the field.
+	pub field: &'static str,
+	/// Value of the target snapshot summary.
+	pub expected: String,
+	/// Value of the replay.
+	pub found: String,
+}
+
+impl ReplayReport {
+	/// Compares the report with a snapshot summary, usually the one the operator submitted for the
+	/// last replayed message.
+	///
+	/// # Parameters
+	///
+	/// * `target`: Snapshot summary to compare the report with.
+	pub fn diff(&self, target: &SnapshotSummary<AccountId>) -> Vec<ReplayMismatch> {
+		let mut mismatches = vec![];
+		let mut compare = |field: &'static str, expected: String, found: String| {
+			if expected != found {
+				mismatches.push(ReplayMismatch { field, expected, found });
+			}
+		};
+		compare("state_root", format!("{:?}", target.state_root), format!("{:?}", self.state_root));
+		compare("worker_nonce", target.worker_nonce.to_string(), self.worker_nonce.to_string());
+		compare(
+			"state_change_id",
+			target.state_change_id.to_string(),
+			self.state_change_id.to_string(),
+		);
+		compare(
+			"last_processed_blk",
+			target.last_processed_blk.to_string(),
+			self.last_processed_blk.to_string(),
+		);
+		compare(
+			"withdrawals",
+			format!("{:?}", target.withdrawals),
+			format!("{:?}", self.withdrawals),
+		);
+		let mut target_fees: Vec<(AssetId, Decimal)> =
+			target.trading_fees.iter().map(|fee| (fee.asset, fee.amount)).collect();
+		target_fees.sort();
+		compare("trading_fees", format!("{target_fees:?}"), format!("{:?}", self.trading_fees));
+		mismatches
+	}
+
+	/// Serializes the report to pretty printed JSON.
+	pub fn to_json(&self) -> Result<String, Error> {
+		serde_json::to_string_pretty(self).map_err(|err| Error::ReplayInput(err.to_string()))
+	}
+}
+
+/// State changed by the replayed messages.
+#[derive(Clone)]
+struct ReplayState {
+	memory_db: MemoryDB<RefHasher, HashKey<RefHasher>, Vec<u8>>,
+	working_state_root: [u8; 32],
+	worker_nonce: u64,
+	state_change_id: u64,
+	last_processed_blk: BlockNumber,
+	exchange_operational: bool,
+	trading_pair_configs: BTreeMap<TradingPair, TradingPairConfig>,
+	pending_withdrawals: Vec<Withdrawal<AccountId>>,
+}
+
+impl ReplayState {
+	fn trie(&mut self) -> TrieDBMut<ExtensionLayout> {
+		if self.working_state_root == [0u8; 32] {
+			TrieDBMutBuilder::new(&mut self.memory_db, &mut self.working_state_root).build()
+		} else {
+			TrieDBMutBuilder::from_existing(&mut self.memory_db, &mut self.working_state_root)
+				.build()
+		}
+	}
+}
+
+/// Replays the orderbook messages offline on top of a snapshot.
+pub struct Replayer {
+	state: ReplayState,
+	/// State of the snapshot, restored when a reset message is replayed.
+	snapshot_state: ReplayState,
+	ingress_messages: BTreeMap<BlockNumber, Vec<IngressMessages<AccountId>>>,
+	operator_public_keys: Vec<ecdsa::Public>,
+	accounts: BTreeSet<AccountId>,
+	assets: BTreeSet<AssetId>,
+}
+
+impl Replayer {
+	/// Creates a replayer starting from a snapshot.
+	///
+	/// # Parameters
+	///
+	/// * `base`: State the messages are replayed on top of.
+	/// * `base_summary`: Summary of the snapshot, required for a `SnapshotStore` base as the state
+	///   root, worker nonce, state change id and last processed block are not part of the store.
+	/// * `trading_pair_configs`: Trading pairs open at the snapshot.
+	/// * `ingress_messages`: Ingress messages of the blocks imported by the replayed messages.
+	/// * `operator_public_keys`: Operator keys the message signatures are verified against, the
+	///   signatures are not verified if it is empty.
+	pub fn new(
+		base: ReplayBase,
+		base_summary: Option<&SnapshotSummary<AccountId>>,
+		trading_pair_configs: Vec<TradingPairConfig>,
+		ingress_messages: BTreeMap<BlockNumber, Vec<IngressMessages<AccountId>>>,
+		operator_public_keys: Vec<ecdsa::Public>,
+	) -> Result<Self, Error> {
+		let mut state = ReplayState {
+			memory_db: MemoryDB::default(),
+			working_state_root: [0u8; 32],
+			worker_nonce: 0,
+			state_change_id: 0,
+			last_processed_blk: 0,
+			exchange_operational: true,
+			trading_pair_configs: BTreeMap::new(),
+			pending_withdrawals: vec![],
+		};
+		let mut accounts = BTreeSet::new();
+		let mut assets = BTreeSet::new();
+		for config in trading_pair_configs {
+			assets.extend([config.base_asset, config.quote_asset]);
+			let pair = TradingPair::from(config.quote_asset, config.base_asset);
+			state.trading_pair_configs.insert(pair, config);
+		}
+		match base {
+			ReplayBase::Store(store) => {
+				let summary = base_summary.ok_or_else(|| {
+					Error::ReplayInput(
+						"the snapshot summary is required to replay on top of a snapshot store"
+							.to_string(),
+					)
+				})?;
+				state.memory_db.load_from(store.convert_to_hashmap());
+				state.working_state_root = summary.state_root.0;
+				migrate_account_infos(&mut state.memory_db, &mut state.working_state_root)?;
+				if state.working_state_root != [0u8; 32] {
+					let exchange_operational = get_exchange_state(&state.trie())?;
+					state.exchange_operational = exchange_operational;
+				}
+			},
+			ReplayBase::Recovery(recovery) => {
+				state.worker_nonce = recovery.worker_nonce;
+				state.state_change_id = recovery.state_change_id;
+				state.last_processed_blk = recovery.last_processed_block_number;
+				let mut trie = state.trie();
+				let mut proxy_permissions = recovery.proxy_permissions;
+				for (main, proxies) in recovery.account_ids {
+					let mut account_info = AccountInfo::new(proxies);
+					account_info.permissions = proxy_permissions.remove(&main).unwrap_or_default();
+					trie.insert(&main.encode(), &account_info.encode())?;
+					accounts.insert(main);
+				}
+				for (account_asset, balance) in recovery.balances {
+					trie.insert(&account_asset.encode(), &balance.encode())?;
+					assets.insert(account_asset.asset);
+				}
+				for (account_asset, balance) in recovery.reserved_balances {
+					trie.insert(&account_asset.reserved_balance_key(), &balance.encode())?;
+				}
+				// The reserved balances already include the reservations of the open orders
+				let mut order_reserved = BTreeMap::<AccountAsset, Decimal>::new();
+				for order in recovery.open_orders {
+					trie.insert(&Order::open_order_key(&order.pair, &order.id), &order.encode())?;
+					let (account_asset, amount) = order_reservation(&order);
+					let reserved = order_reserved.entry(account_asset).or_default();
+					*reserved = reserved.saturating_add(amount);
+				}
+				for (account_asset, amount) in order_reserved {
+					trie.insert(&account_asset.order_reserved_balance_key(), &amount.encode())?;
+				}
+				trie.commit();
+			},
+		}
+		if let Some(summary) = base_summary {
+			state.worker_nonce = summary.worker_nonce;
+			state.state_change_id = summary.state_change_id;
+			state.last_processed_blk = summary.last_processed_blk;
+		}
+		Ok(Self {
+			snapshot_state: state.clone(),
+			state,
+			ingress_messages,
+			operator_public_keys,
+			accounts,
+			assets,
+		})
+	}
+
+	/// Applies a message to the replayed state.
+	///
+	/// Messages already included in the snapshot are skipped.
+	///
+	/// # Parameters
+	///
+	/// * `message`: Message to replay.
+	pub fn apply(&mut self, message: &ObMessage) -> Result<(), Error> {
+		if !self.operator_public_keys.is_empty() &&
+			!message.verify_with_any(&self.operator_public_keys)
+		{
+			return Err(Error::SignatureVerificationFailed)
+		}
+		if message.reset {
+			info!(target:"orderbook","📒 Replay reset to the snapshot on worker nonce: {:?}", message.worker_nonce);
+			self.state = self.snapshot_state.clone();
+			self.state.worker_nonce = message.worker_nonce;
+			return Ok(())
+		}
+		if message.worker_nonce <= self.state.worker_nonce {
+			debug!(target:"orderbook","📒 Skipping replayed worker nonce: {:?}", message.worker_nonce);
+			return Ok(())
+		}
+		let expected = self.state.worker_nonce.saturating_add(1);
+		if message.worker_nonce != expected {
+			return Err(Error::WorkerNonceGap(expected, message.worker_nonce))
+		}
+		match message.action.clone() {
+			UserActions::Trade(trades) => {
+				if !self.state.exchange_operational {
+					return Err(Error::ExchangeNotOperational)
+				}
+				let configs = self.state.trading_pair_configs.clone();
+				let mut trie = self.state.trie();
+				for trade in trades {
+					let config =
+						configs.get(&trade.maker.pair).ok_or(Error::TradingPairConfigNotFound)?;
+					self.accounts.extend([
+						trade.maker.main_account.clone(),
+						trade.taker.main_account.clone(),
+					]);
+					process_trade(&mut trie, trade, config.clone())?;
+				}
+				trie.commit();
+			},
+			UserActions::Withdraw(withdraw) => {
+				self.accounts.insert(withdraw.main.clone());
+				self.assets.insert(withdraw.payload.asset_id);
+				let mut trie = self.state.trie();
+				let withdrawal = process_withdrawal_request(
+					&mut trie,
+					withdraw,
+					message.worker_nonce,
+					message.stid,
+				)?;
+				trie.commit();
+				drop(trie);
+				self.state.pending_withdrawals.push(withdrawal);
+			},
+			UserActions::BlockImport(num) => self.import_block(num, message)?,
+			UserActions::PlaceOrder(order) => {
+				if !self.state.exchange_operational {
+					return Err(Error::ExchangeNotOperational)
+				}
+				let config = self
+					.state
+					.trading_pair_configs
+					.get(&order.pair)
+					.ok_or(Error::TradingPairConfigNotFound)?;
+				if !order.verify_signature() || !order.verify_config(config) {
+					return Err(Error::InvalidOrder)
+				}
+				self.accounts.insert(order.main_account.clone());
+				let mut trie = self.state.trie();
+				open_order(&mut trie, &order)?;
+				trie.commit();
+			},
+			UserActions::CancelOrder(pair, order_id) => {
+				let mut trie = self.state.trie();
+				cancel_open_order(&mut trie, &pair, &order_id)?;
+				trie.commit();
+			},
+			UserActions::Reset => {},
+		}
+		self.state.worker_nonce = message.worker_nonce;
+		self.state.state_change_id = message.stid;
+		Ok(())
+	}
+
+	fn import_block(&mut self, num: BlockNumber, message: &ObMessage) -> Result<(), Error> {
+		if num == 0 {
+			return Ok(())
+		}
+		if self.state.last_processed_blk.saturating_add(1) != num {
+			return Err(Error::OutOfSequenceBlockImport)
+		}
+		let messages = self.ingress_messages.get(&num).cloned().unwrap_or_default();
+		for ingress in &messages {
+			match ingress {
+				IngressMessages::RegisterUser(main, _) => {
+					self.accounts.insert(main.clone());
+				},
+				IngressMessages::Deposit(main, asset, _) => {
+					self.accounts.insert(main.clone());
+					self.assets.insert(*asset);
+				},
+				IngressMessages::OpenTradingPair(config) => {
+					self.assets.extend([config.base_asset, config.quote_asset]);
+				},
+				_ => {},
+			}
+		}
+		let state = &mut self.state;
+		let mut trie = if state.working_state_root == [0u8; 32] {
+			TrieDBMutBuilder::new(&mut state.memory_db, &mut state.working_state_root).build()
+		} else {
+			TrieDBMutBuilder::from_existing(&mut state.memory_db, &mut state.working_state_root)
+				.build()
+		};
+		process_ingress_messages(
+			&mut trie,
+			messages,
+			message.worker_nonce,
+			message.stid,
+			&mut state.trading_pair_configs,
+			&mut state.exchange_operational,
+			&mut state.pending_withdrawals,
+		)?;
+		trie.commit();
+		drop(trie);
+		state.last_processed_blk = num;
+		Ok(())
+	}
+
+	/// Returns the report of the replayed state as a snapshot of the last replayed message reports
+	/// it, the collected trading fees are paid out and removed from the reported state.
+	pub fn report(&self) -> Result<ReplayReport, Error> {
+		let mut state = self.state.clone();
+		let mut balances = vec![];
+		let mut trading_fees = vec![];
+		if state.working_state_root != [0u8; 32] {
+			let mut trie = state.trie();
+			trading_fees = take_collected_fees(&mut trie)?
+				.into_iter()
+				.map(|fee| (fee.asset, fee.amount))
+				.collect();
+			trie.commit();
+			for main in &self.accounts {
+				for asset in &self.assets {
+					let account_asset = AccountAsset::new(main.clone(), *asset);
+					let free = trie
+						.get(&account_asset.encode())?
+						.map(|data| Decimal::decode(&mut &data[..]))
+						.transpose()?;
+					let reserved = trie
+						.get(&account_asset.reserved_balance_key())?
+						.map(|data| Decimal::decode(&mut &data[..]))
+						.transpose()?;
+					if free.is_some() || reserved.is_some() {
+						balances.push(AccountBalance {
+							main: main.clone(),
+							asset: *asset,
+							free: free.unwrap_or_default(),
+							reserved: reserved.unwrap_or_default(),
+						});
+					}
+				}
+			}
+		}
+		Ok(ReplayReport {
+			state_root: H256::from(state.working_state_root),
+			worker_nonce: state.worker_nonce,
+			state_change_id: state.state_change_id,
+			last_processed_blk: state.last_processed_blk,
+			balances,
+			withdrawals: state.pending_withdrawals,
+			trading_fees,
+		})
+	}
+}
+
+fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T, Error> {
+	let data =
+		fs::read(path).map_err(|err| Error::ReplayInput(format!("{}: {err}", path.display())))?;
+	serde_json::from_slice(&data)
+		.map_err(|err| Error::ReplayInput(format!("{}: {err}", path.display())))
+}
+
+/// Reads a SCALE encoded snapshot summary stored as hex in a file, as returned by the
+/// `get_snapshot_by_id` runtime API.
+///
+/// # Parameters
+///
+/// * `path`: Path of the file.
+pub fn read_snapshot_summary(path: &Path) -> Result<SnapshotSummary<AccountId>, Error> {
+	let data = fs::read_to_string(path)
+		.map_err(|err| Error::ReplayInput(format!("{}: {err}", path.display())))?;
+	let data = hex::decode(data.trim().trim_start_matches("0x"))
+		.map_err(|err| Error::ReplayInput(format!("{}: {err}", path.display())))?;
+	Ok(SnapshotSummary::decode(&mut &data[..])?)
+}
+
+/// Replays the messages of the files on top of their snapshot.
+///
+/// # Parameters
+///
+/// * `files`: Files read by the replay.
+/// * `base_summary`: Summary of the snapshot the messages are replayed on top of, required for a
+///   `SnapshotStore` base.
+pub fn replay_files(
+	files: &ReplayFiles,
+	base_summary: Option<&SnapshotSummary<AccountId>>,
+) -> Result<ReplayReport, Error> {
+	let base: ReplayBase = read_json(&files.snapshot)?;
+	let trading_pair_configs = match files.trading_pairs.as_ref() {
+		Some(path) => read_json(path)?,
+		None => vec![],
+	};
+	let ingress_messages = match files.ingress_messages.as_ref() {
+		Some(path) => read_json(path)?,
+		None => BTreeMap::new(),
+	};
+	let operator_public_keys = files
+		.operator_public_keys
+		.iter()
+		.map(|key| {
+			let data = hex::decode(key.trim_start_matches("0x"))
+				.map_err(|err| Error::ReplayInput(format!("operator public key: {err}")))?;
+			ecdsa::Public::try_from(&data[..])
+				.map_err(|_| Error::ReplayInput("operator public key: invalid length".to_string()))
+		})
+		.collect::<Result<Vec<_>, _>>()?;
+	let messages: Vec<ObMessage> = read_json(&files.messages)?;
+
+	let mut replayer = Replayer::new(
+		base,
+		base_summary,
+		trading_pair_configs,
+		ingress_messages,
+		operator_public_keys,
+	)?;
+	info!(target:"orderbook","📒 Replaying {:?} messages", messages.len());
+	for message in &messages {
+		replayer.apply(message)?;
+	}
+	replayer.report()
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		error::Error,
+		replay::{AccountBalance, ReplayBase, Replayer},
+		snapshot::{read_legacy_account_infos, SnapshotStore},
+		worker::{deposit, register_main},
+	};
+	use memory_db::{HashKey, MemoryDB};
+	use orderbook_primitives::{
+		recovery::ObRecoveryState,
+		types::{ObMessage, UserActions},
+		SnapshotSummary,
+	};
+	use parity_scale_codec::Encode;
+	use polkadex_primitives::{ingress::IngressMessages, AccountId, AssetId};
+	use primitive_types::H256;
+	use reference_trie::{ExtensionLayout, RefHasher};
+	use rust_decimal::Decimal;
+	use std::collections::BTreeMap;
+	use trie_db::{TrieDBMut, TrieDBMutBuilder, TrieMut};
+
+	fn block_import(worker_nonce: u64, num: u32) -> ObMessage {
+		ObMessage {
+			stid: worker_nonce,
+			worker_nonce,
+			action: UserActions::BlockImport(num),
+			signature: Default::default(),
+			reset: false,
+			version: 0,
+		}
+	}
+
+	#[test]
+	pub fn test_messages_are_replayed_and_diffed_against_the_summary() {
+		let main = AccountId::new([1; 32]);
+		let proxy = AccountId::new([2; 32]);
+		let ingress_messages = BTreeMap::from([(
+			1,
+			vec![
+				IngressMessages::RegisterUser(main.clone(), proxy),
+				IngressMessages::Deposit(main.clone(), AssetId::Polkadex, Decimal::new(10, 0)),
+			],
+		)]);
+		let mut replayer = Replayer::new(
+			ReplayBase::Recovery(ObRecoveryState::default()),
+			None,
+			vec![],
+			ingress_messages,
+			vec![],
+		)
+		.unwrap();
+		replayer.apply(&block_import(1, 1)).unwrap();
+		replayer.apply(&block_import(2, 2)).unwrap();
+		// Already replayed messages are skipped
+		replayer.apply(&block_import(1, 1)).unwrap();
+		assert_eq!(replayer.apply(&block_import(4, 3)), Err(Error::WorkerNonceGap(3, 4)));
+
+		let report = replayer.report().unwrap();
+		assert_eq!(report.worker_nonce, 2);
+		assert_eq!(report.last_processed_blk, 2);
+		assert_eq!(
+			report.balances,
+			vec![AccountBalance {
+				main,
+				asset: AssetId::Polkadex,
+				free: Decimal::new(10, 0),
+				reserved: Decimal::new(0, 0),
+			}]
+		);
+
+		let mut summary = SnapshotSummary {
+			state_root: report.state_root,
+			worker_nonce: 2,
+			state_change_id: 2,
+			last_processed_blk: 2,
+			..Default::default()
+		};
+		assert!(report.diff(&summary).is_empty());
+		summary.worker_nonce = 3;
+		let mismatches = report.diff(&summary);
+		assert_eq!(mismatches.len(), 1);
+		assert_eq!(mismatches[0].field, "worker_nonce");
+	}
+	#[test]
+	pub fn test_legacy_accounts_are_migrated_whatever_the_state_version() {
+		let mut state_root = [0u8; 32];
+		let mut memory_db: MemoryDB<RefHasher, HashKey<RefHasher>, Vec<u8>> = Default::default();
+		let main = AccountId::new([1; 32]);
+		let proxy = AccountId::new([2; 32]);
+		let legacy_main = AccountId::new([3; 32]);
+		let legacy_proxy = AccountId::new([4; 32]);
+		{
+			let mut trie: TrieDBMut<ExtensionLayout> =
+				TrieDBMutBuilder::new(&mut memory_db, &mut state_root).build();
+			register_main(&mut trie, main.clone(), proxy).unwrap();
+			deposit(&mut trie, main, AssetId::Polkadex, Decimal::new(10, 0)).unwrap();
+			// Accounts stored before the permissions were introduced only encoded their proxies
+			trie.insert(&legacy_main.encode(), &vec![legacy_proxy].encode()).unwrap();
+			trie.commit();
+		}
+		assert_eq!(read_legacy_account_infos(&memory_db, &state_root).unwrap().len(), 1);
+
+		// The state version is bumped by resets, it does not tell the account encoding
+		let summary = SnapshotSummary {
+			state_root: H256(state_root),
+			state_version: 2,
+			..Default::default()
+		};
+		let store = SnapshotStore::new(memory_db.data().clone().into_iter());
+		let replayer = Replayer::new(
+			ReplayBase::Store(store),
+			Some(&summary),
+			vec![],
+			BTreeMap::new(),
+			vec![],
+		)
+		.unwrap();
+		assert_ne!(replayer.state.working_state_root, state_root);
+		assert!(read_legacy_account_infos(
+			&replayer.state.memory_db,
+			&replayer.state.working_state_root
+		)
+		.unwrap()
+		.is_empty());
+	}
+}
@@ -16,10 +16,81 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
-use std::collections::{BTreeMap, HashMap};
+//! Snapshots of the orderbook state.
+//!
+//! Snapshots are written as a sequence of binary chunks, every chunk starts with
+//! `SNAPSHOT_CHUNK_MAGIC` and the format version, followed by the SCALE encoded memory db entries
+//! it contains. Entries are never split across chunks so every chunk can be verified against its
+//! hash and loaded on its own. Snapshots written before the binary format are JSON documents split
+//! at arbitrary offsets, they are still loaded.
+//!
+//! Between two full snapshots, the worker can write delta snapshots containing only the entries
+//! changed since the previous snapshot. Delta chunks start with `SNAPSHOT_DELTA_CHUNK_MAGIC` and
+//! the format version, followed by the id of the base snapshot, the SCALE encoded changed entries
+//! and the removed keys. Loading a delta snapshot requires replaying it on top of its base.
 
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use memory_db::{HashKey, MemoryDB};
+use orderbook_primitives::{
+	proof::BalanceProof,
+	types::{AccountAsset, AccountInfo, Order, TradingPair},
+	SnapshotSummary,
+};
+use parity_scale_codec::{Decode, DecodeAll, Encode, MaxEncodedLen};
+use polkadex_primitives::AccountId;
+use primitive_types::H128;
+use reference_trie::{ExtensionLayout, RefHasher};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use serde_with::{json::JsonString, serde_as};
+use sp_core::blake2_128;
+use trie_db::{
+	proof::generate_proof, Trie, TrieDBBuilder, TrieDBIterator, TrieDBMutBuilder, TrieMut,
+};
+
+/// Magic bytes starting every binary snapshot chunk, JSON snapshots start with `{`.
+pub const SNAPSHOT_CHUNK_MAGIC: &[u8; 4] = b"OBSC";
+/// Magic bytes starting every delta snapshot chunk.
+pub const SNAPSHOT_DELTA_CHUNK_MAGIC: &[u8; 4] = b"OBSD";
+/// Version of the binary snapshot format.
+pub const SNAPSHOT_FORMAT_VERSION: u8 = 1;
+/// Maximum size of a snapshot chunk, unless a single entry is bigger.
+pub const SNAPSHOT_CHUNK_SIZE: usize = 10 * 1024 * 1024;
+/// Number of snapshots between two full snapshots, the others are delta snapshots.
+pub const FULL_SNAPSHOT_INTERVAL: u64 = 10;
+
+/// Memory db entry: key, value and reference count.
+type SnapshotEntry = ([u8; 32], (Vec<u8>, i32));
+
+/// Snapshot encoding and decoding errors.
+#[derive(Debug, thiserror::Error)]
+pub enum SnapshotError {
+	#[error("Unsupported snapshot format version: {0}")]
+	UnsupportedVersion(u8),
+	#[error("Snapshot chunk is not in the format of the previous chunks")]
+	MixedFormats,
+	#[error("Delta snapshot chunk is based on snapshot {found}, expected {expected}")]
+	DeltaBaseMismatch { expected: u64, found: u64 },
+	#[error("Error decoding snapshot chunk: {0}")]
+	Codec(#[from] parity_scale_codec::Error),
+	#[error("Error decoding JSON snapshot: {0}")]
+	Json(#[from] serde_json::Error),
+	#[error("Error accessing snapshot trie: {0}")]
+	Trie(String),
+	#[error("Snapshot chunk {0:?} not found")]
+	ChunkNotFound(H128),
+	#[error("Snapshot chunk hash mismatch: computed: {computed:?}, expected: {expected:?}")]
+	ChunkHashMismatch { expected: H128, computed: H128 },
+	#[error("Invalid base {base} of delta snapshot {snapshot_id}")]
+	InvalidDeltaBase { snapshot_id: u64, base: u64 },
+	#[error("Snapshot summary {0} not found")]
+	SummaryNotFound(u64),
+	#[error("Error reading snapshot summary: {0}")]
+	Runtime(String),
+	#[error("Balance proof exceeds the size accepted by the runtime")]
+	BalanceProofTooLarge,
+}
 
 /// This is a dummy struct used to serialize memory db.
 /// We cannot serialize the hashmap below because of non-string type in key.
@@ -39,23 +110,486 @@ impl SnapshotStore {
 	pub fn convert_to_hashmap(self) -> HashMap<[u8; 32], (Vec<u8>, i32)> {
 		HashMap::from_iter(self.map.into_iter())
 	}
+
+	/// Encodes the store into binary chunks of at most `SNAPSHOT_CHUNK_SIZE` bytes.
+	///
+	/// Entries are encoded in key order so the chunks are deterministic. At least one chunk is
+	/// returned, even for an empty store.
+	pub fn encode_chunks(&self) -> Vec<Vec<u8>> {
+		// Magic, version and the compact encoded entries count
+		const HEADER_SIZE: usize = 4 + 1 + 5;
+		let mut chunks: Vec<Vec<u8>> = split_chunks(&self.map, HEADER_SIZE)
+			.iter()
+			.map(|entries| Self::encode_chunk(entries))
+			.collect();
+		if chunks.is_empty() {
+			chunks.push(Self::encode_chunk(&[]));
+		}
+		chunks
+	}
+
+	fn encode_chunk(entries: &[(&[u8; 32], &(Vec<u8>, i32))]) -> Vec<u8> {
+		let mut chunk = SNAPSHOT_CHUNK_MAGIC.to_vec();
+		chunk.push(SNAPSHOT_FORMAT_VERSION);
+		entries.encode_to(&mut chunk);
+		chunk
+	}
+
+	/// Returns the reference count of every entry, used to compute the next delta snapshot.
+	pub fn reference_counts(&self) -> BTreeMap<[u8; 32], i32> {
+		self.map.iter().map(|(key, (_, rc))| (*key, *rc)).collect()
+	}
+}
+
+/// Entries of the memory db changed since a base snapshot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotDelta {
+	/// Id of the snapshot this delta applies to.
+	pub base: u64,
+	/// Entries added or whose reference count changed.
+	pub changed: BTreeMap<[u8; 32], (Vec<u8>, i32)>,
+	/// Keys no longer present.
+	pub removed: BTreeSet<[u8; 32]>,
+}
+
+impl SnapshotDelta {
+	/// Computes the delta between the base snapshot and the current store.
+	///
+	/// Memory db keys are the hashes of their values, so an entry only changes through its
+	/// reference count and comparing the counts is enough.
+	///
+	/// # Parameters
+	///
+	/// * `base`: Id of the base snapshot.
+	/// * `base_reference_counts`: Reference counts of the base snapshot entries.
+	/// * `store`: Current store.
+	pub fn new(
+		base: u64,
+		base_reference_counts: &BTreeMap<[u8; 32], i32>,
+		store: &SnapshotStore,
+	) -> Self {
+		let changed = store
+			.map
+			.iter()
+			.filter(|(key, (_, rc))| base_reference_counts.get(*key) != Some(rc))
+			.map(|(key, entry)| (*key, entry.clone()))
+			.collect();
+		let removed = base_reference_counts
+			.keys()
+			.filter(|key| !store.map.contains_key(*key))
+			.copied()
+			.collect();
+		Self { base, changed, removed }
+	}
+
+	/// Replays the delta on top of the store of its base snapshot.
+	pub fn apply(self, store: &mut SnapshotStore) {
+		for key in &self.removed {
+			store.map.remove(key);
+		}
+		store.map.extend(self.changed);
+	}
+
+	/// Encodes the delta into binary chunks of at most `SNAPSHOT_CHUNK_SIZE` bytes.
+	///
+	/// Changed entries and removed keys are written to separate chunks, in key order. At least
+	/// one chunk is returned so the base of an empty delta is still known.
+	pub fn encode_chunks(&self) -> Vec<Vec<u8>> {
+		// Magic, version, base and the compact encoded entries and keys counts
+		const HEADER_SIZE: usize = 4 + 1 + 8 + 5 + 5;
+		let mut chunks: Vec<Vec<u8>> = split_chunks(&self.changed, HEADER_SIZE)
+			.iter()
+			.map(|entries| self.encode_chunk(entries, &[]))
+			.collect();
+		chunks.extend(
+			split_chunks(&self.removed, HEADER_SIZE)
+				.iter()
+				.map(|keys| self.encode_chunk(&[], keys)),
+		);
+		if chunks.is_empty() {
+			chunks.push(self.encode_chunk(&[], &[]));
+		}
+		chunks
+	}
+
+	fn encode_chunk(
+		&self,
+		entries: &[(&[u8; 32], &(Vec<u8>, i32))],
+		removed: &[&[u8; 32]],
+	) -> Vec<u8> {
+		let mut chunk = SNAPSHOT_DELTA_CHUNK_MAGIC.to_vec();
+		chunk.push(SNAPSHOT_FORMAT_VERSION);
+		self.base.encode_to(&mut chunk);
+		entries.encode_to(&mut chunk);
+		removed.encode_to(&mut chunk);
+		chunk
+	}
+}
+
+/// Splits the items into groups fitting in a chunk along with a header of `header_size` bytes.
+///
+/// An item bigger than a chunk gets a group of its own.
+fn split_chunks<T: Encode>(items: impl IntoIterator<Item = T>, header_size: usize) -> Vec<Vec<T>> {
+	let mut groups = Vec::new();
+	let mut group = Vec::new();
+	let mut size = header_size;
+	for item in items {
+		let item_size = item.encoded_size();
+		if !group.is_empty() && size.saturating_add(item_size) > SNAPSHOT_CHUNK_SIZE {
+			groups.push(std::mem::take(&mut group));
+			size = header_size;
+		}
+		group.push(item);
+		size = size.saturating_add(item_size);
+	}
+	if !group.is_empty() {
+		groups.push(group);
+	}
+	groups
+}
+
+/// Returns the id of the base snapshot if the chunk belongs to a delta snapshot.
+///
+/// # Parameters
+///
+/// * `chunk`: Snapshot chunk, already verified against its hash.
+pub fn delta_snapshot_base(chunk: &[u8]) -> Option<u64> {
+	let data = chunk.strip_prefix(SNAPSHOT_DELTA_CHUNK_MAGIC.as_slice())?;
+	match data.split_first() {
+		Some((&SNAPSHOT_FORMAT_VERSION, mut data)) => u64::decode(&mut data).ok(),
+		_ => None,
+	}
+}
+
+/// Generates the proof of the balances of an account asset against the state root of a snapshot.
+///
+/// # Parameters
+///
+/// * `memory_db`: Memory db loaded from the snapshot store.
+/// * `state_root`: State root of the snapshot.
+/// * `snapshot_id`: Snapshot id.
+/// * `account_asset`: Account asset to prove the balances of.
+pub fn generate_balance_proof(
+	memory_db: &MemoryDB<RefHasher, HashKey<RefHasher>, Vec<u8>>,
+	state_root: &[u8; 32],
+	snapshot_id: u64,
+	account_asset: AccountAsset,
+) -> Result<BalanceProof, SnapshotError> {
+	let keys = [account_asset.encode(), account_asset.reserved_balance_key()];
+	let trie = TrieDBBuilder::<ExtensionLayout>::new(memory_db, state_root).build();
+	let mut balances = Vec::with_capacity(keys.len());
+	for key in &keys {
+		let balance = trie
+			.get(key)
+			.map_err(|err| SnapshotError::Trie(format!("{err:?}")))?
+			.map(|data| Decimal::decode(&mut &data[..]))
+			.transpose()?;
+		balances.push(balance);
+	}
+	let proof = generate_proof::<_, ExtensionLayout, _, _>(memory_db, state_root, &keys)
+		.map_err(|err| SnapshotError::Trie(format!("{err:?}")))?
+		.into_iter()
+		.map(|node| node.try_into())
+		.collect::<Result<Vec<_>, _>>()
+		.map_err(|_| SnapshotError::BalanceProofTooLarge)?
+		.try_into()
+		.map_err(|_| SnapshotError::BalanceProofTooLarge)?;
+	Ok(BalanceProof { snapshot_id, account_asset, free: balances[0], reserved: balances[1], proof })
+}
+
+/// Reads the open orders of a trading pair from the state, ordered by their identifier.
+///
+/// Open orders are keyed by their trading pair and identifier, so they are read by iterating
+/// over the keys prefixed by the trading pair.
+///
+/// # Parameters
+///
+/// * `memory_db`: Memory db of the state.
+/// * `state_root`: State root, an empty state has no open order.
+/// * `pair`: Trading pair.
+pub fn read_open_orders(
+	memory_db: &MemoryDB<RefHasher, HashKey<RefHasher>, Vec<u8>>,
+	state_root: &[u8; 32],
+	pair: &TradingPair,
+) -> Result<Vec<Order>, SnapshotError> {
+	if *state_root == [0u8; 32] {
+		return Ok(Vec::new())
+	}
+	let trie = TrieDBBuilder::<ExtensionLayout>::new(memory_db, state_root).build();
+	let iter = TrieDBIterator::new_prefixed(&trie, &pair.open_orders_prefix())
+		.map_err(|err| SnapshotError::Trie(format!("{err:?}")))?;
+	let mut orders = Vec::new();
+	for item in iter {
+		let (_, data) = item.map_err(|err| SnapshotError::Trie(format!("{err:?}")))?;
+		orders.push(Order::decode(&mut &data[..])?);
+	}
+	Ok(orders)
+}
+
+/// Reads the accounts of a state whose accounts are not all encoded with the permissions of their
+/// proxies, so that they are migrated to the current encoding of `AccountInfo`.
+///
+/// Main accounts are the only keys of the size of an account id, accounts already in the current
+/// encoding are skipped.
+///
+/// # Parameters
+///
+/// * `memory_db`: Memory db of the state.
+/// * `state_root`: State root, an empty state has no account.
+pub fn read_legacy_account_infos(
+	memory_db: &MemoryDB<RefHasher, HashKey<RefHasher>, Vec<u8>>,
+	state_root: &[u8; 32],
+) -> Result<Vec<(Vec<u8>, AccountInfo)>, SnapshotError> {
+	if *state_root == [0u8; 32] {
+		return Ok(Vec::new())
+	}
+	let trie = TrieDBBuilder::<ExtensionLayout>::new(memory_db, state_root).build();
+	let iter = trie.iter().map_err(|err| SnapshotError::Trie(format!("{err:?}")))?;
+	let mut accounts = Vec::new();
+	for item in iter {
+		let (key, data) = item.map_err(|err| SnapshotError::Trie(format!("{err:?}")))?;
+		if key.len() != AccountId::max_encoded_len() ||
+			AccountInfo::decode_all(&mut &data[..]).is_ok()
+		{
+			continue
+		}
+		accounts.push((key, AccountInfo::decode_legacy(&data)?));
+	}
+	Ok(accounts)
+}
+
+/// Migrates the accounts of a state to the current encoding of `AccountInfo`, see
+/// [`read_legacy_account_infos`].
+///
+/// # Parameters
+///
+/// * `memory_db`: Memory db of the state.
+/// * `state_root`: State root, updated if an account is migrated.
+///
+/// # Returns
+///
+/// * `usize`: Number of migrated accounts.
+pub fn migrate_account_infos(
+	memory_db: &mut MemoryDB<RefHasher, HashKey<RefHasher>, Vec<u8>>,
+	state_root: &mut [u8; 32],
+) -> Result<usize, SnapshotError> {
+	let accounts = read_legacy_account_infos(memory_db, state_root)?;
+	if !accounts.is_empty() {
+		let mut trie =
+			TrieDBMutBuilder::<ExtensionLayout>::from_existing(memory_db, state_root).build();
+		for (main, account_info) in &accounts {
+			trie.insert(main, &account_info.encode())
+				.map_err(|err| SnapshotError::Trie(format!("{err:?}")))?;
+		}
+		trie.commit();
+	}
+	Ok(accounts.len())
+}
+
+/// Checks if a snapshot is stored as a full snapshot, the others are delta snapshots of their
+/// previous snapshot.
+///
+/// The first snapshot and every `FULL_SNAPSHOT_INTERVAL` snapshot are full snapshots. The kind
+/// only depends on the snapshot id so that validators agree on the chunk hashes.
+///
+/// # Parameters
+///
+/// * `snapshot_id`: Snapshot id.
+pub fn is_full_snapshot(snapshot_id: u64) -> bool {
+	snapshot_id <= 1 || snapshot_id % FULL_SNAPSHOT_INTERVAL == 0
+}
+
+/// Reads the chunks of a snapshot, verified against their hashes.
+///
+/// # Parameters
+///
+/// * `summary`: Snapshot summary listing the chunk hashes.
+/// * `read_chunk`: Returns a stored chunk by its hash.
+pub fn read_snapshot<C>(
+	summary: &SnapshotSummary<AccountId>,
+	read_chunk: C,
+) -> Result<Snapshot, SnapshotError>
+where
+	C: Fn(&H128) -> Option<Vec<u8>>,
+{
+	let mut loader = SnapshotLoader::default();
+	for chunk_hash in &summary.state_chunk_hashes {
+		let chunk = read_chunk(chunk_hash).ok_or(SnapshotError::ChunkNotFound(*chunk_hash))?;
+		let computed = H128::from(blake2_128(&chunk));
+		if computed != *chunk_hash {
+			return Err(SnapshotError::ChunkHashMismatch { expected: *chunk_hash, computed })
+		}
+		loader.load_chunk(&chunk)?;
+	}
+	loader.finish()
+}
+
+/// Rebuilds the store of a snapshot, replaying delta snapshots on top of their last full
+/// snapshot.
+///
+/// # Parameters
+///
+/// * `summary`: Snapshot summary of the snapshot to rebuild.
+/// * `read_chunk`: Returns a stored chunk by its hash.
+/// * `get_summary`: Returns a snapshot summary by its id, used to read the bases of the delta
+///   snapshots.
+pub fn read_snapshot_store<C, S>(
+	summary: &SnapshotSummary<AccountId>,
+	read_chunk: C,
+	get_summary: S,
+) -> Result<SnapshotStore, SnapshotError>
+where
+	C: Fn(&H128) -> Option<Vec<u8>>,
+	S: Fn(u64) -> Result<Option<SnapshotSummary<AccountId>>, SnapshotError>,
+{
+	let mut deltas = vec![];
+	let mut summary = summary.clone();
+	let mut store = loop {
+		match read_snapshot(&summary, &read_chunk)? {
+			Snapshot::Full(store) => break store,
+			Snapshot::Delta(delta) => {
+				// Bases are older snapshots, which also guarantees the chain ends
+				if delta.base == 0 || delta.base >= summary.snapshot_id {
+					return Err(SnapshotError::InvalidDeltaBase {
+						snapshot_id: summary.snapshot_id,
+						base: delta.base,
+					})
+				}
+				summary =
+					get_summary(delta.base)?.ok_or(SnapshotError::SummaryNotFound(delta.base))?;
+				deltas.push(delta);
+			},
+		}
+	};
+	for delta in deltas.into_iter().rev() {
+		delta.apply(&mut store);
+	}
+	Ok(store)
+}
+
+/// Snapshot rebuilt by a `SnapshotLoader`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Snapshot {
+	/// Full snapshot of the state.
+	Full(SnapshotStore),
+	/// Delta to replay on top of its base snapshot.
+	Delta(SnapshotDelta),
+}
+
+/// Format of the chunks loaded by a `SnapshotLoader`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SnapshotFormat {
+	Binary,
+	Delta(u64),
+	Json,
+}
+
+/// Rebuilds a snapshot chunk by chunk.
+///
+/// Binary chunks are decoded as soon as they are loaded while JSON chunks are buffered and parsed
+/// once the last one is loaded.
+#[derive(Debug, Default)]
+pub struct SnapshotLoader {
+	format: Option<SnapshotFormat>,
+	map: BTreeMap<[u8; 32], (Vec<u8>, i32)>,
+	removed: BTreeSet<[u8; 32]>,
+	json: Vec<u8>,
+}
+
+impl SnapshotLoader {
+	/// Loads the next snapshot chunk, the format of the snapshot is detected on the first one.
+	///
+	/// # Parameters
+	///
+	/// * `chunk`: Snapshot chunk, already verified against its hash.
+	pub fn load_chunk(&mut self, chunk: &[u8]) -> Result<(), SnapshotError> {
+		if self.format == Some(SnapshotFormat::Json) {
+			self.json.extend_from_slice(chunk);
+			return Ok(())
+		}
+		let (data, is_delta) =
+			if let Some(data) = chunk.strip_prefix(SNAPSHOT_CHUNK_MAGIC.as_slice()) {
+				(data, false)
+			} else if let Some(data) = chunk.strip_prefix(SNAPSHOT_DELTA_CHUNK_MAGIC.as_slice()) {
+				(data, true)
+			} else {
+				// JSON chunks are split at arbitrary offsets, their content is not checked here
+				return match self.format.get_or_insert(SnapshotFormat::Json) {
+					SnapshotFormat::Json => {
+						self.json.extend_from_slice(chunk);
+						Ok(())
+					},
+					_ => Err(SnapshotError::MixedFormats),
+				}
+			};
+		let mut data = match data.split_first() {
+			Some((&SNAPSHOT_FORMAT_VERSION, data)) => data,
+			Some((version, _)) => return Err(SnapshotError::UnsupportedVersion(*version)),
+			None => return Err(parity_scale_codec::Error::from("Missing version").into()),
+		};
+		let format = if is_delta {
+			SnapshotFormat::Delta(u64::decode(&mut data)?)
+		} else {
+			SnapshotFormat::Binary
+		};
+		let expected = *self.format.get_or_insert(format);
+		match (expected, format) {
+			(SnapshotFormat::Binary, SnapshotFormat::Binary) => {
+				let entries = Vec::<SnapshotEntry>::decode_all(&mut data)?;
+				self.map.extend(entries);
+			},
+			(SnapshotFormat::Delta(expected), SnapshotFormat::Delta(found)) => {
+				if expected != found {
+					return Err(SnapshotError::DeltaBaseMismatch { expected, found })
+				}
+				let (entries, removed) =
+					<(Vec<SnapshotEntry>, Vec<[u8; 32]>)>::decode_all(&mut data)?;
+				self.map.extend(entries);
+				self.removed.extend(removed);
+			},
+			_ => return Err(SnapshotError::MixedFormats),
+		}
+		Ok(())
+	}
+
+	/// Returns the snapshot rebuilt from the loaded chunks.
+	pub fn finish(self) -> Result<Snapshot, SnapshotError> {
+		match self.format {
+			Some(SnapshotFormat::Json) => Ok(Snapshot::Full(serde_json::from_slice(&self.json)?)),
+			Some(SnapshotFormat::Delta(base)) => Ok(Snapshot::Delta(SnapshotDelta {
+				base,
+				changed: self.map,
+				removed: self.removed,
+			})),
+			_ => Ok(Snapshot::Full(SnapshotStore { map: self.map })),
+		}
+	}
 }
 
 #[cfg(test)]
 mod tests {
-	use std::collections::HashMap;
+	use std::collections::{BTreeMap, HashMap};
 
 	use memory_db::{HashKey, MemoryDB};
 	use parity_scale_codec::{Decode, Encode};
+	use primitive_types::H128;
 	use reference_trie::{ExtensionLayout, RefHasher};
 	use rust_decimal::Decimal;
+	use sp_core::blake2_128;
 	use trie_db::{TrieDBMut, TrieDBMutBuilder, TrieMut};
 
-	use orderbook_primitives::types::AccountAsset;
-	use polkadex_primitives::AssetId;
+	use orderbook_primitives::{types::AccountAsset, SnapshotSummary};
+	use polkadex_primitives::{AccountId, AssetId};
 
 	use crate::{
-		snapshot::SnapshotStore, worker::*, worker_tests::get_alice_main_and_proxy_account,
+		snapshot::{
+			delta_snapshot_base, generate_balance_proof, is_full_snapshot, migrate_account_infos,
+			read_legacy_account_infos, read_snapshot_store, Snapshot, SnapshotDelta, SnapshotError,
+			SnapshotLoader, SnapshotStore, FULL_SNAPSHOT_INTERVAL, SNAPSHOT_CHUNK_MAGIC,
+			SNAPSHOT_CHUNK_SIZE, SNAPSHOT_DELTA_CHUNK_MAGIC,
+		},
+		worker::*,
+		worker_tests::get_alice_main_and_proxy_account,
 	};
 
 	#[test]
@@ -135,4 +669,243 @@ mod tests {
 
 		assert!(deposit(&mut trie, alice_main.clone(), asset_id.clone(), starting_balance).is_ok());
 	}
+
+	#[test]
+	pub fn test_binary_snapshot_is_loaded_chunk_by_chunk() {
+		// Every value takes a third of a chunk so the store spans several chunks
+		let value = vec![1u8; SNAPSHOT_CHUNK_SIZE / 3];
+		let store = SnapshotStore::new((0..5u8).map(|index| ([index; 32], (value.clone(), 1))));
+
+		let chunks = store.encode_chunks();
+		assert_eq!(chunks.len(), 3);
+		assert!(chunks.iter().all(|chunk| chunk.len() <= SNAPSHOT_CHUNK_SIZE));
+		assert!(chunks.iter().all(|chunk| chunk.starts_with(SNAPSHOT_CHUNK_MAGIC)));
+		assert_eq!(store.encode_chunks(), chunks);
+
+		let mut loader = SnapshotLoader::default();
+		for chunk in &chunks {
+			assert!(loader.load_chunk(chunk).is_ok());
+		}
+		assert_eq!(loader.finish().unwrap(), Snapshot::Full(store));
+
+		// An empty store is written as a single chunk
+		let empty = SnapshotStore::new(Vec::new());
+		let chunks = empty.encode_chunks();
+		assert_eq!(chunks.len(), 1);
+		let mut loader = SnapshotLoader::default();
+		assert!(loader.load_chunk(&chunks[0]).is_ok());
+		assert_eq!(loader.finish().unwrap(), Snapshot::Full(empty));
+	}
+
+	#[test]
+	pub fn test_json_snapshot_is_still_loaded() {
+		let store = SnapshotStore::new((0..3u8).map(|index| ([index; 32], (vec![index], 1))));
+		let data = serde_json::to_vec(&store).unwrap();
+
+		let mut loader = SnapshotLoader::default();
+		for chunk in data.chunks(7) {
+			assert!(loader.load_chunk(chunk).is_ok());
+		}
+		assert_eq!(loader.finish().unwrap(), Snapshot::Full(store));
+	}
+
+	#[test]
+	pub fn test_snapshot_chunk_with_unknown_version_is_rejected() {
+		let mut chunk = SnapshotStore::new(Vec::new()).encode_chunks().remove(0);
+		chunk[SNAPSHOT_CHUNK_MAGIC.len()] = 2;
+		let mut loader = SnapshotLoader::default();
+		assert!(matches!(loader.load_chunk(&chunk), Err(SnapshotError::UnsupportedVersion(2))));
+
+		let mut loader = SnapshotLoader::default();
+		let chunks = SnapshotStore::new(Vec::new()).encode_chunks();
+		assert!(loader.load_chunk(&chunks[0]).is_ok());
+		assert!(matches!(loader.load_chunk(b"{}"), Err(SnapshotError::MixedFormats)));
+	}
+
+	#[test]
+	pub fn test_delta_snapshot_is_replayed_on_its_base() {
+		let base = SnapshotStore::new((0..4u8).map(|index| ([index; 32], (vec![index], 1))));
+		// Entry 0 is kept, 1 is removed, 2 and 3 have new reference counts and 4 is added
+		let current = SnapshotStore::new(vec![
+			([0; 32], (vec![0], 1)),
+			([2; 32], (vec![2], 2)),
+			([3; 32], (vec![3], 0)),
+			([4; 32], (vec![4], 1)),
+		]);
+
+		let delta = SnapshotDelta::new(7, &base.reference_counts(), &current);
+		assert_eq!(delta.base, 7);
+		assert_eq!(
+			delta.changed.keys().copied().collect::<Vec<_>>(),
+			vec![[2; 32], [3; 32], [4; 32]]
+		);
+		assert_eq!(delta.removed.iter().copied().collect::<Vec<_>>(), vec![[1; 32]]);
+
+		let chunks = delta.encode_chunks();
+		assert!(chunks.iter().all(|chunk| chunk.starts_with(SNAPSHOT_DELTA_CHUNK_MAGIC)));
+		assert!(chunks.iter().all(|chunk| delta_snapshot_base(chunk) == Some(7)));
+		assert_eq!(delta.encode_chunks(), chunks);
+
+		let mut loader = SnapshotLoader::default();
+		for chunk in &chunks {
+			assert!(loader.load_chunk(chunk).is_ok());
+		}
+		let loaded = match loader.finish().unwrap() {
+			Snapshot::Delta(loaded) => loaded,
+			Snapshot::Full(_) => panic!("Expected a delta snapshot"),
+		};
+		assert_eq!(loaded, delta);
+
+		let mut restored = base.clone();
+		loaded.apply(&mut restored);
+		assert_eq!(restored, current);
+
+		// Full snapshots have no base
+		let chunks = base.encode_chunks();
+		assert_eq!(delta_snapshot_base(&chunks[0]), None);
+
+		// An empty delta still records its base
+		let chunks = SnapshotDelta::new(7, &base.reference_counts(), &base).encode_chunks();
+		assert_eq!(chunks.len(), 1);
+		assert_eq!(delta_snapshot_base(&chunks[0]), Some(7));
+	}
+
+	#[test]
+	pub fn test_snapshot_store_is_read_through_its_delta_bases() {
+		let first = SnapshotStore::new(vec![([0; 32], (vec![0], 1))]);
+		let second = SnapshotStore::new(vec![([0; 32], (vec![0], 1)), ([1; 32], (vec![1], 1))]);
+		let third = SnapshotStore::new(vec![([1; 32], (vec![1], 2))]);
+		let mut chunks = BTreeMap::new();
+		let mut summaries = BTreeMap::new();
+		for (snapshot_id, snapshot_chunks) in [
+			(1, first.encode_chunks()),
+			(2, SnapshotDelta::new(1, &first.reference_counts(), &second).encode_chunks()),
+			(3, SnapshotDelta::new(2, &second.reference_counts(), &third).encode_chunks()),
+		] {
+			let mut summary = SnapshotSummary::<AccountId>::default();
+			summary.snapshot_id = snapshot_id;
+			for chunk in snapshot_chunks {
+				let chunk_hash = H128::from(blake2_128(&chunk));
+				chunks.insert(chunk_hash, chunk);
+				summary.state_chunk_hashes.push(chunk_hash);
+			}
+			summaries.insert(snapshot_id, summary);
+		}
+		let read_chunk = |chunk_hash: &H128| chunks.get(chunk_hash).cloned();
+		let get_summary = |snapshot_id: u64| -> Result<_, SnapshotError> {
+			Ok(summaries.get(&snapshot_id).cloned())
+		};
+
+		assert_eq!(read_snapshot_store(&summaries[&3], read_chunk, get_summary).unwrap(), third);
+		assert_eq!(read_snapshot_store(&summaries[&2], read_chunk, get_summary).unwrap(), second);
+
+		// Every chunk of the chain is required
+		let missing_base = |snapshot_id: u64| -> Result<_, SnapshotError> {
+			Ok((snapshot_id != 1).then(|| summaries[&snapshot_id].clone()))
+		};
+		assert!(matches!(
+			read_snapshot_store(&summaries[&3], read_chunk, missing_base),
+			Err(SnapshotError::SummaryNotFound(1))
+		));
+		let mut corrupted = summaries[&1].clone();
+		corrupted.state_chunk_hashes[0] = H128::zero();
+		assert!(matches!(
+			read_snapshot_store(&corrupted, read_chunk, get_summary),
+			Err(SnapshotError::ChunkNotFound(_))
+		));
+	}
+
+	#[test]
+	pub fn test_snapshot_kind_only_depends_on_its_id() {
+		assert!(is_full_snapshot(1));
+		assert!((2..FULL_SNAPSHOT_INTERVAL).all(|snapshot_id| !is_full_snapshot(snapshot_id)));
+		assert!(is_full_snapshot(FULL_SNAPSHOT_INTERVAL));
+		assert!(!is_full_snapshot(FULL_SNAPSHOT_INTERVAL + 1));
+	}
+
+	#[test]
+	pub fn test_delta_snapshot_chunks_must_share_their_base() {
+		let base = SnapshotStore::new(vec![([0; 32], (vec![0], 1))]);
+		let current = SnapshotStore::new(vec![([1; 32], (vec![1], 1))]);
+		let first = SnapshotDelta::new(1, &base.reference_counts(), &current).encode_chunks();
+		let second = SnapshotDelta::new(2, &base.reference_counts(), &current).encode_chunks();
+
+		let mut loader = SnapshotLoader::default();
+		assert!(loader.load_chunk(&first[0]).is_ok());
+		assert!(matches!(
+			loader.load_chunk(&second[0]),
+			Err(SnapshotError::DeltaBaseMismatch { expected: 1, found: 2 })
+		));
+
+		let mut loader = SnapshotLoader::default();
+		assert!(loader.load_chunk(&first[0]).is_ok());
+		assert!(matches!(
+			loader.load_chunk(&base.encode_chunks()[0]),
+			Err(SnapshotError::MixedFormats)
+		));
+	}
+
+	#[test]
+	pub fn test_balance_proof_of_snapshot_is_verified() {
+		let mut state_root = [0u8; 32];
+		let mut memory_db: MemoryDB<RefHasher, HashKey<RefHasher>, Vec<u8>> = Default::default();
+		let (alice_main, alice_proxy) = get_alice_main_and_proxy_account();
+		let asset_id = AssetId::Polkadex;
+		{
+			let mut trie: TrieDBMut<ExtensionLayout> =
+				TrieDBMutBuilder::new(&mut memory_db, &mut state_root).build();
+			assert!(register_main(&mut trie, alice_main.clone(), alice_proxy).is_ok());
+			assert!(deposit(&mut trie, alice_main.clone(), asset_id, Decimal::new(10, 0)).is_ok());
+			trie.commit();
+		}
+
+		// The proof is generated from the memory db restored from the snapshot chunks
+		let mut loader = SnapshotLoader::default();
+		for chunk in SnapshotStore::new(memory_db.data().clone()).encode_chunks() {
+			assert!(loader.load_chunk(&chunk).is_ok());
+		}
+		let store = match loader.finish().unwrap() {
+			Snapshot::Full(store) => store,
+			Snapshot::Delta(_) => panic!("Expected a full snapshot"),
+		};
+		let mut restored: MemoryDB<RefHasher, HashKey<RefHasher>, Vec<u8>> = Default::default();
+		restored.load_from(store.convert_to_hashmap());
+
+		let account_asset = AccountAsset { main: alice_main, asset: asset_id };
+		let proof = generate_balance_proof(&restored, &state_root, 1, account_asset).unwrap();
+		assert_eq!(proof.free, Some(Decimal::new(10, 0)));
+		assert_eq!(proof.reserved, None);
+		assert!(proof.verify::<ExtensionLayout>(&state_root));
+		assert!(!proof.verify::<ExtensionLayout>(&[1; 32]));
+	}
+
+	#[test]
+	pub fn test_legacy_account_infos_are_read_for_migration() {
+		let mut state_root = [0u8; 32];
+		let mut memory_db: MemoryDB<RefHasher, HashKey<RefHasher>, Vec<u8>> = Default::default();
+		assert!(read_legacy_account_infos(&memory_db, &state_root).unwrap().is_empty());
+		let (alice_main, alice_proxy) = get_alice_main_and_proxy_account();
+		let bob_main = AccountId::new([2; 32]);
+		let bob_proxy = AccountId::new([3; 32]);
+		{
+			let mut trie: TrieDBMut<ExtensionLayout> =
+				TrieDBMutBuilder::new(&mut memory_db, &mut state_root).build();
+			assert!(register_main(&mut trie, alice_main.clone(), alice_proxy).is_ok());
+			assert!(deposit(&mut trie, alice_main, AssetId::Polkadex, Decimal::new(10, 0)).is_ok());
+			// Accounts stored before the permissions were introduced only encoded their proxies
+			trie.insert(&bob_main.encode(), &vec![bob_proxy.clone()].encode()).unwrap();
+			trie.commit();
+		}
+
+		let accounts = read_legacy_account_infos(&memory_db, &state_root).unwrap();
+		assert_eq!(accounts.len(), 1);
+		assert_eq!(accounts[0].0, bob_main.encode());
+		assert_eq!(accounts[0].1.proxies, vec![bob_proxy]);
+
+		let legacy_root = state_root;
+		assert_eq!(migrate_account_infos(&mut memory_db, &mut state_root).unwrap(), 1);
+		assert_ne!(state_root, legacy_root);
+		assert!(read_legacy_account_infos(&memory_db, &state_root).unwrap().is_empty());
+		assert_eq!(migrate_account_infos(&mut memory_db, &mut state_root).unwrap(), 0);
+	}
 }
@@ -16,8 +16,9 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::tests::{
-	generate_and_finalize_blocks, initialize_orderbook, make_ob_ids, ObTestnet, TestApi,
+use crate::{
+	notification::{ObActionStream, ObSnapshotStream},
+	tests::{generate_and_finalize_blocks, initialize_orderbook, make_ob_ids, ObTestnet, TestApi},
 };
 use memory_db::MemoryDB;
 use orderbook_primitives::{
@@ -105,6 +106,9 @@ pub async fn test_orderbook_gossip() {
 		memory_db: memory_db.clone(),
 		working_state_root: working_state_root.clone(),
 		metrics: None,
+		enable_matching_engine: false,
+		action_sender: ObActionStream::channel().0,
+		snapshot_sender: ObSnapshotStream::channel().0,
 	};
 	use futures::StreamExt;
 	use sc_client_api::BlockchainEvents;
@@ -118,7 +122,7 @@ pub async fn test_orderbook_gossip() {
 	let mut gossips = worker.get_want_worker_nonce_messages(&1, &2);
 	assert_eq!(gossips.len(), 0);
 
-	worker.orderbook_operator_public_key = Some(orderbook_operator.public());
+	worker.orderbook_operator_keys.write().update(vec![orderbook_operator.public()]);
 	println!("Sending some worker messages...");
 	//check how gossip reacts to want massages when it does have all of it
 	for blk in 1..5 {
@@ -19,6 +19,7 @@
 mod gosssip;
 pub mod rpc;
 pub mod sync;
+use crate::notification::{ObActionStream, ObSnapshotStream};
 use futures::{channel::mpsc::UnboundedSender, stream::FuturesUnordered, StreamExt};
 use memory_db::{HashKey, MemoryDB};
 use orderbook_primitives::{
@@ -87,6 +88,7 @@ impl TestApi {
 				withdrawals: vec![],
 				aggregate_signature: None,
 				state_version: 0,
+				trading_fees: vec![],
 			})
 			.clone()
 	}
@@ -146,6 +148,10 @@ impl TestApi {
 		self.operator_key
 	}
 
+	pub fn get_orderbook_operator_keys(&self) -> Vec<Public> {
+		self.operator_key.into_iter().collect()
+	}
+
 	pub fn get_last_accepted_worker_nonce(&self) -> u64 {
 		self.snapshots
 			.read()
@@ -162,6 +168,7 @@ impl TestApi {
 				withdrawals: vec![],
 				aggregate_signature: None,
 				state_version: 0,
+				trading_fees: vec![],
 			})
 			.worker_nonce
 	}
@@ -225,6 +232,11 @@ sp_api::mock_impl_runtime_apis! {
 			self.inner.get_orderbook_opearator_key()
 		}
 
+		/// Returns Public Keys the Orderbook Operator messages are accepted from
+		fn get_orderbook_operator_keys() -> Vec<sp_core::ecdsa::Public>{
+			self.inner.get_orderbook_operator_keys()
+		}
+
 
 		/// Returns last processed stid from last snapshot
 		fn get_last_accepted_worker_nonce() -> u64{
@@ -420,6 +432,9 @@ where
 			marker: Default::default(),
 			memory_db: net.peers[peer_id].data.memory_db.clone(),
 			working_state_root: net.peers[peer_id].data.working_state_root.clone(),
+			enable_matching_engine: false,
+			action_sender: ObActionStream::channel().0,
+			snapshot_sender: ObSnapshotStream::channel().0,
 		};
 
 		let gadget = if is_validator {
@@ -16,16 +16,21 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::tests::{generate_and_finalize_blocks, make_ob_ids, ObTestnet, TestApi};
+use crate::{
+	notification::{ObActionStream, ObSnapshotStream},
+	tests::{generate_and_finalize_blocks, make_ob_ids, ObTestnet, TestApi},
+};
 use futures::{future::BoxFuture, StreamExt};
 use memory_db::MemoryDB;
 use orderbook_primitives::{
 	crypto::AuthorityId,
-	types::{AccountAsset, ObMessage, UserActions},
+	types::{AccountAsset, AccountInfo, ObMessage, UserActions},
 };
 use orderbook_rpc::OrderbookRpc;
+use parity_scale_codec::Encode;
 use parking_lot::RwLock;
 use polkadex_primitives::{ingress::IngressMessages, AssetId};
+use reference_trie::ExtensionLayout;
 use rust_decimal::{prelude::FromPrimitive, Decimal};
 use sc_client_api::BlockchainEvents;
 use sc_keystore::LocalKeystore;
@@ -34,6 +39,7 @@ use sp_core::Pair;
 use sp_keyring::AccountKeyring;
 use sp_keystore::CryptoStore;
 use std::{collections::HashMap, sync::Arc};
+use trie_db::proof::verify_proof;
 
 #[tokio::test]
 pub async fn test_orderbook_rpc() {
@@ -112,6 +118,9 @@ pub async fn test_orderbook_rpc() {
 		.unwrap();
 
 	let sync_oracle = testnet.peers[peer_id].network_service().clone();
+	let (action_sender, action_stream) = ObActionStream::channel();
+	let (snapshot_sender, snapshot_stream) = ObSnapshotStream::channel();
+	let mut actions = action_stream.subscribe(100_000);
 
 	let deps = orderbook_rpc::OrderbookDeps {
 		rpc_channel: sender,
@@ -120,6 +129,9 @@ pub async fn test_orderbook_rpc() {
 		backend: testnet.peers[peer_id].client().as_backend().clone(),
 		client: testnet.peers[peer_id].client().as_client().clone(),
 		runtime: runtime.clone(),
+		action_stream,
+		snapshot_stream,
+		subscription_executor: Arc::new(DummyTaskExecutor),
 	};
 	let rpc_handle = OrderbookRpc::new(deps);
 	let worker_params = crate::worker::WorkerParams {
@@ -136,6 +148,9 @@ pub async fn test_orderbook_rpc() {
 		memory_db: testnet.peers[peer_id].data.memory_db.clone(),
 		working_state_root: testnet.peers[peer_id].data.working_state_root.clone(),
 		keystore,
+		enable_matching_engine: false,
+		action_sender,
+		snapshot_sender,
 	};
 
 	let mut finality_stream_future = testnet.peers[peer_id]
@@ -165,6 +180,12 @@ pub async fn test_orderbook_rpc() {
 
 	worker.process_new_user_action(&message).await.unwrap();
 
+	// Subscribers are notified about the applied action
+	let notification = actions.next().await.unwrap();
+	assert_eq!(notification.worker_nonce, 1);
+	assert_eq!(notification.action.stid, 10);
+	assert_eq!(notification.state_root.0, *testnet.peers[peer_id].data.working_state_root.read());
+
 	let result: String = rpc_handle.get_orderbook_recovery_state_inner().await.unwrap();
 
 	let offchain_state: orderbook_primitives::recovery::ObRecoveryState =
@@ -187,6 +208,39 @@ pub async fn test_orderbook_rpc() {
 			.unwrap(),
 		Decimal::from_f64(1.456).unwrap()
 	);
+
+	// Query the working state directly
+	let state_root = *testnet.peers[peer_id].data.working_state_root.read();
+	let account_asset = AccountAsset { main: main.to_account_id(), asset: AssetId::Polkadex };
+	let balance = rpc_handle.get_balance_inner(main.to_account_id(), AssetId::Polkadex).unwrap();
+	assert_eq!(balance.free, Decimal::from_f64(1.456).unwrap());
+	assert_eq!(balance.reserved, Decimal::ZERO);
+	assert_eq!(balance.state_root.0, state_root);
+	let proof: Vec<Vec<u8>> = balance.proof.into_iter().map(|node| node.0).collect();
+	let items = vec![
+		(account_asset.encode(), Some(balance.free.encode())),
+		(account_asset.reserved_balance_key(), None),
+	];
+	assert!(verify_proof::<ExtensionLayout, _, _, _>(&state_root, &proof, &items).is_ok());
+	// A tampered balance is rejected
+	let items = vec![
+		(account_asset.encode(), Some(Decimal::from(2).encode())),
+		(account_asset.reserved_balance_key(), None),
+	];
+	assert!(verify_proof::<ExtensionLayout, _, _, _>(&state_root, &proof, &items).is_err());
+
+	let account_info = rpc_handle.get_account_info_inner(main.to_account_id()).unwrap();
+	let proxies = account_info.account.as_ref().map(|account| account.proxies.clone());
+	assert_eq!(proxies, Some(vec![proxy1.to_account_id(), proxy2.to_account_id()]));
+	let proof: Vec<Vec<u8>> = account_info.proof.into_iter().map(|node| node.0).collect();
+	let items =
+		vec![(main.to_account_id().encode(), Some(AccountInfo::new(proxies.unwrap()).encode()))];
+	assert!(verify_proof::<ExtensionLayout, _, _, _>(&state_root, &proof, &items).is_ok());
+
+	// Unregistered accounts are proven absent
+	let account_info =
+		rpc_handle.get_account_info_inner(AccountKeyring::Eve.to_account_id()).unwrap();
+	assert!(account_info.account.is_none());
 }
 
 #[derive(Clone)]
@@ -16,8 +16,9 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::tests::{
-	generate_and_finalize_blocks, initialize_orderbook, make_ob_ids, ObTestnet, TestApi,
+use crate::{
+	notification::{ObActionStream, ObSnapshotStream},
+	tests::{generate_and_finalize_blocks, initialize_orderbook, make_ob_ids, ObTestnet, TestApi},
 };
 use futures::SinkExt;
 use memory_db::MemoryDB;
@@ -159,6 +160,9 @@ pub async fn test_orderbook_snapshot() {
 		marker: Default::default(),
 		memory_db: memory_db.clone(),
 		working_state_root: working_state_root.clone(),
+		enable_matching_engine: false,
+		action_sender: ObActionStream::channel().0,
+		snapshot_sender: ObSnapshotStream::channel().0,
 	};
 
 	let gadget = crate::start_orderbook_gadget::<_, _, _, _, _>(ob_params)
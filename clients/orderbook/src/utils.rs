@@ -21,8 +21,12 @@
 use crate::error::Error;
 use orderbook_primitives::types::AccountAsset;
 use parity_scale_codec::{Decode, Encode};
+use polkadex_primitives::UNIT_BALANCE;
 use reference_trie::ExtensionLayout;
-use rust_decimal::Decimal;
+use rust_decimal::{
+	prelude::{FromPrimitive, Zero},
+	Decimal,
+};
 use sp_tracing::info;
 use trie_db::{TrieDBMut, TrieMut};
 
@@ -84,3 +88,74 @@ pub fn sub_balance(
 	trie.insert(&account_asset.encode(), &account_balance.encode())?;
 	Ok(())
 }
+
+/// Returns the reserved balance of the account asset, zero if nothing was reserved.
+///
+/// # Parameters
+///
+/// * `trie`: Trie db to read from.
+/// * `account_asset`: Account asset to look for in the db.
+pub fn get_reserved_balance(
+	trie: &TrieDBMut<ExtensionLayout>,
+	account_asset: &AccountAsset,
+) -> Result<Decimal, Error> {
+	match trie.get(&account_asset.reserved_balance_key())? {
+		Some(data) => Ok(Decimal::decode(&mut &data[..])?),
+		None => Ok(Decimal::zero()),
+	}
+}
+
+/// Moves the amount from the free balance of the account asset to its reserved balance.
+///
+/// # Parameters
+///
+/// * `trie`: Trie db to update.
+/// * `account_asset`: Account asset to reserve the balance of.
+/// * `balance`: Amount to be reserved.
+pub fn reserve_balance(
+	trie: &mut TrieDBMut<ExtensionLayout>,
+	account_asset: AccountAsset,
+	balance: Decimal,
+) -> Result<(), Error> {
+	info!(target:"orderbook","📒 Reserving balance of account");
+	let reserved = get_reserved_balance(trie, &account_asset)?.saturating_add(balance);
+	sub_balance(trie, account_asset.clone(), balance)?;
+	trie.insert(&account_asset.reserved_balance_key(), &reserved.encode())?;
+	Ok(())
+}
+
+/// Moves the amount from the reserved balance of the account asset back to its free balance.
+///
+/// If the reserved balance is lower than the amount `InsufficientReservedBalance` error will be
+/// returned.
+///
+/// # Parameters
+///
+/// * `trie`: Trie db to update.
+/// * `account_asset`: Account asset to unreserve the balance of.
+/// * `balance`: Amount to be unreserved.
+pub fn unreserve_balance(
+	trie: &mut TrieDBMut<ExtensionLayout>,
+	account_asset: AccountAsset,
+	balance: Decimal,
+) -> Result<(), Error> {
+	info!(target:"orderbook","📒 Unreserving balance of account");
+	let reserved = get_reserved_balance(trie, &account_asset)?;
+	if reserved < balance {
+		return Err(Error::InsufficientReservedBalance)
+	}
+	trie.insert(&account_asset.reserved_balance_key(), &reserved.saturating_sub(balance).encode())?;
+	add_balance(trie, account_asset, balance)
+}
+
+/// Converts an on-chain balance expressed in the smallest units into the `Decimal` representation
+/// used by the orderbook state.
+///
+/// # Parameters
+///
+/// * `balance`: On-chain balance to convert.
+pub fn convert_balance_to_decimal(balance: u128) -> Result<Decimal, Error> {
+	Decimal::from_u128(balance)
+		.and_then(|balance| balance.checked_div(Decimal::from(UNIT_BALANCE)))
+		.ok_or(Error::BalanceConversionFailed(balance))
+}
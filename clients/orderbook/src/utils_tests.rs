@@ -18,7 +18,7 @@
 
 use crate::{
 	error::Error,
-	utils::{add_balance, sub_balance},
+	utils::{add_balance, convert_balance_to_decimal, sub_balance},
 };
 use memory_db::{HashKey, MemoryDB};
 use orderbook_primitives::types::AccountAsset;
@@ -140,6 +140,21 @@ fn test_sub_balance_returns_insufficient_balance() {
 	);
 }
 
+#[test]
+fn test_convert_balance_to_decimal_scales_by_unit_balance() {
+	assert_eq!(convert_balance_to_decimal(1_500_000_000_000u128), Ok(Decimal::new(15, 1)));
+	assert_eq!(convert_balance_to_decimal(1u128), Ok(Decimal::new(1, 12)));
+	assert_eq!(convert_balance_to_decimal(0u128), Ok(Decimal::from(0)));
+}
+
+#[test]
+fn test_convert_balance_to_decimal_returns_error_on_overflow() {
+	assert_eq!(
+		convert_balance_to_decimal(u128::MAX),
+		Err(Error::BalanceConversionFailed(u128::MAX))
+	);
+}
+
 fn get_trie_db_client<'a>(
 	memory_db: &'a mut MemoryDB<RefHasher, HashKey<RefHasher>, Vec<u8>>,
 	working_state_root: &'a mut [u8; 32],
@@ -19,11 +19,11 @@
 //! Worker which manages/processes Orderbook client requests.
 
 use std::{
-	collections::{BTreeMap, BTreeSet},
+	collections::{btree_map::Entry, BTreeMap, BTreeSet, VecDeque},
 	marker::PhantomData,
 	ops::Div,
 	sync::Arc,
-	time::Duration,
+	time::{Duration, Instant},
 };
 
 use chrono::Utc;
@@ -33,16 +33,18 @@ use memory_db::{HashKey, MemoryDB};
 use orderbook_primitives::{
 	crypto::AuthorityId,
 	types::{
-		AccountAsset, AccountInfo, GossipMessage, ObMessage, StateSyncStatus, Trade, TradingPair,
-		UserActions, WithdrawalRequest,
+		AccountAsset, AccountInfo, GossipMessage, ObMessage, Order, OrderId, OrderSide,
+		OrderStatus, OrderType, StateSyncStatus, Trade, TradingPair, UserActions,
+		WithdrawalRequest,
 	},
-	ObApi, SnapshotSummary, ValidatorSet,
+	Fees, ObApi, SnapshotSummary, ValidatorSet,
 };
 use parity_scale_codec::{Codec, Decode, Encode};
 use parking_lot::RwLock;
 use polkadex_primitives::{
-	ingress::IngressMessages,
-	ocex::TradingPairConfig,
+	fees::FeeConfig,
+	ingress::{HandleBalance, IngressMessages},
+	ocex::{ProxyPermissions, TradingPairConfig},
 	utils::{prepare_bitmap, return_set_bits, set_bit_field},
 	withdrawal::Withdrawal,
 	AccountId, AssetId, BlockNumber,
@@ -52,9 +54,9 @@ use reference_trie::{ExtensionLayout, RefHasher};
 use rust_decimal::Decimal;
 use sc_client_api::{Backend, FinalityNotification};
 use sc_keystore::LocalKeystore;
-use sc_network::PeerId;
+use sc_network::{PeerId, ReputationChange};
 use sc_network_gossip::{GossipEngine, Network as GossipNetwork};
-use sp_api::ProvideRuntimeApi;
+use sp_api::{ApiExt, ProvideRuntimeApi};
 use sp_arithmetic::traits::SaturatedConversion;
 use sp_consensus::SyncOracle;
 use sp_core::{blake2_128, offchain::OffchainStorage};
@@ -66,11 +68,19 @@ use trie_db::{TrieDBMut, TrieDBMutBuilder, TrieMut};
 
 use crate::{
 	error::Error,
-	gossip::{topic, GossipValidator},
+	gossip::{cost, topic, GossipValidator, OperatorKeys, PeerReport, SignatureCheck},
 	keystore::OrderbookKeyStore,
-	metric_add, metric_inc, metric_set,
+	matching::{is_same_trade, MatchingEngine},
+	message_log::MessageLog,
+	metric_add, metric_inc, metric_observe, metric_set,
 	metrics::Metrics,
-	snapshot::SnapshotStore,
+	notification::{
+		ObActionNotification, ObActionSender, ObSnapshotNotification, ObSnapshotSender,
+	},
+	snapshot::{
+		delta_snapshot_base, is_full_snapshot, migrate_account_infos, read_open_orders,
+		read_snapshot_store, SnapshotDelta, SnapshotError, SnapshotStore,
+	},
 	utils::*,
 	Client, DbRef,
 };
@@ -84,6 +94,15 @@ pub const ORDERBOOK_SNAPSHOT_SUMMARY_PREFIX: &[u8; 24] = b"OrderbookSnapshotSumm
 /// Orderbook state chunk prefix used as an offchain storage item prefix.
 pub const ORDERBOOK_STATE_CHUNK_PREFIX: &[u8; 27] = b"OrderbookSnapshotStateChunk";
 
+/// Orderbook fee config prefix used as a trie key prefix of the per account fee configs.
+pub const ORDERBOOK_FEE_CONFIG_PREFIX: &[u8; 18] = b"OrderbookFeeConfig";
+
+/// Trie key under which the exchange operational state is stored.
+pub const ORDERBOOK_EXCHANGE_STATE_KEY: &[u8; 22] = b"OrderbookExchangeState";
+
+/// Trie key under which the trading fees collected since the last snapshot are stored.
+pub const ORDERBOOK_COLLECTED_FEES_KEY: &[u8; 22] = b"OrderbookCollectedFees";
+
 /// Definition of the worker parameters required for the worker initialization.
 pub(crate) struct WorkerParams<B: Block, BE, C, SO, N, R> {
 	/// Orderbook client.
@@ -111,10 +130,16 @@ pub(crate) struct WorkerParams<B: Block, BE, C, SO, N, R> {
 	pub memory_db: DbRef,
 	/// Working state root.
 	pub working_state_root: Arc<RwLock<[u8; 32]>>,
+	/// Enables the matching engine verifying the trades submitted by the operator.
+	pub enable_matching_engine: bool,
+	/// Notifies the RPC subscribers about applied actions.
+	pub action_sender: ObActionSender,
+	/// Notifies the RPC subscribers about generated snapshots.
+	pub snapshot_sender: ObSnapshotSender,
 }
 
 /// An Orderbook worker plays the Orderbook protocol.
-pub(crate) struct ObWorker<B: Block, BE, C, SO, N, R> {
+pub(crate) struct ObWorker<B: Block, BE: Backend<B>, C, SO, N, R> {
 	client: Arc<C>,
 	backend: Arc<BE>,
 	runtime: Arc<R>,
@@ -126,10 +151,14 @@ pub(crate) struct ObWorker<B: Block, BE, C, SO, N, R> {
 	gossip_engine: GossipEngine<B>,
 	/// Last processed snapshot summary.
 	pub last_snapshot: Arc<RwLock<SnapshotSummary<AccountId>>>,
+	/// Instant the last snapshot was accepted by the runtime, as seen by this node.
+	last_snapshot_accepted_at: Instant,
 	/// Working state root.
 	pub working_state_root: Arc<RwLock<[u8; 32]>>,
 	/// Known state ids.
 	known_messages: BTreeMap<u64, ObMessage>,
+	/// Accepted messages persisted until they are included in an accepted snapshot.
+	message_log: MessageLog<BE::OffchainStorage>,
 	pending_withdrawals: Vec<Withdrawal<AccountId>>,
 	/// Orderbook client metrics.
 	metrics: Option<Metrics>,
@@ -140,7 +169,9 @@ pub(crate) struct ObWorker<B: Block, BE, C, SO, N, R> {
 	/// Last finalized block.
 	last_finalized_block: BlockNumber,
 	state_is_syncing: bool,
-	// (snapshot id, chunk index) => status of sync
+	/// Snapshot whose chunks are being synced, the base of a delta snapshot is synced after it.
+	sync_snapshot_id: u64,
+	// chunk index => status of sync
 	sync_state_map: BTreeMap<usize, StateSyncStatus>,
 	/// Last block at which snapshot was generated.
 	last_block_snapshot_generated: Arc<RwLock<BlockNumber>>,
@@ -149,12 +180,28 @@ pub(crate) struct ObWorker<B: Block, BE, C, SO, N, R> {
 	latest_state_change_id: u64,
 	/// Map of trading pair configs.
 	trading_pair_configs: BTreeMap<TradingPair, TradingPairConfig>,
-	pub(crate) orderbook_operator_public_key: Option<sp_core::ecdsa::Public>,
+	/// Orderbook operator keys shared with the gossip validator.
+	pub(crate) orderbook_operator_keys: Arc<RwLock<OperatorKeys>>,
 	/// Our last snapshot waiting for approval.
 	pending_snapshot_summary: Option<SnapshotSummary<AccountId>>,
 	last_processed_block_in_offchain_state: BlockNumber,
 	// Version of current state
 	state_version: Arc<RwLock<u16>>,
+	/// Exchange operational state, trades are rejected while it is false.
+	exchange_operational: bool,
+	/// Matching engine re-deriving the operator trades, if enabled.
+	matching_engine: Option<MatchingEngine>,
+	/// Trades produced by the matching engine not yet submitted by the operator.
+	expected_trades: VecDeque<Trade>,
+	/// Chunk hashes and reference counts of the last stored or loaded snapshot, base of the next
+	/// delta snapshot.
+	snapshot_cache: Option<(Vec<H128>, BTreeMap<[u8; 32], i32>)>,
+	/// Notifies the RPC subscribers about applied actions.
+	action_sender: ObActionSender,
+	/// Notifies the RPC subscribers about generated snapshots.
+	snapshot_sender: ObSnapshotSender,
+	/// Misbehaving peers reported by the gossip validator.
+	gossip_reports: UnboundedReceiver<PeerReport>,
 }
 
 impl<B, BE, C, SO, N, R> ObWorker<B, BE, C, SO, N, R>
@@ -192,6 +239,9 @@ where
 			_marker,
 			memory_db,
 			working_state_root,
+			enable_matching_engine,
+			action_sender,
+			snapshot_sender,
 		} = worker_params;
 		// Shared data
 		let last_snapshot = Arc::new(RwLock::new(SnapshotSummary::default()));
@@ -209,9 +259,12 @@ where
 			},
 		};
 		let latest_worker_nonce = Arc::new(RwLock::new(nonce));
+		let message_log = MessageLog::new(offchain_storage);
 		let network = Arc::new(network);
 		let fullnodes = Arc::new(RwLock::new(BTreeSet::new()));
 		let state_version = Arc::new(RwLock::new(0));
+		let orderbook_operator_keys = Arc::new(RwLock::new(OperatorKeys::default()));
+		let (report_sender, gossip_reports) = futures::channel::mpsc::unbounded();
 		// Gossip Validator
 		let gossip_validator = Arc::new(GossipValidator::new(
 			latest_worker_nonce.clone(),
@@ -219,6 +272,8 @@ where
 			is_validator,
 			last_snapshot.clone(),
 			state_version.clone(),
+			orderbook_operator_keys.clone(),
+			report_sender,
 		));
 		let gossip_engine =
 			GossipEngine::new(network.clone(), protocol_name, gossip_validator, None);
@@ -239,20 +294,30 @@ where
 			state_is_syncing: false,
 			metrics,
 			last_snapshot,
+			last_snapshot_accepted_at: Instant::now(),
 			_marker: Default::default(),
 			known_messages: Default::default(),
+			message_log,
 			working_state_root,
 			pending_withdrawals: vec![],
 			last_finalized_block: 0,
+			sync_snapshot_id: 0,
 			sync_state_map: Default::default(),
 			last_block_snapshot_generated: Arc::new(RwLock::new(0)),
 			latest_worker_nonce,
 			latest_state_change_id: 0,
 			trading_pair_configs: Default::default(),
-			orderbook_operator_public_key: None,
+			orderbook_operator_keys,
 			pending_snapshot_summary: None,
 			last_processed_block_in_offchain_state: 0,
 			state_version,
+			exchange_operational: true,
+			matching_engine: enable_matching_engine.then(MatchingEngine::default),
+			expected_trades: Default::default(),
+			snapshot_cache: None,
+			action_sender,
+			snapshot_sender,
+			gossip_reports,
 		}
 	}
 
@@ -309,28 +374,16 @@ where
 		let mut memory_db = self.memory_db.write();
 		let mut working_state_root = self.working_state_root.write();
 		let mut trie = Self::get_trie(&mut memory_db, &mut working_state_root);
-
-		// Get main account
-		let proxies = trie.get(&withdraw.main.encode())?.ok_or(Error::MainAccountNotFound)?;
-
-		let account_info = AccountInfo::decode(&mut &proxies[..])?;
-		// Check proxy registration
-		if !account_info.proxies.contains(&withdraw.proxy) {
-			return Err(Error::ProxyNotAssociatedWithMain)
-		}
-		// Verify signature
-		if !withdraw.verify() {
-			return Err(Error::WithdrawSignatureCheckFailed)
-		}
-		// Deduct balance
-		sub_balance(&mut trie, withdraw.account_asset(), withdraw.amount()?)?;
+		let withdrawal =
+			process_withdrawal_request(&mut trie, withdraw, worker_nonce, state_change_id)?;
 		// Commit the trie
 		trie.commit();
 		drop(trie);
 		drop(memory_db);
 		drop(working_state_root);
 		// Queue withdrawal
-		self.pending_withdrawals.push(withdraw.convert(state_change_id, worker_nonce)?);
+		self.pending_withdrawals.push(withdrawal);
+		metric_set!(self, ob_pending_withdrawals, self.pending_withdrawals.len());
 		info!(target:"orderbook","📒 Queued withdrawal to pending list"); // Check if snapshot should be generated or not
 		if self.should_generate_snapshot() {
 			if let Err(err) = self.snapshot(worker_nonce, state_change_id) {
@@ -346,7 +399,14 @@ where
 	/// # Parameters
 	///
 	/// * `num`: Number of importing block.
-	pub fn handle_blk_import(&mut self, num: BlockNumber) -> Result<(), Error> {
+	/// * `worker_nonce`: Worker nonce of the block import message.
+	/// * `stid`: State change id of the block import message.
+	pub fn handle_blk_import(
+		&mut self,
+		num: BlockNumber,
+		worker_nonce: u64,
+		stid: u64,
+	) -> Result<(), Error> {
 		info!("📒Handling block import: {:?}", num);
 		if num.is_zero() {
 			return Ok(())
@@ -384,31 +444,20 @@ where
 		{
 			let mut trie = Self::get_trie(&mut memory_db, &mut working_state_root);
 			// 3. Execute RegisterMain, AddProxy, RemoveProxy, Deposit messages
-			for message in messages {
-				match message {
-					IngressMessages::RegisterUser(main, proxy) =>
-						register_main(&mut trie, main, proxy)?,
-					IngressMessages::Deposit(main, asset, amt) =>
-						deposit(&mut trie, main, asset, amt)?,
-					IngressMessages::AddProxy(main, proxy) => add_proxy(&mut trie, main, proxy)?,
-					IngressMessages::RemoveProxy(main, proxy) =>
-						remove_proxy(&mut trie, main, proxy)?,
-					IngressMessages::OpenTradingPair(config) |
-					IngressMessages::UpdateTradingPair(config) => {
-						let pair = TradingPair::from(config.quote_asset, config.base_asset);
-						self.trading_pair_configs.insert(pair, config);
-					},
-					IngressMessages::CloseTradingPair(config) => {
-						let pair = TradingPair::from(config.quote_asset, config.base_asset);
-						self.trading_pair_configs.remove(&pair);
-					},
-					_ => {},
-				}
-			}
+			process_ingress_messages(
+				&mut trie,
+				messages,
+				worker_nonce,
+				stid,
+				&mut self.trading_pair_configs,
+				&mut self.exchange_operational,
+				&mut self.pending_withdrawals,
+			)?;
 			// Commit the trie
 			trie.commit();
 		}
 		info!("📒state root after processing: {:?}", hex::encode(*working_state_root));
+		metric_set!(self, ob_pending_withdrawals, self.pending_withdrawals.len());
 		self.last_processed_block_in_offchain_state = num;
 		Ok(())
 	}
@@ -445,7 +494,12 @@ where
 			}
 		}
 
+		let started = Instant::now();
 		let mut summary = self.store_snapshot(worker_nonce, stid, next_snapshot_id, &active_set)?;
+		metric_observe!(self, ob_snapshot_generation_duration, started.elapsed().as_secs_f64());
+		let _ = self
+			.snapshot_sender
+			.notify(|| Ok::<_, ()>(ObSnapshotNotification::from(&summary)));
 		if !self.is_validator {
 			info!(target:"orderbook","📒 Not a validator, skipping snapshot signing.");
 			// We are done if we are not a validator
@@ -482,6 +536,145 @@ where
 		Ok(())
 	}
 
+	/// Opens a placed LIMIT order in the state, reserving the balance it requires, and matches
+	/// the order with the matching engine if enabled. The trades produced by the engine are
+	/// queued, they are compared with the trades submitted by the operator. The reservation of a
+	/// POST_ONLY order rejected by the engine is released.
+	///
+	/// # Parameters
+	///
+	/// * `order`: Placed order.
+	pub fn handle_place_order(&mut self, order: Order) -> Result<(), Error> {
+		if !self.exchange_operational {
+			warn!(target:"orderbook","📒 Rejecting order, exchange is not operational");
+			return Err(Error::ExchangeNotOperational)
+		}
+		let config = self
+			.trading_pair_configs
+			.get(&order.pair)
+			.ok_or(Error::TradingPairConfigNotFound)?;
+		if !order.verify_signature() || !order.verify_config(config) {
+			return Err(Error::InvalidOrder)
+		}
+		{
+			let mut memory_db = self.memory_db.write();
+			let mut working_state_root = self.working_state_root.write();
+			let mut trie = Self::get_trie(&mut memory_db, &mut working_state_root);
+			open_order(&mut trie, &order)?;
+			trie.commit();
+		}
+		if let Some(engine) = self.matching_engine.as_mut() {
+			match engine.place_order(order.clone(), config) {
+				Ok(trades) => {
+					debug!(target:"orderbook","📒 Matching engine produced {:?} trades", trades.len());
+					if is_rejected_by_matching_engine(engine, &order, &trades) {
+						info!(target:"orderbook","📒 Post only order rejected by matching engine: {:?}", order.id);
+						let mut memory_db = self.memory_db.write();
+						let mut working_state_root = self.working_state_root.write();
+						let mut trie = Self::get_trie(&mut memory_db, &mut working_state_root);
+						cancel_open_order(&mut trie, &order.pair, &order.id)?;
+						trie.commit();
+					}
+					self.expected_trades.extend(trades);
+				},
+				Err(err) => {
+					// The matching engine is advisory, it is rebuilt from the working state
+					error!(target:"orderbook","📒 Matching engine failed to place order: {:?}", err);
+					self.expected_trades.clear();
+					return self.restore_matching_engine()
+				},
+			}
+		}
+		Ok(())
+	}
+
+	/// Closes a cancelled order in the state, releasing its reserved balance, and removes it from
+	/// the matching engine if enabled.
+	///
+	/// # Parameters
+	///
+	/// * `pair`: Trading pair of the order.
+	/// * `order_id`: Order identifier.
+	pub fn handle_cancel_order(
+		&mut self,
+		pair: TradingPair,
+		order_id: OrderId,
+	) -> Result<(), Error> {
+		{
+			let mut memory_db = self.memory_db.write();
+			let mut working_state_root = self.working_state_root.write();
+			let mut trie = Self::get_trie(&mut memory_db, &mut working_state_root);
+			if cancel_open_order(&mut trie, &pair, &order_id)?.is_none() {
+				warn!(target:"orderbook","📒 Cancelled order not found in state: {:?}", order_id);
+			}
+			trie.commit();
+		}
+		if let Some(engine) = self.matching_engine.as_mut() {
+			if engine.cancel_order(&pair, &order_id).is_none() {
+				warn!(target:"orderbook","📒 Cancelled order not found in matching engine: {:?}", order_id);
+			}
+		}
+		Ok(())
+	}
+
+	/// Rebuilds the matching engine books from the open orders of the working state. Nothing is
+	/// done if the matching engine is disabled.
+	pub fn restore_matching_engine(&mut self) -> Result<(), Error> {
+		let engine = match self.matching_engine.as_mut() {
+			Some(engine) => engine,
+			None => return Ok(()),
+		};
+		engine.clear();
+		let mut memory_db = self.memory_db.write();
+		let mut working_state_root = self.working_state_root.write();
+		if *working_state_root == [0u8; 32] {
+			return Ok(())
+		}
+		let trie = Self::get_trie(&mut memory_db, &mut working_state_root);
+		for pair in self.trading_pair_configs.keys() {
+			if let Some(price) = get_last_trade_price(&trie, pair)? {
+				engine.restore_last_price(*pair, price);
+			}
+		}
+		drop(trie);
+		for pair in self.trading_pair_configs.keys() {
+			// The last trade prices are restored first as they trigger the STOP_LIMIT orders
+			let orders = read_open_orders(&memory_db, &working_state_root, pair)
+				.map_err(|err| Error::TrieError(err.to_string()))?;
+			for order in orders {
+				engine.restore_order(order);
+			}
+		}
+		info!(target:"orderbook","📒 Matching engine restored from state");
+		Ok(())
+	}
+
+	/// Checks that the trades applied from the operator are the next trades produced by the
+	/// matching engine. Nothing is checked if the matching engine is disabled.
+	///
+	/// The check is advisory as the matching engine is enabled per node: a mismatch is logged and
+	/// the matching engine is rebuilt from the working state, which follows the operator trades.
+	///
+	/// # Parameters
+	///
+	/// * `trades`: Trades applied from the operator.
+	fn verify_trades_with_engine(&mut self, trades: &[Trade]) -> Result<(), Error> {
+		if self.matching_engine.is_none() {
+			return Ok(())
+		}
+		for trade in trades {
+			match self.expected_trades.pop_front() {
+				Some(expected) if is_same_trade(&expected, trade) => {},
+				expected => {
+					error!(target:"orderbook","📒 Trade mismatch: expected: {:?}, received: {:?}", expected, trade);
+					self.expected_trades.clear();
+					return self.restore_matching_engine()
+				},
+			}
+		}
+		Ok(())
+	}
+
 	/// Entrypoint for the known messages processing.
 	///
 	/// # Parameters
@@ -489,35 +682,63 @@ where
 	/// * `action`: Orderbook message DTO.
 	pub fn handle_action(&mut self, action: &ObMessage) -> Result<(), Error> {
 		debug!(target:"orderbook","📒 Processing action: {:?}", action);
+		let started = Instant::now();
 		match action.action.clone() {
 			// Get Trie here itself and pass to required function
 			// No need to change Test cases
 			UserActions::Trade(trades) => {
-				let mut memory_db = self.memory_db.write();
-				let mut working_state_root = self.working_state_root.write();
-				let mut trie = Self::get_trie(&mut memory_db, &mut working_state_root);
-
-				for trade in trades {
-					let config = self
-						.trading_pair_configs
-						.get(&trade.maker.pair)
-						.ok_or(Error::TradingPairConfigNotFound)?
-						.clone();
-					process_trade(&mut trie, trade, config)?
+				if !self.exchange_operational {
+					warn!(target:"orderbook","📒 Rejecting trades, exchange is not operational");
+					return Err(Error::ExchangeNotOperational)
 				}
-				// Commit the trie
-				trie.commit();
+				{
+					let mut memory_db = self.memory_db.write();
+					let mut working_state_root = self.working_state_root.write();
+					let mut trie = Self::get_trie(&mut memory_db, &mut working_state_root);
+
+					for trade in trades.iter().cloned() {
+						let config = self
+							.trading_pair_configs
+							.get(&trade.maker.pair)
+							.ok_or(Error::TradingPairConfigNotFound)?
+							.clone();
+						let pair = trade.maker.pair.to_string();
+						process_trade(&mut trie, trade, config)?;
+						metric_inc!(self, ob_trades_processed, &[&pair]);
+					}
+					// Commit the trie
+					trie.commit();
+				}
+				// The expected trades are only consumed once the trades are applied
+				self.verify_trades_with_engine(&trades)?;
 			},
 			UserActions::Withdraw(withdraw) =>
 				self.process_withdraw(withdraw, action.worker_nonce, action.stid)?,
-			UserActions::BlockImport(num) => self.handle_blk_import(num)?,
+			UserActions::BlockImport(num) =>
+				self.handle_blk_import(num, action.worker_nonce, action.stid)?,
+			UserActions::PlaceOrder(order) => self.handle_place_order(order)?,
+			UserActions::CancelOrder(pair, order_id) => self.handle_cancel_order(pair, order_id)?,
 			UserActions::Reset => {
 				// Nothing to do here, we will not reach here.
 				info!(target:"orderbook","📒state is reset.");
 			},
 		}
+		metric_observe!(
+			self,
+			ob_handle_action_duration,
+			&[action_label(&action.action)],
+			started.elapsed().as_secs_f64()
+		);
 		*self.latest_worker_nonce.write() = action.worker_nonce;
 		info!(target:"orderbook","📒Updated working state root: {:?}",hex::encode(*self.working_state_root.read()));
+		let state_root = (*self.working_state_root.read()).into();
+		let _ = self.action_sender.notify(|| {
+			Ok::<_, ()>(ObActionNotification {
+				action: action.clone(),
+				worker_nonce: action.worker_nonce,
+				state_root,
+			})
+		});
 		metric_set!(self, ob_snapshot_id, action.worker_nonce);
 		self.latest_state_change_id = action.stid;
 		// Multicast the message to other peers
@@ -563,39 +784,44 @@ where
 		Ok(())
 	}
 
-	/// Hydrates snapshot from the encoded data and loads it to the memory db.
+	/// Loads the snapshot store to the memory db.
 	///
 	/// # Parameters
 	///
-	/// * `data`: Encoded snapshot to be hydrated.
+	/// * `store`: Snapshot store rebuilt from the snapshot chunks.
 	/// * `summary`: Snapshot summary DTO.
-	pub fn load_state_from_data(
+	pub fn load_state_from_store(
 		&mut self,
-		data: &[u8],
+		store: SnapshotStore,
 		summary: &SnapshotSummary<AccountId>,
 	) -> Result<(), Error> {
-		info!(target: "orderbook", "📒 Loading state from snapshot data ({} bytes)", data.len());
-		match serde_json::from_slice::<SnapshotStore>(data) {
-			Ok(store) => {
-				info!(target: "orderbook", "📒 Loaded state from snapshot data ({} keys in memory db)",  store.map.len());
-				let mut memory_db = self.memory_db.write();
-				memory_db.load_from(store.convert_to_hashmap());
-				info!(target: "orderbook", "📒 {} keys in loaded memory db",memory_db.data().len());
-				let summary_clone = summary.clone();
-				*self.last_snapshot.write() = summary_clone;
-				*self.latest_worker_nonce.write() = summary.worker_nonce;
-				self.latest_state_change_id = summary.state_change_id;
-				self.last_processed_block_in_offchain_state = summary.last_processed_blk;
-				*self.working_state_root.write() = summary.state_root.0;
-				info!(target: "orderbook", "📒 0x{} state root loaded",hex::encode(summary.state_root.0));
-				*self.state_version.write() = summary.state_version;
-			},
-			Err(err) => {
-				error!(target: "orderbook", "📒 Error decoding snapshot data: {err:?}");
-				return Err(Error::Backend(format!("Error decoding snapshot data: {err:?}")))
-			},
+		info!(target: "orderbook", "📒 Loaded state from snapshot data ({} keys in memory db)",  store.map.len());
+		{
+			let mut memory_db = self.memory_db.write();
+			memory_db.load_from(store.convert_to_hashmap());
+			info!(target: "orderbook", "📒 {} keys in loaded memory db",memory_db.data().len());
+			let summary_clone = summary.clone();
+			*self.last_snapshot.write() = summary_clone;
+			*self.latest_worker_nonce.write() = summary.worker_nonce;
+			self.latest_state_change_id = summary.state_change_id;
+			self.last_processed_block_in_offchain_state = summary.last_processed_blk;
+			let mut working_state_root = self.working_state_root.write();
+			*working_state_root = summary.state_root.0;
+			info!(target: "orderbook", "📒 0x{} state root loaded",hex::encode(summary.state_root.0));
+			// The state version only counts resets, so any snapshot may still hold accounts stored
+			// before the permissions of their proxies, accounts already migrated are skipped
+			let migrated = migrate_account_infos(&mut memory_db, &mut working_state_root)?;
+			if migrated > 0 {
+				info!(target: "orderbook", "📒 Migrated {} accounts to the current encoding", migrated);
+			}
+			if *working_state_root != [0u8; 32] {
+				let trie = Self::get_trie(&mut memory_db, &mut working_state_root);
+				self.exchange_operational = get_exchange_state(&trie)?;
+			}
+			info!(target: "orderbook", "📒 Exchange operational state loaded: {:?}", self.exchange_operational);
+			*self.state_version.write() = summary.state_version;
 		}
-		Ok(())
+		self.restore_matching_engine()
 	}
 
 	/// Processes `Orderbook` gossip messages.
@@ -616,18 +842,23 @@ where
 			return Ok(())
 		}
 		info!(target: "orderbook", "📒 Processing new user action: {:?}", action);
-		if let Some(expected_singer) = self.orderbook_operator_public_key {
-			if !action.verify(&expected_singer) {
+		let signature_check = self.orderbook_operator_keys.read().check(action);
+		match signature_check {
+			SignatureCheck::Valid => {},
+			SignatureCheck::KeysNotLoaded => {
+				warn!(target: "orderbook", "📒 Orderbook operator public key not set");
+				return Err(Error::SignatureVerificationFailed)
+			},
+			SignatureCheck::ExpiredKey | SignatureCheck::Invalid => {
 				error!(target: "orderbook", "📒 Invalid signature for action: {:?}",action);
 				return Err(Error::SignatureVerificationFailed)
-			}
-		} else {
-			warn!(target: "orderbook", "📒 Orderbook operator public key not set");
-			return Err(Error::SignatureVerificationFailed)
+			},
 		}
 		info!(target: "orderbook", "📒 Ob message recieved worker_nonce: {:?}",action.worker_nonce);
 		// Cache the message
 		self.known_messages.insert(action.worker_nonce, action.clone());
+		self.message_log.append(action);
+		metric_set!(self, ob_known_messages, self.known_messages.len());
 		if self.sync_oracle.is_major_syncing() | self.state_is_syncing {
 			info!(target: "orderbook", "📒 Ob message cached for sync to complete: worker_nonce: {:?}",action.worker_nonce);
 			return Ok(())
@@ -680,63 +911,79 @@ where
 	) -> Result<SnapshotSummary<AccountId>, Error> {
 		info!(target: "orderbook", "📒 Storing snapshot: {:?}", snapshot_id);
 		if let Some(mut offchain_storage) = self.backend.offchain_storage() {
+			// The collected fees are paid out by the snapshot, so they are removed from its state
+			let trading_fees = {
+				let mut memory_db = self.memory_db.write();
+				let mut working_state_root = self.working_state_root.write();
+				let mut trie = Self::get_trie(&mut memory_db, &mut working_state_root);
+				let trading_fees = take_collected_fees(&mut trie)?;
+				trie.commit();
+				trading_fees
+			};
 			// TODO: How to avoid cloning memory_db
 			let store = SnapshotStore::new(self.memory_db.read().data().clone().into_iter());
 			info!(target: "orderbook", "📒 snapshot contains {:?} keys", store.map.len());
-			return match serde_json::to_vec(&store) {
-				Ok(data) => {
-					info!(target: "orderbook", "📒 Stored snapshot data ({} bytes)", data.len());
-					let mut state_chunk_hashes = vec![];
-					// Slice the data into chunks of 10 MB
-					let mut chunks = data.chunks(10 * 1024 * 1024);
-					for chunk in &mut chunks {
-						let chunk_hash = H128::from(blake2_128(chunk));
-						offchain_storage.set(
-							ORDERBOOK_STATE_CHUNK_PREFIX,
-							chunk_hash.0.as_ref(),
-							chunk,
-						);
-						info!(target: "orderbook", "📒 Stored snapshot chunk: {}", chunk_hash);
-						state_chunk_hashes.push(chunk_hash);
-					}
-
-					let withdrawals = self.pending_withdrawals.clone();
-					self.pending_withdrawals.clear();
-					info!(target: "orderbook", "📒 Stored snapshot withdrawals ({} bytes)", withdrawals.len());
-
-					let working_state_root = self.working_state_root.read();
-
-					let summary = SnapshotSummary {
-						validator_set_id: active_set.set_id,
-						snapshot_id,
-						worker_nonce,
-						state_root: (*working_state_root).into(),
-						state_change_id,
-						bitflags: vec![0; active_set.len().div(128).saturating_add(1)],
-						withdrawals,
-						aggregate_signature: None,
-						state_chunk_hashes,
-						last_processed_blk: self.last_processed_block_in_offchain_state,
-						state_version: *self.state_version.read(),
-					};
-
-					info!(target: "orderbook", "📒 Writing summary to offchain storage: {:?}", summary);
-
-					offchain_storage.set(
-						ORDERBOOK_SNAPSHOT_SUMMARY_PREFIX,
-						&snapshot_id.encode(),
-						&summary.encode(),
+			let (kind, chunks) = match self.delta_base_reference_counts(snapshot_id)? {
+				Some(base_reference_counts) => {
+					let delta = SnapshotDelta::new(
+						snapshot_id.saturating_sub(1),
+						&base_reference_counts,
+						&store,
 					);
-					// Store the last processed worker nonce too
-					offchain_storage.set(
-						ORDERBOOK_WORKER_NONCE_PREFIX,
-						ORDERBOOK_WORKER_NONCE_PREFIX,
-						&summary.worker_nonce.encode(),
-					);
-					Ok(summary)
+					info!(target: "orderbook", "📒 Delta snapshot contains {:?} changed and {:?} removed keys", delta.changed.len(), delta.removed.len());
+					("delta", delta.encode_chunks())
 				},
-				Err(err) => Err(Error::Backend(format!("📒 Error serializing the data: {err:?}"))),
+				None => ("full", store.encode_chunks()),
+			};
+			let mut state_chunk_hashes = vec![];
+			let mut size = 0;
+			for chunk in chunks {
+				let chunk_hash = H128::from(blake2_128(&chunk));
+				offchain_storage.set(ORDERBOOK_STATE_CHUNK_PREFIX, chunk_hash.0.as_ref(), &chunk);
+				info!(target: "orderbook", "📒 Stored snapshot chunk: {}", chunk_hash);
+				state_chunk_hashes.push(chunk_hash);
+				size += chunk.len();
 			}
+			info!(target: "orderbook", "📒 Stored snapshot data ({} bytes)", size);
+			metric_set!(self, ob_snapshot_size, &[kind], size);
+			self.snapshot_cache = Some((state_chunk_hashes.clone(), store.reference_counts()));
+
+			let withdrawals = self.pending_withdrawals.clone();
+			self.pending_withdrawals.clear();
+			metric_set!(self, ob_pending_withdrawals, 0);
+			info!(target: "orderbook", "📒 Stored snapshot withdrawals ({} bytes)", withdrawals.len());
+
+			let working_state_root = self.working_state_root.read();
+
+			let summary = SnapshotSummary {
+				validator_set_id: active_set.set_id,
+				snapshot_id,
+				worker_nonce,
+				state_root: (*working_state_root).into(),
+				state_change_id,
+				bitflags: vec![0; active_set.len().div(128).saturating_add(1)],
+				withdrawals,
+				aggregate_signature: None,
+				state_chunk_hashes,
+				last_processed_blk: self.last_processed_block_in_offchain_state,
+				state_version: *self.state_version.read(),
+				trading_fees,
+			};
+
+			info!(target: "orderbook", "📒 Writing summary to offchain storage: {:?}", summary);
+
+			offchain_storage.set(
+				ORDERBOOK_SNAPSHOT_SUMMARY_PREFIX,
+				&snapshot_id.encode(),
+				&summary.encode(),
+			);
+			// Store the last processed worker nonce too
+			offchain_storage.set(
+				ORDERBOOK_WORKER_NONCE_PREFIX,
+				ORDERBOOK_WORKER_NONCE_PREFIX,
+				&summary.worker_nonce.encode(),
+			);
+			return Ok(summary)
 		}
 		Err(Error::Backend("📒 Offchain Storage not Found".parse().unwrap()))
 	}
@@ -752,29 +999,104 @@ where
 			// Nothing to do if we are on state_id 0
 			return Ok(())
 		}
-		if let Some(offchain_storage) = self.backend.offchain_storage() {
-			let mut data = Vec::new();
-			for chunk_hash in &summary.state_chunk_hashes {
+		let store = self.read_snapshot_store(summary)?;
+		self.snapshot_cache = Some((summary.state_chunk_hashes.clone(), store.reference_counts()));
+		self.load_state_from_store(store, summary)
+	}
+
+	/// Rebuilds the store of a snapshot, replaying delta snapshots on top of their last full
+	/// snapshot.
+	///
+	/// # Parameters
+	///
+	/// * `summary`: Snapshot summary DTO of the snapshot to rebuild.
+	pub fn read_snapshot_store(
+		&self,
+		summary: &SnapshotSummary<AccountId>,
+	) -> Result<SnapshotStore, Error> {
+		let offchain_storage =
+			self.backend.offchain_storage().ok_or(Error::OffchainStorageNotAvailable)?;
+		let at = BlockId::Number(self.client.info().finalized_number);
+		read_snapshot_store(
+			summary,
+			|chunk_hash| offchain_storage.get(ORDERBOOK_STATE_CHUNK_PREFIX, chunk_hash.0.as_ref()),
+			|snapshot_id| {
+				self.runtime
+					.runtime_api()
+					.get_snapshot_by_id(&at, snapshot_id)
+					.map_err(|err| SnapshotError::Runtime(err.to_string()))
+			},
+		)
+		.map_err(|err| {
+			error!(target: "orderbook", "📒 Error reading snapshot {:?}: {:?}", summary.snapshot_id, err);
+			Error::from(err)
+		})
+	}
+
+	/// Returns the reference counts of the base of the given snapshot if it is a delta snapshot.
+	///
+	/// Whether a snapshot is a delta snapshot only depends on its id, see [`is_full_snapshot`], so
+	/// that validators agree on the chunk hashes. Storing a delta snapshot fails if its base can't
+	/// be read.
+	///
+	/// # Parameters
+	///
+	/// * `snapshot_id`: Id of the snapshot being stored.
+	pub fn delta_base_reference_counts(
+		&self,
+		snapshot_id: u64,
+	) -> Result<Option<BTreeMap<[u8; 32], i32>>, Error> {
+		if is_full_snapshot(snapshot_id) {
+			return Ok(None)
+		}
+		let at = BlockId::Number(self.client.info().finalized_number);
+		let base = self
+			.runtime
+			.runtime_api()
+			.get_snapshot_by_id(&at, snapshot_id - 1)?
+			.ok_or(Error::SnapshotNotFound)?;
+		if let Some((chunk_hashes, reference_counts)) = self.snapshot_cache.as_ref() {
+			if *chunk_hashes == base.state_chunk_hashes {
+				return Ok(Some(reference_counts.clone()))
+			}
+		}
+		Ok(Some(self.read_snapshot_store(&base)?.reference_counts()))
+	}
+
+	/// Returns the first snapshot, walking from the given one down its chain of delta snapshot
+	/// bases, whose chunks are not all in the offchain storage.
+	///
+	/// # Parameters
+	///
+	/// * `summary`: Snapshot summary DTO of the snapshot to load.
+	pub fn find_unavailable_snapshot(
+		&self,
+		summary: &SnapshotSummary<AccountId>,
+	) -> Result<Option<SnapshotSummary<AccountId>>, Error> {
+		let offchain_storage =
+			self.backend.offchain_storage().ok_or(Error::OffchainStorageNotAvailable)?;
+		let at = BlockId::Number(self.client.info().finalized_number);
+		let mut summary = summary.clone();
+		loop {
+			let mut base = None;
+			for (index, chunk_hash) in summary.state_chunk_hashes.iter().enumerate() {
 				match offchain_storage.get(ORDERBOOK_STATE_CHUNK_PREFIX, chunk_hash.0.as_ref()) {
-					None => {
-						error!(target:"orderbook","📒 Unable to find chunk from offchain state: {:?}",chunk_hash);
-						return Err(Error::SnapshotNotFound)
-					},
-					Some(mut chunk) => {
-						let computed_hash = H128::from(blake2_128(&chunk));
-						if computed_hash != *chunk_hash {
-							warn!(target:"orderbook","📒 orderbook state hash mismatch: computed: {:?}, expected: {:?}",computed_hash,chunk_hash);
-							return Err(Error::StateHashMisMatch)
-						}
-						data.append(&mut chunk);
-					},
+					None => return Ok(Some(summary)),
+					// Every chunk of a delta snapshot records its base
+					Some(chunk) if index == 0 => base = delta_snapshot_base(&chunk),
+					Some(_) => {},
 				}
 			}
-			self.load_state_from_data(&data, summary)?;
-		} else {
-			warn!(target:"orderbook","📒 orderbook state chunk not found");
+			match base {
+				Some(base) if !base.is_zero() && base < summary.snapshot_id =>
+					summary = self
+						.runtime
+						.runtime_api()
+						.get_snapshot_by_id(&at, base)?
+						.ok_or(Error::SnapshotNotFound)?,
+				_ => return Ok(None),
+			}
 		}
-		Ok(())
 	}
 
 	/// Iterates through the `known_messages` incrementally starting from the last snapshot's
@@ -814,6 +1136,11 @@ where
 		// We forget about everything else from cache.
 		self.known_messages.clear();
 		info!(target:"orderbook","📒 OB messages cache cleared.");
+		if let Some(engine) = self.matching_engine.as_mut() {
+			engine.clear();
+		}
+		self.expected_trades.clear();
+		self.exchange_operational = true;
 		let latest_summary = self
 			.runtime
 			.runtime_api()
@@ -821,9 +1148,51 @@ where
 		self.load_snapshot(&latest_summary)?;
 		*self.state_version.write() = latest_summary.state_version.saturating_add(1);
 		info!(target:"orderbook","📒 New state version is updated: version: {:?}",self.state_version.read());
+		self.replay_message_log(latest_summary.worker_nonce);
 		Ok(())
 	}
 
+	/// Caches the logged messages after the worker nonce of the loaded snapshot, they are
+	/// processed by the worker nonce gap filling.
+	///
+	/// Each message is verified against the operator keys accepted at its worker nonce, messages
+	/// with an invalid signature are dropped.
+	///
+	/// If the log starts with a reset message, the message stream was restarted from the loaded
+	/// snapshot at the worker nonce of the reset message.
+	///
+	/// # Parameters
+	///
+	/// * `worker_nonce`: Worker nonce of the loaded snapshot.
+	pub fn replay_message_log(&mut self, worker_nonce: u64) {
+		let current_keys = self.orderbook_operator_keys.read().current().to_vec();
+		let messages: Vec<ObMessage> = self
+			.message_log
+			.messages_after(worker_nonce)
+			.into_iter()
+			.filter(|message| {
+				// Messages logged before any key was recorded were accepted with the current keys
+				let keys = self
+					.message_log
+					.operator_keys_at(message.worker_nonce)
+					.unwrap_or_else(|| current_keys.clone());
+				let valid = message.verify_with_any(&keys);
+				if !valid {
+					warn!(target:"orderbook","📒 Dropping logged message with invalid signature: {:?}", message.worker_nonce);
+				}
+				valid
+			})
+			.collect();
+		info!(target:"orderbook","📒 Replaying {:?} logged messages after worker nonce: {:?}", messages.len(), worker_nonce);
+		if let Some(reset) = messages.first().filter(|message| message.reset) {
+			*self.latest_worker_nonce.write() = reset.worker_nonce;
+			*self.state_version.write() = reset.version.saturating_add(1);
+		}
+		for message in messages {
+			self.known_messages.entry(message.worker_nonce).or_insert(message);
+		}
+	}
+
 	/// Checks the local `known_messages` to see if we have any messages between the `from` and `to`
 	/// worker_nonce. If we do, we gossip the `WorkerNonces` message to the peer that requested it.
 	///
@@ -876,6 +1245,9 @@ where
 			if let Some(msg) = self.known_messages.get(&worker_nonce) {
 				info!(target: "test", "📒 known_messages: {:?}", self.known_messages.len());
 				messages.push(msg.clone());
+			} else if let Some(msg) = self.message_log.get(worker_nonce) {
+				// Cache is cleared on reload, the log still has the messages
+				messages.push(msg);
 			}
 		}
 		// Send the final chunk if any
@@ -892,6 +1264,7 @@ where
 	/// # Parameters
 	///
 	/// * `messages`: The list of worker_nonces received via gossip.
+	/// * `remote`: Peer the worker_nonces are received from.
 	///
 	/// # Returns
 	///
@@ -900,12 +1273,20 @@ where
 	pub async fn got_worker_nonces_via_gossip(
 		&mut self,
 		messages: &Vec<ObMessage>,
+		remote: Option<PeerId>,
 	) -> Result<(), Error> {
 		info!(target: "orderbook", "📒 Got worker_nonces via gossip: {:?}", messages.len());
 		for message in messages {
-			// TODO: handle reputation change.
-			self.known_messages.entry(message.worker_nonce).or_insert(message.clone());
+			// Messages sent directly to us are not verified by the gossip validator
+			if !self.check_operator_signature(message, remote) {
+				continue
+			}
+			if let Entry::Vacant(entry) = self.known_messages.entry(message.worker_nonce) {
+				entry.insert(message.clone());
+				self.message_log.append(message);
+			}
 		}
+		metric_set!(self, ob_known_messages, self.known_messages.len());
 		self.check_worker_nonce_gap_fill().await
 	}
 
@@ -974,6 +1355,10 @@ where
 	/// * `remote`: Peer id to emit message to.
 	pub async fn have(&mut self, snapshot_id: &u64, bitmap: &Vec<u128>, remote: Option<PeerId>) {
 		info!(target: "orderbook", "📒 Have snapshot: {:?} - {:?}", snapshot_id, bitmap);
+		// Chunks of other snapshots are not tracked by the sync map
+		if *snapshot_id != self.sync_snapshot_id {
+			return
+		}
 		if let Some(peer) = remote {
 			// Note: Set bits here are available for syncing
 			let available_chunks: Vec<usize> = return_set_bits(bitmap);
@@ -988,6 +1373,7 @@ where
 					}
 				}
 			}
+			self.update_sync_chunk_metrics();
 
 			if !want_chunks.is_empty() {
 				let message = GossipMessage::RequestChunk(
@@ -1067,7 +1453,14 @@ where
 	/// * `snapshot_id`: Snapshot identifier.
 	/// * `index`: Index of a chunk.
 	/// * `data`: Snapshot chunk data.
-	pub fn process_chunk(&mut self, snapshot_id: &u64, index: &usize, data: &[u8]) {
+	/// * `remote`: Peer the chunk is received from.
+	pub fn process_chunk(
+		&mut self,
+		snapshot_id: &u64,
+		index: &usize,
+		data: &[u8],
+		remote: Option<PeerId>,
+	) {
 		info!(target: "orderbook", "📒 Chunk snapshot: {:?} - {:?} - {:?}", snapshot_id, index, data.len());
 		if let Some(mut offchian_storage) = self.backend.offchain_storage() {
 			let at = BlockId::Number(self.client.info().finalized_number);
@@ -1075,8 +1468,10 @@ where
 				self.runtime.runtime_api().get_snapshot_by_id(&at, *snapshot_id)
 			{
 				match summary.state_chunk_hashes.get(*index) {
-					None =>
-						warn!(target:"orderbook","📒 Invalid index received, index > length of state chunk hashes"),
+					None => {
+						warn!(target:"orderbook","📒 Invalid index received, index > length of state chunk hashes");
+						self.report_peer(remote, cost::INVALID_CHUNK);
+					},
 					Some(expected_hash) => {
 						let computed_hash: H128 = H128::from(blake2_128(data));
 						if *expected_hash == computed_hash {
@@ -1088,14 +1483,18 @@ where
 							);
 							info!(target: "orderbook", "📒 Chunk {:?} of snapshot: {:?} stored", computed_hash, snapshot_id);
 							// Update sync status map
-							self.sync_state_map
-								.entry(*index)
-								.and_modify(|status| {
-									*status = StateSyncStatus::Available;
-								})
-								.or_insert(StateSyncStatus::Available);
+							if *snapshot_id == self.sync_snapshot_id {
+								self.sync_state_map
+									.entry(*index)
+									.and_modify(|status| {
+										*status = StateSyncStatus::Available;
+									})
+									.or_insert(StateSyncStatus::Available);
+								self.update_sync_chunk_metrics();
+							}
 						} else {
 							log::warn!(target:"orderbook","📒 Invalid chunk hash, dropping chunk...");
+							self.report_peer(remote, cost::INVALID_CHUNK);
 						}
 					},
 				}
@@ -1107,6 +1506,92 @@ where
 		}
 	}
 
+	/// Verifies the operator signature of a message which is not verified by the gossip
+	/// validator, the sender is reported if the signature is not valid.
+	///
+	/// The message is held until the operator keys are read from the runtime.
+	///
+	/// # Parameters
+	///
+	/// * `message`: Message to verify.
+	/// * `remote`: Peer the message is received from.
+	pub fn check_operator_signature(
+		&mut self,
+		message: &ObMessage,
+		remote: Option<PeerId>,
+	) -> bool {
+		let signature_check = self.orderbook_operator_keys.read().check(message);
+		match signature_check {
+			SignatureCheck::Valid => return true,
+			SignatureCheck::KeysNotLoaded =>
+				self.orderbook_operator_keys.write().hold(remote, message.clone()),
+			SignatureCheck::ExpiredKey => {
+				warn!(target: "orderbook", "📒 Dropping worker_nonce signed with an expired key: {:?}", message.worker_nonce);
+				self.report_peer(remote, cost::EXPIRED_KEY_SIGNATURE);
+			},
+			SignatureCheck::Invalid => {
+				warn!(target: "orderbook", "📒 Dropping worker_nonce with invalid signature: {:?}", message.worker_nonce);
+				self.report_peer(remote, cost::INVALID_SIGNATURE);
+			},
+		}
+		false
+	}
+
+	/// Reads the orderbook operator keys from the runtime, runtimes before version 2 of the
+	/// `ObApi` only provide the whitelisted key.
+	///
+	/// # Parameters
+	///
+	/// * `at`: Block the keys are read at.
+	pub fn get_orderbook_operator_keys(
+		&self,
+		at: &BlockId<B>,
+	) -> Result<Vec<sp_core::ecdsa::Public>, Error> {
+		let runtime_api = self.runtime.runtime_api();
+		if runtime_api.has_api_with::<dyn ObApi<B>, _>(at, |version| version >= 2)? {
+			Ok(runtime_api.get_orderbook_operator_keys(at)?)
+		} else {
+			Ok(runtime_api.get_orderbook_opearator_key(at)?.into_iter().collect())
+		}
+	}
+
+	/// Replaces the orderbook operator keys with the ones read from the runtime and processes the
+	/// messages held until the keys were read.
+	///
+	/// # Parameters
+	///
+	/// * `public_keys`: Orderbook operator keys read from the runtime.
+	pub async fn update_operator_keys(&mut self, public_keys: Vec<sp_core::ecdsa::Public>) {
+		if !public_keys.is_empty() {
+			let worker_nonce = self.latest_worker_nonce.read().saturating_add(1);
+			self.message_log.record_operator_keys(worker_nonce, &public_keys);
+		}
+		let held = self.orderbook_operator_keys.write().update(public_keys);
+		if !held.is_empty() {
+			info!(target:"orderbook","📒 Processing {:?} messages held until the operator keys were read", held.len());
+		}
+		for (remote, message) in held {
+			if self.check_operator_signature(&message, remote) {
+				if let Err(err) = self.process_new_user_action(&message).await {
+					error!(target: "orderbook", "📒 Error while processing held message: {:?}", err);
+				}
+			}
+		}
+	}
+
+	/// Reports the reputation change of a peer to the network.
+	///
+	/// # Parameters
+	///
+	/// * `remote`: Reported peer, nothing is reported if `None`.
+	/// * `cost_benefit`: Reputation change of the peer.
+	pub fn report_peer(&self, remote: Option<PeerId>, cost_benefit: ReputationChange) {
+		if let Some(who) = remote {
+			warn!(target:"orderbook","📒 Reporting peer {:?}: {:?}", who, cost_benefit.reason);
+			self.gossip_engine.report(who, cost_benefit);
+		}
+	}
+
 	/// Highest level method used to process incoming gossip message.
 	///
 	/// Note. Message emitted only if peer id is provided.
@@ -1127,14 +1612,14 @@ where
 			GossipMessage::WantWorkerNonce(from, to, version) =>
 				self.want_worker_nonce(from, to, *version, remote),
 			GossipMessage::WorkerNonces(messages) =>
-				self.got_worker_nonces_via_gossip(messages).await?,
+				self.got_worker_nonces_via_gossip(messages, remote).await?,
 			GossipMessage::ObMessage(msg) => self.process_new_user_action(msg).await?,
 			GossipMessage::Want(snap_id, bitmap) => self.want(snap_id, bitmap, remote).await,
 			GossipMessage::Have(snap_id, bitmap) => self.have(snap_id, bitmap, remote).await,
 			GossipMessage::RequestChunk(snap_id, bitmap) =>
 				self.request_chunk(snap_id, bitmap, remote).await,
 			GossipMessage::Chunk(snap_id, index, data) =>
-				self.process_chunk(snap_id, &(*index).into(), data),
+				self.process_chunk(snap_id, &(*index).into(), data, remote),
 		}
 		Ok(())
 	}
@@ -1225,21 +1710,31 @@ where
 						error!(target:"orderbook","📒 Error while sending sync requests to peers: {:?}",err);
 					}
 				}
+				if latest_summary.snapshot_id != self.last_snapshot.read().snapshot_id {
+					self.last_snapshot_accepted_at = Instant::now();
+				}
 				// There is a valid snapshot from runtime, so update our state.
 				*self.last_snapshot.write() = latest_summary;
 				// Prune the known messages cache
 				// Remove all worker nonces older than the last processed worker nonce
 				self.known_messages.retain(|k, _| *k > last_worker_nonce);
+				self.message_log.prune(last_worker_nonce);
 			}
-			if let Some(orderbook_operator_public_key) =
-				self.runtime.runtime_api().get_orderbook_opearator_key(&BlockId::number(
-					self.last_finalized_block.saturated_into(),
-				))? {
-				info!(target:"orderbook","📒 Orderbook operator public key found in runtime: {:?}",orderbook_operator_public_key);
-				self.orderbook_operator_public_key = Some(orderbook_operator_public_key);
-			} else {
+			metric_set!(self, ob_known_messages, self.known_messages.len());
+			metric_set!(
+				self,
+				ob_last_snapshot_age,
+				self.last_snapshot_accepted_at.elapsed().as_secs()
+			);
+			let orderbook_operator_public_keys = self.get_orderbook_operator_keys(
+				&BlockId::number(self.last_finalized_block.saturated_into()),
+			)?;
+			if orderbook_operator_public_keys.is_empty() {
 				warn!(target:"orderbook","📒 Orderbook operator public key not found in runtime");
+			} else {
+				info!(target:"orderbook","📒 Orderbook operator public keys found in runtime: {:?}",orderbook_operator_public_keys);
 			}
+			self.update_operator_keys(orderbook_operator_public_keys).await;
 		}
 		// if we are syncing the check progress
 		if self.state_is_syncing {
@@ -1273,15 +1768,19 @@ where
 				}
 			}
 			info!(target:"orderbook","📒 State chunks sync status: inprogress: {:?}, unavailable: {:?}, total: {:?}",inprogress,unavailable,total);
+			self.update_sync_chunk_metrics();
 			// If we have missing indexes, ask again to peers for these indexes
 			if !missing_indexes.is_empty() {
 				let message = GossipMessage::Want(
-					last_summary.snapshot_id,
+					self.sync_snapshot_id,
 					prepare_bitmap(&missing_indexes, highest_missing_index)
 						.expect("📒 Expected to create bitmap"),
 				);
 				info!(target:"orderbook","📒 Sending sync requests to neighbours...");
 				self.gossip_engine.gossip_message(topic::<B>(), message.encode(), true);
+			} else if let Some(summary) = self.find_unavailable_snapshot(&last_summary)? {
+				// The base of a delta snapshot is missing, sync it too
+				self.send_sync_requests(&summary)?;
 			} else {
 				// We have all the data, state is synced,
 				// so load snapshot shouldn't have any problem now
@@ -1423,6 +1922,8 @@ where
 
 		// Check the chunks we need
 		// Store the missing chunk indexes
+		self.sync_snapshot_id = summary.snapshot_id;
+		self.sync_state_map.clear();
 		let mut missing_chunks = vec![];
 		let mut highest_chunk_index = 0;
 		for (index, chunk_hash) in summary.state_chunk_hashes.iter().enumerate() {
@@ -1437,6 +1938,7 @@ where
 				self.sync_state_map.insert(index, StateSyncStatus::Available);
 			}
 		}
+		self.update_sync_chunk_metrics();
 		// Prepare bitmap
 		let bitmap = prepare_bitmap(&missing_chunks, highest_chunk_index)
 			.expect("📒 Expected to create bitmap");
@@ -1447,6 +1949,21 @@ where
 		Ok(())
 	}
 
+	/// Updates the sync chunk metrics with the number of chunks in each sync status.
+	fn update_sync_chunk_metrics(&self) {
+		let (mut available, mut in_progress, mut unavailable) = (0usize, 0usize, 0usize);
+		for status in self.sync_state_map.values() {
+			match status {
+				StateSyncStatus::Available => available = available.saturating_add(1),
+				StateSyncStatus::InProgress(..) => in_progress = in_progress.saturating_add(1),
+				StateSyncStatus::Unavailable => unavailable = unavailable.saturating_add(1),
+			}
+		}
+		metric_set!(self, ob_sync_chunks, &["available"], available);
+		metric_set!(self, ob_sync_chunks, &["in_progress"], in_progress);
+		metric_set!(self, ob_sync_chunks, &["unavailable"], unavailable);
+	}
+
 	/// Public method to get a mutable trie instance with the given mutable memory_db and
 	/// working_state_root.
 	///
@@ -1506,12 +2023,10 @@ where
 			tokio::time::sleep(Duration::from_secs(12)).await;
 		}
 
-		if let Ok(public_key) = self
-			.runtime
-			.runtime_api()
-			.get_orderbook_opearator_key(&BlockId::Number(self.client.info().finalized_number))
+		if let Ok(public_keys) =
+			self.get_orderbook_operator_keys(&BlockId::Number(self.client.info().finalized_number))
 		{
-			self.orderbook_operator_public_key = public_key;
+			self.update_operator_keys(public_keys).await;
 		}
 
 		// Get the latest summary from the runtime
@@ -1548,6 +2063,18 @@ where
 			error!(target:"orderbook","📒 Error while loading trading pair configs: {:?}",err);
 			return
 		}
+		// Open orders of the loaded snapshot can only be matched once the configs are known
+		if let Err(err) = self.restore_matching_engine() {
+			error!(target:"orderbook","📒 Error while restoring matching engine: {:?}",err);
+			return
+		}
+		// Messages accepted before the restart are not known by the peers anymore
+		self.replay_message_log(latest_summary.worker_nonce);
+		if !self.state_is_syncing {
+			if let Err(err) = self.check_worker_nonce_gap_fill().await {
+				error!(target:"orderbook","📒 Error while replaying logged messages: {:?}",err);
+			}
+		}
 
 		info!(target:"orderbook","📒 Starting event streams...");
 		let mut gossip_messages = Box::pin(
@@ -1596,6 +2123,11 @@ where
 						return;
 					}
 				},
+				report = self.gossip_reports.next() => {
+					if let Some(PeerReport { who, cost_benefit }) = report {
+						self.report_peer(Some(who), cost_benefit);
+					}
+				},
 				message = self.message_sender_link.next() => {
 					if let Some(message) = message {
 						if let Err(err) = self.process_new_user_action(&message).await {
@@ -1632,7 +2164,7 @@ pub fn register_main(
 		warn!(target: "orderbook", "📒 Main account already registered: {:?}", main);
 		return Ok(())
 	}
-	let account_info = AccountInfo { proxies: vec![proxy] };
+	let account_info = AccountInfo::new(vec![proxy]);
 	trie.insert(&main.encode(), &account_info.encode())?;
 	Ok(())
 }
@@ -1699,6 +2231,7 @@ pub fn remove_proxy(
 					.iter()
 					.position(|x| *x == proxy)
 					.map(|i| account_info.proxies.remove(i));
+				account_info.permissions.remove(&proxy);
 				trie.insert(&main.encode(), &account_info.encode())?;
 			} else {
 				return Err(Error::ProxyAccountNotFound)
@@ -1709,47 +2242,703 @@ pub fn remove_proxy(
 	Ok(())
 }
 
-/// Deposits a specified amount of an asset into an account.
+/// Sets the actions a proxy account is allowed to sign on behalf of its main account.
 ///
 /// # Parameters
 ///
-/// * `trie`: A mutable reference to a `TrieDBMut` object of type `ExtensionLayout`.
-/// * `main`: An `AccountId` object representing the main account to deposit the asset into.
-/// * `asset`: An `AssetId` object representing the asset to deposit.
-/// * `amount`: The `Decimal` object representing the amount of the asset to deposit.
+/// * `trie`: A mutable reference to a `TrieDBMut<ExtensionLayout>` instance, which represents the
+///   trie database to modify.
+/// * `main`: An `AccountId` representing the main account of the proxy.
+/// * `proxy`: An `AccountId` representing the proxy account to scope.
+/// * `permissions`: Actions the proxy is allowed to sign.
 ///
 /// # Returns
 ///
-/// A `Result<(), Error>` indicating whether the deposit was successful or not.
-pub fn deposit(
+/// Returns `Ok(())` on success, or an `Error` if the main or proxy account is not registered.
+pub fn set_proxy_permissions(
 	trie: &mut TrieDBMut<ExtensionLayout>,
 	main: AccountId,
-	asset: AssetId,
-	amount: Decimal,
+	proxy: AccountId,
+	permissions: ProxyPermissions,
 ) -> Result<(), Error> {
-	info!(target: "orderbook", "📒 Depositing asset: {:?}", asset);
-	if !trie.contains(&main.encode())? {
-		return Err(Error::MainAccountNotFound)
-	}
-	let account_asset = AccountAsset { main, asset };
-	match trie.get(&account_asset.encode())? {
+	info!(target: "orderbook", "📒 Setting proxy permissions: {:?}, {:?}", proxy, permissions);
+	match trie.get(&main.encode())? {
 		Some(data) => {
-			info!(target: "orderbook", "📒 Account asset found: {:?}", account_asset);
-			let mut balance = Decimal::decode(&mut &data[..])?;
-			balance = balance.saturating_add(amount);
-			trie.insert(&account_asset.encode(), &balance.encode())?;
+			let mut account_info = AccountInfo::decode(&mut &data[..])?;
+			if !account_info.proxies.contains(&proxy) {
+				return Err(Error::ProxyAccountNotFound)
+			}
+			// Proxies without an entry are allowed every action.
+			if permissions == ProxyPermissions::ALL {
+				account_info.permissions.remove(&proxy);
+			} else {
+				account_info.permissions.insert(proxy, permissions);
+			}
+			trie.insert(&main.encode(), &account_info.encode())?;
 		},
-		None => {
-			info!(target: "orderbook", "📒 Account asset created: {:?}", account_asset);
-			trie.insert(&account_asset.encode(), &amount.encode())?;
+		None => return Err(Error::MainAccountNotFound),
+	}
+	Ok(())
+}
+
+/// Checks that a proxy account is allowed to sign an action on behalf of its main account.
+///
+/// # Parameters
+///
+/// * `trie`: A mutable reference to a `TrieDBMut<ExtensionLayout>` instance, which represents the
+///   trie database to read.
+/// * `main`: An `AccountId` representing the main account the action is signed for.
+/// * `proxy`: An `AccountId` representing the proxy account which signed the action.
+/// * `required`: Permissions required by the action.
+///
+/// # Returns
+///
+/// Returns `Ok(())` if the proxy is registered with the required permissions, or an `Error`
+/// otherwise.
+pub fn check_proxy_permissions(
+	trie: &mut TrieDBMut<ExtensionLayout>,
+	main: &AccountId,
+	proxy: &AccountId,
+	required: ProxyPermissions,
+) -> Result<(), Error> {
+	let account_info = match trie.get(&main.encode())? {
+		Some(data) => AccountInfo::decode(&mut &data[..])?,
+		None => return Err(Error::MainAccountNotFound),
+	};
+	match account_info.proxy_permissions(proxy) {
+		Some(permissions) if permissions.contains(required) => Ok(()),
+		Some(_) => Err(Error::ProxyPermissionDenied(proxy.clone(), required)),
+		None => Err(Error::ProxyNotAssociatedWithMain),
+	}
+}
+
+/// Deposits a specified amount of an asset into an account.
+///
+/// # Parameters
+///
+/// * `trie`: A mutable reference to a `TrieDBMut` object of type `ExtensionLayout`.
+/// * `main`: An `AccountId` object representing the main account to deposit the asset into.
+/// * `asset`: An `AssetId` object representing the asset to deposit.
+/// * `amount`: The `Decimal` object representing the amount of the asset to deposit.
+///
+/// # Returns
+///
+/// A `Result<(), Error>` indicating whether the deposit was successful or not.
+pub fn deposit(
+	trie: &mut TrieDBMut<ExtensionLayout>,
+	main: AccountId,
+	asset: AssetId,
+	amount: Decimal,
+) -> Result<(), Error> {
+	info!(target: "orderbook", "📒 Depositing asset: {:?}", asset);
+	if !trie.contains(&main.encode())? {
+		return Err(Error::MainAccountNotFound)
+	}
+	let account_asset = AccountAsset { main, asset };
+	match trie.get(&account_asset.encode())? {
+		Some(data) => {
+			info!(target: "orderbook", "📒 Account asset found: {:?}", account_asset);
+			let mut balance = Decimal::decode(&mut &data[..])?;
+			balance = balance.saturating_add(amount);
+			trie.insert(&account_asset.encode(), &balance.encode())?;
 		},
+		None => {
+			info!(target: "orderbook", "📒 Account asset created: {:?}", account_asset);
+			trie.insert(&account_asset.encode(), &amount.encode())?;
+		},
+	}
+	Ok(())
+}
+
+/// Overwrites the free and reserved balances of the given accounts with the values set by
+/// governance.
+///
+/// The balance held by the open orders of the account is kept reserved in addition to the
+/// governance reserve, so that the orders can still be filled or cancelled. Entries of accounts
+/// which are not registered are skipped.
+///
+/// # Parameters
+///
+/// * `trie`: A mutable reference to a `TrieDBMut` object of type `ExtensionLayout`.
+/// * `balances`: Collection of balances to be set, amounts are expressed in `UNIT_BALANCE`s.
+///
+/// # Returns
+///
+/// A `Result<(), Error>` indicating whether all the balances were set or not.
+pub fn set_free_reserve_balance_for_accounts(
+	trie: &mut TrieDBMut<ExtensionLayout>,
+	balances: &[HandleBalance<AccountId>],
+) -> Result<(), Error> {
+	for balance in balances {
+		info!(target: "orderbook", "📒 Setting balance of asset: {:?} for: {:?}", balance.asset_id, balance.main_account);
+		// A single bad entry must not halt the ingress processing of every validator
+		if !trie.contains(&balance.main_account.encode())? {
+			warn!(target: "orderbook", "📒 Skipping balance of not registered account: {:?}", balance.main_account);
+			continue
+		}
+		let free = convert_balance_to_decimal(balance.free)?;
+		let reserve = convert_balance_to_decimal(balance.reserve)?;
+		let account_asset = AccountAsset::new(balance.main_account.clone(), balance.asset_id);
+		// The balance held by the open orders stays reserved on top of the new reserve
+		let reserve = reserve.saturating_add(get_order_reserved_balance(trie, &account_asset)?);
+		trie.insert(&account_asset.encode(), &free.encode())?;
+		trie.insert(&account_asset.reserved_balance_key(), &reserve.encode())?;
 	}
 	Ok(())
 }
 
+/// Stores the exchange operational state in the trie so that it is part of the snapshots.
+///
+/// # Parameters
+///
+/// * `trie`: A mutable reference to a `TrieDBMut` object of type `ExtensionLayout`.
+/// * `state`: New exchange operational state.
+///
+/// # Returns
+///
+/// A `Result<(), Error>` indicating whether the state was stored or not.
+pub fn set_exchange_state(trie: &mut TrieDBMut<ExtensionLayout>, state: bool) -> Result<(), Error> {
+	info!(target: "orderbook", "📒 Setting exchange operational state: {:?}", state);
+	trie.insert(ORDERBOOK_EXCHANGE_STATE_KEY, &state.encode())?;
+	Ok(())
+}
+
+/// Reads the exchange operational state from the trie.
+///
+/// States created before the exchange state was tracked don't have the key, the exchange is
+/// considered operational in that case.
+///
+/// # Parameters
+///
+/// * `trie`: A reference to a `TrieDBMut` object of type `ExtensionLayout`.
+///
+/// # Returns
+///
+/// A `Result<bool, Error>` with the exchange operational state.
+pub fn get_exchange_state(trie: &TrieDBMut<ExtensionLayout>) -> Result<bool, Error> {
+	match trie.get(ORDERBOOK_EXCHANGE_STATE_KEY)? {
+		Some(data) => Ok(bool::decode(&mut &data[..])?),
+		None => Ok(true),
+	}
+}
+
+/// Debits a withdrawal queued directly on-chain by the main account, bypassing the operator.
+///
+/// If the free balance is lower than `amount`, the whole free balance is withdrawn when
+/// `do_force_withdraw` is set, otherwise the withdrawal is skipped.
+///
+/// # Parameters
+///
+/// * `trie`: A mutable reference to a `TrieDBMut` object of type `ExtensionLayout`.
+/// * `main`: Main account identifier.
+/// * `asset`: Asset identifier.
+/// * `amount`: Requested withdrawal amount.
+/// * `do_force_withdraw`: Withdraw the available balance if it is lower than `amount`.
+///
+/// # Returns
+///
+/// A `Result<Option<Decimal>, Error>` with the debited amount, or `None` if nothing was debited.
+pub fn process_direct_withdrawal(
+	trie: &mut TrieDBMut<ExtensionLayout>,
+	main: AccountId,
+	asset: AssetId,
+	amount: Decimal,
+	do_force_withdraw: bool,
+) -> Result<Option<Decimal>, Error> {
+	info!(target: "orderbook", "📒 Processing direct withdrawal of {:?} {:?} for: {:?}", amount, asset, main);
+	if !trie.contains(&main.encode())? {
+		return Err(Error::MainAccountNotFound)
+	}
+	let account_asset = AccountAsset::new(main, asset);
+	let available = match trie.get(&account_asset.encode())? {
+		Some(data) => Decimal::decode(&mut &data[..])?,
+		None => Decimal::zero(),
+	};
+	let amount = if available >= amount {
+		amount
+	} else if do_force_withdraw {
+		available
+	} else {
+		warn!(target: "orderbook", "📒 Skipping direct withdrawal, insufficient balance: {:?}", available);
+		return Ok(None)
+	};
+	if amount.is_zero() {
+		return Ok(None)
+	}
+	sub_balance(trie, account_asset, amount)?;
+	Ok(Some(amount))
+}
+
+/// Applies the ingress messages of an imported block to the trie.
+///
+/// # Parameters
+///
+/// * `trie`: A mutable reference to a `TrieDBMut` object of type `ExtensionLayout`.
+/// * `messages`: Ingress messages of the block, in the order they were emitted by the runtime.
+/// * `worker_nonce`: Worker nonce of the block import message.
+/// * `stid`: State change id of the block import message.
+/// * `trading_pair_configs`: Trading pair configurations updated by the trading pair messages.
+/// * `exchange_operational`: Exchange operational state updated by the exchange state messages.
+/// * `pending_withdrawals`: Withdrawals queued by the direct withdrawal messages.
+///
+/// # Returns
+///
+/// A `Result<(), Error>` indicating whether all the messages were applied or not.
+pub fn process_ingress_messages(
+	trie: &mut TrieDBMut<ExtensionLayout>,
+	messages: Vec<IngressMessages<AccountId>>,
+	worker_nonce: u64,
+	stid: u64,
+	trading_pair_configs: &mut BTreeMap<TradingPair, TradingPairConfig>,
+	exchange_operational: &mut bool,
+	pending_withdrawals: &mut Vec<Withdrawal<AccountId>>,
+) -> Result<(), Error> {
+	for message in messages {
+		match message {
+			IngressMessages::RegisterUser(main, proxy) => register_main(trie, main, proxy)?,
+			IngressMessages::Deposit(main, asset, amt) => deposit(trie, main, asset, amt)?,
+			IngressMessages::AddProxy(main, proxy) => add_proxy(trie, main, proxy)?,
+			IngressMessages::RemoveProxy(main, proxy) => remove_proxy(trie, main, proxy)?,
+			IngressMessages::SetProxyPermissions(main, proxy, permissions) =>
+				set_proxy_permissions(trie, main, proxy, permissions)?,
+			IngressMessages::OpenTradingPair(config) |
+			IngressMessages::UpdateTradingPair(config) => {
+				let pair = TradingPair::from(config.quote_asset, config.base_asset);
+				trading_pair_configs.insert(pair, config);
+			},
+			IngressMessages::CloseTradingPair(config) => {
+				let pair = TradingPair::from(config.quote_asset, config.base_asset);
+				trading_pair_configs.remove(&pair);
+			},
+			IngressMessages::SetFreeReserveBalanceForAccounts(balances) =>
+				set_free_reserve_balance_for_accounts(trie, &balances)?,
+			IngressMessages::SetFeeConfig(main, config) => set_fee_config(trie, &main, config)?,
+			IngressMessages::SetExchangeState(state) => {
+				set_exchange_state(trie, state)?;
+				*exchange_operational = state;
+			},
+			IngressMessages::DirectWithdrawal(main, asset, amt, do_force_withdraw) =>
+				if let Some(amount) =
+					process_direct_withdrawal(trie, main.clone(), asset, amt, do_force_withdraw)?
+				{
+					pending_withdrawals.push(Withdrawal {
+						main_account: main,
+						amount,
+						asset,
+						fees: Decimal::zero(),
+						stid,
+						worker_nonce,
+					});
+				},
+			_ => {},
+		}
+	}
+	Ok(())
+}
+
+/// Checks a withdrawal request against the proxies of the main account and deducts the withdrawn
+/// amount from its balance.
+///
+/// # Parameters
+///
+/// * `trie`: A mutable reference to a `TrieDBMut` object of type `ExtensionLayout`.
+/// * `withdraw`: Withdraw request DTO.
+/// * `worker_nonce`: Worker nonce required for the snapshot generation.
+/// * `state_change_id`: State change id required for the snapshot generation.
+///
+/// # Returns
+///
+/// A `Result<Withdrawal<AccountId>, Error>` containing the withdrawal to be included in the next
+/// snapshot.
+pub fn process_withdrawal_request(
+	trie: &mut TrieDBMut<ExtensionLayout>,
+	withdraw: WithdrawalRequest,
+	worker_nonce: u64,
+	state_change_id: u64,
+) -> Result<Withdrawal<AccountId>, Error> {
+	// Check proxy registration and its permission to withdraw
+	check_proxy_permissions(trie, &withdraw.main, &withdraw.proxy, ProxyPermissions::WITHDRAW)?;
+	// Verify signature
+	if !withdraw.verify() {
+		return Err(Error::WithdrawSignatureCheckFailed)
+	}
+	// Deduct balance
+	sub_balance(trie, withdraw.account_asset(), withdraw.amount()?)?;
+	Ok(withdraw.convert(state_change_id, worker_nonce)?)
+}
+
+/// Returns the label of a user action used in the metrics.
+///
+/// # Parameters
+///
+/// * `action`: User action.
+fn action_label(action: &UserActions) -> &'static str {
+	match action {
+		UserActions::Trade(_) => "trade",
+		UserActions::Withdraw(_) => "withdraw",
+		UserActions::BlockImport(_) => "block_import",
+		UserActions::Reset => "reset",
+		UserActions::PlaceOrder(_) => "place_order",
+		UserActions::CancelOrder(..) => "cancel_order",
+	}
+}
+
+/// Returns the account asset and the amount to be kept reserved for the unfilled part of an open
+/// order.
+///
+/// # Parameters
+///
+/// * `order`: Open order.
+pub(crate) fn order_reservation(order: &Order) -> (AccountAsset, Decimal) {
+	let remaining = order.qty.saturating_sub(order.filled_quantity).max(Decimal::zero());
+	match order.side {
+		OrderSide::Ask =>
+			(AccountAsset::new(order.main_account.clone(), order.pair.base), remaining),
+		OrderSide::Bid => (
+			AccountAsset::new(order.main_account.clone(), order.pair.quote),
+			order.price.saturating_mul(remaining),
+		),
+	}
+}
+
+/// Reads an open order from the trie.
+///
+/// # Parameters
+///
+/// * `trie`: A reference to a `TrieDBMut` object of type `ExtensionLayout`.
+/// * `pair`: Trading pair of the order.
+/// * `id`: Order identifier.
+///
+/// # Returns
+///
+/// A `Result<Option<Order>, Error>` with the open order, or `None` if the order is not open.
+pub fn get_open_order(
+	trie: &TrieDBMut<ExtensionLayout>,
+	pair: &TradingPair,
+	id: &OrderId,
+) -> Result<Option<Order>, Error> {
+	match trie.get(&Order::open_order_key(pair, id))? {
+		Some(data) => Ok(Some(Order::decode(&mut &data[..])?)),
+		None => Ok(None),
+	}
+}
+
+/// Reads the price of the last trade of a trading pair from the trie.
+///
+/// # Parameters
+///
+/// * `trie`: A reference to a `TrieDBMut` object of type `ExtensionLayout`.
+/// * `pair`: Trading pair.
+///
+/// # Returns
+///
+/// A `Result<Option<Decimal>, Error>` with the last trade price, or `None` if no trade happened.
+pub fn get_last_trade_price(
+	trie: &TrieDBMut<ExtensionLayout>,
+	pair: &TradingPair,
+) -> Result<Option<Decimal>, Error> {
+	match trie.get(&pair.last_trade_price_key())? {
+		Some(data) => Ok(Some(Decimal::decode(&mut &data[..])?)),
+		None => Ok(None),
+	}
+}
+
+/// Returns the part of the reserved balance of the account asset held by its open orders.
+///
+/// # Parameters
+///
+/// * `trie`: A reference to a `TrieDBMut` object of type `ExtensionLayout`.
+/// * `account_asset`: Account asset to look for in the trie.
+pub fn get_order_reserved_balance(
+	trie: &TrieDBMut<ExtensionLayout>,
+	account_asset: &AccountAsset,
+) -> Result<Decimal, Error> {
+	match trie.get(&account_asset.order_reserved_balance_key())? {
+		Some(data) => Ok(Decimal::decode(&mut &data[..])?),
+		None => Ok(Decimal::zero()),
+	}
+}
+
+/// Reserves the balance required to fill an open order.
+///
+/// # Parameters
+///
+/// * `trie`: A mutable reference to a `TrieDBMut` object of type `ExtensionLayout`.
+/// * `account_asset`: Account asset to reserve the balance of.
+/// * `amount`: Amount to be reserved.
+fn reserve_order_balance(
+	trie: &mut TrieDBMut<ExtensionLayout>,
+	account_asset: AccountAsset,
+	amount: Decimal,
+) -> Result<(), Error> {
+	let order_reserved = get_order_reserved_balance(trie, &account_asset)?.saturating_add(amount);
+	reserve_balance(trie, account_asset.clone(), amount)?;
+	trie.insert(&account_asset.order_reserved_balance_key(), &order_reserved.encode())?;
+	Ok(())
+}
+
+/// Releases the balance reserved by an open order.
+///
+/// # Parameters
+///
+/// * `trie`: A mutable reference to a `TrieDBMut` object of type `ExtensionLayout`.
+/// * `account_asset`: Account asset to unreserve the balance of.
+/// * `amount`: Amount to be unreserved.
+fn unreserve_order_balance(
+	trie: &mut TrieDBMut<ExtensionLayout>,
+	account_asset: AccountAsset,
+	amount: Decimal,
+) -> Result<(), Error> {
+	// Saturating, as orders opened before the held balances were tracked are not accounted
+	let order_reserved = get_order_reserved_balance(trie, &account_asset)?.saturating_sub(amount);
+	unreserve_balance(trie, account_asset.clone(), amount)?;
+	if order_reserved.is_zero() {
+		trie.remove(&account_asset.order_reserved_balance_key())?;
+	} else {
+		trie.insert(&account_asset.order_reserved_balance_key(), &order_reserved.encode())?;
+	}
+	Ok(())
+}
+
+/// Stores a placed order resting in the book as open and reserves the balance required to fill
+/// it.
+///
+/// MARKET orders and orders which are not good till cancelled never rest in the book, nothing is
+/// reserved for them. The proxy signing the order must be allowed to trade in any case.
+///
+/// # Parameters
+///
+/// * `trie`: A mutable reference to a `TrieDBMut` object of type `ExtensionLayout`.
+/// * `order`: Placed order.
+///
+/// # Returns
+///
+/// A `Result<(), Error>` indicating whether the order was opened or not.
+pub fn open_order(trie: &mut TrieDBMut<ExtensionLayout>, order: &Order) -> Result<(), Error> {
+	check_proxy_permissions(trie, &order.main_account, &order.user, ProxyPermissions::TRADE)?;
+	if !order.is_resting() {
+		return Ok(())
+	}
+	info!(target: "orderbook", "📒 Opening order: {:?}", order.id);
+	let key = Order::open_order_key(&order.pair, &order.id);
+	if trie.contains(&key)? {
+		return Err(Error::OrderAlreadyOpen)
+	}
+	let (account_asset, amount) = order_reservation(order);
+	reserve_order_balance(trie, account_asset, amount)?;
+	trie.insert(&key, &order.encode())?;
+	Ok(())
+}
+
+/// Removes an order from the open orders of the trie.
+///
+/// # Parameters
+///
+/// * `trie`: A mutable reference to a `TrieDBMut` object of type `ExtensionLayout`.
+/// * `order`: Open order.
+fn close_order(trie: &mut TrieDBMut<ExtensionLayout>, order: &Order) -> Result<(), Error> {
+	info!(target: "orderbook", "📒 Closing order: {:?}", order.id);
+	trie.remove(&Order::open_order_key(&order.pair, &order.id))?;
+	Ok(())
+}
+
+/// Checks if the matching engine rejected a placed order, a POST_ONLY order that would take
+/// liquidity is neither matched nor added to the book. Its reservation has to be released as the
+/// operator will never trade or cancel it.
+///
+/// # Parameters
+///
+/// * `engine`: Matching engine the order was placed with.
+/// * `order`: Placed order.
+/// * `trades`: Trades produced by the matching engine for the order.
+pub fn is_rejected_by_matching_engine(
+	engine: &MatchingEngine,
+	order: &Order,
+	trades: &[Trade],
+) -> bool {
+	order.order_type == OrderType::POST_ONLY &&
+		trades.is_empty() &&
+		engine.book(&order.pair).and_then(|book| book.get(&order.id)).is_none()
+}
+
+/// Closes a cancelled open order and releases the balance reserved for its unfilled part.
+///
+/// # Parameters
+///
+/// * `trie`: A mutable reference to a `TrieDBMut` object of type `ExtensionLayout`.
+/// * `pair`: Trading pair of the order.
+/// * `id`: Order identifier.
+///
+/// # Returns
+///
+/// A `Result<Option<Order>, Error>` with the cancelled order, or `None` if the order is not open.
+pub fn cancel_open_order(
+	trie: &mut TrieDBMut<ExtensionLayout>,
+	pair: &TradingPair,
+	id: &OrderId,
+) -> Result<Option<Order>, Error> {
+	let mut order = match get_open_order(trie, pair, id)? {
+		Some(order) => order,
+		None => return Ok(None),
+	};
+	let (account_asset, amount) = order_reservation(&order);
+	unreserve_order_balance(trie, account_asset, amount)?;
+	close_order(trie, &order)?;
+	order.status = OrderStatus::CANCELLED;
+	Ok(Some(order))
+}
+
+/// Updates the filled quantity of an open order and releases the balance reserved for the
+/// filled part, so that the trade can be debited from the free balance. The order is closed once
+/// fully filled. Nothing is done if the order is not open.
+///
+/// # Parameters
+///
+/// * `trie`: A mutable reference to a `TrieDBMut` object of type `ExtensionLayout`.
+/// * `pair`: Trading pair of the order.
+/// * `id`: Order identifier.
+/// * `price`: Trade price.
+/// * `amount`: Trade amount.
+fn fill_open_order(
+	trie: &mut TrieDBMut<ExtensionLayout>,
+	pair: &TradingPair,
+	id: &OrderId,
+	price: Decimal,
+	amount: Decimal,
+) -> Result<(), Error> {
+	let mut order = match get_open_order(trie, pair, id)? {
+		Some(order) => order,
+		None => return Ok(()),
+	};
+	let (account_asset, reserved_before) = order_reservation(&order);
+	order.update_avg_price_and_filled_qty(price, amount);
+	let (_, reserved_after) = order_reservation(&order);
+	unreserve_order_balance(trie, account_asset, reserved_before.saturating_sub(reserved_after))?;
+	if order.filled_quantity >= order.qty {
+		order.status = OrderStatus::CLOSED;
+		close_order(trie, &order)
+	} else {
+		trie.insert(&Order::open_order_key(&order.pair, &order.id), &order.encode())?;
+		Ok(())
+	}
+}
+
+/// Returns the trading fees collected since the last snapshot by asset.
+///
+/// # Parameters
+///
+/// * `trie`: A reference to a `TrieDBMut` object of type `ExtensionLayout`.
+///
+/// # Returns
+///
+/// A `Result<BTreeMap<AssetId, Decimal>, Error>` with the collected fees.
+pub fn get_collected_fees(
+	trie: &TrieDBMut<ExtensionLayout>,
+) -> Result<BTreeMap<AssetId, Decimal>, Error> {
+	match trie.get(ORDERBOOK_COLLECTED_FEES_KEY)? {
+		Some(data) => Ok(BTreeMap::decode(&mut &data[..])?),
+		None => Ok(BTreeMap::new()),
+	}
+}
+
+/// Removes the trading fees collected since the last snapshot from the state and returns them,
+/// they are paid out by the snapshot.
+///
+/// # Parameters
+///
+/// * `trie`: A mutable reference to a `TrieDBMut` object of type `ExtensionLayout`.
+///
+/// # Returns
+///
+/// A `Result<Vec<Fees>, Error>` with the collected fees by asset.
+pub fn take_collected_fees(trie: &mut TrieDBMut<ExtensionLayout>) -> Result<Vec<Fees>, Error> {
+	let fees = get_collected_fees(trie)?;
+	trie.remove(ORDERBOOK_COLLECTED_FEES_KEY)?;
+	Ok(fees.into_iter().map(|(asset, amount)| Fees { asset, amount }).collect())
+}
+
+/// Returns the fee configuration of the main account, the default configuration is returned if
+/// none was assigned.
+///
+/// # Parameters
+///
+/// * `trie`: A reference to a `TrieDBMut` object of type `ExtensionLayout`.
+/// * `main`: Main account identifier.
+///
+/// # Returns
+///
+/// A `Result<FeeConfig, Error>` with the fee configuration of the account.
+pub fn get_fee_config(
+	trie: &TrieDBMut<ExtensionLayout>,
+	main: &AccountId,
+) -> Result<FeeConfig, Error> {
+	match trie.get(&(ORDERBOOK_FEE_CONFIG_PREFIX, main).encode())? {
+		Some(data) => Ok(FeeConfig::decode(&mut &data[..])?),
+		None => Ok(FeeConfig::default()),
+	}
+}
+
+/// Stores the fee configuration of the main account.
+///
+/// # Parameters
+///
+/// * `trie`: A mutable reference to a `TrieDBMut` object of type `ExtensionLayout`.
+/// * `main`: Main account identifier.
+/// * `config`: Fee configuration to be applied to the account trades.
+///
+/// # Returns
+///
+/// A `Result<(), Error>` indicating whether the configuration was stored or not.
+pub fn set_fee_config(
+	trie: &mut TrieDBMut<ExtensionLayout>,
+	main: &AccountId,
+	config: FeeConfig,
+) -> Result<(), Error> {
+	info!(target: "orderbook", "📒 Setting fee config: {:?} for: {:?}", config, main);
+	if !trie.contains(&main.encode())? {
+		return Err(Error::MainAccountNotFound)
+	}
+	trie.insert(&(ORDERBOOK_FEE_CONFIG_PREFIX, main).encode(), &config.encode())?;
+	Ok(())
+}
+
+/// Credits a trade participant with the traded amount minus the fee, and adds the fee to the
+/// fees collected since the last snapshot.
+///
+/// # Parameters
+///
+/// * `trie`: A mutable reference to a `TrieDBMut` object of type `ExtensionLayout`.
+/// * `account_asset`: Credited account and asset.
+/// * `amount`: Traded amount.
+/// * `fee`: Fee charged on the traded amount.
+///
+/// # Returns
+///
+/// A `Result<(), Error>` indicating whether the account was credited or not.
+fn credit_with_fee(
+	trie: &mut TrieDBMut<ExtensionLayout>,
+	account_asset: AccountAsset,
+	amount: Decimal,
+	fee: Decimal,
+) -> Result<(), Error> {
+	let fee = fee.min(amount);
+	let asset = account_asset.asset;
+	add_balance(trie, account_asset, amount.saturating_sub(fee))?;
+	if fee.is_zero() {
+		return Ok(())
+	}
+	let mut fees = get_collected_fees(trie)?;
+	let collected = fees.entry(asset).or_default();
+	*collected = collected.saturating_add(fee);
+	trie.insert(ORDERBOOK_COLLECTED_FEES_KEY, &fees.encode())?;
+	Ok(())
+}
+
 /// Processes a trade between a maker and a taker, updating their order states and balances
 /// accordingly.
 ///
+/// Maker and taker fees of the credited amounts are collected until the next snapshot.
+///
 /// # Parameters
 ///
 /// * `trie`: A mutable reference to a `TrieDBMut` object of type `ExtensionLayout`.
@@ -1769,16 +2958,30 @@ pub fn process_trade(
 		error!(target: "orderbook", "📒 Trade verification failed");
 		return Err(Error::InvalidTrade)
 	}
+	for order in [&trade.maker, &trade.taker] {
+		check_proxy_permissions(trie, &order.main_account, &order.user, ProxyPermissions::TRADE)?;
+	}
+
+	// Release the balances reserved for the filled part of the orders before debiting them
+	fill_open_order(trie, &trade.maker.pair, &trade.maker.id, trade.price, trade.amount)?;
+	fill_open_order(trie, &trade.taker.pair, &trade.taker.id, trade.price, trade.amount)?;
+	// The last trade price triggers the STOP_LIMIT orders
+	trie.insert(&trade.maker.pair.last_trade_price_key(), &trade.price.encode())?;
+
+	let maker_fee_config = get_fee_config(trie, &trade.maker.main_account)?;
+	let taker_fee_config = get_fee_config(trie, &trade.taker.main_account)?;
 
 	// Update balances
 	let (maker_asset, maker_credit) = trade.credit(true);
-	add_balance(trie, maker_asset, maker_credit)?;
+	let maker_fee = maker_fee_config.maker_fee(maker_credit);
+	credit_with_fee(trie, maker_asset, maker_credit, maker_fee)?;
 
 	let (maker_asset, maker_debit) = trade.debit(true);
 	sub_balance(trie, maker_asset, maker_debit)?;
 
 	let (taker_asset, taker_credit) = trade.credit(false);
-	add_balance(trie, taker_asset, taker_credit)?;
+	let taker_fee = taker_fee_config.taker_fee(taker_credit);
+	credit_with_fee(trie, taker_asset, taker_credit, taker_fee)?;
 
 	let (taker_asset, taker_debit) = trade.debit(false);
 	sub_balance(trie, taker_asset, taker_debit)?;
@@ -20,18 +20,37 @@
 
 use crate::{
 	error::Error,
-	worker::{add_proxy, deposit, process_trade, register_main, remove_proxy},
+	matching::MatchingEngine,
+	snapshot::read_open_orders,
+	utils::get_reserved_balance,
+	worker::{
+		add_proxy, cancel_open_order, deposit, get_collected_fees, get_exchange_state,
+		is_rejected_by_matching_engine, open_order, process_direct_withdrawal, process_trade,
+		process_withdrawal_request, register_main, remove_proxy, set_exchange_state,
+		set_fee_config, set_free_reserve_balance_for_accounts, set_proxy_permissions,
+		take_collected_fees,
+	},
 };
 use memory_db::{HashKey, MemoryDB};
-use orderbook_primitives::types::{
-	AccountAsset, AccountInfo, Order, OrderPayload, OrderSide, OrderType, Trade, TradingPair,
+use orderbook_primitives::{
+	types::{
+		AccountAsset, AccountInfo, Order, OrderPayload, OrderSide, OrderType, Trade, TradingPair,
+		WithdrawPayloadCallByUser, WithdrawalRequest,
+	},
+	Fees,
 };
 use parity_scale_codec::{Decode, Encode};
-use polkadex_primitives::{ocex::TradingPairConfig, AccountId, AssetId, Signature};
+use polkadex_primitives::{
+	fees::FeeConfig,
+	ingress::HandleBalance,
+	ocex::{ProxyPermissions, TradingPairConfig},
+	AccountId, AssetId, Signature, UNIT_BALANCE,
+};
 use reference_trie::{ExtensionLayout, RefHasher};
 use rust_decimal::Decimal;
-use sp_core::Pair;
+use sp_core::{Pair, H256};
 use sp_keyring::AccountKeyring;
+use std::collections::BTreeMap;
 use trie_db::{TrieDBMut, TrieDBMutBuilder, TrieMut};
 
 /// This function returns a tuple containing Alice's main account and a proxy account.
@@ -267,3 +286,544 @@ pub fn process_trade_will_process_successfully() {
 	assert_eq!(bob_balance_asset_1, Decimal::from(20));
 	assert_eq!(bob_balance_asset_2, Decimal::from(20));
 }
+
+/// Set balances from governance and assert the previous balance is overwritten.
+#[test]
+pub fn set_free_reserve_balance_for_accounts_will_overwrite_balance() {
+	let mut working_state_root = [0u8; 32];
+	let mut memory_db: MemoryDB<RefHasher, HashKey<RefHasher>, Vec<u8>> = Default::default();
+	let mut trie: TrieDBMut<ExtensionLayout> =
+		TrieDBMutBuilder::new(&mut memory_db, &mut working_state_root).build();
+	let (alice_main, alice_proxy) = get_alice_main_and_proxy_account();
+	let asset_id = AssetId::Asset(1);
+	assert!(register_main(&mut trie, alice_main.clone(), alice_proxy).is_ok());
+	assert!(deposit(&mut trie, alice_main.clone(), asset_id, Decimal::new(10, 0)).is_ok());
+
+	let balances = vec![HandleBalance {
+		main_account: alice_main.clone(),
+		asset_id,
+		free: 5 * UNIT_BALANCE,
+		reserve: UNIT_BALANCE / 2,
+	}];
+	assert!(set_free_reserve_balance_for_accounts(&mut trie, &balances).is_ok());
+
+	let account_asset = AccountAsset { main: alice_main, asset: asset_id };
+	let get_db_val = trie.get(&account_asset.encode()).unwrap().unwrap().to_vec();
+	let balance = Decimal::decode(&mut &get_db_val[..]).unwrap();
+	assert_eq!(balance, Decimal::new(5, 0));
+	assert_eq!(get_reserved_balance(&trie, &account_asset), Ok(Decimal::new(5, 1)));
+}
+
+/// Set balance of a not registered main account and assert the entry is skipped.
+#[test]
+pub fn set_free_reserve_balance_for_not_registered_main_account_will_be_skipped() {
+	let mut working_state_root = [0u8; 32];
+	let mut memory_db: MemoryDB<RefHasher, HashKey<RefHasher>, Vec<u8>> = Default::default();
+	let mut trie: TrieDBMut<ExtensionLayout> =
+		TrieDBMutBuilder::new(&mut memory_db, &mut working_state_root).build();
+	let (alice_main, _alice_proxy) = get_alice_main_and_proxy_account();
+	let balances = vec![HandleBalance {
+		main_account: alice_main,
+		asset_id: AssetId::Polkadex,
+		free: UNIT_BALANCE,
+		reserve: 0,
+	}];
+	trie.commit();
+	let root_before = *trie.root();
+	assert_eq!(set_free_reserve_balance_for_accounts(&mut trie, &balances), Ok(()));
+	trie.commit();
+	assert_eq!(*trie.root(), root_before);
+}
+
+/// Set balances of an account with an open order and assert the balance held by the order stays
+/// reserved, so that the order can still be cancelled.
+#[test]
+pub fn set_free_reserve_balance_for_accounts_will_keep_open_order_reservations() {
+	let mut working_state_root = [0u8; 32];
+	let mut memory_db: MemoryDB<RefHasher, HashKey<RefHasher>, Vec<u8>> = Default::default();
+	let mut trie: TrieDBMut<ExtensionLayout> =
+		TrieDBMutBuilder::new(&mut memory_db, &mut working_state_root).build();
+	let (alice_main, alice_proxy) = get_alice_main_and_proxy_account();
+	let (base, quote) = (AssetId::Polkadex, AssetId::Asset(1));
+	assert!(register_main(&mut trie, alice_main.clone(), alice_proxy.clone()).is_ok());
+	assert!(deposit(&mut trie, alice_main.clone(), base, Decimal::new(10, 0)).is_ok());
+
+	let trading_pair = TradingPair { base, quote };
+	let mut order = Order::random_order_for_testing(trading_pair, OrderSide::Ask, OrderType::LIMIT);
+	order.price = Decimal::from(3_u32);
+	order.qty = Decimal::from(2_u32);
+	order.user = alice_proxy;
+	order.main_account = alice_main.clone();
+	assert!(open_order(&mut trie, &order).is_ok());
+
+	let balances = vec![HandleBalance {
+		main_account: alice_main.clone(),
+		asset_id: base,
+		free: 5 * UNIT_BALANCE,
+		reserve: UNIT_BALANCE,
+	}];
+	assert!(set_free_reserve_balance_for_accounts(&mut trie, &balances).is_ok());
+	let alice_base = AccountAsset::new(alice_main.clone(), base);
+	assert_eq!(get_balance(&trie, &alice_main, base), Decimal::new(5, 0));
+	assert_eq!(get_reserved_balance(&trie, &alice_base), Ok(Decimal::new(3, 0)));
+
+	assert!(cancel_open_order(&mut trie, &trading_pair, &order.id).unwrap().is_some());
+	assert_eq!(get_balance(&trie, &alice_main, base), Decimal::new(7, 0));
+	assert_eq!(get_reserved_balance(&trie, &alice_base), Ok(Decimal::new(1, 0)));
+}
+
+/// Apply the same governance balance reset on independent states and assert that every node
+/// ends up with the same state root.
+#[test]
+pub fn set_free_reserve_balance_for_accounts_will_produce_deterministic_state_root() {
+	let (alice_main, alice_proxy) = get_alice_main_and_proxy_account();
+	let (bob_main, bob_proxy) = get_bob_main_and_proxy_account();
+	let balances = vec![
+		HandleBalance {
+			main_account: alice_main.clone(),
+			asset_id: AssetId::Polkadex,
+			free: 3 * UNIT_BALANCE,
+			reserve: 0,
+		},
+		HandleBalance {
+			main_account: bob_main.clone(),
+			asset_id: AssetId::Asset(1),
+			free: 7 * UNIT_BALANCE,
+			reserve: UNIT_BALANCE,
+		},
+	];
+
+	let mut roots = vec![];
+	for _validator in 0..3 {
+		let mut working_state_root = [0u8; 32];
+		let mut memory_db: MemoryDB<RefHasher, HashKey<RefHasher>, Vec<u8>> = Default::default();
+		let mut trie: TrieDBMut<ExtensionLayout> =
+			TrieDBMutBuilder::new(&mut memory_db, &mut working_state_root).build();
+		assert!(register_main(&mut trie, alice_main.clone(), alice_proxy.clone()).is_ok());
+		assert!(register_main(&mut trie, bob_main.clone(), bob_proxy.clone()).is_ok());
+		assert!(
+			deposit(&mut trie, alice_main.clone(), AssetId::Polkadex, Decimal::new(10, 0)).is_ok()
+		);
+		trie.commit();
+		let root_before = *trie.root();
+
+		assert!(set_free_reserve_balance_for_accounts(&mut trie, &balances).is_ok());
+		trie.commit();
+		let root_after = *trie.root();
+		assert_ne!(root_before, root_after);
+		roots.push(root_after);
+	}
+	assert!(roots.windows(2).all(|pair| pair[0] == pair[1]));
+}
+
+/// Process a direct withdrawal with enough balance and assert the requested amount is debited.
+#[test]
+pub fn process_direct_withdrawal_will_debit_requested_amount() {
+	let mut working_state_root = [0u8; 32];
+	let mut memory_db: MemoryDB<RefHasher, HashKey<RefHasher>, Vec<u8>> = Default::default();
+	let mut trie: TrieDBMut<ExtensionLayout> =
+		TrieDBMutBuilder::new(&mut memory_db, &mut working_state_root).build();
+	let (alice_main, alice_proxy) = get_alice_main_and_proxy_account();
+	assert!(register_main(&mut trie, alice_main.clone(), alice_proxy).is_ok());
+	assert!(deposit(&mut trie, alice_main.clone(), AssetId::Polkadex, Decimal::new(10, 0)).is_ok());
+
+	assert_eq!(
+		process_direct_withdrawal(
+			&mut trie,
+			alice_main.clone(),
+			AssetId::Polkadex,
+			Decimal::new(4, 0),
+			false
+		),
+		Ok(Some(Decimal::new(4, 0)))
+	);
+
+	let account_asset = AccountAsset { main: alice_main, asset: AssetId::Polkadex };
+	let get_db_val = trie.get(&account_asset.encode()).unwrap().unwrap().to_vec();
+	let balance = Decimal::decode(&mut &get_db_val[..]).unwrap();
+	assert_eq!(balance, Decimal::new(6, 0));
+}
+
+/// Process direct withdrawals exceeding the balance and assert that only forced ones are debited.
+#[test]
+pub fn process_direct_withdrawal_with_insufficient_balance() {
+	let mut working_state_root = [0u8; 32];
+	let mut memory_db: MemoryDB<RefHasher, HashKey<RefHasher>, Vec<u8>> = Default::default();
+	let mut trie: TrieDBMut<ExtensionLayout> =
+		TrieDBMutBuilder::new(&mut memory_db, &mut working_state_root).build();
+	let (alice_main, alice_proxy) = get_alice_main_and_proxy_account();
+	assert!(register_main(&mut trie, alice_main.clone(), alice_proxy).is_ok());
+	assert!(deposit(&mut trie, alice_main.clone(), AssetId::Polkadex, Decimal::new(10, 0)).is_ok());
+
+	assert_eq!(
+		process_direct_withdrawal(
+			&mut trie,
+			alice_main.clone(),
+			AssetId::Polkadex,
+			Decimal::new(15, 0),
+			false
+		),
+		Ok(None)
+	);
+	assert_eq!(
+		process_direct_withdrawal(
+			&mut trie,
+			alice_main.clone(),
+			AssetId::Polkadex,
+			Decimal::new(15, 0),
+			true
+		),
+		Ok(Some(Decimal::new(10, 0)))
+	);
+	assert_eq!(
+		process_direct_withdrawal(&mut trie, alice_main, AssetId::Polkadex, Decimal::ONE, true),
+		Ok(None)
+	);
+}
+
+/// Try to process a direct withdrawal of a not registered main account and assert expected error.
+#[test]
+pub fn process_direct_withdrawal_for_not_registered_main_account_will_return_error() {
+	let mut working_state_root = [0u8; 32];
+	let mut memory_db: MemoryDB<RefHasher, HashKey<RefHasher>, Vec<u8>> = Default::default();
+	let mut trie: TrieDBMut<ExtensionLayout> =
+		TrieDBMutBuilder::new(&mut memory_db, &mut working_state_root).build();
+	let (alice_main, _alice_proxy) = get_alice_main_and_proxy_account();
+	assert_eq!(
+		process_direct_withdrawal(&mut trie, alice_main, AssetId::Polkadex, Decimal::ONE, true),
+		Err(Error::MainAccountNotFound)
+	);
+}
+
+/// Assert that the exchange is operational by default and that its state survives reloading the
+/// trie from the same memory db, as done when a snapshot is loaded.
+#[test]
+pub fn set_exchange_state_will_be_restored_from_state() {
+	let mut working_state_root = [0u8; 32];
+	let mut memory_db: MemoryDB<RefHasher, HashKey<RefHasher>, Vec<u8>> = Default::default();
+	{
+		let mut trie: TrieDBMut<ExtensionLayout> =
+			TrieDBMutBuilder::new(&mut memory_db, &mut working_state_root).build();
+		let (alice_main, alice_proxy) = get_alice_main_and_proxy_account();
+		assert!(register_main(&mut trie, alice_main, alice_proxy).is_ok());
+		assert_eq!(get_exchange_state(&trie), Ok(true));
+		assert!(set_exchange_state(&mut trie, false).is_ok());
+		assert_eq!(get_exchange_state(&trie), Ok(false));
+		trie.commit();
+	}
+
+	let mut restored_state_root = working_state_root;
+	let mut trie: TrieDBMut<ExtensionLayout> =
+		TrieDBMutBuilder::from_existing(&mut memory_db, &mut restored_state_root).build();
+	assert_eq!(get_exchange_state(&trie), Ok(false));
+	assert!(set_exchange_state(&mut trie, true).is_ok());
+	assert_eq!(get_exchange_state(&trie), Ok(true));
+}
+
+/// Process a trade between accounts with fee configs and assert that the fees are collected from
+/// the credited amounts until the snapshot pays them out.
+#[test]
+pub fn process_trade_will_collect_maker_and_taker_fees() {
+	let mut working_state_root = [0u8; 32];
+	let mut memory_db: MemoryDB<RefHasher, HashKey<RefHasher>, Vec<u8>> = Default::default();
+	let mut trie: TrieDBMut<ExtensionLayout> =
+		TrieDBMutBuilder::new(&mut memory_db, &mut working_state_root).build();
+	let (alice_main, alice_proxy) = get_alice_main_and_proxy_account();
+	let (bob_main, bob_proxy) = get_bob_main_and_proxy_account();
+	let base = AssetId::Asset(1);
+	let quote = AssetId::Asset(2);
+	for (main, proxy) in
+		[(alice_main.clone(), alice_proxy.clone()), (bob_main.clone(), bob_proxy.clone())]
+	{
+		assert!(register_main(&mut trie, main.clone(), proxy).is_ok());
+		assert!(deposit(&mut trie, main.clone(), base, Decimal::new(10, 0)).is_ok());
+		assert!(deposit(&mut trie, main, quote, Decimal::new(10, 0)).is_ok());
+	}
+	let fee_config = FeeConfig::new(Decimal::new(1, 1), Decimal::new(2, 1));
+	assert!(set_fee_config(&mut trie, &alice_main, fee_config).is_ok());
+	assert!(set_fee_config(&mut trie, &bob_main, fee_config).is_ok());
+
+	let trading_pair = TradingPair { base, quote };
+	let mut alice_ask_limit_order =
+		Order::random_order_for_testing(trading_pair, OrderSide::Ask, OrderType::LIMIT);
+	alice_ask_limit_order.price = Decimal::from(3_u32);
+	alice_ask_limit_order.qty = Decimal::from(2_u32);
+	alice_ask_limit_order.user = alice_proxy;
+	alice_ask_limit_order.main_account = alice_main.clone();
+	alice_ask_limit_order.signature = Signature::from(
+		AccountKeyring::Charlie
+			.pair()
+			.sign(&OrderPayload::from(alice_ask_limit_order.clone()).encode()[..]),
+	);
+
+	let mut bob_bid_limit_order =
+		Order::random_order_for_testing(trading_pair, OrderSide::Bid, OrderType::LIMIT);
+	bob_bid_limit_order.price = Decimal::from(3_u32);
+	bob_bid_limit_order.qty = Decimal::from(2_u32);
+	bob_bid_limit_order.user = bob_proxy;
+	bob_bid_limit_order.main_account = bob_main.clone();
+	bob_bid_limit_order.signature = Signature::from(
+		AccountKeyring::Eve
+			.pair()
+			.sign(&OrderPayload::from(bob_bid_limit_order.clone()).encode()[..]),
+	);
+
+	// Bob is the maker, Alice is the taker
+	let trade =
+		Trade::new(bob_bid_limit_order, alice_ask_limit_order, Decimal::from(3), Decimal::from(2));
+	let config = TradingPairConfig::default(base, quote);
+	assert_eq!(process_trade(&mut trie, trade, config), Ok(()));
+
+	assert_eq!(get_balance(&trie, &bob_main, base), Decimal::new(118, 1));
+	assert_eq!(get_balance(&trie, &bob_main, quote), Decimal::new(4, 0));
+	assert_eq!(get_balance(&trie, &alice_main, base), Decimal::new(8, 0));
+	assert_eq!(get_balance(&trie, &alice_main, quote), Decimal::new(148, 1));
+	assert_eq!(
+		get_collected_fees(&trie),
+		Ok(BTreeMap::from([(base, Decimal::new(2, 1)), (quote, Decimal::new(12, 1))]))
+	);
+
+	// The snapshot pays out the collected fees
+	assert_eq!(
+		take_collected_fees(&mut trie),
+		Ok(vec![
+			Fees { asset: base, amount: Decimal::new(2, 1) },
+			Fees { asset: quote, amount: Decimal::new(12, 1) },
+		])
+	);
+	assert_eq!(get_collected_fees(&trie), Ok(BTreeMap::new()));
+}
+
+/// Try to set the fee config of a not registered main account and assert expected error.
+#[test]
+pub fn set_fee_config_for_not_registered_main_account_will_return_error() {
+	let mut working_state_root = [0u8; 32];
+	let mut memory_db: MemoryDB<RefHasher, HashKey<RefHasher>, Vec<u8>> = Default::default();
+	let mut trie: TrieDBMut<ExtensionLayout> =
+		TrieDBMutBuilder::new(&mut memory_db, &mut working_state_root).build();
+	let (alice_main, _alice_proxy) = get_alice_main_and_proxy_account();
+	assert_eq!(
+		set_fee_config(&mut trie, &alice_main, FeeConfig::default()),
+		Err(Error::MainAccountNotFound)
+	);
+}
+
+/// Open a bid and an ask, fill the ask and part of the bid, then cancel the bid and assert that
+/// the reserved balances follow the unfilled part of the open orders.
+#[test]
+pub fn open_orders_will_reserve_and_release_balances() {
+	let mut working_state_root = [0u8; 32];
+	let mut memory_db: MemoryDB<RefHasher, HashKey<RefHasher>, Vec<u8>> = Default::default();
+	let mut trie: TrieDBMut<ExtensionLayout> =
+		TrieDBMutBuilder::new(&mut memory_db, &mut working_state_root).build();
+	let (alice_main, alice_proxy) = get_alice_main_and_proxy_account();
+	let (bob_main, bob_proxy) = get_bob_main_and_proxy_account();
+	let base = AssetId::Asset(1);
+	let quote = AssetId::Asset(2);
+	for (main, proxy) in
+		[(alice_main.clone(), alice_proxy.clone()), (bob_main.clone(), bob_proxy.clone())]
+	{
+		assert!(register_main(&mut trie, main.clone(), proxy).is_ok());
+		assert!(deposit(&mut trie, main.clone(), base, Decimal::new(10, 0)).is_ok());
+		assert!(deposit(&mut trie, main, quote, Decimal::new(10, 0)).is_ok());
+	}
+
+	let trading_pair = TradingPair { base, quote };
+	let mut bob_bid_limit_order =
+		Order::random_order_for_testing(trading_pair, OrderSide::Bid, OrderType::LIMIT);
+	bob_bid_limit_order.price = Decimal::from(3_u32);
+	bob_bid_limit_order.qty = Decimal::from(2_u32);
+	bob_bid_limit_order.user = bob_proxy;
+	bob_bid_limit_order.main_account = bob_main.clone();
+	bob_bid_limit_order.signature = Signature::from(
+		AccountKeyring::Eve
+			.pair()
+			.sign(&OrderPayload::from(bob_bid_limit_order.clone()).encode()[..]),
+	);
+
+	let mut alice_ask_limit_order =
+		Order::random_order_for_testing(trading_pair, OrderSide::Ask, OrderType::LIMIT);
+	alice_ask_limit_order.price = Decimal::from(3_u32);
+	alice_ask_limit_order.qty = Decimal::from(1_u32);
+	alice_ask_limit_order.user = alice_proxy;
+	alice_ask_limit_order.main_account = alice_main.clone();
+	alice_ask_limit_order.signature = Signature::from(
+		AccountKeyring::Charlie
+			.pair()
+			.sign(&OrderPayload::from(alice_ask_limit_order.clone()).encode()[..]),
+	);
+
+	let bob_quote = AccountAsset::new(bob_main.clone(), quote);
+	let alice_base = AccountAsset::new(alice_main.clone(), base);
+	assert!(open_order(&mut trie, &bob_bid_limit_order).is_ok());
+	assert_eq!(open_order(&mut trie, &bob_bid_limit_order), Err(Error::OrderAlreadyOpen));
+	assert!(open_order(&mut trie, &alice_ask_limit_order).is_ok());
+	assert_eq!(get_balance(&trie, &bob_main, quote), Decimal::new(4, 0));
+	assert_eq!(get_reserved_balance(&trie, &bob_quote), Ok(Decimal::new(6, 0)));
+	assert_eq!(get_balance(&trie, &alice_main, base), Decimal::new(9, 0));
+	assert_eq!(get_reserved_balance(&trie, &alice_base), Ok(Decimal::new(1, 0)));
+
+	// Bob is the maker, Alice is the taker
+	let trade = Trade::new(
+		bob_bid_limit_order.clone(),
+		alice_ask_limit_order,
+		Decimal::from(3),
+		Decimal::from(1),
+	);
+	let config = TradingPairConfig::default(base, quote);
+	assert!(process_trade(&mut trie, trade, config).is_ok());
+
+	assert_eq!(get_balance(&trie, &bob_main, quote), Decimal::new(4, 0));
+	assert_eq!(get_reserved_balance(&trie, &bob_quote), Ok(Decimal::new(3, 0)));
+	assert_eq!(get_balance(&trie, &bob_main, base), Decimal::new(11, 0));
+	assert_eq!(get_balance(&trie, &alice_main, base), Decimal::new(9, 0));
+	assert_eq!(get_reserved_balance(&trie, &alice_base), Ok(Decimal::new(0, 0)));
+	assert_eq!(get_balance(&trie, &alice_main, quote), Decimal::new(13, 0));
+	trie.commit();
+	drop(trie);
+	let open_orders = read_open_orders(&memory_db, &working_state_root, &trading_pair).unwrap();
+	assert_eq!(open_orders.len(), 1);
+	assert_eq!(open_orders[0].id, bob_bid_limit_order.id);
+	assert_eq!(open_orders[0].filled_quantity, Decimal::from(1));
+
+	let mut trie: TrieDBMut<ExtensionLayout> =
+		TrieDBMutBuilder::from_existing(&mut memory_db, &mut working_state_root).build();
+	let cancelled = cancel_open_order(&mut trie, &trading_pair, &bob_bid_limit_order.id).unwrap();
+	assert_eq!(cancelled.map(|order| order.id), Some(bob_bid_limit_order.id));
+	assert_eq!(get_balance(&trie, &bob_main, quote), Decimal::new(7, 0));
+	assert_eq!(get_reserved_balance(&trie, &bob_quote), Ok(Decimal::new(0, 0)));
+	assert_eq!(cancel_open_order(&mut trie, &trading_pair, &bob_bid_limit_order.id), Ok(None));
+	trie.commit();
+	drop(trie);
+	assert!(read_open_orders(&memory_db, &working_state_root, &trading_pair)
+		.unwrap()
+		.is_empty());
+}
+
+/// Place a POST_ONLY bid crossing a resting ask and assert that its reservation is released once
+/// the matching engine rejects it, while a POST_ONLY bid resting in the book keeps its reservation.
+#[test]
+pub fn post_only_order_rejected_by_matching_engine_will_release_its_reservation() {
+	let mut working_state_root = [0u8; 32];
+	let mut memory_db: MemoryDB<RefHasher, HashKey<RefHasher>, Vec<u8>> = Default::default();
+	let mut trie: TrieDBMut<ExtensionLayout> =
+		TrieDBMutBuilder::new(&mut memory_db, &mut working_state_root).build();
+	let (alice_main, alice_proxy) = get_alice_main_and_proxy_account();
+	let (bob_main, bob_proxy) = get_bob_main_and_proxy_account();
+	let (base, quote) = (AssetId::Asset(1), AssetId::Asset(2));
+	assert!(register_main(&mut trie, alice_main.clone(), alice_proxy.clone()).is_ok());
+	assert!(register_main(&mut trie, bob_main.clone(), bob_proxy.clone()).is_ok());
+	assert!(deposit(&mut trie, alice_main.clone(), base, Decimal::new(10, 0)).is_ok());
+	assert!(deposit(&mut trie, bob_main.clone(), quote, Decimal::new(10, 0)).is_ok());
+
+	let trading_pair = TradingPair { base, quote };
+	let config = TradingPairConfig::default(base, quote);
+	let mut engine = MatchingEngine::default();
+	let mut alice_ask =
+		Order::random_order_for_testing(trading_pair, OrderSide::Ask, OrderType::LIMIT);
+	alice_ask.price = Decimal::from(3_u32);
+	alice_ask.qty = Decimal::from(1_u32);
+	alice_ask.user = alice_proxy;
+	alice_ask.main_account = alice_main;
+	assert!(open_order(&mut trie, &alice_ask).is_ok());
+	assert!(engine.place_order(alice_ask.clone(), &config).unwrap().is_empty());
+	assert!(!is_rejected_by_matching_engine(&engine, &alice_ask, &[]));
+
+	let mut bob_bid =
+		Order::random_order_for_testing(trading_pair, OrderSide::Bid, OrderType::POST_ONLY);
+	bob_bid.id = H256([3; 32]);
+	bob_bid.price = Decimal::from(3_u32);
+	bob_bid.qty = Decimal::from(2_u32);
+	bob_bid.user = bob_proxy;
+	bob_bid.main_account = bob_main.clone();
+	let bob_quote = AccountAsset::new(bob_main.clone(), quote);
+	assert!(open_order(&mut trie, &bob_bid).is_ok());
+	assert_eq!(get_reserved_balance(&trie, &bob_quote), Ok(Decimal::new(6, 0)));
+	let trades = engine.place_order(bob_bid.clone(), &config).unwrap();
+	assert!(is_rejected_by_matching_engine(&engine, &bob_bid, &trades));
+	assert!(cancel_open_order(&mut trie, &trading_pair, &bob_bid.id).unwrap().is_some());
+	assert_eq!(get_balance(&trie, &bob_main, quote), Decimal::new(10, 0));
+	assert_eq!(get_reserved_balance(&trie, &bob_quote), Ok(Decimal::new(0, 0)));
+
+	bob_bid.id = H256([4; 32]);
+	bob_bid.price = Decimal::from(2_u32);
+	assert!(open_order(&mut trie, &bob_bid).is_ok());
+	let trades = engine.place_order(bob_bid.clone(), &config).unwrap();
+	assert!(!is_rejected_by_matching_engine(&engine, &bob_bid, &trades));
+	assert_eq!(get_reserved_balance(&trie, &bob_quote), Ok(Decimal::new(4, 0)));
+}
+
+/// Scope a proxy to trading only, then assert that it can place orders but its withdrawal
+/// requests are rejected while the unscoped proxy can still withdraw.
+#[test]
+pub fn trade_only_proxy_will_not_be_allowed_to_withdraw() {
+	let mut working_state_root = [0u8; 32];
+	let mut memory_db: MemoryDB<RefHasher, HashKey<RefHasher>, Vec<u8>> = Default::default();
+	let mut trie: TrieDBMut<ExtensionLayout> =
+		TrieDBMutBuilder::new(&mut memory_db, &mut working_state_root).build();
+	let (alice_main, alice_proxy) = get_alice_main_and_proxy_account();
+	let bot_proxy = AccountId::from(AccountKeyring::Ferdie.pair().public());
+	let base = AssetId::Asset(1);
+	let quote = AssetId::Asset(2);
+	assert!(register_main(&mut trie, alice_main.clone(), alice_proxy.clone()).is_ok());
+	assert!(add_proxy(&mut trie, alice_main.clone(), bot_proxy.clone()).is_ok());
+	assert!(set_proxy_permissions(
+		&mut trie,
+		alice_main.clone(),
+		bot_proxy.clone(),
+		ProxyPermissions::TRADE
+	)
+	.is_ok());
+	assert!(deposit(&mut trie, alice_main.clone(), base, Decimal::new(10, 0)).is_ok());
+
+	let withdrawal_request = |keyring: AccountKeyring, proxy: AccountId| {
+		let payload =
+			WithdrawPayloadCallByUser { asset_id: base, amount: "1".to_string(), timestamp: 0 };
+		WithdrawalRequest {
+			signature: Signature::from(keyring.pair().sign(&payload.encode())),
+			payload,
+			main: alice_main.clone(),
+			proxy,
+		}
+	};
+	assert_eq!(
+		process_withdrawal_request(
+			&mut trie,
+			withdrawal_request(AccountKeyring::Ferdie, bot_proxy.clone()),
+			1,
+			1
+		)
+		.map(|_| ()),
+		Err(Error::ProxyPermissionDenied(bot_proxy.clone(), ProxyPermissions::WITHDRAW))
+	);
+	assert_eq!(get_balance(&trie, &alice_main, base), Decimal::new(10, 0));
+	assert!(process_withdrawal_request(
+		&mut trie,
+		withdrawal_request(AccountKeyring::Charlie, alice_proxy),
+		2,
+		2
+	)
+	.is_ok());
+	assert_eq!(get_balance(&trie, &alice_main, base), Decimal::new(9, 0));
+
+	let trading_pair = TradingPair { base, quote };
+	let mut order = Order::random_order_for_testing(trading_pair, OrderSide::Ask, OrderType::LIMIT);
+	order.price = Decimal::from(1_u32);
+	order.qty = Decimal::from(2_u32);
+	order.user = bot_proxy.clone();
+	order.main_account = alice_main.clone();
+	assert!(open_order(&mut trie, &order).is_ok());
+
+	// Removing the proxy drops its scope
+	assert!(remove_proxy(&mut trie, alice_main.clone(), bot_proxy.clone()).is_ok());
+	let data = trie.get(&alice_main.encode()).unwrap().unwrap();
+	let account_info = AccountInfo::decode(&mut &data[..]).unwrap();
+	assert!(account_info.permissions.is_empty());
+	assert_eq!(account_info.proxy_permissions(&bot_proxy), None);
+}
+
+fn get_balance(trie: &TrieDBMut<ExtensionLayout>, main: &AccountId, asset: AssetId) -> Decimal {
+	let account_asset = AccountAsset::new(main.clone(), asset);
+	let data = trie.get(&account_asset.encode()).unwrap().unwrap();
+	Decimal::decode(&mut &data[..]).unwrap()
+}
@@ -0,0 +1,61 @@
+// This file is part of Polkadex.
+//
+// Copyright (c) 2023 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Defines RPC abstraction and concrete implementation required to communicate with the `Thea`
+//! worker.
+
+#![warn(missing_docs)]
+
+use std::collections::BTreeMap;
+
+use jsonrpsee::{
+	core::{async_trait, RpcResult},
+	proc_macros::rpc,
+};
+use thea_client::{ConnectorHealth, ConnectorsHealth};
+use thea_primitives::Network;
+
+#[rpc(client, server)]
+pub trait TheaApi {
+	/// Returns the health of the foreign connectors of this node by network.
+	#[method(name = "thea_connectorsHealth")]
+	async fn connectors_health(&self) -> RpcResult<BTreeMap<Network, ConnectorHealth>>;
+}
+
+/// A structure that represents the Thea RPC, which allows querying the state of the Thea worker.
+pub struct TheaRpc {
+	connectors_health: ConnectorsHealth,
+}
+
+impl TheaRpc {
+	/// Constructor.
+	///
+	/// # Parameters
+	///
+	/// * `connectors_health`: Foreign connectors shared with the Thea worker.
+	pub fn new(connectors_health: ConnectorsHealth) -> Self {
+		Self { connectors_health }
+	}
+}
+
+#[async_trait]
+impl TheaApiServer for TheaRpc {
+	async fn connectors_health(&self) -> RpcResult<BTreeMap<Network, ConnectorHealth>> {
+		Ok(self.connectors_health.health())
+	}
+}
@@ -0,0 +1,354 @@
+// This file is part of Polkadex.
+//
+// Copyright (c) 2023 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! In this module defined concrete implementation of the foreign connector abstraction for EVM
+//! chains, which are bridged through the Thea bridge contract.
+//!
+//! Outgoing messages are read from the `TheaMessage` event logs of the contract over JSON-RPC,
+//! the payload of an event being the SCALE encoded [`Message`]. The final blocks are scanned once
+//! and their messages are indexed by nonce. Messages from Polkadex are submitted to the contract
+//! with their aggregated BLS signature by a relayer account, the receipts of the submitted
+//! transactions are tracked in the background.
+
+use std::{collections::BTreeMap, str::FromStr, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use ethers::{
+	contract::abigen,
+	middleware::SignerMiddleware,
+	providers::{Http, Middleware, PendingTransaction, Provider},
+	signers::{LocalWallet, Signer},
+	types::{Address, BlockId, BlockNumber, H256, U64},
+};
+use log::{error, info, warn};
+use parity_scale_codec::{Decode, Encode};
+use parking_lot::Mutex;
+use thea_primitives::types::Message;
+use url::Url;
+
+use crate::{connector::traits::ForeignConnector, error::Error, types::GossipMessage};
+
+abigen!(
+	TheaBridge,
+	r#"[
+		event TheaMessage(uint64 indexed nonce, bytes payload)
+		function incomingNonce() external view returns (uint64)
+		function validatorSetId() external view returns (uint64)
+		function authoritiesCount(uint64 setId) external view returns (uint256)
+		function incomingMessage(uint128[] bitmap, bytes payload, bytes aggregateSignature) external
+	]"#
+);
+
+/// Environment variable holding the hex encoded private key of the relayer account.
+pub const RELAYER_KEY_ENV: &str = "THEA_EVM_RELAYER_KEY";
+
+/// Number of blocks after which an EVM block is considered final by default.
+pub const DEFAULT_CONFIRMATIONS: u64 = 12;
+
+/// Ethereum block time.
+const DEFAULT_BLOCK_TIME: Duration = Duration::from_secs(12);
+
+/// Maximum number of blocks searched for the bridge events in one request.
+const MAX_BLOCK_RANGE: u64 = 5_000;
+
+/// Maximum number of outgoing messages kept in the index, the oldest are read again by nonce if
+/// they are needed.
+const MAX_INDEXED_MESSAGES: usize = 1024;
+
+/// Configuration of the EVM connector.
+#[derive(Clone, Debug)]
+pub struct EvmConfig {
+	/// JSON-RPC endpoint of the EVM node.
+	pub url: String,
+	/// Address of the Thea bridge contract.
+	pub bridge: Address,
+	/// Number of blocks after which a block is considered final.
+	pub confirmations: u64,
+	/// First block searched for the bridge events, usually the contract deployment block.
+	pub start_block: u64,
+	/// Block time of the chain, also used as the polling interval of the submitted transactions.
+	pub block_time: Duration,
+	/// Relayer account submitting the messages from Polkadex, messages can't be submitted
+	/// without it.
+	pub relayer: Option<LocalWallet>,
+}
+
+impl EvmConfig {
+	/// Parses the configuration from the connector url.
+	///
+	/// The url is the JSON-RPC endpoint with the connector parameters as query: `bridge` is the
+	/// address of the bridge contract, `start_block` is the block it was deployed at and
+	/// `confirmations` is optional. E.g.
+	/// `http://127.0.0.1:8545?bridge=0x5FbDB2315678afecb367f032d93F642f64180aa3&start_block=1`.
+	///
+	/// The start block is required as the first outgoing message lookup searches every block
+	/// from it.
+	///
+	/// # Parameters
+	///
+	/// * `url`: Connector url.
+	pub fn from_url(url: &str) -> Result<Self, Error> {
+		let mut url = Url::parse(url).map_err(|err| Error::Evm(err.to_string()))?;
+		let mut bridge = None;
+		let mut confirmations = DEFAULT_CONFIRMATIONS;
+		let mut start_block = None;
+		for (key, value) in url.query_pairs() {
+			match key.as_ref() {
+				"bridge" => bridge = Some(parse_param::<Address>(&key, &value)?),
+				"confirmations" => confirmations = parse_param(&key, &value)?,
+				"start_block" => start_block = Some(parse_param(&key, &value)?),
+				_ => return Err(Error::Evm(format!("Unknown connector parameter: {key}"))),
+			}
+		}
+		url.set_query(None);
+		Ok(EvmConfig {
+			url: url.to_string(),
+			bridge: bridge.ok_or(Error::Evm(String::from("Bridge address not configured")))?,
+			confirmations,
+			start_block: start_block
+				.ok_or(Error::Evm(String::from("Bridge start block not configured")))?,
+			block_time: DEFAULT_BLOCK_TIME,
+			relayer: None,
+		})
+	}
+}
+
+/// Parses a query parameter of the connector url.
+fn parse_param<T: FromStr>(key: &str, value: &str) -> Result<T, Error> {
+	value
+		.parse()
+		.map_err(|_| Error::Evm(format!("Invalid connector parameter {key}: {value}")))
+}
+
+/// Outgoing messages of the scanned final blocks.
+#[derive(Default)]
+struct OutgoingMessages {
+	/// Last block searched for the bridge events.
+	scanned_until: Option<u64>,
+	/// Messages by nonce.
+	messages: BTreeMap<u64, Message>,
+}
+
+/// Client to communicate with an EVM chain.
+pub struct EvmClient {
+	provider: Arc<Provider<Http>>,
+	bridge: TheaBridge<Provider<Http>>,
+	relayer: Option<TheaBridge<SignerMiddleware<Provider<Http>, LocalWallet>>>,
+	confirmations: u64,
+	start_block: u64,
+	block_time: Duration,
+	/// Index of the outgoing messages, locked while the new final blocks are scanned.
+	outgoing: tokio::sync::Mutex<OutgoingMessages>,
+	/// Transactions submitted by the relayer waiting for their receipt, by message nonce.
+	pending_transactions: Arc<Mutex<BTreeMap<u64, H256>>>,
+}
+
+impl EvmClient {
+	/// Connects to the EVM node.
+	///
+	/// # Parameters
+	///
+	/// * `config`: Connector configuration.
+	pub async fn connect_with(config: EvmConfig) -> Result<Self, Error> {
+		let provider = Provider::<Http>::try_from(config.url.as_str())
+			.map_err(|err| Error::Evm(err.to_string()))?
+			.interval(config.block_time);
+		let chain_id = provider.get_chainid().await?;
+		info!(target:"thea", "🌉 Connected to EVM chain: {:?}", chain_id);
+		let relayer = config.relayer.map(|wallet| {
+			let middleware =
+				SignerMiddleware::new(provider.clone(), wallet.with_chain_id(chain_id.as_u64()));
+			TheaBridge::new(config.bridge, Arc::new(middleware))
+		});
+		let provider = Arc::new(provider);
+		Ok(EvmClient {
+			bridge: TheaBridge::new(config.bridge, provider.clone()),
+			provider,
+			relayer,
+			confirmations: config.confirmations,
+			start_block: config.start_block,
+			block_time: config.block_time,
+			outgoing: Default::default(),
+			pending_transactions: Default::default(),
+		})
+	}
+
+	/// Returns the nonces of the submitted messages whose transaction has no receipt yet.
+	pub fn pending_transactions(&self) -> Vec<u64> {
+		self.pending_transactions.lock().keys().copied().collect()
+	}
+
+	/// Returns the last block considered final.
+	async fn final_block(&self) -> Result<U64, Error> {
+		let best = self.provider.get_block_number().await?;
+		Ok(best.saturating_sub(self.confirmations.into()))
+	}
+
+	/// Returns the outgoing message with the given nonce from the bridge events of the final
+	/// blocks, the blocks finalized since the last call are scanned first.
+	///
+	/// # Parameters
+	///
+	/// * `nonce`: Nonce of the message.
+	async fn outgoing_message(&self, nonce: u64) -> Result<Option<Message>, Error> {
+		let mut outgoing = self.outgoing.lock().await;
+		let final_block = self.final_block().await?.as_u64();
+		let mut from = outgoing.scanned_until.map_or(self.start_block, |block| block + 1);
+		while from <= final_block {
+			let to = final_block.min(from.saturating_add(MAX_BLOCK_RANGE - 1));
+			let events = self
+				.bridge
+				.event::<TheaMessageFilter>()
+				.from_block(from)
+				.to_block(to)
+				.query()
+				.await?;
+			for event in events {
+				match Message::decode(&mut &event.payload[..]) {
+					Ok(message) => {
+						outgoing.messages.insert(event.nonce, message);
+					},
+					Err(err) =>
+						warn!(target:"thea", "🌉 Ignoring undecodable message: nonce: {:?}, {:?}", event.nonce, err),
+				}
+			}
+			outgoing.scanned_until = Some(to);
+			from = to + 1;
+		}
+		while outgoing.messages.len() > MAX_INDEXED_MESSAGES {
+			outgoing.messages.pop_first();
+		}
+		if let Some(message) = outgoing.messages.get(&nonce) {
+			return Ok(Some(message.clone()))
+		}
+		// Only the messages older than the index are not indexed
+		let first_indexed = outgoing.messages.keys().next().copied();
+		if first_indexed.map_or(true, |first| nonce >= first) {
+			return Ok(None)
+		}
+		drop(outgoing);
+		let events = self
+			.bridge
+			.event::<TheaMessageFilter>()
+			.topic1(H256::from_low_u64_be(nonce))
+			.from_block(self.start_block)
+			.to_block(final_block)
+			.query()
+			.await?;
+		match events.into_iter().next() {
+			None => Ok(None),
+			Some(event) => Ok(Some(Message::decode(&mut &event.payload[..])?)),
+		}
+	}
+
+	/// Waits for the receipt of a submitted transaction in the background, the message can be
+	/// submitted again once its transaction is reverted or dropped.
+	///
+	/// # Parameters
+	///
+	/// * `nonce`: Nonce of the submitted message.
+	/// * `tx_hash`: Hash of the transaction.
+	fn track_receipt(&self, nonce: u64, tx_hash: H256) {
+		self.pending_transactions.lock().insert(nonce, tx_hash);
+		let provider = self.provider.clone();
+		let pending_transactions = self.pending_transactions.clone();
+		let block_time = self.block_time;
+		tokio::spawn(async move {
+			let receipt = PendingTransaction::new(tx_hash, &provider).interval(block_time).await;
+			match receipt {
+				Ok(Some(receipt)) if receipt.status == Some(U64::one()) =>
+					info!(target:"thea", "🌉 Tx included: nonce: {:?}, block: {:?}", nonce, receipt.block_hash),
+				Ok(Some(receipt)) =>
+					error!(target:"thea", "🌉 Tx reverted: nonce: {:?}, {:?}", nonce, receipt.transaction_hash),
+				Ok(None) =>
+					error!(target:"thea", "🌉 Tx dropped from the mempool: nonce: {:?}, {:?}", nonce, tx_hash),
+				Err(err) =>
+					error!(target:"thea", "🌉 Failed to track tx: nonce: {:?}, {:?}: {:?}", nonce, tx_hash, err),
+			}
+			let mut pending_transactions = pending_transactions.lock();
+			if pending_transactions.get(&nonce) == Some(&tx_hash) {
+				pending_transactions.remove(&nonce);
+			}
+		});
+	}
+}
+
+#[async_trait]
+impl ForeignConnector for EvmClient {
+	fn block_duration(&self) -> Duration {
+		self.block_time
+	}
+
+	async fn connect(url: String) -> Result<Self, Error> {
+		let mut config = EvmConfig::from_url(&url)?;
+		if let Ok(key) = std::env::var(RELAYER_KEY_ENV) {
+			config.relayer = Some(
+				LocalWallet::from_str(key.trim_start_matches("0x"))
+					.map_err(|err| Error::Evm(err.to_string()))?,
+			);
+		}
+		Self::connect_with(config).await
+	}
+
+	async fn read_events(&self, nonce: u64) -> Result<Option<Message>, Error> {
+		self.outgoing_message(nonce).await
+	}
+
+	async fn send_transaction(&self, message: GossipMessage) -> Result<(), Error> {
+		info!(target:"thea", "Sending message to EVM bridge");
+		let relayer = self
+			.relayer
+			.as_ref()
+			.ok_or(Error::Evm(String::from("Relayer account not configured")))?;
+		let nonce = message.payload.nonce;
+		if let Some(tx_hash) = self.pending_transactions.lock().get(&nonce) {
+			info!(target:"thea", "🌉 Tx of nonce {:?} is still pending: {:?}", nonce, tx_hash);
+			return Ok(())
+		}
+		let call = relayer
+			.incoming_message(
+				message.bitmap,
+				message.payload.encode().into(),
+				message.aggregate_signature.encode().into(),
+			)
+			.legacy();
+		let tx_hash = *call.send().await?;
+		info!(target:"thea", "🌉 Tx submitted: nonce: {:?}, {:?}", nonce, tx_hash);
+		self.track_receipt(nonce, tx_hash);
+		Ok(())
+	}
+
+	async fn check_message(&self, message: &Message) -> Result<bool, Error> {
+		match self.outgoing_message(message.nonce).await? {
+			None => Ok(false),
+			Some(message_from_chain) => Ok(message_from_chain == *message),
+		}
+	}
+
+	async fn last_processed_nonce_from_native(&self) -> Result<u64, Error> {
+		let at = BlockId::Number(BlockNumber::Number(self.final_block().await?));
+		Ok(self.bridge.incoming_nonce().block(at).call().await?)
+	}
+
+	async fn check_thea_authority_initialization(&self) -> Result<bool, Error> {
+		let at = BlockId::Number(BlockNumber::Number(self.final_block().await?));
+		let set_id = self.bridge.validator_set_id().block(at).call().await?;
+		let count = self.bridge.authorities_count(set_id).block(at).call().await?;
+		Ok(!count.is_zero())
+	}
+}
@@ -18,5 +18,7 @@
 
 //! Abstraction and concrete implementations of the foreign connector are located in this module.
 
+pub mod evm;
 pub mod parachain;
+pub mod supervisor;
 pub mod traits;
@@ -15,68 +15,279 @@
 //
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
-
 //! In this module defined concrete implementation of the foreign connector abstraction for the
 //! parachain.
+//!
+//! The connector follows the finalized heads of the parachain with a single subscription. Once
+//! the subscription drops the connector is closed and its message stream ends, so that the
+//! [`SupervisedConnector`](crate::connector::supervisor::SupervisedConnector) replaces it with a
+//! new connection. Storage reads are cached for the current finalized head and the new outgoing
+//! messages are pushed to the worker as soon as they are final. The node is reached through
+//! [`ParachainNode`], implemented by the subxt client.
 
-use std::time::Duration;
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
 
 use async_trait::async_trait;
-use futures::StreamExt;
-use log::info;
+use futures::{
+	channel::mpsc::{unbounded, UnboundedSender},
+	stream::BoxStream,
+	StreamExt,
+};
+use log::{error, info, warn};
 use parity_scale_codec::{Decode, Encode};
+use parking_lot::{Mutex, RwLock};
 use subxt::{OnlineClient, PolkadotConfig};
-use thea_primitives::types::Message;
+use thea_primitives::{types::Message, MAX_MESSAGES_IN_FLIGHT};
+use tokio::task::JoinHandle;
 
 use crate::{connector::traits::ForeignConnector, error::Error, types::GossipMessage};
 
 #[subxt::subxt(runtime_metadata_path = "../../parachain-metadata/metadata.scale")]
 mod parachain {}
 
-/// Client to communicate with the parachain.
-pub struct ParachainClient {
-	api: OnlineClient<PolkadotConfig>,
+/// Parachain block hash.
+pub type BlockHash = <PolkadotConfig as subxt::Config>::Hash;
+
+/// Stream of the finalized heads of the parachain.
+pub type FinalizedHeads = BoxStream<'static, Result<BlockHash, Error>>;
+
+/// Maximum time waited for the first finalized head when connecting.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Storage reads cached for a finalized block.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CacheKey {
+	/// Outgoing message with the given nonce.
+	OutgoingMessage(u64),
+	/// Last processed nonce from native.
+	IncomingNonce,
+	/// Thea authorities initialization.
+	AuthoritiesInitialized,
 }
 
+/// Connection to a parachain node.
 #[async_trait]
-impl ForeignConnector for ParachainClient {
-	fn block_duration(&self) -> Duration {
-		// Parachain block time is 12 second , but we check every 10s to prevent drift
-		Duration::from_secs(10)
+pub trait ParachainNode: Clone + Send + Sync + Sized + 'static {
+	/// Connects to the node and subscribes to its finalized heads.
+	///
+	/// # Parameters
+	///
+	/// * `url`: Parachain node url.
+	async fn subscribe_finalized(url: &str) -> Result<(Self, FinalizedHeads), Error>;
+
+	/// Reads the SCALE encoded storage value at the given block.
+	///
+	/// # Parameters
+	///
+	/// * `at`: Block hash.
+	/// * `key`: Storage read.
+	async fn fetch(&self, at: BlockHash, key: &CacheKey) -> Result<Vec<u8>, Error>;
+
+	/// Reads the nonce of the last outgoing message at the given block.
+	///
+	/// # Parameters
+	///
+	/// * `at`: Block hash.
+	async fn fetch_outgoing_nonce(&self, at: BlockHash) -> Result<u64, Error>;
+
+	/// Submits a message to the Thea message handler and waits for its inclusion.
+	///
+	/// # Parameters
+	///
+	/// * `message`: Message with a majority of signatures.
+	async fn submit(&self, message: GossipMessage) -> Result<(), Error>;
+}
+
+/// Last finalized head of the parachain with the storage reads at this block.
+struct FinalizedHead<N> {
+	/// Client of the connection the head was received on.
+	api: N,
+	/// Block hash.
+	hash: BlockHash,
+	/// SCALE encoded storage reads at this block.
+	cache: BTreeMap<CacheKey, Vec<u8>>,
+}
+
+/// State shared between the client and the task following the finalized heads.
+struct SharedState<N> {
+	/// Last finalized head, none once the connection is closed.
+	head: RwLock<Option<FinalizedHead<N>>>,
+	/// Sender of the new outgoing messages, if the worker subscribed to them.
+	messages: Mutex<Option<UnboundedSender<Message>>>,
+}
+
+impl<N: ParachainNode> SharedState<N> {
+	/// Returns the state of a connection whose first finalized head is the given one.
+	///
+	/// # Parameters
+	///
+	/// * `api`: Client of the connection.
+	/// * `hash`: First finalized head.
+	fn new(api: N, hash: BlockHash) -> Self {
+		SharedState {
+			head: RwLock::new(Some(FinalizedHead { api, hash, cache: BTreeMap::new() })),
+			messages: Mutex::new(None),
+		}
 	}
 
-	async fn connect(url: String) -> Result<Self, Error> {
-		let api = OnlineClient::<PolkadotConfig>::from_url(url).await?;
-		Ok(ParachainClient { api })
+	/// Returns the connection and the hash of the last finalized head.
+	fn head(&self) -> Result<(N, BlockHash), Error> {
+		self.head
+			.read()
+			.as_ref()
+			.map(|head| (head.api.clone(), head.hash))
+			.ok_or(Error::Subxt(String::from("Not connected to the parachain")))
 	}
 
-	async fn read_events(&self, nonce: u64) -> Result<Option<Message>, Error> {
-		// Read thea messages from foreign chain
-		let storage_address = parachain::storage().thea_message_handler().outgoing_messages(nonce);
+	/// Returns the cached storage read at the given block.
+	///
+	/// # Parameters
+	///
+	/// * `hash`: Block hash.
+	/// * `key`: Storage read.
+	fn cached<T: Decode>(&self, hash: BlockHash, key: &CacheKey) -> Option<T> {
+		let head = self.head.read();
+		let encoded = head.as_ref().filter(|head| head.hash == hash)?.cache.get(key)?;
+		T::decode(&mut &encoded[..]).ok()
+	}
 
-		// Get last finalized block hash
-		let last_finalized_blk = self
-			.api
-			.blocks()
-			.subscribe_finalized()
-			.await?
-			.next()
+	/// Caches a storage read at the given block, ignored if the finalized head moved since.
+	///
+	/// # Parameters
+	///
+	/// * `hash`: Block hash.
+	/// * `key`: Storage read.
+	/// * `value`: Value read.
+	fn cache<T: Encode>(&self, hash: BlockHash, key: CacheKey, value: &T) {
+		if let Some(head) = self.head.write().as_mut().filter(|head| head.hash == hash) {
+			head.cache.insert(key, value.encode());
+		}
+	}
+
+	/// Pushes a new outgoing message to the worker.
+	///
+	/// # Parameters
+	///
+	/// * `message`: Outgoing message.
+	fn push_message(&self, message: Message) {
+		let mut sender = self.messages.lock();
+		if let Some(tx) = sender.as_ref() {
+			if tx.unbounded_send(message).is_err() {
+				// The worker dropped the stream
+				*sender = None;
+			}
+		}
+	}
+}
+
+/// Client to communicate with the parachain.
+pub struct ParachainClient<N: ParachainNode = OnlineClient<PolkadotConfig>> {
+	state: Arc<SharedState<N>>,
+	follower: JoinHandle<()>,
+}
+
+impl<N: ParachainNode> Drop for ParachainClient<N> {
+	fn drop(&mut self) {
+		self.follower.abort();
+	}
+}
+
+impl<N: ParachainNode> ParachainClient<N> {
+	/// Connects to the parachain and waits for its first finalized head.
+	///
+	/// # Parameters
+	///
+	/// * `url`: Parachain node url.
+	/// * `timeout`: Maximum time waited for the first finalized head.
+	pub(crate) async fn connect_with_timeout(
+		url: String,
+		timeout: Duration,
+	) -> Result<Self, Error> {
+		let (api, mut heads) = N::subscribe_finalized(&url).await?;
+		let hash = tokio::time::timeout(timeout, heads.next())
 			.await
+			.map_err(|_| {
+				Error::Subxt(String::from("Timed out waiting for the last finalized head"))
+			})?
 			.ok_or(Error::Subxt(String::from("Failed to get last finalized ")))??;
+		let last_pushed = api.fetch_outgoing_nonce(hash).await?;
+		let state = Arc::new(SharedState::new(api.clone(), hash));
+		let follower = tokio::spawn(follow_finalized_heads(state.clone(), api, heads, last_pushed));
+		Ok(ParachainClient { state, follower })
+	}
+
+	/// Reads a storage value at the last finalized head, from the cache if it was already read
+	/// at this block.
+	///
+	/// # Parameters
+	///
+	/// * `key`: Storage read.
+	async fn read<T: Encode + Decode>(&self, key: CacheKey) -> Result<T, Error> {
+		let (api, hash) = self.state.head()?;
+		if let Some(value) = self.state.cached(hash, &key) {
+			return Ok(value)
+		}
+		let encoded = api.fetch(hash, &key).await?;
+		let value = T::decode(&mut &encoded[..])?;
+		self.state.cache(hash, key, &value);
+		Ok(value)
+	}
+}
 
-		let encoded_bytes = self
-			.api
-			.storage()
-			.at(last_finalized_blk.hash())
-			.fetch(&storage_address)
+#[async_trait]
+impl ParachainNode for OnlineClient<PolkadotConfig> {
+	async fn subscribe_finalized(url: &str) -> Result<(Self, FinalizedHeads), Error> {
+		let api = OnlineClient::<PolkadotConfig>::from_url(url).await?;
+		let heads = api
+			.blocks()
+			.subscribe_finalized()
 			.await?
-			.encode();
+			.map(|block| block.map(|block| block.hash()).map_err(Error::from))
+			.boxed();
+		Ok((api, heads))
+	}
+
+	async fn fetch(&self, at: BlockHash, key: &CacheKey) -> Result<Vec<u8>, Error> {
+		let storage = self.storage().at(at);
+		match key {
+			CacheKey::OutgoingMessage(nonce) => {
+				// Read thea messages from foreign chain
+				let storage_address =
+					parachain::storage().thea_message_handler().outgoing_messages(*nonce);
+				Ok(storage.fetch(&storage_address).await?.encode())
+			},
+			CacheKey::IncomingNonce => {
+				// Read native network nonce from foreign chain
+				let storage_address = parachain::storage().thea_message_handler().incoming_nonce();
+				Ok(storage.fetch_or_default(&storage_address).await?.encode())
+			},
+			CacheKey::AuthoritiesInitialized => {
+				// Get current validator set id
+				let storage_address =
+					parachain::storage().thea_message_handler().validator_set_id();
+				let set_id = storage.fetch_or_default(&storage_address).await.map_err(|err| {
+					log::error!(target:"parachain","Error while fetching current set id: {:?}",err);
+					err
+				})?;
 
-		Ok(parity_scale_codec::Decode::decode(&mut &encoded_bytes[..])?)
+				// Get validator set
+				let storage_address =
+					parachain::storage().thea_message_handler().authorities(set_id);
+				let auths = storage.fetch_or_default(&storage_address).await.map_err(|err| {
+					log::error!(target:"parachain","Error while fetching auth set {:?}",err);
+					err
+				})?;
+				Ok((!auths.0.is_empty()).encode())
+			},
+		}
 	}
 
-	async fn send_transaction(&self, message: GossipMessage) -> Result<(), Error> {
-		info!(target:"thea", "Sending message to foreign runtime");
+	async fn fetch_outgoing_nonce(&self, at: BlockHash) -> Result<u64, Error> {
+		let storage_address = parachain::storage().thea_message_handler().outgoing_nonce();
+		Ok(self.storage().at(at).fetch_or_default(&storage_address).await?)
+	}
+
+	async fn submit(&self, message: GossipMessage) -> Result<(), Error> {
 		let call = parachain::tx().thea_message_handler().incoming_message(
 			message.bitmap,
 			Decode::decode(&mut &message.payload.encode()[..])?,
@@ -84,7 +295,6 @@ impl ForeignConnector for ParachainClient {
 		);
 		info!(target:"thea", "Tx created: {:?}",call);
 		let tx_result = self
-			.api
 			.tx()
 			.create_unsigned(&call)?
 			.submit_and_watch()
@@ -97,30 +307,100 @@ impl ForeignConnector for ParachainClient {
 		info!(target:"thea", "Tx included: {:?}",tx_result.block_hash());
 		Ok(())
 	}
+}
 
-	async fn check_message(&self, message: &Message) -> Result<bool, Error> {
-		// Read thea messages from foreign chain
-		let storage_address =
-			parachain::storage().thea_message_handler().outgoing_messages(message.nonce);
-		// Get last finalized block hash
-		let last_finalized_blk = self
-			.api
-			.blocks()
-			.subscribe_finalized()
-			.await?
-			.next()
-			.await
-			.ok_or(Error::Subxt(String::from("Failed to get last finalized ")))??;
-		let encoded_bytes = self
-			.api
-			.storage()
-			.at(last_finalized_blk.hash())
-			.fetch(&storage_address)
-			.await?
-			.encode();
+/// Follows the finalized heads of the parachain, caching the storage reads of each head and
+/// pushing the new outgoing messages. The connection is closed once the subscription drops.
+///
+/// # Parameters
+///
+/// * `state`: State shared with the client.
+/// * `api`: Client of the established connection.
+/// * `heads`: Established subscription.
+/// * `last_pushed`: Nonce of the last outgoing message known when connecting.
+async fn follow_finalized_heads<N: ParachainNode>(
+	state: Arc<SharedState<N>>,
+	api: N,
+	mut heads: FinalizedHeads,
+	mut last_pushed: u64,
+) {
+	while let Some(head) = heads.next().await {
+		let hash = match head {
+			Ok(hash) => hash,
+			Err(err) => {
+				error!(target:"thea", "🌉 Error following parachain finalized heads: {:?}", err);
+				break
+			},
+		};
+		*state.head.write() =
+			Some(FinalizedHead { api: api.clone(), hash, cache: BTreeMap::new() });
+		if let Err(err) = push_new_messages(&state, &api, hash, &mut last_pushed).await {
+			error!(target:"thea", "🌉 Error reading parachain outgoing messages: {:?}", err);
+		}
+	}
+	warn!(target:"thea", "🌉 Parachain connection dropped");
+	// Calls fail and the message stream ends
+	*state.head.write() = None;
+	*state.messages.lock() = None;
+}
 
+/// Pushes the outgoing messages finalized since the last pushed one, at most the last
+/// [`MAX_MESSAGES_IN_FLIGHT`] ones.
+///
+/// # Parameters
+///
+/// * `state`: State shared with the client.
+/// * `api`: Parachain client.
+/// * `hash`: Finalized head.
+/// * `last_pushed`: Nonce of the last pushed message.
+async fn push_new_messages<N: ParachainNode>(
+	state: &SharedState<N>,
+	api: &N,
+	hash: BlockHash,
+	last_pushed: &mut u64,
+) -> Result<(), Error> {
+	let outgoing_nonce = api.fetch_outgoing_nonce(hash).await?;
+	let first_nonce = last_pushed
+		.saturating_add(1)
+		.max(outgoing_nonce.saturating_sub(MAX_MESSAGES_IN_FLIGHT).saturating_add(1));
+	for nonce in first_nonce..=outgoing_nonce {
+		let key = CacheKey::OutgoingMessage(nonce);
+		let encoded = api.fetch(hash, &key).await?;
+		let message = Option::<Message>::decode(&mut &encoded[..])?;
+		state.cache(hash, key, &message);
+		if let Some(message) = message {
+			info!(target:"thea", "🌉 New parachain message: nonce: {:?}", nonce);
+			state.push_message(message);
+		}
+		*last_pushed = nonce;
+	}
+	Ok(())
+}
+
+#[async_trait]
+impl<N: ParachainNode> ForeignConnector for ParachainClient<N> {
+	fn block_duration(&self) -> Duration {
+		// Parachain block time is 12 second , but we check every 10s to prevent drift
+		Duration::from_secs(10)
+	}
+
+	async fn connect(url: String) -> Result<Self, Error> {
+		Self::connect_with_timeout(url, CONNECT_TIMEOUT).await
+	}
+
+	async fn read_events(&self, nonce: u64) -> Result<Option<Message>, Error> {
+		self.read(CacheKey::OutgoingMessage(nonce)).await
+	}
+
+	async fn send_transaction(&self, message: GossipMessage) -> Result<(), Error> {
+		info!(target:"thea", "Sending message to foreign runtime");
+		let (api, _) = self.state.head()?;
+		api.submit(message).await
+	}
+
+	async fn check_message(&self, message: &Message) -> Result<bool, Error> {
 		let message_option: Option<Message> =
-			parity_scale_codec::Decode::decode(&mut &encoded_bytes[..])?;
+			self.read(CacheKey::OutgoingMessage(message.nonce)).await?;
 
 		match message_option {
 			None => return Ok(false),
@@ -129,71 +409,21 @@ impl ForeignConnector for ParachainClient {
 	}
 
 	async fn last_processed_nonce_from_native(&self) -> Result<u64, Error> {
-		// Read native network nonce from foreign chain
-		let storage_address = parachain::storage().thea_message_handler().incoming_nonce();
-		// Get last finalized block hash
-		let last_finalized_blk = self
-			.api
-			.blocks()
-			.subscribe_finalized()
-			.await?
-			.next()
-			.await
-			.ok_or(Error::Subxt(String::from("Failed to get last finalized ")))??;
-		let nonce = self
-			.api
-			.storage()
-			.at(last_finalized_blk.hash())
-			.fetch_or_default(&storage_address)
-			.await?;
-		Ok(nonce)
+		self.read(CacheKey::IncomingNonce).await
 	}
 
 	async fn check_thea_authority_initialization(&self) -> Result<bool, Error> {
-		// Get current validator set id
-		let storage_address = parachain::storage().thea_message_handler().validator_set_id();
-		// Get last finalized block hash
-		let last_finalized_blk = self
-			.api
-			.blocks()
-			.subscribe_finalized()
-			.await?
-			.next()
-			.await
-			.ok_or(Error::Subxt(String::from("Failed to get last finalized ")))??;
-		let set_id = self
-			.api
-			.storage()
-			.at(last_finalized_blk.hash())
-			.fetch_or_default(&storage_address)
-			.await
-			.map_err(|err| {
-				log::error!(target:"parachain","Error while fetching current set id: {:?}",err);
-				err
-			})?;
-
-		// Get validator set
-		let storage_address = parachain::storage().thea_message_handler().authorities(set_id);
-		// Get last finalized block hash
-		let last_finalized_blk = self
-			.api
-			.blocks()
-			.subscribe_finalized()
-			.await?
-			.next()
-			.await
-			.ok_or(Error::Subxt(String::from("Failed to get last finalized ")))??;
-		let auths = self
-			.api
-			.storage()
-			.at(last_finalized_blk.hash())
-			.fetch_or_default(&storage_address)
-			.await
-			.map_err(|err| {
-				log::error!(target:"parachain","Error while fetching auth set {:?}",err);
-				err
-			})?;
+		self.read(CacheKey::AuthoritiesInitialized).await
+	}
 
-		Ok(!auths.0.is_empty())
+	/// The stream ends once the connection is closed.
+	fn message_stream(&self) -> Option<BoxStream<'static, Message>> {
+		let (tx, rx) = unbounded();
+		// The head is locked so that the sender is not set after the connection is closed
+		let head = self.state.head.read();
+		if head.is_some() {
+			*self.state.messages.lock() = Some(tx);
+		}
+		Some(rx.boxed())
 	}
 }
@@ -0,0 +1,335 @@
+// This file is part of Polkadex.
+//
+// Copyright (c) 2023 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! In this module defined the supervision of the foreign connectors.
+//!
+//! A [`SupervisedConnector`] connects to the foreign chain in the background and reconnects with
+//! an exponential backoff once the connection is lost, so an unreachable foreign node never
+//! stops the Thea worker. The connection is lost when the message stream of the connector ends
+//! or after consecutive failed calls. The connectors don't reconnect by themselves, the
+//! supervisor is the only place reconnecting. Calls made while disconnected fail with
+//! [`Error::ConnectorNotConnected`].
+
+use std::{
+	collections::BTreeMap,
+	future::Future,
+	sync::{
+		atomic::{AtomicBool, AtomicU32, Ordering},
+		Arc,
+	},
+	time::Duration,
+};
+
+use async_trait::async_trait;
+use futures::{
+	channel::mpsc::{unbounded, UnboundedSender},
+	stream::BoxStream,
+	StreamExt,
+};
+use log::{info, warn};
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use thea_primitives::{types::Message, Network};
+
+use crate::{connector::traits::ForeignConnector, error::Error, types::GossipMessage};
+
+/// Delay before the first reconnection attempt.
+const INITIAL_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Maximum delay between two reconnection attempts.
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
+
+/// Number of consecutive failed calls after which the connection is considered lost.
+const MAX_CONSECUTIVE_FAILURES: u32 = 3;
+
+/// Block duration reported while disconnected.
+const DEFAULT_BLOCK_DURATION: Duration = Duration::from_secs(12);
+
+/// Health of a foreign connector.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectorHealth {
+	/// Connecting to the foreign chain, calls fail until connected.
+	Connecting,
+	/// Connected, but the last calls failed.
+	Degraded,
+	/// Connected and the last call succeeded.
+	Healthy,
+}
+
+impl ConnectorHealth {
+	/// Returns the value reported in the metrics.
+	pub fn metric(&self) -> u64 {
+		match self {
+			ConnectorHealth::Connecting => 0,
+			ConnectorHealth::Degraded => 1,
+			ConnectorHealth::Healthy => 2,
+		}
+	}
+}
+
+/// Foreign connectors by network, shared with the RPC to report their health.
+#[derive(Clone, Default)]
+pub struct ConnectorsHealth(Arc<RwLock<BTreeMap<Network, Arc<dyn ForeignConnector>>>>);
+
+impl ConnectorsHealth {
+	/// Registers the connector of a foreign network.
+	///
+	/// # Parameters
+	///
+	/// * `network`: Foreign network.
+	/// * `connector`: Connector of the network.
+	pub(crate) fn insert(&self, network: Network, connector: Arc<dyn ForeignConnector>) {
+		self.0.write().insert(network, connector);
+	}
+
+	/// Returns the health of the connectors by network.
+	pub fn health(&self) -> BTreeMap<Network, ConnectorHealth> {
+		self.0
+			.read()
+			.iter()
+			.map(|(network, connector)| (*network, connector.health()))
+			.collect()
+	}
+}
+
+struct Supervisor<C> {
+	/// Url of the foreign chain.
+	url: String,
+	/// Connector of the current connection, none while connecting.
+	connector: RwLock<Option<Arc<C>>>,
+	/// Health of the connector.
+	health: RwLock<ConnectorHealth>,
+	/// Number of consecutive failed calls.
+	failures: AtomicU32,
+	/// Indicates if a reconnection task is running.
+	reconnecting: AtomicBool,
+	/// Sender of the new outgoing messages, if the worker subscribed to them.
+	messages: Mutex<Option<UnboundedSender<Message>>>,
+}
+
+impl<C: ForeignConnector + 'static> Supervisor<C> {
+	/// Drops the current connection and connects again in the background, retrying with an
+	/// exponential backoff until the connection succeeds.
+	fn reconnect(self: &Arc<Self>) {
+		if self.reconnecting.swap(true, Ordering::SeqCst) {
+			return
+		}
+		*self.connector.write() = None;
+		*self.health.write() = ConnectorHealth::Connecting;
+		let url = self.url.clone();
+		let supervisor = Arc::downgrade(self);
+		tokio::spawn(async move {
+			let mut delay = INITIAL_RECONNECT_DELAY;
+			loop {
+				let result = C::connect(url.clone()).await;
+				// Stop once the connector is dropped
+				let supervisor = match supervisor.upgrade() {
+					Some(supervisor) => supervisor,
+					None => return,
+				};
+				match result {
+					Ok(connector) => {
+						supervisor.connected(connector);
+						return
+					},
+					Err(err) => {
+						warn!(target:"thea", "🌉 Failed to connect to foreign chain: {:?}, retrying in {:?}", err, delay)
+					},
+				}
+				drop(supervisor);
+				tokio::time::sleep(delay).await;
+				delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+			}
+		});
+	}
+
+	/// Replaces the connector with the one of a new connection.
+	///
+	/// # Parameters
+	///
+	/// * `connector`: Connector of the new connection.
+	fn connected(self: &Arc<Self>, connector: C) {
+		info!(target:"thea", "🌉 Connected to foreign chain");
+		let connector = Arc::new(connector);
+		let stream = connector.message_stream();
+		*self.connector.write() = Some(connector.clone());
+		self.failures.store(0, Ordering::SeqCst);
+		*self.health.write() = ConnectorHealth::Healthy;
+		self.reconnecting.store(false, Ordering::SeqCst);
+		if let Some(mut stream) = stream {
+			// Forwards the messages of the connection until it is replaced or closed
+			let supervisor = Arc::downgrade(self);
+			let connector = Arc::downgrade(&connector);
+			tokio::spawn(async move {
+				while let Some(message) = stream.next().await {
+					match supervisor.upgrade() {
+						Some(supervisor) => supervisor.push_message(message),
+						None => return,
+					}
+				}
+				// The stream of a connection ends when the connection is closed
+				if let (Some(supervisor), Some(connector)) =
+					(supervisor.upgrade(), connector.upgrade())
+				{
+					if supervisor.is_current(&connector) {
+						warn!(target:"thea", "🌉 Foreign chain connection closed, reconnecting");
+						supervisor.reconnect();
+					}
+				}
+			});
+		}
+	}
+
+	/// Sends a new outgoing message to the worker, if it subscribed to them.
+	///
+	/// # Parameters
+	///
+	/// * `message`: New outgoing message.
+	fn push_message(&self, message: Message) {
+		if let Some(sender) = self.messages.lock().as_ref() {
+			let _ = sender.unbounded_send(message);
+		}
+	}
+
+	/// Checks if the given connector is the one of the current connection.
+	///
+	/// # Parameters
+	///
+	/// * `connector`: Connector to check.
+	fn is_current(&self, connector: &Arc<C>) -> bool {
+		self.connector
+			.read()
+			.as_ref()
+			.map_or(false, |current| Arc::ptr_eq(current, connector))
+	}
+}
+
+/// Connector which keeps a connection to the foreign chain alive.
+pub struct SupervisedConnector<C> {
+	supervisor: Arc<Supervisor<C>>,
+}
+
+impl<C: ForeignConnector + 'static> SupervisedConnector<C> {
+	/// Starts connecting to the foreign chain in the background.
+	///
+	/// # Parameters
+	///
+	/// * `url`: Url of the foreign chain.
+	pub fn new(url: String) -> Self {
+		let supervisor = Arc::new(Supervisor {
+			url,
+			connector: RwLock::new(None),
+			health: RwLock::new(ConnectorHealth::Connecting),
+			failures: AtomicU32::new(0),
+			reconnecting: AtomicBool::new(false),
+			messages: Mutex::new(None),
+		});
+		supervisor.reconnect();
+		SupervisedConnector { supervisor }
+	}
+
+	/// Calls the connector of the current connection and updates the health with the result.
+	/// The connection is dropped and established again after [`MAX_CONSECUTIVE_FAILURES`]
+	/// consecutive failed calls.
+	///
+	/// # Parameters
+	///
+	/// * `call`: Call to make.
+	async fn call<T, F, Fut>(&self, call: F) -> Result<T, Error>
+	where
+		F: FnOnce(Arc<C>) -> Fut,
+		Fut: Future<Output = Result<T, Error>>,
+	{
+		let connector =
+			self.supervisor.connector.read().clone().ok_or(Error::ConnectorNotConnected)?;
+		let result = call(connector.clone()).await;
+		// Results of a replaced connection don't tell anything about the current one
+		if !self.supervisor.is_current(&connector) {
+			return result
+		}
+		match &result {
+			Ok(_) => {
+				self.supervisor.failures.store(0, Ordering::SeqCst);
+				*self.supervisor.health.write() = ConnectorHealth::Healthy;
+			},
+			Err(err) => {
+				let failures = self.supervisor.failures.fetch_add(1, Ordering::SeqCst) + 1;
+				if failures >= MAX_CONSECUTIVE_FAILURES {
+					warn!(target:"thea", "🌉 Foreign chain call failed {:?} times, reconnecting: {:?}", failures, err);
+					self.supervisor.reconnect();
+				} else {
+					*self.supervisor.health.write() = ConnectorHealth::Degraded;
+				}
+			},
+		}
+		result
+	}
+}
+
+#[async_trait]
+impl<C: ForeignConnector + 'static> ForeignConnector for SupervisedConnector<C> {
+	fn block_duration(&self) -> Duration {
+		self.supervisor
+			.connector
+			.read()
+			.as_ref()
+			.map_or(DEFAULT_BLOCK_DURATION, |connector| connector.block_duration())
+	}
+
+	async fn connect(url: String) -> Result<Self, Error> {
+		Ok(Self::new(url))
+	}
+
+	async fn read_events(&self, last_processed_nonce: u64) -> Result<Option<Message>, Error> {
+		self.call(|connector| async move { connector.read_events(last_processed_nonce).await })
+			.await
+	}
+
+	async fn send_transaction(&self, message: GossipMessage) -> Result<(), Error> {
+		self.call(|connector| async move { connector.send_transaction(message).await })
+			.await
+	}
+
+	async fn check_message(&self, message: &Message) -> Result<bool, Error> {
+		self.call(|connector| async move { connector.check_message(message).await })
+			.await
+	}
+
+	async fn last_processed_nonce_from_native(&self) -> Result<u64, Error> {
+		self.call(|connector| async move { connector.last_processed_nonce_from_native().await })
+			.await
+	}
+
+	async fn check_thea_authority_initialization(&self) -> Result<bool, Error> {
+		self.call(|connector| async move { connector.check_thea_authority_initialization().await })
+			.await
+	}
+
+	/// The stream outlives the connections, messages of every new connection are forwarded to
+	/// it.
+	fn message_stream(&self) -> Option<BoxStream<'static, Message>> {
+		let (sender, receiver) = unbounded();
+		*self.supervisor.messages.lock() = Some(sender);
+		Some(receiver.boxed())
+	}
+
+	fn health(&self) -> ConnectorHealth {
+		*self.supervisor.health.read()
+	}
+}
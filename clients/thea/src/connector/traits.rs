@@ -22,8 +22,9 @@
 //! This module contains as well the concrete implementation of a foreign connector abstraction,
 //! which used in development/testing environments as a stub.
 
-use crate::{error::Error, types::GossipMessage};
+use crate::{connector::supervisor::ConnectorHealth, error::Error, types::GossipMessage};
 use async_trait::async_trait;
+use futures::stream::BoxStream;
 use std::time::Duration;
 use thea_primitives::types::Message;
 
@@ -46,6 +47,17 @@ pub trait ForeignConnector: Send + Sync {
 	async fn last_processed_nonce_from_native(&self) -> Result<u64, Error>;
 	/// Check if the foreign chain is initialized with thea validators.
 	async fn check_thea_authority_initialization(&self) -> Result<bool, Error>;
+	/// Returns a stream of the new outgoing messages, pushed as soon as they are final. A new
+	/// stream replaces the previous one. Connectors without push support return `None` and are
+	/// only polled.
+	fn message_stream(&self) -> Option<BoxStream<'static, Message>> {
+		None
+	}
+	/// Returns the health of the connection to the foreign chain. Connectors which are not
+	/// supervised are always reported healthy.
+	fn health(&self) -> ConnectorHealth {
+		ConnectorHealth::Healthy
+	}
 }
 
 /// ForeignConnector that does nothing, mainly used for starting node in development mode
@@ -20,6 +20,10 @@
 //!
 //! Used for Thea gadget internal error handling only.
 
+use ethers::{
+	contract::ContractError,
+	providers::{Middleware, ProviderError},
+};
 use sp_api::ApiError;
 use std::fmt::Debug;
 use thea_primitives::Network;
@@ -47,6 +51,12 @@ pub enum Error {
 	BLSError(String),
 	#[error("No validators found on runtime")]
 	NoValidatorsFound,
+	#[error("Error from EVM connector: {0}")]
+	Evm(String),
+	#[error("No foreign connector configured for network: {0}")]
+	ConnectorNotConfigured(Network),
+	#[error("Foreign connector is not connected")]
+	ConnectorNotConnected,
 }
 
 impl From<subxt::Error> for Error {
@@ -55,6 +65,18 @@ impl From<subxt::Error> for Error {
 	}
 }
 
+impl<M: Middleware> From<ContractError<M>> for Error {
+	fn from(value: ContractError<M>) -> Self {
+		Self::Evm(value.to_string())
+	}
+}
+
+impl From<ProviderError> for Error {
+	fn from(value: ProviderError) -> Self {
+		Self::Evm(value.to_string())
+	}
+}
+
 impl From<parity_scale_codec::Error> for Error {
 	fn from(value: parity_scale_codec::Error) -> Self {
 		Self::CodecError(value.to_string())
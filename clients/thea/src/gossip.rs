@@ -30,7 +30,7 @@ use std::{
 	collections::{BTreeMap, BTreeSet},
 	sync::Arc,
 };
-use thea_primitives::{Message, NATIVE_NETWORK};
+use thea_primitives::{Message, Network, MAX_MESSAGES_IN_FLIGHT, NATIVE_NETWORK};
 use tokio::time::Instant;
 
 /// Gossip engine messages topic.
@@ -52,11 +52,11 @@ where
 pub struct GossipValidator {
 	pub(crate) peers: Arc<RwLock<BTreeSet<PeerId>>>,
 	pub(crate) fullnodes: Arc<RwLock<BTreeSet<PeerId>>>,
-	cache: Arc<RwLock<BTreeMap<Message, (Instant, GossipMessage)>>>,
-	// Nonce of foreign message that was last processed in native.
-	foreign_last_nonce: Arc<RwLock<u64>>,
-	// Nonce of native message that was last processed in foreign.
-	native_last_nonce: Arc<RwLock<u64>>,
+	cache: Arc<RwLock<BTreeMap<(Network, Message), (Instant, GossipMessage)>>>,
+	// Nonce of foreign message that was last processed in native, by network.
+	foreign_last_nonce: Arc<RwLock<BTreeMap<Network, u64>>>,
+	// Nonce of native message that was last processed in foreign, by network.
+	native_last_nonce: Arc<RwLock<BTreeMap<Network, u64>>>,
 }
 
 impl GossipValidator {
@@ -64,12 +64,14 @@ impl GossipValidator {
 	///
 	/// # Parameters
 	/// * `cache`: Messages cache.
-	/// * `foreign_last_nonce`: Nonce of foreign message that was last processed in native.
-	/// * `native_last_nonce`: Nonce of native message that was last processed in foreign.
+	/// * `foreign_last_nonce`: Nonce of foreign message that was last processed in native, by
+	///   network.
+	/// * `native_last_nonce`: Nonce of native message that was last processed in foreign, by
+	///   network.
 	pub fn new(
-		cache: Arc<RwLock<BTreeMap<Message, (Instant, GossipMessage)>>>,
-		foreign_last_nonce: Arc<RwLock<u64>>,
-		native_last_nonce: Arc<RwLock<u64>>,
+		cache: Arc<RwLock<BTreeMap<(Network, Message), (Instant, GossipMessage)>>>,
+		foreign_last_nonce: Arc<RwLock<BTreeMap<Network, u64>>>,
+		native_last_nonce: Arc<RwLock<BTreeMap<Network, u64>>>,
 	) -> GossipValidator {
 		log::debug!(target: "thea", "🌉Creating gossip validator");
 		GossipValidator {
@@ -81,20 +83,29 @@ impl GossipValidator {
 		}
 	}
 
-	/// Validates provided message.
+	/// Validates provided message, only the next [`MAX_MESSAGES_IN_FLIGHT`] messages of a network
+	/// are accepted.
 	///
 	/// # Parameters
 	///
 	/// * `message`: `GossipMessage` reference to perform validation on.
 	pub fn validate_message(&self, message: &GossipMessage) -> bool {
 		// verify the message with our message cache and foreign chain connector
-		if message.payload.network == NATIVE_NETWORK {
+		let last_nonce = if message.network == NATIVE_NETWORK {
+			return false
+		} else if message.payload.network == NATIVE_NETWORK {
 			// Message origin is native
-			self.native_last_nonce.read().lt(&message.payload.nonce)
-		} else {
+			self.native_last_nonce.read().get(&message.network).copied()
+		} else if message.payload.network == message.network {
 			// Message origin is foreign
-			self.foreign_last_nonce.read().lt(&message.payload.nonce)
-		}
+			self.foreign_last_nonce.read().get(&message.network).copied()
+		} else {
+			// Foreign messages are only exchanged with their origin network
+			return false
+		};
+		let last_nonce = last_nonce.unwrap_or_default();
+		last_nonce.lt(&message.payload.nonce) &&
+			message.payload.nonce <= last_nonce.saturating_add(MAX_MESSAGES_IN_FLIGHT)
 	}
 
 	/// Defines if the message can be rebroadcasted.
@@ -105,7 +116,7 @@ impl GossipValidator {
 	pub fn rebroadcast_check(&self, message: &GossipMessage) -> bool {
 		// We rebroadcast it as long as its in our cache, if its not in our cache,
 		// then don't broadcast it, its removed from cache when the message is accepted.
-		self.cache.read().contains_key(&message.payload)
+		self.cache.read().contains_key(&(message.network, message.payload.clone()))
 	}
 }
 
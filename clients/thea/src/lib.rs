@@ -26,6 +26,7 @@
 //! * interval stream
 
 #![feature(unwrap_infallible)]
+pub use connector::supervisor::{ConnectorHealth, ConnectorsHealth};
 use prometheus::Registry;
 use sc_chain_spec::ChainType;
 use sc_client_api::{Backend, BlockchainEvents, Finalizer};
@@ -34,13 +35,14 @@ use sp_api::ProvideRuntimeApi;
 use sp_blockchain::HeaderBackend;
 use sp_consensus::SyncOracle;
 use sp_runtime::traits::Block;
-use std::{marker::PhantomData, sync::Arc};
-use thea_primitives::TheaApi;
+use std::{collections::BTreeMap, marker::PhantomData, sync::Arc};
+use thea_primitives::{Network, TheaApi, ETHEREUM_NETWORK};
 pub use thea_protocol_name::standard_name as protocol_standard_name;
 
 mod error;
 mod gossip;
 mod metrics;
+mod queue;
 mod worker;
 
 #[cfg(test)]
@@ -102,7 +104,9 @@ where
 
 use crate::{
 	connector::{
+		evm::EvmClient,
 		parachain::ParachainClient,
+		supervisor::SupervisedConnector,
 		traits::{ForeignConnector, NoOpConnector},
 	},
 	thea_protocol_name::standard_name,
@@ -137,8 +141,10 @@ where
 	pub marker: PhantomData<B>,
 	/// Defines the chain type our current deployment (Dev or production).
 	pub chain_type: ChainType,
-	/// Foreign Chain URL.
-	pub foreign_chain_url: String,
+	/// Foreign chain URLs by network id, the network id defines which connector is used.
+	pub foreign_chains: BTreeMap<Network, String>,
+	/// Foreign connectors by network, shared with the RPC reporting their health.
+	pub connectors_health: ConnectorsHealth,
 	/// Foreign chain dummy mode
 	pub dummy_mode: bool,
 }
@@ -165,7 +171,8 @@ where
 		is_validator,
 		marker: _,
 		chain_type,
-		foreign_chain_url,
+		foreign_chains: foreign_chain_urls,
+		connectors_health,
 		dummy_mode,
 	} = ob_params;
 
@@ -185,9 +192,13 @@ where
 			},
 		);
 
-	let foreign_connector = get_connector(chain_type, is_validator, foreign_chain_url, dummy_mode)
-		.await
-		.connector;
+	let mut foreign_chains = BTreeMap::new();
+	for (network, url) in foreign_chain_urls {
+		let connector =
+			get_connector(chain_type.clone(), is_validator, network, url, dummy_mode).await;
+		connectors_health.insert(network, connector.connector.clone());
+		foreign_chains.insert(network, connector.connector);
+	}
 
 	let worker_params = worker::WorkerParams {
 		client,
@@ -199,7 +210,7 @@ where
 		network,
 		metrics,
 		_marker: Default::default(),
-		foreign_chain: foreign_connector,
+		foreign_chains,
 	};
 
 	let worker = TheaWorker::<_, _, _, _, _, _, _>::new(worker_params).await;
@@ -215,31 +226,31 @@ pub struct Connector {
 /// Connector resolver/factory.
 ///
 /// Based on chain type or validators group member - resolves, creates and returns a new
-/// connector instance.
+/// connector instance. The network id picks the connector: EVM networks are connected through
+/// the bridge contract, the other networks are parachains.
 ///
 /// # Parameters
 ///
 /// * `chain_type`: Type of chain for which connector should be created.
 /// * `is_validator`: Defines if connector should be created for validator or not.
+/// * `network`: Network id of the foreign chain.
 /// * `url`: The address to which connector should be connected.
 pub async fn get_connector(
 	chain_type: ChainType,
 	is_validator: bool,
+	network: Network,
 	url: String,
 	dummy_mode: bool,
 ) -> Connector {
-	log::info!(target:"thea","🌉 Assigning connector based on chain type: {:?}",chain_type);
+	log::info!(target:"thea","🌉 Assigning connector based on chain type: {:?}, network: {:?}",chain_type, network);
 	if !is_validator | dummy_mode {
 		return Connector { connector: Arc::new(NoOpConnector) }
 	}
-	match chain_type {
-		ChainType::Development => Connector { connector: Arc::new(NoOpConnector) },
-		_ => Connector {
-			connector: Arc::new(
-				ParachainClient::connect(url)
-					.await
-					.expect("🌉 Expected to connect to local foreign node"),
-			),
-		},
+	match (chain_type, network) {
+		(ChainType::Development, _) => Connector { connector: Arc::new(NoOpConnector) },
+		// Connections are established in the background, the worker waits for them
+		(_, ETHEREUM_NETWORK) =>
+			Connector { connector: Arc::new(SupervisedConnector::<EvmClient>::new(url)) },
+		_ => Connector { connector: Arc::new(SupervisedConnector::<ParachainClient>::new(url)) },
 	}
 }
@@ -18,7 +18,7 @@
 
 //! Thea Prometheus metrics definition.
 
-use prometheus::{register, Counter, Gauge, PrometheusError, Registry, U64};
+use prometheus::{register, Counter, Gauge, GaugeVec, Opts, PrometheusError, Registry, U64};
 
 /// Thea metrics exposed through Prometheus.
 pub struct Metrics {
@@ -32,6 +32,8 @@ pub struct Metrics {
 	pub thea_data_sent: Gauge<U64>,
 	/// Total data recv by thea worker.
 	pub thea_data_recv: Gauge<U64>,
+	/// Health of the foreign connectors by network.
+	pub thea_connector_health: GaugeVec<U64>,
 }
 
 impl Metrics {
@@ -68,6 +70,16 @@ impl Metrics {
 				Gauge::new("polkadex_thea_data_recv", "Total Data received by Thea worker")?,
 				registry,
 			)?,
+			thea_connector_health: register(
+				GaugeVec::new(
+					Opts::new(
+						"polkadex_thea_connector_health",
+						"Health of the foreign connectors: 0 connecting, 1 degraded, 2 healthy",
+					),
+					&["network"],
+				)?,
+				registry,
+			)?,
 		})
 	}
 }
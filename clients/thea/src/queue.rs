@@ -0,0 +1,141 @@
+// This file is part of Polkadex.
+//
+// Copyright (c) 2023 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+//! Queue of the messages with a majority of signatures waiting to be submitted to their
+//! destination in the order of their nonces.
+
+use log::info;
+use std::{collections::BTreeMap, future::Future, time::Duration};
+use thea_primitives::Network;
+use tokio::time::Instant;
+
+use crate::{error::Error, types::GossipMessage};
+
+/// Origin and foreign network of the messages, the foreign network being the destination of the
+/// native messages.
+pub(crate) type Route = (Network, Network);
+
+/// Messages waiting to be submitted with the last nonce submitted, by route.
+pub(crate) struct SubmissionQueue {
+	/// Messages waiting for the previous nonces to be submitted.
+	ready: BTreeMap<Route, BTreeMap<u64, GossipMessage>>,
+	/// Last nonce submitted with the submission time.
+	last_submitted: BTreeMap<Route, (u64, Instant)>,
+	/// Time after which the submitted messages which are not processed are submitted again.
+	expiry: Duration,
+}
+
+impl SubmissionQueue {
+	/// Returns an empty queue.
+	///
+	/// # Parameters
+	///
+	/// * `expiry`: Time after which the submitted messages which are not processed are submitted
+	///   again.
+	pub(crate) fn new(expiry: Duration) -> Self {
+		SubmissionQueue { ready: BTreeMap::new(), last_submitted: BTreeMap::new(), expiry }
+	}
+
+	/// Queues a message with a majority of signatures.
+	///
+	/// # Parameters
+	///
+	/// * `route`: Origin and foreign network of the message.
+	/// * `message`: Gossip message with a majority of signatures.
+	pub(crate) fn push(&mut self, route: Route, message: GossipMessage) {
+		self.ready.entry(route).or_default().insert(message.payload.nonce, message);
+	}
+
+	/// Indicates if no message of the route is waiting to be submitted.
+	///
+	/// # Parameters
+	///
+	/// * `route`: Origin and foreign network of the messages.
+	pub(crate) fn is_empty(&self, route: Route) -> bool {
+		self.ready.get(&route).map_or(true, |ready| ready.is_empty())
+	}
+
+	/// Indicates if the message with the given nonce is waiting to be submitted.
+	///
+	/// # Parameters
+	///
+	/// * `route`: Origin and foreign network of the message.
+	/// * `nonce`: Nonce of the message.
+	pub(crate) fn contains(&self, route: Route, nonce: u64) -> bool {
+		self.ready.get(&route).map_or(false, |ready| ready.contains_key(&nonce))
+	}
+
+	/// Returns the last nonce processed by the destination, accounting for the messages
+	/// submitted which are not processed yet.
+	///
+	/// # Parameters
+	///
+	/// * `route`: Origin and foreign network of the messages.
+	/// * `processed`: Last nonce processed by the destination.
+	pub(crate) fn last_nonce(&self, route: Route, processed: u64) -> u64 {
+		// Submitted messages are expected to be processed before they expire, otherwise they are
+		// submitted again
+		match self.last_submitted.get(&route) {
+			Some((nonce, submitted_at))
+				if Instant::now().duration_since(*submitted_at) < self.expiry =>
+				processed.max(*nonce),
+			_ => processed,
+		}
+	}
+
+	/// Submits the queued messages in the order of their nonces, stopping at the first missing
+	/// nonce. A message which fails to be submitted is kept to be retried.
+	///
+	/// # Parameters
+	///
+	/// * `route`: Origin and foreign network of the messages.
+	/// * `processed`: Last nonce processed by the destination.
+	/// * `send`: Submits a message to its destination.
+	pub(crate) async fn submit<F, Fut>(
+		&mut self,
+		route: Route,
+		processed: u64,
+		mut send: F,
+	) -> Result<(), Error>
+	where
+		F: FnMut(GossipMessage) -> Fut,
+		Fut: Future<Output = Result<(), Error>>,
+	{
+		let mut next_nonce = self.last_nonce(route, processed).saturating_add(1);
+		loop {
+			let message = match self.ready.get_mut(&route) {
+				None => return Ok(()),
+				Some(ready) => {
+					// Drop the messages already processed by the destination
+					*ready = ready.split_off(&next_nonce);
+					match ready.remove(&next_nonce) {
+						None => return Ok(()),
+						Some(message) => message,
+					}
+				},
+			};
+			if let Err(err) = send(message.clone()).await {
+				// Retry on the next tick
+				self.push(route, message);
+				return Err(err)
+			}
+			info!(target:"thea", "🌉 Submitted message: nonce: {:?}, origin: {:?}, network: {:?}", next_nonce, route.0, route.1);
+			self.last_submitted.insert(route, (next_nonce, Instant::now()));
+			next_nonce = next_nonce.saturating_add(1);
+		}
+	}
+}
@@ -127,9 +127,9 @@ pub async fn test_foreign_deposit() {
 		validator_set_id: 0,
 		_next_authorities: BTreeMap::new(),
 		network_pref: BTreeMap::from([
-			(active[0].clone(), network),
-			(active[1].clone(), network),
-			(active[2].clone(), network),
+			(active[0].clone(), vec![network]),
+			(active[1].clone(), vec![network]),
+			(active[2].clone(), vec![network]),
 		]),
 		outgoing_messages: BTreeMap::new(),
 		incoming_messages: Arc::new(RwLock::new(BTreeMap::new())),
@@ -169,9 +169,27 @@ pub async fn test_foreign_deposit() {
 	// not if we artificially gossip these messages to each other.
 
 	// Get all the messages
-	let _message0 = workers[0].0.message_cache.read().get(&message).cloned().unwrap();
-	let message1 = workers[1].0.message_cache.read().get(&message).cloned().unwrap();
-	let message2 = workers[2].0.message_cache.read().get(&message).cloned().unwrap();
+	let _message0 = workers[0]
+		.0
+		.message_cache
+		.read()
+		.get(&(network, message.clone()))
+		.cloned()
+		.unwrap();
+	let message1 = workers[1]
+		.0
+		.message_cache
+		.read()
+		.get(&(network, message.clone()))
+		.cloned()
+		.unwrap();
+	let message2 = workers[2]
+		.0
+		.message_cache
+		.read()
+		.get(&(network, message.clone()))
+		.cloned()
+		.unwrap();
 
 	// Send 1,2 to 0
 	workers[0].0.process_gossip_message(&mut message1.clone(), None).await.unwrap(); // We got majority here
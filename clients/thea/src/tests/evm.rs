@@ -0,0 +1,341 @@
+// This file is part of Polkadex.
+//
+// Copyright (c) 2023 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Tests of the EVM connector against a local stand-in of an anvil node, which serves the
+//! JSON-RPC methods used by the connector on top of an in memory Thea bridge contract.
+
+use crate::{
+	connector::{
+		evm::{EvmClient, EvmConfig, TheaBridgeCalls, TheaMessageFilter},
+		traits::ForeignConnector,
+	},
+	types::GossipMessage,
+};
+use ethers::{
+	abi::{AbiDecode, AbiEncode, Token},
+	contract::EthEvent,
+	signers::LocalWallet,
+	types::{
+		transaction::eip2718::TypedTransaction, Address, Bytes, Log, Transaction,
+		TransactionReceipt, H256, U256, U64,
+	},
+	utils::{keccak256, rlp::Rlp},
+};
+use jsonrpsee::{
+	core::Error as RpcError,
+	server::{ServerBuilder, ServerHandle},
+	RpcModule,
+};
+use parity_scale_codec::{Decode, Encode};
+use parking_lot::RwLock;
+use serde_json::Value;
+use std::{collections::BTreeMap, str::FromStr, sync::Arc, time::Duration};
+use thea_primitives::{Message, ETHEREUM_NETWORK};
+
+/// Chain id of anvil.
+const CHAIN_ID: u64 = 31337;
+
+/// First account of anvil.
+const RELAYER_KEY: &str = "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+/// In memory state of the stand-in node and its bridge contract.
+#[derive(Default)]
+struct StandInState {
+	/// Best block number.
+	block_number: u64,
+	/// Outgoing messages with the block they were emitted in.
+	outgoing: Vec<(u64, Message)>,
+	/// Last nonce received from Polkadex.
+	incoming_nonce: u64,
+	/// Number of Thea authorities of the current set.
+	authorities: u64,
+	/// Messages received from Polkadex with their bitmap and aggregated signature.
+	incoming: Vec<(Message, Vec<u128>, Vec<u8>)>,
+	/// Receipts of the submitted transactions.
+	receipts: BTreeMap<H256, TransactionReceipt>,
+	/// Whether the submitted transactions stay pending.
+	withhold_receipts: bool,
+	/// Receipts of the pending transactions.
+	withheld: BTreeMap<H256, TransactionReceipt>,
+	/// Block ranges of the log queries.
+	log_queries: Vec<(u64, u64)>,
+}
+
+type SharedState = Arc<RwLock<StandInState>>;
+
+/// Returns the calldata of a call request.
+fn call_data(request: &Value) -> Result<Vec<u8>, RpcError> {
+	let data = request.get("data").or_else(|| request.get("input")).cloned();
+	let data: Bytes = serde_json::from_value(data.unwrap_or_default())?;
+	Ok(data.to_vec())
+}
+
+/// Returns the log of an outgoing message.
+fn message_log(bridge: Address, block: u64, message: &Message) -> Log {
+	Log {
+		address: bridge,
+		topics: vec![TheaMessageFilter::signature(), H256::from_low_u64_be(message.nonce)],
+		data: ethers::abi::encode(&[Token::Bytes(Encode::encode(message))]).into(),
+		block_number: Some(block.into()),
+		..Default::default()
+	}
+}
+
+/// Starts the stand-in node and returns its url.
+async fn start_stand_in_node(bridge: Address, state: SharedState) -> (String, ServerHandle) {
+	let mut module = RpcModule::new(state);
+	module.register_method("eth_chainId", |_, _| Ok(U64::from(CHAIN_ID))).unwrap();
+	module
+		.register_method("eth_blockNumber", |_, state| Ok(U64::from(state.read().block_number)))
+		.unwrap();
+	module.register_method("eth_gasPrice", |_, _| Ok(U256::one())).unwrap();
+	module
+		.register_method("eth_estimateGas", |_, _| Ok(U256::from(100_000)))
+		.unwrap();
+	module
+		.register_method("eth_getTransactionCount", |_, _| Ok(U256::zero()))
+		.unwrap();
+	module
+		.register_method("eth_call", |params, state| {
+			let (request, _block): (Value, Value) = params.parse()?;
+			let state = state.read();
+			let result = match TheaBridgeCalls::decode(call_data(&request)?)
+				.map_err(|err| RpcError::Custom(err.to_string()))?
+			{
+				TheaBridgeCalls::IncomingNonce(_) => AbiEncode::encode(state.incoming_nonce),
+				TheaBridgeCalls::ValidatorSetId(_) => AbiEncode::encode(1u64),
+				TheaBridgeCalls::AuthoritiesCount(_) =>
+					AbiEncode::encode(U256::from(state.authorities)),
+				TheaBridgeCalls::IncomingMessage(_) =>
+					return Err(RpcError::Custom(String::from("Not a view function"))),
+			};
+			Ok(Bytes::from(result))
+		})
+		.unwrap();
+	module
+		.register_method("eth_getLogs", move |params, state| {
+			let (filter,): (Value,) = params.parse()?;
+			let from_block: U64 = serde_json::from_value(filter["fromBlock"].clone())?;
+			let to_block: U64 = serde_json::from_value(filter["toBlock"].clone())?;
+			let nonce: Option<H256> = serde_json::from_value(filter["topics"][1].clone())?;
+			let mut state = state.write();
+			state.log_queries.push((from_block.as_u64(), to_block.as_u64()));
+			let logs: Vec<Log> = state
+				.outgoing
+				.iter()
+				.filter(|(block, message)| {
+					(from_block.as_u64()..=to_block.as_u64()).contains(block) &&
+						nonce.map_or(true, |nonce| H256::from_low_u64_be(message.nonce) == nonce)
+				})
+				.map(|(block, message)| message_log(bridge, *block, message))
+				.collect();
+			Ok(logs)
+		})
+		.unwrap();
+	module
+		.register_method("eth_sendRawTransaction", |params, state| {
+			let (raw,): (Bytes,) = params.parse()?;
+			let (tx, _signature) = TypedTransaction::decode_signed(&Rlp::new(&raw))
+				.map_err(|err| RpcError::Custom(err.to_string()))?;
+			let data = tx.data().cloned().unwrap_or_default();
+			let call = match TheaBridgeCalls::decode(data.as_ref())
+				.map_err(|err| RpcError::Custom(err.to_string()))?
+			{
+				TheaBridgeCalls::IncomingMessage(call) => call,
+				_ => return Err(RpcError::Custom(String::from("Not a transaction"))),
+			};
+			let message = Message::decode(&mut &call.payload[..])
+				.map_err(|err| RpcError::Custom(err.to_string()))?;
+			let hash = H256::from(keccak256(&raw));
+			let mut state = state.write();
+			state.block_number += 1;
+			state.incoming_nonce = message.nonce;
+			state.incoming.push((message, call.bitmap, call.aggregate_signature.to_vec()));
+			let receipt = TransactionReceipt {
+				transaction_hash: hash,
+				block_number: Some(state.block_number.into()),
+				status: Some(U64::one()),
+				..Default::default()
+			};
+			if state.withhold_receipts {
+				state.withheld.insert(hash, receipt);
+			} else {
+				state.receipts.insert(hash, receipt);
+			}
+			Ok(hash)
+		})
+		.unwrap();
+	module
+		.register_method("eth_getTransactionByHash", |params, state| {
+			let (hash,): (H256,) = params.parse()?;
+			let state = state.read();
+			if state.withheld.contains_key(&hash) {
+				return Ok(Some(Transaction { hash, ..Default::default() }))
+			}
+			Ok(state.receipts.get(&hash).map(|receipt| Transaction {
+				hash,
+				block_number: receipt.block_number,
+				..Default::default()
+			}))
+		})
+		.unwrap();
+	module
+		.register_method("eth_getTransactionReceipt", |params, state| {
+			let (hash,): (H256,) = params.parse()?;
+			Ok(state.read().receipts.get(&hash).cloned())
+		})
+		.unwrap();
+
+	let server = ServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	let url = format!("http://{}", server.local_addr().unwrap());
+	(url, server.start(module).unwrap())
+}
+
+fn message(nonce: u64, data: Vec<u8>) -> Message {
+	Message {
+		block_no: 10,
+		nonce,
+		data,
+		network: ETHEREUM_NETWORK,
+		is_key_change: false,
+		validator_set_id: 1,
+		validator_set_len: 3,
+	}
+}
+
+fn config(url: String, bridge: Address, relayer: Option<LocalWallet>) -> EvmConfig {
+	EvmConfig {
+		url,
+		bridge,
+		confirmations: 2,
+		start_block: 0,
+		block_time: Duration::from_millis(10),
+		relayer,
+	}
+}
+
+#[tokio::test]
+pub async fn test_evm_connector_reads_final_bridge_events() {
+	let bridge = Address::from_low_u64_be(0xbeef);
+	let state = SharedState::default();
+	{
+		let mut state = state.write();
+		state.block_number = 10;
+		state.authorities = 3;
+		state.outgoing.push((5, message(1, vec![1, 2, 3])));
+		// Not final yet
+		state.outgoing.push((9, message(2, vec![4, 5, 6])));
+	}
+	let (url, _handle) = start_stand_in_node(bridge, state.clone()).await;
+	let connector = EvmClient::connect_with(config(url, bridge, None)).await.unwrap();
+
+	assert!(connector.check_thea_authority_initialization().await.unwrap());
+	assert_eq!(connector.last_processed_nonce_from_native().await.unwrap(), 0);
+	assert_eq!(connector.read_events(1).await.unwrap(), Some(message(1, vec![1, 2, 3])));
+	assert_eq!(connector.read_events(2).await.unwrap(), None);
+	assert!(connector.check_message(&message(1, vec![1, 2, 3])).await.unwrap());
+	assert!(!connector.check_message(&message(1, vec![3, 2, 1])).await.unwrap());
+
+	// The second message is final once enough blocks are built on top of it
+	state.write().block_number = 11;
+	assert_eq!(connector.read_events(2).await.unwrap(), Some(message(2, vec![4, 5, 6])));
+	assert_eq!(connector.read_events(3).await.unwrap(), None);
+
+	// Each final block is searched once
+	assert_eq!(state.read().log_queries, vec![(0, 8), (9, 9)]);
+
+	state.write().authorities = 0;
+	assert!(!connector.check_thea_authority_initialization().await.unwrap());
+}
+
+#[tokio::test]
+pub async fn test_evm_connector_submits_aggregated_messages() {
+	let bridge = Address::from_low_u64_be(0xbeef);
+	let state = SharedState::default();
+	state.write().block_number = 10;
+	let (url, _handle) = start_stand_in_node(bridge, state.clone()).await;
+
+	let gossip_message = GossipMessage {
+		payload: message(1, vec![1, 2, 3]),
+		network: ETHEREUM_NETWORK,
+		bitmap: vec![0b111],
+		aggregate_signature: bls_primitives::Signature([7; 48]),
+	};
+
+	// Messages can't be submitted without a relayer account
+	let connector = EvmClient::connect_with(config(url.clone(), bridge, None)).await.unwrap();
+	assert!(connector.send_transaction(gossip_message.clone()).await.is_err());
+
+	let relayer = LocalWallet::from_str(RELAYER_KEY).unwrap();
+	let connector = EvmClient::connect_with(config(url, bridge, Some(relayer))).await.unwrap();
+	state.write().withhold_receipts = true;
+	connector.send_transaction(gossip_message.clone()).await.unwrap();
+	assert_eq!(connector.pending_transactions(), vec![1]);
+
+	// The message is not submitted again while its transaction is pending
+	connector.send_transaction(gossip_message.clone()).await.unwrap();
+	assert_eq!(state.read().incoming.len(), 1);
+
+	// The receipt is tracked in the background
+	{
+		let mut state = state.write();
+		let withheld = std::mem::take(&mut state.withheld);
+		state.receipts.extend(withheld);
+	}
+	for _ in 0..100 {
+		if connector.pending_transactions().is_empty() {
+			break
+		}
+		tokio::time::sleep(Duration::from_millis(10)).await;
+	}
+	assert!(connector.pending_transactions().is_empty());
+
+	let state = state.read();
+	assert_eq!(state.incoming_nonce, 1);
+	assert_eq!(
+		state.incoming,
+		vec![(
+			message(1, vec![1, 2, 3]),
+			vec![0b111],
+			Encode::encode(&gossip_message.aggregate_signature)
+		)]
+	);
+}
+
+#[test]
+pub fn test_evm_config_from_url() {
+	let config = EvmConfig::from_url(
+		"http://127.0.0.1:8545/?bridge=0x5FbDB2315678afecb367f032d93F642f64180aa3&confirmations=1&start_block=12",
+	)
+	.unwrap();
+	assert_eq!(config.url, "http://127.0.0.1:8545/");
+	assert_eq!(
+		config.bridge,
+		Address::from_str("0x5FbDB2315678afecb367f032d93F642f64180aa3").unwrap()
+	);
+	assert_eq!(config.confirmations, 1);
+	assert_eq!(config.start_block, 12);
+
+	assert!(EvmConfig::from_url("http://127.0.0.1:8545?start_block=12").is_err());
+	assert!(EvmConfig::from_url("http://127.0.0.1:8545?bridge=0x12&start_block=12").is_err());
+	// Without the start block the first lookup would search the whole chain
+	assert!(EvmConfig::from_url(
+		"http://127.0.0.1:8545?bridge=0x5FbDB2315678afecb367f032d93F642f64180aa3"
+	)
+	.is_err());
+}
@@ -46,7 +46,7 @@ use sp_keyring::AccountKeyring;
 use sp_keystore::{SyncCryptoStore, SyncCryptoStorePtr};
 use sp_runtime::key_types::GRANDPA;
 use std::{
-	collections::{BTreeMap, HashMap},
+	collections::{BTreeMap, BTreeSet, HashMap},
 	future::Future,
 	sync::{Arc, Mutex},
 	time::Duration,
@@ -58,8 +58,12 @@ use thea_primitives::{
 use tokio::time::Instant;
 
 //pub mod deposit;
+mod evm;
 mod grandpa;
+mod parachain;
+mod queue;
 //mod protocol;
+mod supervisor;
 //pub mod withdrawal;
 
 pub(crate) use grandpa::*;
@@ -71,7 +75,7 @@ pub(crate) struct TestApi {
 	authorities: BTreeMap<Network, ValidatorSet<AuthorityId>>,
 	validator_set_id: ValidatorSetId,
 	_next_authorities: BTreeMap<Network, ValidatorSet<AuthorityId>>,
-	network_pref: BTreeMap<AuthorityId, Network>,
+	network_pref: BTreeMap<AuthorityId, Vec<Network>>,
 	outgoing_messages: BTreeMap<(Network, u64), Message>,
 	incoming_messages: Arc<RwLock<BTreeMap<(Network, u64), Message>>>,
 	incoming_nonce: Arc<RwLock<BTreeMap<Network, u64>>>,
@@ -95,8 +99,8 @@ impl TestApi {
 		self.outgoing_messages.get(&(network, nonce)).cloned()
 	}
 
-	fn network(&self, auth: AuthorityId) -> Option<Network> {
-		self.network_pref.get(&auth).cloned()
+	fn networks(&self, auth: AuthorityId) -> Vec<Network> {
+		self.network_pref.get(&auth).cloned().unwrap_or_default()
 	}
 
 	fn incoming_message(
@@ -174,7 +178,12 @@ sp_api::mock_impl_runtime_apis! {
 
 		/// Get Thea network associated with Validator
 		fn network(auth: AuthorityId) -> Option<Network>{
-			self.inner.network(auth)
+			self.inner.networks(auth).into_iter().next()
+		}
+
+		/// Get Thea networks associated with Validator
+		fn networks(auth: AuthorityId) -> Vec<Network>{
+			self.inner.networks(auth)
 		}
 
 		/// Incoming messages
@@ -232,7 +241,8 @@ pub struct PeerData {
 pub struct TheaTestnet {
 	api: Arc<TestApi>,
 	peers: Vec<GrandpaPeer>,
-	worker_massages: HashMap<usize, Arc<RwLock<BTreeMap<Message, (Instant, GossipMessage)>>>>,
+	worker_massages:
+		HashMap<usize, Arc<RwLock<BTreeMap<(Network, Message), (Instant, GossipMessage)>>>>,
 }
 
 impl TheaTestnet {
@@ -363,10 +373,10 @@ where
 			_marker: Default::default(),
 			is_validator,
 			metrics: None,
-			foreign_chain: connector,
+			foreign_chains: BTreeMap::from([(1, connector)]),
 		};
 		let mut gadget = crate::worker::TheaWorker::new(worker_params).await;
-		gadget.thea_network = Some(1);
+		gadget.thea_networks = BTreeSet::from([1]);
 		net.worker_massages.insert(peer_id, gadget.message_cache.clone());
 		let run_future = gadget.run();
 		fn assert_send<T: Send>(_: &T) {}
@@ -434,7 +444,7 @@ where
 			_marker: Default::default(),
 			is_validator,
 			metrics: None,
-			foreign_chain: connector,
+			foreign_chains: BTreeMap::from([(1, connector)]),
 		};
 		let gadget = crate::worker::TheaWorker::new(worker_params).await;
 		let finality_stream_future =
@@ -0,0 +1,268 @@
+// This file is part of Polkadex.
+//
+// Copyright (c) 2023 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+//! Tests of the parachain connector against a stand-in node, which serves the finalized heads
+//! and the storage of the Thea message handler.
+
+use crate::{
+	connector::{
+		parachain::{BlockHash, CacheKey, FinalizedHeads, ParachainClient, ParachainNode},
+		supervisor::{ConnectorHealth, SupervisedConnector},
+		traits::ForeignConnector,
+	},
+	error::Error,
+	types::GossipMessage,
+};
+use async_trait::async_trait;
+use futures::{
+	channel::mpsc::{unbounded, UnboundedSender},
+	StreamExt,
+};
+use parity_scale_codec::Encode;
+use parking_lot::{const_mutex, Mutex, RwLock};
+use std::{
+	collections::BTreeMap,
+	sync::{
+		atomic::{AtomicBool, AtomicU32, Ordering},
+		Arc,
+	},
+	time::Duration,
+};
+use thea_primitives::{Message, PARACHAIN_NETWORK};
+
+/// Stand-in nodes by url.
+static NODES: Mutex<BTreeMap<String, Arc<StandInNode>>> = const_mutex(BTreeMap::new());
+
+/// Storage of the Thea message handler at a block.
+#[derive(Clone, Default)]
+struct BlockState {
+	/// Outgoing messages by nonce.
+	outgoing: BTreeMap<u64, Message>,
+	/// Last processed nonce from native.
+	incoming_nonce: u64,
+	/// Indicates if the Thea authorities are initialized.
+	authorities_initialized: bool,
+}
+
+/// In memory state of the stand-in node.
+#[derive(Default)]
+struct StandInNode {
+	/// Indicates if the node accepts connections.
+	up: AtomicBool,
+	/// Storage by block.
+	blocks: RwLock<BTreeMap<BlockHash, BlockState>>,
+	/// Last finalized head.
+	finalized: RwLock<Option<BlockHash>>,
+	/// Sender of the finalized heads of the current subscription.
+	subscription: Mutex<Option<UnboundedSender<Result<BlockHash, Error>>>>,
+	/// Number of subscriptions to the finalized heads.
+	subscriptions: AtomicU32,
+	/// Number of storage reads.
+	reads: AtomicU32,
+}
+
+impl StandInNode {
+	/// Starts a stand-in node at the given url.
+	fn start(url: &str) -> Arc<Self> {
+		let node = Arc::new(StandInNode::default());
+		node.up.store(true, Ordering::SeqCst);
+		NODES.lock().insert(url.to_string(), node.clone());
+		node
+	}
+
+	/// Finalizes a new block with the given storage.
+	fn finalize(&self, byte: u8, state: BlockState) -> BlockHash {
+		let hash = BlockHash::repeat_byte(byte);
+		self.blocks.write().insert(hash, state);
+		*self.finalized.write() = Some(hash);
+		if let Some(subscription) = self.subscription.lock().as_ref() {
+			let _ = subscription.unbounded_send(Ok(hash));
+		}
+		hash
+	}
+
+	/// Drops the current subscription.
+	fn drop_connection(&self) {
+		*self.subscription.lock() = None;
+	}
+}
+
+/// Client of a stand-in node.
+#[derive(Clone)]
+struct StandInClient(Arc<StandInNode>);
+
+#[async_trait]
+impl ParachainNode for StandInClient {
+	async fn subscribe_finalized(url: &str) -> Result<(Self, FinalizedHeads), Error> {
+		let node = NODES
+			.lock()
+			.get(url)
+			.cloned()
+			.filter(|node| node.up.load(Ordering::SeqCst))
+			.ok_or(Error::Subxt(String::from("Connection refused")))?;
+		let (sender, receiver) = unbounded();
+		// The last finalized head is sent when subscribing
+		if let Some(hash) = *node.finalized.read() {
+			let _ = sender.unbounded_send(Ok(hash));
+		}
+		*node.subscription.lock() = Some(sender);
+		node.subscriptions.fetch_add(1, Ordering::SeqCst);
+		Ok((StandInClient(node), receiver.boxed()))
+	}
+
+	async fn fetch(&self, at: BlockHash, key: &CacheKey) -> Result<Vec<u8>, Error> {
+		self.0.reads.fetch_add(1, Ordering::SeqCst);
+		let blocks = self.0.blocks.read();
+		let block = blocks.get(&at).ok_or(Error::Subxt(String::from("Unknown block")))?;
+		Ok(match key {
+			CacheKey::OutgoingMessage(nonce) => block.outgoing.get(nonce).cloned().encode(),
+			CacheKey::IncomingNonce => block.incoming_nonce.encode(),
+			CacheKey::AuthoritiesInitialized => block.authorities_initialized.encode(),
+		})
+	}
+
+	async fn fetch_outgoing_nonce(&self, at: BlockHash) -> Result<u64, Error> {
+		let blocks = self.0.blocks.read();
+		let block = blocks.get(&at).ok_or(Error::Subxt(String::from("Unknown block")))?;
+		Ok(block.outgoing.keys().last().copied().unwrap_or_default())
+	}
+
+	async fn submit(&self, _: GossipMessage) -> Result<(), Error> {
+		Ok(())
+	}
+}
+
+fn message(nonce: u64) -> Message {
+	Message {
+		block_no: 10,
+		nonce,
+		data: vec![1, 2, 3],
+		network: PARACHAIN_NETWORK,
+		is_key_change: false,
+		validator_set_id: 1,
+		validator_set_len: 3,
+	}
+}
+
+fn block_state(nonces: std::ops::RangeInclusive<u64>) -> BlockState {
+	BlockState {
+		outgoing: nonces.map(|nonce| (nonce, message(nonce))).collect(),
+		incoming_nonce: 0,
+		authorities_initialized: true,
+	}
+}
+
+#[tokio::test]
+pub async fn test_parachain_connector_follows_finalized_heads() {
+	let url = "ws://follows-finalized-heads";
+	let node = StandInNode::start(url);
+	node.finalize(1, block_state(1..=1));
+	let connector = ParachainClient::<StandInClient>::connect(url.to_string()).await.unwrap();
+	let mut messages = connector.message_stream().unwrap();
+
+	assert!(connector.check_thea_authority_initialization().await.unwrap());
+	assert_eq!(connector.last_processed_nonce_from_native().await.unwrap(), 0);
+	assert_eq!(connector.read_events(1).await.unwrap(), Some(message(1)));
+	assert_eq!(connector.read_events(2).await.unwrap(), None);
+
+	// Storage reads are cached for the finalized head
+	let reads = node.reads.load(Ordering::SeqCst);
+	assert_eq!(connector.read_events(1).await.unwrap(), Some(message(1)));
+	assert!(connector.check_message(&message(1)).await.unwrap());
+	assert_eq!(node.reads.load(Ordering::SeqCst), reads);
+
+	// New messages are pushed once final
+	node.finalize(2, BlockState { incoming_nonce: 4, ..block_state(1..=2) });
+	let pushed = tokio::time::timeout(Duration::from_secs(1), messages.next()).await.unwrap();
+	assert_eq!(pushed, Some(message(2)));
+	assert_eq!(connector.last_processed_nonce_from_native().await.unwrap(), 4);
+	assert_eq!(connector.read_events(2).await.unwrap(), Some(message(2)));
+	assert_eq!(node.subscriptions.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+pub async fn test_parachain_connector_times_out_without_finalized_head() {
+	let url = "ws://no-finalized-head";
+	StandInNode::start(url);
+	let result = ParachainClient::<StandInClient>::connect_with_timeout(
+		url.to_string(),
+		Duration::from_millis(100),
+	)
+	.await;
+	assert!(result.is_err());
+
+	// Unreachable nodes fail right away
+	assert!(ParachainClient::<StandInClient>::connect(String::from("ws://unknown"))
+		.await
+		.is_err());
+}
+
+#[tokio::test]
+pub async fn test_parachain_connector_closes_when_connection_dropped() {
+	let url = "ws://connection-dropped";
+	let node = StandInNode::start(url);
+	node.finalize(1, block_state(1..=1));
+	let connector = ParachainClient::<StandInClient>::connect(url.to_string()).await.unwrap();
+	let mut messages = connector.message_stream().unwrap();
+	assert_eq!(connector.read_events(1).await.unwrap(), Some(message(1)));
+
+	// The message stream ends and the calls fail, the connector doesn't reconnect by itself
+	node.drop_connection();
+	let pushed = tokio::time::timeout(Duration::from_secs(1), messages.next()).await.unwrap();
+	assert_eq!(pushed, None);
+	assert!(connector.read_events(1).await.is_err());
+	assert_eq!(node.subscriptions.load(Ordering::SeqCst), 1);
+
+	// The stream of a closed connection ends right away
+	let messages = connector.message_stream().unwrap();
+	assert_eq!(messages.collect::<Vec<_>>().await, vec![]);
+}
+
+#[tokio::test]
+pub async fn test_supervised_parachain_connector_reconnects_when_connection_dropped() {
+	let url = "ws://supervised-connection-dropped";
+	let node = StandInNode::start(url);
+	node.finalize(1, block_state(1..=1));
+	let connector = SupervisedConnector::<ParachainClient<StandInClient>>::new(url.to_string());
+	wait_for_subscriptions(&node, 1).await;
+	assert_eq!(connector.read_events(1).await.unwrap(), Some(message(1)));
+
+	node.drop_connection();
+	node.finalize(2, block_state(1..=2));
+	wait_for_subscriptions(&node, 2).await;
+	for _ in 0..100 {
+		if connector.health() == ConnectorHealth::Healthy {
+			break
+		}
+		tokio::time::sleep(Duration::from_millis(10)).await;
+	}
+	assert_eq!(connector.read_events(2).await.unwrap(), Some(message(2)));
+	assert_eq!(node.subscriptions.load(Ordering::SeqCst), 2);
+}
+
+/// Waits until the stand-in node got the expected number of subscriptions.
+async fn wait_for_subscriptions(node: &StandInNode, subscriptions: u32) {
+	for _ in 0..100 {
+		if node.subscriptions.load(Ordering::SeqCst) >= subscriptions {
+			// Let the connector finish connecting
+			tokio::time::sleep(Duration::from_millis(50)).await;
+			return
+		}
+		tokio::time::sleep(Duration::from_millis(10)).await;
+	}
+	panic!("Expected {:?} subscriptions", subscriptions);
+}
@@ -60,9 +60,9 @@ async fn dropped_one_validator_still_works_test() {
 		validator_set_id: 0,
 		_next_authorities: BTreeMap::new(),
 		network_pref: BTreeMap::from([
-			(active[0].clone(), network),
-			(active[1].clone(), network),
-			(active[2].clone(), network),
+			(active[0].clone(), vec![network]),
+			(active[1].clone(), vec![network]),
+			(active[2].clone(), vec![network]),
 		]),
 		outgoing_messages: BTreeMap::from([((network, 1), message.clone())]),
 		incoming_messages: Arc::new(RwLock::new(BTreeMap::new())),
@@ -96,8 +96,8 @@ async fn dropped_one_validator_still_works_test() {
 
 	// push some message
 	let message_cache = Arc::new(RwLock::new(BTreeMap::new()));
-	let foreign_nonce = Arc::new(RwLock::new(0));
-	let native_nonce = Arc::new(RwLock::new(0));
+	let foreign_nonce = Arc::new(RwLock::new(BTreeMap::new()));
+	let native_nonce = Arc::new(RwLock::new(BTreeMap::new()));
 	let gossip_validator = Arc::new(GossipValidator::<Block>::new(
 		message_cache.clone(),
 		foreign_nonce.clone(),
@@ -186,9 +186,9 @@ async fn validator_set_change_mid_messaging_works_test() {
 		validator_set_id: 0,
 		_next_authorities: BTreeMap::new(),
 		network_pref: BTreeMap::from([
-			(active[0].clone(), network),
-			(active[1].clone(), network),
-			(active[2].clone(), network),
+			(active[0].clone(), vec![network]),
+			(active[1].clone(), vec![network]),
+			(active[2].clone(), vec![network]),
 		]),
 		outgoing_messages: BTreeMap::from([((network, 1), message.clone())]),
 		incoming_messages: Arc::new(RwLock::new(BTreeMap::new())),
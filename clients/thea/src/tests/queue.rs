@@ -0,0 +1,135 @@
+// This file is part of Polkadex.
+//
+// Copyright (c) 2023 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+//! Tests of the submission of the messages with a majority of signatures to their destination.
+
+use crate::{error::Error, queue::SubmissionQueue, types::GossipMessage};
+use std::time::Duration;
+use thea_primitives::{Message, Network, NATIVE_NETWORK, PARACHAIN_NETWORK};
+
+const ROUTE: (Network, Network) = (NATIVE_NETWORK, PARACHAIN_NETWORK);
+
+fn gossip_message(nonce: u64) -> GossipMessage {
+	GossipMessage {
+		payload: Message {
+			block_no: 10,
+			nonce,
+			data: vec![1, 2, 3],
+			network: NATIVE_NETWORK,
+			is_key_change: false,
+			validator_set_id: 1,
+			validator_set_len: 3,
+		},
+		network: PARACHAIN_NETWORK,
+		bitmap: vec![0b111],
+		aggregate_signature: bls_primitives::Signature([7; 48]),
+	}
+}
+
+/// Submits the queued messages and returns the submitted nonces.
+async fn submit(
+	queue: &mut SubmissionQueue,
+	processed: u64,
+	fail_at: Option<u64>,
+) -> (Result<(), Error>, Vec<u64>) {
+	let mut submitted = vec![];
+	let result = queue
+		.submit(ROUTE, processed, |message| {
+			let nonce = message.payload.nonce;
+			let result = match fail_at == Some(nonce) {
+				true => Err(Error::Subxt(String::from("Connection refused"))),
+				false => Ok(()),
+			};
+			if result.is_ok() {
+				submitted.push(nonce);
+			}
+			async move { result }
+		})
+		.await;
+	(result, submitted)
+}
+
+#[tokio::test]
+pub async fn test_messages_are_submitted_in_the_order_of_their_nonces() {
+	let mut queue = SubmissionQueue::new(Duration::from_secs(60));
+	for nonce in [3, 1, 5, 2] {
+		queue.push(ROUTE, gossip_message(nonce));
+	}
+	assert!(queue.contains(ROUTE, 5));
+	assert!(!queue.contains(ROUTE, 4));
+
+	// Submission stops at the first missing nonce
+	let (result, submitted) = submit(&mut queue, 0, None).await;
+	assert!(result.is_ok());
+	assert_eq!(submitted, vec![1, 2, 3]);
+	assert_eq!(queue.last_nonce(ROUTE, 0), 3);
+	assert!(queue.contains(ROUTE, 5));
+
+	queue.push(ROUTE, gossip_message(4));
+	let (result, submitted) = submit(&mut queue, 1, None).await;
+	assert!(result.is_ok());
+	assert_eq!(submitted, vec![4, 5]);
+	assert!(queue.is_empty(ROUTE));
+}
+
+#[tokio::test]
+pub async fn test_messages_already_processed_are_dropped() {
+	let mut queue = SubmissionQueue::new(Duration::from_secs(60));
+	for nonce in 1..=4 {
+		queue.push(ROUTE, gossip_message(nonce));
+	}
+	let (result, submitted) = submit(&mut queue, 2, None).await;
+	assert!(result.is_ok());
+	assert_eq!(submitted, vec![3, 4]);
+	assert!(queue.is_empty(ROUTE));
+}
+
+#[tokio::test]
+pub async fn test_failed_messages_are_retried() {
+	let mut queue = SubmissionQueue::new(Duration::from_secs(60));
+	for nonce in 1..=3 {
+		queue.push(ROUTE, gossip_message(nonce));
+	}
+	let (result, submitted) = submit(&mut queue, 0, Some(2)).await;
+	assert!(result.is_err());
+	assert_eq!(submitted, vec![1]);
+	assert!(queue.contains(ROUTE, 2));
+	assert!(queue.contains(ROUTE, 3));
+
+	// The failed message and the following ones are submitted on the next attempt
+	let (result, submitted) = submit(&mut queue, 0, None).await;
+	assert!(result.is_ok());
+	assert_eq!(submitted, vec![2, 3]);
+}
+
+#[tokio::test]
+pub async fn test_expired_submissions_are_submitted_again() {
+	let mut queue = SubmissionQueue::new(Duration::from_millis(50));
+	queue.push(ROUTE, gossip_message(1));
+	let (result, submitted) = submit(&mut queue, 0, None).await;
+	assert!(result.is_ok());
+	assert_eq!(submitted, vec![1]);
+	assert_eq!(queue.last_nonce(ROUTE, 0), 1);
+
+	// The message is not processed before its submission expires
+	tokio::time::sleep(Duration::from_millis(100)).await;
+	assert_eq!(queue.last_nonce(ROUTE, 0), 0);
+	queue.push(ROUTE, gossip_message(1));
+	let (result, submitted) = submit(&mut queue, 0, None).await;
+	assert!(result.is_ok());
+	assert_eq!(submitted, vec![1]);
+}
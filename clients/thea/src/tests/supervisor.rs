@@ -0,0 +1,135 @@
+// This file is part of Polkadex.
+//
+// Copyright (c) 2023 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Tests of the supervision of the foreign connectors against a connector whose foreign node
+//! can be taken down.
+
+use crate::{
+	connector::{
+		supervisor::{ConnectorHealth, ConnectorsHealth, SupervisedConnector},
+		traits::ForeignConnector,
+	},
+	error::Error,
+	types::GossipMessage,
+};
+use async_trait::async_trait;
+use std::{
+	collections::BTreeMap,
+	sync::{
+		atomic::{AtomicBool, AtomicU32, Ordering},
+		Arc,
+	},
+	time::Duration,
+};
+use thea_primitives::{Message, PARACHAIN_NETWORK};
+
+/// Indicates if the stand-in foreign node is reachable.
+static NODE_UP: AtomicBool = AtomicBool::new(false);
+
+/// Number of connections established to the stand-in foreign node.
+static CONNECTIONS: AtomicU32 = AtomicU32::new(0);
+
+/// Connector of the stand-in foreign node.
+struct FlakyConnector;
+
+impl FlakyConnector {
+	fn node() -> Result<(), Error> {
+		match NODE_UP.load(Ordering::SeqCst) {
+			true => Ok(()),
+			false => Err(Error::Subxt(String::from("Connection refused"))),
+		}
+	}
+}
+
+#[async_trait]
+impl ForeignConnector for FlakyConnector {
+	fn block_duration(&self) -> Duration {
+		Duration::from_secs(6)
+	}
+
+	async fn connect(_: String) -> Result<Self, Error> {
+		Self::node()?;
+		CONNECTIONS.fetch_add(1, Ordering::SeqCst);
+		Ok(FlakyConnector)
+	}
+
+	async fn read_events(&self, _: u64) -> Result<Option<Message>, Error> {
+		Self::node().map(|_| None)
+	}
+
+	async fn send_transaction(&self, _: GossipMessage) -> Result<(), Error> {
+		Self::node()
+	}
+
+	async fn check_message(&self, _: &Message) -> Result<bool, Error> {
+		Self::node().map(|_| true)
+	}
+
+	async fn last_processed_nonce_from_native(&self) -> Result<u64, Error> {
+		Self::node().map(|_| 0)
+	}
+
+	async fn check_thea_authority_initialization(&self) -> Result<bool, Error> {
+		Self::node().map(|_| true)
+	}
+}
+
+/// Waits until the connector reports the expected health.
+async fn wait_for_health(connector: &impl ForeignConnector, health: ConnectorHealth) {
+	for _ in 0..100 {
+		if connector.health() == health {
+			return
+		}
+		tokio::time::sleep(Duration::from_millis(50)).await;
+	}
+	panic!("Connector health is {:?}, expected {:?}", connector.health(), health);
+}
+
+#[tokio::test]
+pub async fn test_supervised_connector_reconnects() {
+	// The foreign node is down at startup
+	let connector = Arc::new(SupervisedConnector::<FlakyConnector>::new(String::from("blah")));
+	let connectors = ConnectorsHealth::default();
+	connectors.insert(PARACHAIN_NETWORK, connector.clone());
+	assert_eq!(connector.health(), ConnectorHealth::Connecting);
+	assert_eq!(connector.block_duration(), Duration::from_secs(12));
+	assert!(matches!(connector.read_events(1).await, Err(Error::ConnectorNotConnected)));
+
+	NODE_UP.store(true, Ordering::SeqCst);
+	wait_for_health(connector.as_ref(), ConnectorHealth::Healthy).await;
+	assert_eq!(CONNECTIONS.load(Ordering::SeqCst), 1);
+	assert_eq!(connector.block_duration(), Duration::from_secs(6));
+	assert!(connector.check_thea_authority_initialization().await.unwrap());
+	assert_eq!(
+		connectors.health(),
+		BTreeMap::from([(PARACHAIN_NETWORK, ConnectorHealth::Healthy)])
+	);
+
+	// Failed calls degrade the connector until the connection is considered lost
+	NODE_UP.store(false, Ordering::SeqCst);
+	assert!(connector.read_events(1).await.is_err());
+	assert_eq!(connector.health(), ConnectorHealth::Degraded);
+	assert!(connector.read_events(1).await.is_err());
+	assert!(connector.read_events(1).await.is_err());
+	assert_eq!(connector.health(), ConnectorHealth::Connecting);
+
+	NODE_UP.store(true, Ordering::SeqCst);
+	wait_for_health(connector.as_ref(), ConnectorHealth::Healthy).await;
+	assert_eq!(CONNECTIONS.load(Ordering::SeqCst), 2);
+	assert_eq!(connector.last_processed_nonce_from_native().await.unwrap(), 0);
+}
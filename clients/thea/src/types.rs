@@ -20,13 +20,15 @@
 
 use bls_primitives::Signature;
 use parity_scale_codec::{Decode, Encode};
-use thea_primitives::types::Message;
+use thea_primitives::{types::Message, Network};
 
 /// Representation of the gossip message structure.
 #[derive(Encode, Decode, Clone, Debug)]
 pub struct GossipMessage {
 	/// Payload of the gossip message.
 	pub(crate) payload: Message,
+	/// Foreign network the payload is exchanged with, the destination of the native messages.
+	pub(crate) network: Network,
 	/// Bitmap generated from active validators.
 	pub(crate) bitmap: Vec<u128>,
 	/// Message aggregated signature.
@@ -18,9 +18,15 @@
 
 //! Worker which manages/processes Thea client requests.
 
-use std::{collections::BTreeMap, marker::PhantomData, ops::AddAssign, sync::Arc, time::Duration};
+use std::{
+	collections::{BTreeMap, BTreeSet},
+	marker::PhantomData,
+	ops::AddAssign,
+	sync::Arc,
+	time::Duration,
+};
 
-use futures::StreamExt;
+use futures::{stream::FuturesOrdered, StreamExt};
 use log::{debug, error, info, warn};
 use parity_scale_codec::{Codec, Decode, Encode};
 use parking_lot::RwLock;
@@ -29,7 +35,7 @@ use sc_client_api::{Backend, FinalityNotification};
 use sc_keystore::LocalKeystore;
 use sc_network::PeerId;
 use sc_network_gossip::{GossipEngine, Network as GossipNetwork};
-use sp_api::ProvideRuntimeApi;
+use sp_api::{ApiExt, ProvideRuntimeApi};
 use sp_arithmetic::traits::SaturatedConversion;
 use sp_consensus::SyncOracle;
 use sp_runtime::{
@@ -37,13 +43,13 @@ use sp_runtime::{
 	traits::{Block, Header, Zero},
 };
 use thea_primitives::{
-	types::Message, AuthorityIndex, Network, TheaApi, MESSAGE_CACHE_DURATION_IN_SECS,
-	NATIVE_NETWORK,
+	types::Message, AuthorityId, AuthorityIndex, Network, TheaApi, MAX_MESSAGES_IN_FLIGHT,
+	MESSAGE_CACHE_DURATION_IN_SECS, NATIVE_NETWORK,
 };
 use tokio::time::Instant;
 
 use crate::{
-	connector::traits::ForeignConnector,
+	connector::{supervisor::ConnectorHealth, traits::ForeignConnector},
 	error::Error,
 	gossip::{topic, GossipValidator},
 	keystore::TheaKeyStore,
@@ -71,8 +77,8 @@ pub(crate) struct WorkerParams<B: Block, BE, C, SO, N, R, FC: ForeignConnector +
 	/// Gossip network.
 	pub network: N,
 	pub _marker: PhantomData<B>,
-	/// Foreign chain connector.
-	pub foreign_chain: Arc<FC>,
+	/// Foreign chain connectors by network.
+	pub foreign_chains: BTreeMap<Network, Arc<FC>>,
 	/// Local key store.
 	pub(crate) keystore: Option<Arc<LocalKeystore>>,
 }
@@ -81,8 +87,8 @@ pub(crate) struct WorkerParams<B: Block, BE, C, SO, N, R, FC: ForeignConnector +
 pub(crate) struct TheaWorker<B: Block, BE, C, SO, N, R, FC: ForeignConnector + ?Sized> {
 	/// Thea client.
 	pub(crate) client: Arc<C>,
-	/// Thea network type.
-	pub(crate) thea_network: Option<Network>,
+	/// Foreign networks served by this validator.
+	pub(crate) thea_networks: BTreeSet<Network>,
 	// Payload to gossip message mapping
 	_backend: Arc<BE>,
 	runtime: Arc<R>,
@@ -93,11 +99,13 @@ pub(crate) struct TheaWorker<B: Block, BE, C, SO, N, R, FC: ForeignConnector + ?
 	keystore: TheaKeyStore,
 	gossip_engine: GossipEngine<B>,
 	// Payload to gossip message mapping
-	pub(crate) message_cache: Arc<RwLock<BTreeMap<Message, (Instant, GossipMessage)>>>,
-	last_foreign_nonce_processed: Arc<RwLock<u64>>,
-	last_native_nonce_processed: Arc<RwLock<u64>>,
-	foreign_chain: Arc<FC>,
+	pub(crate) message_cache: Arc<RwLock<BTreeMap<(Network, Message), (Instant, GossipMessage)>>>,
+	last_foreign_nonce_processed: Arc<RwLock<BTreeMap<Network, u64>>>,
+	last_native_nonce_processed: Arc<RwLock<BTreeMap<Network, u64>>>,
+	foreign_chains: BTreeMap<Network, Arc<FC>>,
 	last_finalized_blk: BlockId<B>,
+	// Messages with a majority of signatures waiting to be submitted to their destination
+	submission_queue: SubmissionQueue,
 }
 
 impl<B, BE, C, SO, N, R, FC> TheaWorker<B, BE, C, SO, N, R, FC>
@@ -126,7 +134,7 @@ where
 			client,
 			backend,
 			runtime,
-			foreign_chain,
+			foreign_chains,
 			keystore,
 			sync_oracle,
 			metrics,
@@ -136,8 +144,8 @@ where
 		} = worker_params;
 
 		let message_cache = Arc::new(RwLock::new(BTreeMap::new()));
-		let foreign_nonce = Arc::new(RwLock::new(0));
-		let native_nonce = Arc::new(RwLock::new(0));
+		let foreign_nonce = Arc::new(RwLock::new(BTreeMap::new()));
+		let native_nonce = Arc::new(RwLock::new(BTreeMap::new()));
 		let gossip_validator = Arc::new(GossipValidator::new(
 			message_cache.clone(),
 			foreign_nonce.clone(),
@@ -159,24 +167,43 @@ where
 			is_validator,
 			_network: Arc::new(network),
 			keystore: TheaKeyStore::new(keystore),
-			thea_network: None,
+			thea_networks: BTreeSet::new(),
 			gossip_engine,
 			message_cache,
 			last_foreign_nonce_processed: foreign_nonce,
 			last_native_nonce_processed: native_nonce,
-			foreign_chain,
+			foreign_chains,
 			last_finalized_blk: BlockId::number(Zero::zero()),
+			submission_queue: SubmissionQueue::new(Duration::from_secs(
+				MESSAGE_CACHE_DURATION_IN_SECS,
+			)),
 		}
 	}
 
+	/// Returns the connector of the given foreign network.
+	///
+	/// # Parameters
+	///
+	/// * `network`: Foreign network.
+	fn foreign_chain(&self, network: Network) -> Result<Arc<FC>, Error> {
+		self.foreign_chains
+			.get(&network)
+			.cloned()
+			.ok_or(Error::ConnectorNotConfigured(network))
+	}
+
 	/// Signs provided message with stored BLS key related to the Thea authority and returns
 	/// instance of the gossip message definition.
 	///
 	/// # Parameters
 	///
 	/// * `message`: Message to sign.
-	pub fn sign_message(&mut self, message: Message) -> Result<GossipMessage, Error> {
-		let network = self.thea_network.expect("Expected the network to be defined here.");
+	/// * `network`: Foreign network the message is exchanged with.
+	pub fn sign_message(
+		&mut self,
+		message: Message,
+		network: Network,
+	) -> Result<GossipMessage, Error> {
 		info!(target:"thea", "Serving network: {:?}", network);
 		let active = self
 			.runtime
@@ -193,7 +220,12 @@ where
 
 		info!(target:"thea","🌉 Bitmap generated for message with nonce: {:?}, bitmap: {:?}",message.nonce, bitmap);
 
-		Ok(GossipMessage { payload: message, bitmap, aggregate_signature: signature.into() })
+		Ok(GossipMessage {
+			payload: message,
+			network,
+			bitmap,
+			aggregate_signature: signature.into(),
+		})
 	}
 
 	/// Validates provided gossip message.
@@ -205,14 +237,13 @@ where
 		// TODO: Do signature check here.
 		// Based on network use the corresponding api to check if the message if valid or not.
 		if message.payload.network != NATIVE_NETWORK {
-			self.foreign_chain.check_message(&message.payload).await
+			self.foreign_chain(message.network)?.check_message(&message.payload).await
 		} else {
 			let finalized_blk = self.last_finalized_blk;
-			let network = self.thea_network.ok_or(Error::NetworkNotConfigured)?;
 			let result = self
 				.runtime
 				.runtime_api()
-				.outgoing_messages(&finalized_blk, network, message.payload.nonce)?
+				.outgoing_messages(&finalized_blk, message.network, message.payload.nonce)?
 				.ok_or(Error::ErrorReadingTheaMessage)?;
 
 			Ok(result == message.payload)
@@ -232,16 +263,24 @@ where
 		if !self.is_validator {
 			return Ok(())
 		}
+		let network = incoming_message.network;
+		// Proceed only if we serve the network of the message
+		if !self.thea_networks.contains(&network) {
+			debug!(target: "thea", "🌉 Ignoring message of network: {:?}, not served", network);
+			return Ok(())
+		}
+		let foreign_chain = self.foreign_chain(network)?;
 		// Proceed only if thea auths are initialized
-		if !self.foreign_chain.check_thea_authority_initialization().await.unwrap_or(false) {
-			warn!(target: "thea", "🌉 Thea authorities not initialized yet!");
+		if !foreign_chain.check_thea_authority_initialization().await.unwrap_or(false) {
+			warn!(target: "thea", "🌉 Thea authorities not initialized yet on network: {:?}!", network);
 			return Ok(())
 		}
 		metric_inc!(self, thea_messages_recv);
 		metric_add!(self, thea_data_recv, incoming_message.encoded_size() as u64);
-		let local_index = self.get_local_auth_index()?;
+		let local_index = self.get_local_auth_index(network)?;
 		info!(target:"thea","🌉 Local validator index: {:?}",local_index);
-		let option = self.message_cache.read().get(&incoming_message.payload).cloned();
+		let key = (network, incoming_message.payload.clone());
+		let option = self.message_cache.read().get(&key).cloned();
 		// Check incoming message in our cache.
 		match option {
 			None => {
@@ -257,7 +296,8 @@ where
 					true => {
 						info!(target:"thea", "🌉 Message with nonce: {:?} is valid",incoming_message.payload.nonce);
 						// Sign the message
-						let gossip_message = self.sign_message(incoming_message.payload.clone())?;
+						let gossip_message =
+							self.sign_message(incoming_message.payload.clone(), network)?;
 
 						// Aggregate the signature and store it.
 						incoming_message.aggregate_signature = incoming_message
@@ -276,27 +316,15 @@ where
 						{
 							// We got majority on this message
 							info!(target:"thea", "🌉 Got majority, sending message to destination");
-							if incoming_message.payload.network == NATIVE_NETWORK {
-								self.foreign_chain
-									.send_transaction(incoming_message.clone())
-									.await?;
-							} else {
-								info!(target:"thea", "🌉 Sending message to native runtime");
-								self.runtime.runtime_api().incoming_message(
-									&self.last_finalized_blk,
-									incoming_message.payload.clone(),
-									incoming_message.bitmap.clone(),
-									incoming_message.aggregate_signature.into(),
-								)??;
-							}
-							self.message_cache.write().remove(&incoming_message.payload);
+							self.queue_ready_message(key, incoming_message.clone());
+							self.submit_ready_messages(incoming_message.payload.network, network)
+								.await?;
 						} else {
 							// Cache it.
 							info!(target:"thea", "🌉 No majority, caching the message");
-							self.message_cache.write().insert(
-								incoming_message.payload.clone(),
-								(Instant::now(), incoming_message.clone()),
-							);
+							self.message_cache
+								.write()
+								.insert(key, (Instant::now(), incoming_message.clone()));
 						}
 					},
 				}
@@ -312,7 +340,8 @@ where
 				// There are two cases here,
 				if !did_we_sign_incoming_message {
 					// Let's add our signature to it
-					let gossip_message = self.sign_message(incoming_message.payload.clone())?;
+					let gossip_message =
+						self.sign_message(incoming_message.payload.clone(), network)?;
 
 					// Aggregate the signature and store it.
 					incoming_message.aggregate_signature = incoming_message
@@ -330,25 +359,15 @@ where
 					{
 						info!(target:"thea","🌉 Got majority on message: nonce: {:?}, network: {:?}", message.payload.nonce, message.payload.network);
 						// We got majority on this message
-						if incoming_message.payload.network == NATIVE_NETWORK {
-							self.foreign_chain.send_transaction(incoming_message.clone()).await?;
-						} else {
-							info!(target:"thea", "🌉 Sending message to native runtime");
-							self.runtime.runtime_api().incoming_message(
-								&self.last_finalized_blk,
-								incoming_message.payload.clone(),
-								incoming_message.bitmap.clone(),
-								incoming_message.aggregate_signature.into(),
-							)??;
-						}
-						self.message_cache.write().remove(&incoming_message.payload);
+						self.queue_ready_message(key, incoming_message.clone());
+						self.submit_ready_messages(incoming_message.payload.network, network)
+							.await?;
 					} else {
 						// Cache it.
 						info!(target:"thea", "🌉 No majority, caching the message");
-						self.message_cache.write().insert(
-							incoming_message.payload.clone(),
-							(Instant::now(), incoming_message.clone()),
-						);
+						self.message_cache
+							.write()
+							.insert(key, (Instant::now(), incoming_message.clone()));
 						// TODO: Send it back to network.
 					}
 				} else {
@@ -361,6 +380,113 @@ where
 		Ok(())
 	}
 
+	/// Moves a message with a majority of signatures from the cache to the messages waiting to be
+	/// submitted.
+	///
+	/// # Parameters
+	///
+	/// * `key`: Foreign network and payload of the message in the cache.
+	/// * `message`: Gossip message with a majority of signatures.
+	fn queue_ready_message(&mut self, key: (Network, Message), message: GossipMessage) {
+		self.message_cache.write().remove(&key);
+		let (network, payload) = key;
+		self.submission_queue.push((payload.network, network), message);
+	}
+
+	/// Returns the last nonce processed by the destination.
+	///
+	/// # Parameters
+	///
+	/// * `origin`: Origin network of the messages.
+	/// * `network`: Foreign network the messages are exchanged with.
+	async fn last_processed_nonce(&self, origin: Network, network: Network) -> Result<u64, Error> {
+		if origin == NATIVE_NETWORK {
+			self.foreign_chain(network)?.last_processed_nonce_from_native().await
+		} else {
+			Ok(self
+				.runtime
+				.runtime_api()
+				.get_last_processed_nonce(&self.last_finalized_blk, network)?)
+		}
+	}
+
+	/// Returns the last nonce processed by the destination, accounting for the messages submitted
+	/// by this node which are not processed yet.
+	///
+	/// # Parameters
+	///
+	/// * `origin`: Origin network of the messages.
+	/// * `network`: Foreign network the messages are exchanged with.
+	async fn last_nonce_at_destination(
+		&self,
+		origin: Network,
+		network: Network,
+	) -> Result<u64, Error> {
+		let processed = self.last_processed_nonce(origin, network).await?;
+		Ok(self.submission_queue.last_nonce((origin, network), processed))
+	}
+
+	/// Submits the messages with a majority of signatures to their destination in the order of
+	/// their nonces, stopping at the first missing nonce.
+	///
+	/// # Parameters
+	///
+	/// * `origin`: Origin network of the messages.
+	/// * `network`: Foreign network the messages are exchanged with.
+	async fn submit_ready_messages(
+		&mut self,
+		origin: Network,
+		network: Network,
+	) -> Result<(), Error> {
+		let route = (origin, network);
+		if self.submission_queue.is_empty(route) {
+			return Ok(())
+		}
+		let processed = self.last_processed_nonce(origin, network).await?;
+		let foreign_chain = self.foreign_chain(network)?;
+		let runtime = self.runtime.clone();
+		let at = self.last_finalized_blk;
+		self.submission_queue
+			.submit(route, processed, |message| {
+				let foreign_chain = foreign_chain.clone();
+				let runtime = runtime.clone();
+				async move {
+					if origin == NATIVE_NETWORK {
+						foreign_chain.send_transaction(message).await
+					} else {
+						info!(target:"thea", "🌉 Sending message to native runtime");
+						runtime
+							.runtime_api()
+							.incoming_message(
+								&at,
+								message.payload,
+								message.bitmap,
+								message.aggregate_signature.into(),
+							)
+							.map_err(Error::from)
+							.and_then(|result| result.map_err(Error::from))
+					}
+				}
+			})
+			.await
+	}
+
+	/// Returns the networks served by the given authority, read with `network` from the runtimes
+	/// without the version 2 of the Thea API.
+	///
+	/// # Parameters
+	///
+	/// * `at`: Block to read the networks at.
+	/// * `authority`: Thea authority.
+	fn networks(&self, at: &BlockId<B>, authority: AuthorityId) -> Result<Vec<Network>, Error> {
+		let runtime_api = self.runtime.runtime_api();
+		if runtime_api.has_api_with::<dyn TheaApi<B>, _>(at, |version| version >= 2)? {
+			Ok(runtime_api.networks(at, authority)?)
+		} else {
+			Ok(runtime_api.network(at, authority)?.into_iter().collect())
+		}
+	}
+
 	/// Handles block finalization notification.
 	///
 	/// # Parameters
@@ -370,12 +496,6 @@ where
 		&mut self,
 		notification: &FinalityNotification<B>,
 	) -> Result<(), Error> {
-		// Proceed only if thea auths are initialized
-		if !self.foreign_chain.check_thea_authority_initialization().await.unwrap_or(false) {
-			warn!(target: "thea", "🌉 Thea authorities not initialized yet!");
-			return Ok(())
-		}
-
 		info!(target: "thea", "🌉 Finality notification for blk: {:?}", notification.header.number());
 		let header = &notification.header;
 		let at = BlockId::hash(header.hash());
@@ -386,23 +506,47 @@ where
 			return Ok(())
 		}
 
-		if self.thea_network.is_none() {
-			let active = self
-				.runtime
-				.runtime_api()
-				.full_validator_set(&at)?
-				.ok_or(Error::NoValidatorsFound)?;
-			let signing_key = self.keystore.get_local_key(active.validators())?;
-			let network = self.runtime.runtime_api().network(&at, signing_key)?;
-
-			if network.is_none() {
-				log::error!(target:"thea","🌉 Thea network is not configured for this validator, please use the local rpc");
-				return Err(Error::NetworkNotConfigured)
-			} else {
-				self.thea_network = network;
+		// The networks served by this validator can be updated at any time on runtime
+		let active = self
+			.runtime
+			.runtime_api()
+			.full_validator_set(&at)?
+			.ok_or(Error::NoValidatorsFound)?;
+		let signing_key = self.keystore.get_local_key(active.validators())?;
+		let networks = self.networks(&at, signing_key)?;
+		if networks.is_empty() {
+			log::error!(target:"thea","🌉 Thea network is not configured for this validator, please use the local rpc");
+			return Err(Error::NetworkNotConfigured)
+		}
+		self.thea_networks = networks.into_iter().collect();
+
+		for network in self.thea_networks.clone() {
+			if let Err(err) = self.process_native_messages(network).await {
+				error!(target: "thea", "🌉 Error processing native messages for network: {:?}, {:?}", network, err);
 			}
 		}
-		let network = self.thea_network.ok_or(Error::NetworkNotConfigured)?;
+
+		Ok(())
+	}
+
+	/// Processes the next [`MAX_MESSAGES_IN_FLIGHT`] native messages to be sent to the given
+	/// foreign network.
+	///
+	/// # Parameters
+	///
+	/// * `network`: Foreign network.
+	async fn process_native_messages(&mut self, network: Network) -> Result<(), Error> {
+		let foreign_chain = self.foreign_chain(network)?;
+		// Skip the network while its connector is reconnecting
+		if foreign_chain.health() == ConnectorHealth::Connecting {
+			warn!(target: "thea", "🌉 Foreign chain of network: {:?} is not connected", network);
+			return Ok(())
+		}
+		// Proceed only if thea auths are initialized
+		if !foreign_chain.check_thea_authority_initialization().await.unwrap_or(false) {
+			warn!(target: "thea", "🌉 Thea authorities not initialized yet on network: {:?}!", network);
+			return Ok(())
+		}
 
 		// Update the last processed foreign nonce from native
 		let last_foreign_nonce_processed: u64 = self
@@ -410,47 +554,82 @@ where
 			.runtime_api()
 			.get_last_processed_nonce(&self.last_finalized_blk, network)?;
 
-		*self.last_foreign_nonce_processed.write() = last_foreign_nonce_processed;
+		self.last_foreign_nonce_processed
+			.write()
+			.insert(network, last_foreign_nonce_processed);
 
-		let last_nonce = self.foreign_chain.last_processed_nonce_from_native().await?;
+		// The gossip validator accepts the messages of the nonces which are not processed yet,
+		// including the ones submitted by this node
+		let last_processed = self.last_processed_nonce(NATIVE_NETWORK, network).await?;
+		self.last_native_nonce_processed.write().insert(network, last_processed);
 
+		let last_nonce =
+			self.submission_queue.last_nonce((NATIVE_NETWORK, network), last_processed);
 		let next_nonce_to_process = last_nonce.saturating_add(1);
 
-		let message =
-			self.runtime
-				.runtime_api()
-				.outgoing_messages(&at, network, next_nonce_to_process)?;
-
-		if let Some(message) = message {
-			info!(target:"thea", "🌉 Processing new message from Polkadex: nonce: {:?}, to_network: {:?}",message.nonce, message.network);
-			// Don't do anything if we already know about the message
-			// It means Thea is already processing it.
-			if !self.message_cache.read().contains_key(&message) {
-				info!(target:"thea", "🌉 Found new native message for processing.. network:{:?} nonce: {:?}",message.network, message.nonce);
-				self.sign_and_submit_message(message)?
-			} else {
-				let mut cache = self.message_cache.write();
-				if let Some((last, _)) = cache.get(&message).cloned() {
-					if Instant::now().duration_since(last) >
-						Duration::from_secs(MESSAGE_CACHE_DURATION_IN_SECS)
-					{
-						cache.remove(&message);
-						info!(target:"thea","🌉 Thea message expired: {:?}",message);
-					} else {
-						info!(target:"thea","🌉 We already processed this message, so ignoring...")
-					}
-				}
+		for nonce in next_nonce_to_process..next_nonce_to_process + MAX_MESSAGES_IN_FLIGHT {
+			let message = self.runtime.runtime_api().outgoing_messages(
+				&self.last_finalized_blk,
+				network,
+				nonce,
+			)?;
+			match message {
+				None => {
+					info!(target:"thea", "🌉 No messages from Polkadex: nonce: {:?}, to_network: {:?}",nonce, network);
+					break
+				},
+				Some(message) => {
+					info!(target:"thea", "🌉 Processing new message from Polkadex: nonce: {:?}, to_network: {:?}",message.nonce, network);
+					self.process_new_message(message, network)?;
+				},
 			}
-		} else {
-			info!(target:"thea", "🌉 No messages from Polkadex: nonce: {:?}, to_network: {:?}",next_nonce_to_process, network);
 		}
 
+		// Retry the messages which failed to be submitted
+		self.submit_ready_messages(NATIVE_NETWORK, network).await
+	}
+
+	/// Signs and submits a message unless Thea is already processing it, expiring it from the
+	/// cache after [`MESSAGE_CACHE_DURATION_IN_SECS`].
+	///
+	/// # Parameters
+	///
+	/// * `message`: Message to process.
+	/// * `network`: Foreign network the message is exchanged with.
+	fn process_new_message(&mut self, message: Message, network: Network) -> Result<(), Error> {
+		// Don't do anything if the message is waiting to be submitted
+		if self.submission_queue.contains((message.network, network), message.nonce) {
+			info!(target:"thea","🌉 Message with nonce: {:?} is waiting for submission", message.nonce);
+			return Ok(())
+		}
+		let key = (network, message);
+		// Don't do anything if we already know about the message
+		// It means Thea is already processing it.
+		if !self.message_cache.read().contains_key(&key) {
+			info!(target:"thea", "🌉 Found new message for processing.. network:{:?} nonce: {:?}",network, key.1.nonce);
+			self.sign_and_submit_message(key.1, network)?
+		} else {
+			let mut cache = self.message_cache.write();
+			if let Some((last, _)) = cache.get(&key).cloned() {
+				if Instant::now().duration_since(last) >
+					Duration::from_secs(MESSAGE_CACHE_DURATION_IN_SECS)
+				{
+					cache.remove(&key);
+					info!(target:"thea","🌉 Thea message expired: {:?}",key.1);
+				} else {
+					info!(target:"thea","🌉 We already processed this message, so ignoring...")
+				}
+			}
+		}
 		Ok(())
 	}
 
 	/// Provides the identity of the Orderbook authority.
-	pub fn get_local_auth_index(&self) -> Result<AuthorityIndex, Error> {
-		let network = self.thea_network.expect("🌉 Expected the thea network to be initialized");
+	///
+	/// # Parameters
+	///
+	/// * `network`: Foreign network of the validator set.
+	pub fn get_local_auth_index(&self, network: Network) -> Result<AuthorityIndex, Error> {
 		let active = self
 			.runtime
 			.runtime_api()
@@ -469,11 +648,18 @@ where
 	/// # Parameters
 	///
 	/// * `message`: Message to process.
-	pub fn sign_and_submit_message(&mut self, message: Message) -> Result<(), Error> {
-		let gossip_message = self.sign_message(message.clone())?;
-		info!(target:"thea","🌉 Message with nonce: {:?} with network: {:?}, is signed",message.nonce, message.network);
+	/// * `network`: Foreign network the message is exchanged with.
+	pub fn sign_and_submit_message(
+		&mut self,
+		message: Message,
+		network: Network,
+	) -> Result<(), Error> {
+		let gossip_message = self.sign_message(message.clone(), network)?;
+		info!(target:"thea","🌉 Message with nonce: {:?} with network: {:?}, is signed",message.nonce, network);
 		self.gossip_engine.gossip_message(topic::<B>(), gossip_message.encode(), true);
-		self.message_cache.write().insert(message, (Instant::now(), gossip_message));
+		self.message_cache
+			.write()
+			.insert((network, message), (Instant::now(), gossip_message));
 		Ok(())
 	}
 
@@ -490,7 +676,29 @@ where
 		}
 	}
 
-	/// Processes foreign chain events.
+	/// Checks if the Thea authorities are initialized on at least one foreign chain.
+	async fn is_any_foreign_chain_initialized(&self) -> bool {
+		for foreign_chain in self.foreign_chains.values() {
+			if foreign_chain.check_thea_authority_initialization().await.unwrap_or(false) {
+				return true
+			}
+		}
+		false
+	}
+
+	/// Reports the health of the foreign connectors to the metrics.
+	fn report_connector_health(&self) {
+		if let Some(metrics) = self.metrics.as_ref() {
+			for (network, foreign_chain) in self.foreign_chains.iter() {
+				metrics
+					.thea_connector_health
+					.with_label_values(&[&network.to_string()])
+					.set(foreign_chain.health().metric());
+			}
+		}
+	}
+
+	/// Processes foreign chain events of every network served by this validator.
 	///
 	/// Note. Processed only if the node started in a "validator" role.
 	pub async fn try_process_foreign_chain_events(&mut self) -> Result<(), Error> {
@@ -499,63 +707,103 @@ where
 			return Ok(())
 		}
 
-		// Proceed only if thea auths are initialized
-		if !self.foreign_chain.check_thea_authority_initialization().await.unwrap_or(false) {
-			warn!(target: "thea", "🌉 Thea authorities not initialized yet!");
+		if self.thea_networks.is_empty() {
+			log::error!(target:"thea", "🌉 Thea network not set on this validator!");
 			return Ok(())
 		}
 
-		match self.thea_network.as_ref() {
-			None => {
-				log::error!(target:"thea", "🌉 Thea network not set on this validator!");
-				return Ok(())
-			},
-			Some(network) => {
-				// Get the next block events from foreign chain as Message
-				let mut best_outgoing_nonce: u64 = self
-					.runtime
-					.runtime_api()
-					.get_last_processed_nonce(&self.last_finalized_blk, *network)?;
-
-				// Get the last processed native nonce from foreign
-				let last_nonce = self.foreign_chain.last_processed_nonce_from_native().await?;
-
-				*self.last_native_nonce_processed.write() = last_nonce;
-
-				info!(target:"thea","🌉 Checking new messages on network: {network:?}, last nonce from native: {best_outgoing_nonce:?}");
-				best_outgoing_nonce.add_assign(1);
-
-				// Check if next best message is available for processing
-				match self.foreign_chain.read_events(best_outgoing_nonce).await? {
-					None =>
-						info!(target:"thea","🌉 No messages found for nonce: {:?}",best_outgoing_nonce),
-					Some(message) => {
-						info!(target:"thea","🌉 Found message for nonce: {:?}",best_outgoing_nonce);
-						// Don't do anything if we already know about the message
-						// It means Thea is already processing it.
-						if !self.message_cache.read().contains_key(&message) {
-							info!(target:"thea", "🌉 Found new message for processing.. network:{:?} nonce: {:?}",message.network, message.nonce);
-							self.sign_and_submit_message(message)?
-						} else {
-							let mut cache = self.message_cache.write();
-							if let Some((last, _)) = cache.get(&message).cloned() {
-								if Instant::now().duration_since(last) >
-									Duration::from_secs(MESSAGE_CACHE_DURATION_IN_SECS)
-								{
-									cache.remove(&message);
-									info!(target:"thea","🌉 Thea message expired: {:?}",message);
-								} else {
-									info!(target:"thea","🌉 We already processed this message, so ignoring...")
-								}
-							}
-						}
-					},
-				}
-			},
+		for network in self.thea_networks.clone() {
+			if let Err(err) = self.process_foreign_chain_events(network).await {
+				error!(target: "thea", "🌉 Error fetching events of network: {:?}, {:?}", network, err);
+			}
 		}
 		Ok(())
 	}
 
+	/// Processes the next [`MAX_MESSAGES_IN_FLIGHT`] messages of the given foreign network.
+	///
+	/// The messages are read concurrently and each message is signed and gossiped as soon as it
+	/// and all the previous messages are read.
+	///
+	/// # Parameters
+	///
+	/// * `network`: Foreign network.
+	async fn process_foreign_chain_events(&mut self, network: Network) -> Result<(), Error> {
+		let foreign_chain = self.foreign_chain(network)?;
+		// Skip the network while its connector is reconnecting
+		if foreign_chain.health() == ConnectorHealth::Connecting {
+			warn!(target: "thea", "🌉 Foreign chain of network: {:?} is not connected", network);
+			return Ok(())
+		}
+		// Proceed only if thea auths are initialized
+		if !foreign_chain.check_thea_authority_initialization().await.unwrap_or(false) {
+			warn!(target: "thea", "🌉 Thea authorities not initialized yet on network: {:?}!", network);
+			return Ok(())
+		}
+
+		// Get the next block events from foreign chain as Message
+		let mut best_outgoing_nonce: u64 = self.last_nonce_at_destination(network, network).await?;
+
+		// Get the last processed native nonce from foreign
+		let last_processed = self.last_processed_nonce(NATIVE_NETWORK, network).await?;
+
+		self.last_native_nonce_processed.write().insert(network, last_processed);
+
+		info!(target:"thea","🌉 Checking new messages on network: {network:?}, last nonce from native: {best_outgoing_nonce:?}");
+		best_outgoing_nonce.add_assign(1);
+
+		// Check if next best messages are available for processing
+		let mut messages: FuturesOrdered<_> = (best_outgoing_nonce..
+			best_outgoing_nonce + MAX_MESSAGES_IN_FLIGHT)
+			.map(|nonce| {
+				let foreign_chain = foreign_chain.clone();
+				async move { (nonce, foreign_chain.read_events(nonce).await) }
+			})
+			.collect();
+		while let Some((nonce, message)) = messages.next().await {
+			match message? {
+				None => {
+					info!(target:"thea","🌉 No messages found for nonce: {:?}",nonce);
+					break
+				},
+				Some(message) => {
+					info!(target:"thea","🌉 Found message for nonce: {:?}",nonce);
+					self.process_new_message(message, network)?
+				},
+			}
+		}
+
+		// Retry the messages which failed to be submitted
+		self.submit_ready_messages(network, network).await
+	}
+
+	/// Handles a new message pushed by the connector of the given foreign network.
+	///
+	/// # Parameters
+	///
+	/// * `network`: Foreign network.
+	/// * `message`: New outgoing message of the foreign network.
+	pub async fn handle_foreign_message(
+		&mut self,
+		network: Network,
+		message: Message,
+	) -> Result<(), Error> {
+		// Proceed only if we are a validator serving the network
+		if !self.is_validator || !self.thea_networks.contains(&network) {
+			return Ok(())
+		}
+		// Messages are processed in the order of their nonces, the others are read again later
+		let last_nonce = self.last_nonce_at_destination(network, network).await?;
+		if message.nonce <= last_nonce ||
+			message.nonce > last_nonce.saturating_add(MAX_MESSAGES_IN_FLIGHT)
+		{
+			debug!(target:"thea","🌉 Ignoring pushed message with nonce: {:?}, last nonce: {:?}", message.nonce, last_nonce);
+			return Ok(())
+		}
+		info!(target:"thea","🌉 Pushed message for nonce: {:?}, network: {:?}", message.nonce, network);
+		self.process_new_message(message, network)
+	}
+
 	/// Entrypoint for thr Thea worker.
 	///
 	/// Wait for thea runtime pallet to be available, then start the main async loop
@@ -570,7 +818,8 @@ where
 			tokio::time::sleep(Duration::from_secs(12)).await;
 		}
 		// Wait for Thea authorities to initialize before starting thea
-		while !self.foreign_chain.check_thea_authority_initialization().await.unwrap_or(false) {
+		while !self.is_any_foreign_chain_initialized().await {
+			self.report_connector_health();
 			info!(target: "thea", "🌉 Thea on hold, waiting for authority initialization on foreign chain");
 			tokio::time::sleep(Duration::from_secs(12)).await;
 		}
@@ -594,10 +843,28 @@ where
 
 		// Interval timer to read foreign chain events
 		debug!(target:"thea","🌉 Starting interval streams...");
-		let interval = tokio::time::interval(self.foreign_chain.block_duration());
+		let block_duration = self
+			.foreign_chains
+			.values()
+			.map(|foreign_chain| foreign_chain.block_duration())
+			.min()
+			.unwrap_or(Duration::from_secs(12));
+		let interval = tokio::time::interval(block_duration);
 		// create a stream from the interval
 		let mut interval_stream = tokio_stream::wrappers::IntervalStream::new(interval).fuse();
 
+		// New messages pushed by the foreign connectors
+		debug!(target:"thea","🌉 Starting foreign message streams...");
+		let mut foreign_messages = futures::stream::select_all(
+			self.foreign_chains.iter().filter_map(|(network, foreign_chain)| {
+				let network = *network;
+				foreign_chain
+					.message_stream()
+					.map(|stream| stream.map(move |message| (network, message)))
+			}),
+		)
+		.fuse();
+
 		loop {
 			let mut gossip_engine = &mut self.gossip_engine;
 			futures::select_biased! {
@@ -630,7 +897,15 @@ where
 						return;
 					}
 				},
+				foreign_message = foreign_messages.next() => {
+					if let Some((network, message)) = foreign_message {
+						if let Err(err) = self.handle_foreign_message(network, message).await {
+							error!(target: "thea", "🌉 Error processing foreign message {:?}", err);
+						}
+					}
+				},
 				_ = interval_stream.next() => {
+					self.report_connector_health();
 					if let Err(err) = self.try_process_foreign_chain_events().await {
 							error!(target: "thea", "🌉 Error fetching foreign chain events {:?}", err);
 						}
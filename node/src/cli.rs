@@ -17,6 +17,7 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 use sc_cli::RunCmd;
+use std::collections::BTreeMap;
 
 /// An overarching CLI command definition.
 #[derive(Debug, clap::Parser)]
@@ -27,13 +28,51 @@ pub struct Cli {
 	#[allow(missing_docs)]
 	#[clap(flatten)]
 	pub run: RunCmd,
-	/// Thea expects foreign chain to run in this url
+	/// Thea expects the parachain to run in this url, used when no `--foreign-chain` is given
 	#[arg(short, long, default_value_t = String::from("ws://127.0.0.1:9902"))]
 	pub foreign_chain_url: String,
 
+	/// Foreign chain served by Thea as `<NETWORK>=<URL>`, can be repeated to serve several
+	/// networks. The network id selects the connector: EVM chains are configured with the address
+	/// and deployment block of the bridge contract as query, e.g.
+	/// `2=http://127.0.0.1:8545?bridge=0x...&start_block=1`, and the relayer key in the
+	/// `THEA_EVM_RELAYER_KEY` environment variable.
+	#[arg(long = "foreign-chain", value_name = "NETWORK=URL", value_parser = parse_foreign_chain)]
+	pub foreign_chains: Vec<(thea_primitives::Network, String)>,
+
 	/// Thea Dummy mode starts the chain with dummy connector ( for local testing only )
 	#[arg(short, long, default_value_t = false)]
 	pub thea_dummy_mode: bool,
+
+	/// Verify the trades submitted by the orderbook operator with the local matching engine,
+	/// mismatches are only logged
+	#[arg(long, default_value_t = false)]
+	pub ob_matching_engine: bool,
+}
+
+impl Cli {
+	/// Returns the foreign chain urls by network.
+	pub fn foreign_chains(&self) -> BTreeMap<thea_primitives::Network, String> {
+		if self.foreign_chains.is_empty() {
+			return BTreeMap::from([(
+				thea_primitives::PARACHAIN_NETWORK,
+				self.foreign_chain_url.clone(),
+			)])
+		}
+		self.foreign_chains.iter().cloned().collect()
+	}
+}
+
+/// Parses a `<NETWORK>=<URL>` foreign chain argument.
+fn parse_foreign_chain(arg: &str) -> Result<(thea_primitives::Network, String), String> {
+	let (network, url) = arg
+		.split_once('=')
+		.ok_or_else(|| format!("Expected <NETWORK>=<URL>, got: {arg}"))?;
+	let network = network.parse().map_err(|_| format!("Invalid network id: {network}"))?;
+	if network == thea_primitives::NATIVE_NETWORK {
+		return Err(String::from("The native network can't be a foreign chain"))
+	}
+	Ok((network, url.to_string()))
 }
 
 #[derive(Debug, clap::Subcommand)]
@@ -89,4 +128,7 @@ pub enum Subcommand {
 
 	/// Revert the chain to a previous state.
 	Revert(sc_cli::RevertCmd),
+
+	/// Replay orderbook messages offline on top of a snapshot to audit the operator.
+	ObReplay(crate::ob_replay::ObReplayCmd),
 }
@@ -25,6 +25,7 @@ mod service;
 mod benchmarking;
 mod cli;
 mod command;
+mod ob_replay;
 mod rpc;
 
 fn main() -> sc_cli::Result<()> {
@@ -0,0 +1,98 @@
+// This file is part of Polkadex.
+//
+// Copyright (c) 2023 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Offline orderbook replay subcommand.
+
+use orderbook::replay::{read_snapshot_summary, replay_files, ReplayFiles};
+use std::path::PathBuf;
+
+/// Replays orderbook messages offline on top of a snapshot and reports the resulting state.
+#[derive(Debug, clap::Parser)]
+pub struct ObReplayCmd {
+	/// JSON `SnapshotStore` or `ObRecoveryState` the messages are replayed on top of.
+	#[arg(long)]
+	pub snapshot: PathBuf,
+
+	/// Hex encoded summary of the snapshot, required if the snapshot is a `SnapshotStore`.
+	#[arg(long)]
+	pub snapshot_summary: Option<PathBuf>,
+
+	/// JSON array of the `ObMessage`s to replay.
+	#[arg(long)]
+	pub messages: PathBuf,
+
+	/// JSON array of the trading pair configurations open at the snapshot.
+	#[arg(long)]
+	pub trading_pairs: Option<PathBuf>,
+
+	/// JSON map of block number to the ingress messages of the imported blocks.
+	#[arg(long)]
+	pub ingress_messages: Option<PathBuf>,
+
+	/// Hex encoded public key of the operator, used to verify the message signatures. Can be
+	/// repeated to accept the keys of an operator rotation.
+	#[arg(long = "operator-public-key")]
+	pub operator_public_keys: Vec<String>,
+
+	/// Hex encoded snapshot summary the replayed state is compared with.
+	#[arg(long)]
+	pub target_summary: Option<PathBuf>,
+}
+
+impl ObReplayCmd {
+	/// Runs the replay, prints the report and fails if it differs from the target summary.
+	pub fn run(&self) -> sc_cli::Result<()> {
+		let files = ReplayFiles {
+			snapshot: self.snapshot.clone(),
+			messages: self.messages.clone(),
+			trading_pairs: self.trading_pairs.clone(),
+			ingress_messages: self.ingress_messages.clone(),
+			operator_public_keys: self.operator_public_keys.clone(),
+		};
+		let base_summary = self
+			.snapshot_summary
+			.as_ref()
+			.map(|path| read_snapshot_summary(path))
+			.transpose()
+			.map_err(|err| sc_cli::Error::Input(err.to_string()))?;
+		let report = replay_files(&files, base_summary.as_ref())
+			.map_err(|err| sc_cli::Error::Input(err.to_string()))?;
+		println!("{}", report.to_json().map_err(|err| sc_cli::Error::Input(err.to_string()))?);
+
+		if let Some(path) = self.target_summary.as_ref() {
+			let target =
+				read_snapshot_summary(path).map_err(|err| sc_cli::Error::Input(err.to_string()))?;
+			let mismatches = report.diff(&target);
+			if !mismatches.is_empty() {
+				for mismatch in &mismatches {
+					eprintln!(
+						"{} mismatch: expected {}, found {}",
+						mismatch.field, mismatch.expected, mismatch.found
+					);
+				}
+				return Err(sc_cli::Error::Input(format!(
+					"Replayed state differs from snapshot {} in {} field(s)",
+					target.snapshot_id,
+					mismatches.len()
+				)))
+			}
+			eprintln!("Replayed state matches snapshot {}", target.snapshot_id);
+		}
+		Ok(())
+	}
+}
@@ -52,6 +52,7 @@ use sp_blockchain::{Error as BlockChainError, HeaderBackend, HeaderMetadata};
 use sp_consensus::SelectChain;
 use sp_consensus_babe::BabeApi;
 use sp_keystore::SyncCryptoStorePtr;
+use thea_rpc::{TheaApiServer, TheaRpc};
 
 /// Extra dependencies for BABE.
 pub struct BabeDeps {
@@ -97,6 +98,8 @@ pub struct FullDeps<C, P, SC, B> {
 	pub grandpa: GrandpaDeps<B>,
 	/// Orderbook specific dependencies
 	pub orderbook: orderbook_rpc::OrderbookDeps<B, C, C>,
+	/// Thea foreign connectors, to report their health
+	pub thea_connectors: thea_client::ConnectorsHealth,
 }
 
 /// Instantiate all Full RPC extensions.
@@ -135,8 +138,17 @@ where
 	// use substrate_state_trie_migration_rpc::{StateMigration, StateMigrationApiServer};
 
 	let mut io = RpcModule::new(());
-	let FullDeps { client, pool, select_chain, chain_spec, deny_unsafe, babe, grandpa, orderbook } =
-		deps;
+	let FullDeps {
+		client,
+		pool,
+		select_chain,
+		chain_spec,
+		deny_unsafe,
+		babe,
+		grandpa,
+		orderbook,
+		thea_connectors,
+	} = deps;
 
 	let BabeDeps { keystore, babe_config, shared_epoch_changes } = babe;
 	let GrandpaDeps {
@@ -182,6 +194,8 @@ where
 	io.merge(Dev::new(client, deny_unsafe).into_rpc())?;
 	// Create Orderbook RPC
 	io.merge(OrderbookRpc::new(orderbook).into_rpc())?;
+	// Create Thea RPC
+	io.merge(TheaRpc::new(thea_connectors).into_rpc())?;
 
 	Ok(io)
 }
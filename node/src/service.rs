@@ -35,7 +35,7 @@ use sc_executor::NativeElseWasmExecutor;
 use sc_network::{Event, NetworkService};
 use sc_service::{config::Configuration, error::Error as ServiceError, TaskManager};
 use sp_runtime::traits::Block as BlockT;
-use std::sync::Arc;
+use std::{collections::BTreeMap, sync::Arc};
 
 use sc_consensus_babe::SlotProportion;
 use sc_telemetry::{Telemetry, TelemetryWorker};
@@ -124,6 +124,7 @@ pub fn create_extrinsic(
 		extra,
 	)
 }
+use orderbook::notification::{ObActionSender, ObActionStream, ObSnapshotSender, ObSnapshotStream};
 use orderbook_primitives::types::ObMessage;
 use orderbook_rpc::OrderbookDeps;
 use sc_network_common::service::NetworkEventStream;
@@ -153,6 +154,8 @@ pub fn new_partial(
 			UnboundedReceiver<ObMessage>,
 			Arc<RwLock<MemoryDB<RefHasher, HashKey<RefHasher>, Vec<u8>>>>,
 			Arc<RwLock<[u8; 32]>>,
+			(ObActionSender, ObSnapshotSender),
+			thea_client::ConnectorsHealth,
 		),
 	>,
 	ServiceError,
@@ -244,6 +247,9 @@ pub fn new_partial(
 	let import_setup = (block_import, grandpa_link, babe_link);
 
 	let (ob_messge_sink, ob_message_stream) = unbounded::<ObMessage>();
+	let (ob_action_sender, ob_action_stream) = ObActionStream::channel();
+	let (ob_snapshot_sender, ob_snapshot_stream) = ObSnapshotStream::channel();
+	let thea_connectors = thea_client::ConnectorsHealth::default();
 
 	let (rpc_extensions_builder, rpc_setup) = {
 		let (_, grandpa_link, babe_link) = &import_setup;
@@ -269,6 +275,7 @@ pub fn new_partial(
 		let memory_db_cloned = memory_db.clone();
 		let working_state_root_cloned = working_state_root.clone();
 		let backend_cloned = backend.clone();
+		let thea_connectors_cloned = thea_connectors.clone();
 		let rpc_extensions_builder = move |deny_unsafe, subscription_executor| {
 			let deps = node_rpc::FullDeps {
 				client: client.clone(),
@@ -285,7 +292,7 @@ pub fn new_partial(
 					shared_voter_state: shared_voter_state.clone(),
 					shared_authority_set: shared_authority_set.clone(),
 					justification_stream: justification_stream.clone(),
-					subscription_executor,
+					subscription_executor: subscription_executor.clone(),
 					finality_provider: finality_proof_provider.clone(),
 				},
 				orderbook: OrderbookDeps {
@@ -295,7 +302,11 @@ pub fn new_partial(
 					client: client.clone(),
 					backend: backend_cloned.clone(),
 					runtime: client.clone(),
+					action_stream: ob_action_stream.clone(),
+					snapshot_stream: ob_snapshot_stream.clone(),
+					subscription_executor,
 				},
+				thea_connectors: thea_connectors_cloned.clone(),
 			};
 
 			node_rpc::create_full(deps).map_err(Into::into)
@@ -321,6 +332,8 @@ pub fn new_partial(
 			ob_message_stream,
 			memory_db,
 			working_state_root,
+			(ob_action_sender, ob_snapshot_sender),
+			thea_connectors,
 		),
 	})
 }
@@ -335,8 +348,9 @@ pub struct NewFullBase {
 /// Creates a full service from the configuration.
 pub fn new_full_base(
 	mut config: Configuration,
-	foreign_chain_url: String,
+	foreign_chains: BTreeMap<thea_primitives::Network, String>,
 	thea_dummy_mode: bool,
+	ob_matching_engine: bool,
 	with_startup_data: impl FnOnce(
 		&sc_consensus_babe::BabeBlockImport<Block, FullClient, FullGrandpaBlockImport>,
 		&sc_consensus_babe::BabeLink<Block>,
@@ -360,6 +374,8 @@ pub fn new_full_base(
 				orderbook_stream,
 				memory_db,
 				working_state_root,
+				(ob_action_sender, ob_snapshot_sender),
+				thea_connectors,
 			),
 	} = new_partial(&config)?;
 
@@ -600,6 +616,9 @@ pub fn new_full_base(
 		message_sender_link: orderbook_stream,
 		memory_db,
 		working_state_root,
+		enable_matching_engine: ob_matching_engine,
+		action_sender: ob_action_sender,
+		snapshot_sender: ob_snapshot_sender,
 	};
 
 	// Orderbook task
@@ -619,7 +638,8 @@ pub fn new_full_base(
 		marker: Default::default(),
 		is_validator: role.is_authority(),
 		chain_type,
-		foreign_chain_url,
+		foreign_chains,
+		connectors_health: thea_connectors,
 		dummy_mode: thea_dummy_mode,
 	};
 
@@ -637,10 +657,11 @@ pub fn new_full_base(
 /// Builds a new service for a full client.
 pub fn new_full(
 	config: Configuration,
-	foreign_chain_url: String,
+	foreign_chains: BTreeMap<thea_primitives::Network, String>,
 	thea_dummy_mode: bool,
+	ob_matching_engine: bool,
 ) -> Result<TaskManager, ServiceError> {
-	new_full_base(config, foreign_chain_url, thea_dummy_mode, |_, _| ())
+	new_full_base(config, foreign_chains, thea_dummy_mode, ob_matching_engine, |_, _| ())
 		.map(|NewFullBase { task_manager, .. }| task_manager)
 }
 
@@ -673,7 +694,7 @@ mod tests {
 		RuntimeAppPublic,
 	};
 	use sp_timestamp;
-	use std::{borrow::Cow, sync::Arc};
+	use std::{borrow::Cow, collections::BTreeMap, sync::Arc};
 
 	type AccountPublic = <Signature as Verify>::Signer;
 
@@ -709,8 +730,9 @@ mod tests {
 				let NewFullBase { task_manager, client, network, transaction_pool, .. } =
 					new_full_base(
 						config,
-						"blah".to_string(),
+						BTreeMap::from([(thea_primitives::PARACHAIN_NETWORK, "blah".to_string())]),
 						true,
+						false,
 						|block_import: &sc_consensus_babe::BabeBlockImport<Block, _, _>,
 						 babe_link: &sc_consensus_babe::BabeLink<Block>| {
 							setup_handles = Some((block_import.clone(), babe_link.clone()));
@@ -886,7 +908,13 @@ mod tests {
 			crate::chain_spec::tests::integration_test_config_with_two_authorities(),
 			|config| {
 				let NewFullBase { task_manager, client, network, transaction_pool, .. } =
-					new_full_base(config, "blah".to_string(), true, |_, _| ())?;
+					new_full_base(
+						config,
+						BTreeMap::from([(thea_primitives::PARACHAIN_NETWORK, "blah".to_string())]),
+						true,
+						false,
+						|_, _| (),
+					)?;
 				Ok(sc_service_test::TestNetComponents::new(
 					task_manager,
 					client,
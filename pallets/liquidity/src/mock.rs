@@ -118,6 +118,7 @@ parameter_types! {
 	pub const ProxyLimit: u32 = 2;
 	pub const OcexPalletId: PalletId = PalletId(*b"OCEX_LMP");
 	pub const MsPerDay: u64 = 86_400_000;
+	pub const FreezeExchangeAfter: u64 = 100;
 }
 
 impl pallet_ocex_lmp::Config for Test {
@@ -130,6 +131,7 @@ impl pallet_ocex_lmp::Config for Test {
 	type Public = <Signature as sp_runtime::traits::Verify>::Signer;
 	type Signature = Signature;
 	type MsPerDay = MsPerDay;
+	type FreezeExchangeAfter = FreezeExchangeAfter;
 	type WeightInfo = pallet_ocex_lmp::weights::WeightInfo<Test>;
 }
 
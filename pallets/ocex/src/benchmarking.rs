@@ -24,16 +24,27 @@ use crate::{fixtures::SNAPSHOT, Pallet as Ocex};
 use frame_benchmarking::{account, benchmarks};
 use frame_support::{dispatch::UnfilteredDispatchable, traits::EnsureOrigin, BoundedVec};
 use frame_system::RawOrigin;
-use orderbook_primitives::Fees;
-use parity_scale_codec::Decode;
+use memory_db::{HashKey, MemoryDB};
+use orderbook_primitives::{
+	layout::{StateHasher, StateLayout},
+	proof::{BalanceProof, BalanceProofNodes, MAX_BALANCE_PROOF_NODES},
+	types::AccountAsset,
+	Fees,
+};
+use parity_scale_codec::{Decode, Encode};
 use polkadex_primitives::{
-	ocex::TradingPairConfig, withdrawal::Withdrawal, ProxyLimit, UNIT_BALANCE,
+	fees::FeeConfig,
+	ocex::{ProxyPermissions, TradingPairConfig},
+	withdrawal::Withdrawal,
+	ProxyLimit, UNIT_BALANCE,
 };
 use rust_decimal::{prelude::*, Decimal};
 use sp_runtime::{
 	traits::{BlockNumberProvider, One},
 	BoundedBTreeSet,
 };
+use sp_std::vec::Vec;
+use trie_db::{proof::generate_proof, TrieDBMutBuilder, TrieMut};
 
 // Check if last event generated by pallet is the one we're expecting
 fn assert_last_event<T: Config>(generic_event: <T as Config>::RuntimeEvent) {
@@ -63,6 +74,36 @@ fn tpc(base_asset: AssetId, quote_asset: AssetId) -> TradingPairConfig {
 	}
 }
 
+/// Generates the proof of the balances of an account asset in a state where the proven keys are
+/// `depth` nibbles deep, each level branching off a key differing by a single nibble.
+fn balance_proof(account_asset: &AccountAsset, depth: u32) -> (H256, BalanceProofNodes) {
+	let keys = [account_asset.encode(), account_asset.reserved_balance_key()];
+	let mut memory_db: MemoryDB<StateHasher, HashKey<StateHasher>, Vec<u8>> = Default::default();
+	let mut state_root = H256::zero();
+	{
+		let mut trie =
+			TrieDBMutBuilder::<StateLayout>::new(&mut memory_db, &mut state_root).build();
+		for key in &keys {
+			trie.insert(key, &Decimal::from(10).encode()).unwrap();
+		}
+		for index in 0..depth as usize {
+			let mut key = keys[index % 2].clone();
+			let nibble = index / 2;
+			key[nibble / 2] ^= if nibble % 2 == 0 { 0x10 } else { 0x01 };
+			trie.insert(&key, &Decimal::from(1).encode()).unwrap();
+		}
+		trie.commit();
+	}
+	let proof = generate_proof::<_, StateLayout, _, _>(&memory_db, &state_root, &keys)
+		.unwrap()
+		.into_iter()
+		.map(|node| node.try_into().unwrap())
+		.collect::<Vec<_>>()
+		.try_into()
+		.unwrap();
+	(state_root, proof)
+}
+
 benchmarks! {
 	register_main_account {
 		let b in 0 .. 50_000;
@@ -241,6 +282,9 @@ benchmarks! {
 		)?;
 		let proxy = account::<T::AccountId>("proxy", x, 0);
 		Ocex::<T>::register_main_account(RawOrigin::Signed(user.clone()).into(), proxy)?;
+		// worst case scenario, a previous deposit is checked against the last snapshot
+		let pending = BoundedVec::try_from(sp_std::vec![(T::BlockNumber::default(), amount)]).unwrap();
+		<PendingDeposits<T>>::insert(&user, asset, pending);
 		let call = Call::<T>::deposit { asset, amount };
 	}: { call.dispatch_bypass_filter(RawOrigin::Signed(user.clone()).into())? }
 	verify {
@@ -312,6 +356,8 @@ benchmarks! {
 		let origin = T::GovernanceOrigin::successful_origin();
 		let main_account = T::AccountId::decode(&mut &[x as u8; 32][..]).unwrap();
 		let asset_id = AssetId::Asset(x as u128);
+		let account_info = polkadex_primitives::ocex::AccountInfo::new(main_account.clone());
+		<Accounts<T>>::insert(&main_account, account_info);
 		let hb = polkadex_primitives::ingress::HandleBalance {
 			main_account,
 			asset_id,
@@ -397,6 +443,68 @@ benchmarks! {
 		assert_last_event::<T>(Event::AllowlistedTokenRemoved(asset_id).into());
 	}
 
+	direct_withdrawal {
+		let x in 1 .. 255; // should not overflow u8
+		let main = account::<T::AccountId>("main", x, 0);
+		let proxy = account::<T::AccountId>("proxy", x, 0);
+		let asset = AssetId::Asset(x.into());
+		let amount = BalanceOf::<T>::decode(&mut &(x as u128).saturating_mul(10u128).to_le_bytes()[..]).unwrap();
+		let governance = T::GovernanceOrigin::successful_origin();
+		Ocex::<T>::set_exchange_state(governance.clone(), true)?;
+		Ocex::<T>::allowlist_token(governance, asset)?;
+		Ocex::<T>::register_main_account(RawOrigin::Signed(main.clone()).into(), proxy)?;
+		let call = Call::<T>::direct_withdrawal { asset, amount, do_force_withdraw: true };
+	}: { call.dispatch_bypass_filter(RawOrigin::Signed(main.clone()).into())? }
+	verify {
+		assert_last_event::<T>(Event::WithdrawFromOrderbook(main, asset, amount).into());
+	}
+
+	set_fee_config {
+		let x in 1 .. 255; // should not overflow u8
+		let main = account::<T::AccountId>("main", x, 0);
+		let proxy = account::<T::AccountId>("proxy", x, 0);
+		let governance = T::GovernanceOrigin::successful_origin();
+		Ocex::<T>::set_exchange_state(governance.clone(), true)?;
+		Ocex::<T>::register_main_account(RawOrigin::Signed(main.clone()).into(), proxy)?;
+		let fee_config = FeeConfig::new(Decimal::new(x.into(), 4), Decimal::new(x.into(), 3));
+		let call = Call::<T>::set_fee_config { main: main.clone(), fee_config };
+	}: { call.dispatch_bypass_filter(governance)? }
+	verify {
+		assert_last_event::<T>(Event::FeeConfigUpdated { main, fee_config }.into());
+	}
+
+	escape_hatch_withdraw {
+		// Leaves room for the root and the leaves of the proven keys
+		let x in 1 .. MAX_BALANCE_PROOF_NODES - 8;
+		let main = account::<T::AccountId>("main", 0, 0);
+		let account_asset = AccountAsset::new(
+			polkadex_primitives::AccountId::decode(&mut &main.encode()[..]).unwrap(),
+			AssetId::Polkadex,
+		);
+		let (state_root, proof) = balance_proof(&account_asset, x);
+		let mut snapshot = SnapshotSummary::decode(&mut SNAPSHOT.as_ref()).unwrap();
+		snapshot.snapshot_id = 1;
+		snapshot.state_root = state_root;
+		<Snapshots<T>>::insert(1, snapshot);
+		<SnapshotNonce<T>>::put(1);
+		frame_system::Pallet::<T>::set_block_number(T::FreezeExchangeAfter::get());
+		let _ = T::NativeCurrency::deposit_creating(
+			&Ocex::<T>::get_pallet_account(),
+			convert_to_balance::<T>(Decimal::from(100)),
+		);
+		let proof = BalanceProof {
+			snapshot_id: 1,
+			account_asset,
+			free: Some(Decimal::from(10)),
+			reserved: Some(Decimal::from(10)),
+			proof,
+		};
+		let call = Call::<T>::escape_hatch_withdraw { proof };
+	}: { call.dispatch_bypass_filter(RawOrigin::Signed(main.clone()).into())? }
+	verify {
+		assert_eq!(<EscapeHatchClaims<T>>::get(main, AssetId::Polkadex), Some(1));
+	}
+
 	set_snapshot {
 		let call = Call::<T>::set_snapshot{ new_snapshot_id: u64::MAX };
 	}: { call.dispatch_bypass_filter(RawOrigin::Root.into())? }
@@ -411,6 +519,43 @@ benchmarks! {
 		let new_snapshot_interval_block = T::BlockNumber::decode(&mut 123u64.to_le_bytes().as_ref()).unwrap();
 		let call = Call::<T>::change_snapshot_interval_block{ new_snapshot_interval_block };
 	}: { call.dispatch_bypass_filter(origin)? }
+
+	rotate_orderbook_operator {
+		// Worst case, the replaced key is kept for the grace period
+		let origin = T::GovernanceOrigin::successful_origin();
+		let previous_public_key = sp_core::ecdsa::Public::from_raw([1; 33]);
+		let operator_public_key = sp_core::ecdsa::Public::from_raw([2; 33]);
+		<OrderbookOperatorPublicKey<T>>::put(previous_public_key);
+		let grace_period = T::BlockNumber::from(100u32);
+		let accepted_until = frame_system::Pallet::<T>::current_block_number() + grace_period;
+		let call = Call::<T>::rotate_orderbook_operator { operator_public_key, grace_period };
+	}: { call.dispatch_bypass_filter(origin)? }
+	verify {
+		assert_last_event::<T>(Event::OrderbookOperatorKeyRotated {
+			operator_public_key,
+			previous_public_key: Some(previous_public_key),
+			accepted_until,
+		}.into());
+	}
+
+	set_proxy_permissions {
+		let main = account::<T::AccountId>("main", 0, 0);
+		let proxy = account::<T::AccountId>("proxy", 0, 0);
+		let governance = T::GovernanceOrigin::successful_origin();
+		Ocex::<T>::set_exchange_state(governance, true)?;
+		Ocex::<T>::register_main_account(RawOrigin::Signed(main.clone()).into(), proxy.clone())?;
+		let call = Call::<T>::set_proxy_permissions {
+			proxy: proxy.clone(),
+			permissions: ProxyPermissions::TRADE,
+		};
+	}: { call.dispatch_bypass_filter(RawOrigin::Signed(main.clone()).into())? }
+	verify {
+		assert_last_event::<T>(Event::ProxyPermissionsUpdated {
+			main,
+			proxy,
+			permissions: ProxyPermissions::TRADE,
+		}.into());
+	}
 }
 
 #[cfg(test)]
@@ -24,7 +24,7 @@ pub(crate) const _PUBLIC: [u8; 96] = [
 	218, 152, 146, 199, 52, 150, 86, 241, 156, 23, 35, 71, 201, 216,
 ];
 
-pub(crate) const SNAPSHOT: [u8; 235] = [
+pub(crate) const SNAPSHOT: [u8; 236] = [
 	0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
 	0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0,
 	1, 0, 0, 0, 0, 8, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0,
@@ -33,5 +33,5 @@ pub(crate) const SNAPSHOT: [u8; 235] = [
 	1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
 	0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 184, 239, 65, 246, 134, 193, 91, 100, 60, 198, 195, 116, 249,
 	216, 96, 170, 33, 186, 179, 109, 207, 180, 3, 231, 107, 80, 42, 123, 100, 156, 75, 86, 62, 120,
-	115, 159, 76, 60, 167, 196, 149, 217, 93, 214, 24, 100, 90, 1, 0, 0,
+	115, 159, 76, 60, 167, 196, 149, 217, 93, 214, 24, 100, 90, 1, 0, 0, 0,
 ];
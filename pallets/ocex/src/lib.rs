@@ -47,6 +47,7 @@ mod mock;
 #[cfg(test)]
 mod tests;
 
+mod migrations;
 pub mod weights;
 
 use orderbook_primitives::{
@@ -91,6 +92,11 @@ pub trait OcexWeightInfo {
 	fn set_snapshot() -> Weight;
 	fn change_pending_withdrawal_limit() -> Weight;
 	fn change_snapshot_interval_block() -> Weight;
+	fn direct_withdrawal(_x: u32) -> Weight;
+	fn set_fee_config(_x: u32) -> Weight;
+	fn escape_hatch_withdraw(_x: u32) -> Weight;
+	fn rotate_orderbook_operator() -> Weight;
+	fn set_proxy_permissions() -> Weight;
 }
 
 // Definition of the pallet logic, to be aggregated at runtime definition through
@@ -113,10 +119,13 @@ pub mod pallet {
 	};
 	use frame_system::{offchain::SendTransactionTypes, pallet_prelude::*};
 	use liquidity::LiquidityModifier;
-	use orderbook_primitives::{crypto::AuthorityId, Fees, SnapshotSummary};
+	use orderbook_primitives::{
+		crypto::AuthorityId, layout::StateLayout, proof::BalanceProof, Fees, SnapshotSummary,
+	};
 	use polkadex_primitives::{
 		assets::AssetId,
-		ocex::{AccountInfo, TradingPairConfig},
+		fees::FeeConfig,
+		ocex::{AccountInfo, ProxyPermissions, TradingPairConfig},
 		withdrawal::Withdrawal,
 		ProxyLimit, UNIT_BALANCE,
 	};
@@ -139,6 +148,14 @@ pub mod pallet {
 		}
 	}
 
+	/// Maximum number of blocks with deposits of an account asset awaiting a snapshot.
+	pub struct PendingDepositsLimit;
+	impl Get<u32> for PendingDepositsLimit {
+		fn get() -> u32 {
+			50
+		}
+	}
+
 	#[pallet::validate_unsigned]
 	impl<T: Config> frame_support::unsigned::ValidateUnsigned for Pallet<T> {
 		type Call = Call<T>;
@@ -206,6 +223,11 @@ pub mod pallet {
 		/// Governance Origin
 		type GovernanceOrigin: EnsureOrigin<<Self as frame_system::Config>::RuntimeOrigin>;
 
+		/// Number of blocks without a new snapshot after which the exchange is frozen and users
+		/// can withdraw their balances at the last snapshot with a balance proof.
+		#[pallet::constant]
+		type FreezeExchangeAfter: Get<Self::BlockNumber>;
+
 		/// Type representing the weight of this pallet
 		type WeightInfo: OcexWeightInfo;
 	}
@@ -214,6 +236,7 @@ pub mod pallet {
 	// method.
 	#[pallet::pallet]
 	#[pallet::generate_store(pub(super) trait Store)]
+	#[pallet::storage_version(crate::migrations::STORAGE_VERSION)]
 	#[pallet::without_storage_info]
 	pub struct Pallet<T>(_);
 
@@ -295,6 +318,20 @@ pub mod pallet {
 		InvalidSnapshotState,
 		/// AccountId cannot be decoded
 		AccountIdCannotBeDecoded,
+		/// Fee fractions must be within [0, 1)
+		InvalidFeeConfig,
+		/// Exchange is frozen, balances can only be withdrawn with a proof
+		ExchangeFrozen,
+		/// Exchange is not frozen
+		ExchangeNotFrozen,
+		/// Balance proof does not hold against the last snapshot
+		InvalidBalanceProof,
+		/// Balance has already been withdrawn with a proof
+		BalanceAlreadyClaimed,
+		/// Orderbook operator key is already the current key
+		OperatorKeyAlreadyActive,
+		/// Proxy permissions grant no action or contain unknown bits
+		InvalidProxyPermissions,
 	}
 
 	#[pallet::hooks]
@@ -315,6 +352,10 @@ pub mod pallet {
 				.saturating_add(T::DbWeight::get().reads(2))
 				.saturating_add(T::DbWeight::get().writes(2))
 		}
+
+		fn on_runtime_upgrade() -> Weight {
+			crate::migrations::migrate::<T>()
+		}
 	}
 
 	#[pallet::call]
@@ -707,6 +748,7 @@ pub mod pallet {
 						.position(|account| *account == proxy)
 						.ok_or(Error::<T>::ProxyNotFound)?;
 					account_info.proxies.remove(proxy_positon);
+					<ProxyPermissionsOf<T>>::remove(&main_account, &proxy);
 					let current_blk = frame_system::Pallet::<T>::current_block_number();
 					<IngressMessages<T>>::mutate(current_blk, |ingress_messages| {
 						ingress_messages.push(
@@ -733,6 +775,7 @@ pub mod pallet {
 		pub fn set_snapshot(origin: OriginFor<T>, new_snapshot_id: u64) -> DispatchResult {
 			T::GovernanceOrigin::ensure_origin(origin)?;
 			<SnapshotNonce<T>>::put(new_snapshot_id);
+			<LastSnapshotBlock<T>>::put(frame_system::Pallet::<T>::current_block_number());
 			Ok(())
 		}
 
@@ -859,6 +902,13 @@ pub mod pallet {
 
 			// Check if exchange is pause
 			ensure!(!Self::orderbook_operational_state(), Error::<T>::ExchangeOperational);
+			// Balances can only be set for registered accounts
+			for balance in change_in_balances.iter() {
+				ensure!(
+					<Accounts<T>>::contains_key(&balance.main_account),
+					Error::<T>::MainAccountNotFound
+				);
+			}
 			let current_blk = frame_system::Pallet::<T>::current_block_number();
 			//Pass the vec as ingress message
 			<IngressMessages<T>>::mutate(current_blk, |ingress_messages| {
@@ -994,6 +1044,7 @@ pub mod pallet {
 			summary: SnapshotSummary<T::AccountId>,
 		) -> DispatchResult {
 			ensure_none(origin)?;
+			ensure!(!<ExchangeFrozen<T>>::get(), Error::<T>::ExchangeFrozen);
 			let last_snapshot_serial_number = <SnapshotNonce<T>>::get();
 			ensure!(
 				summary.snapshot_id.eq(&(last_snapshot_serial_number + 1)),
@@ -1064,6 +1115,7 @@ pub mod pallet {
 				log::debug!(target:"ocex", "Storing snapshot summary data...");
 				// Update the snapshot nonce and move the summary to snapshots storage
 				<SnapshotNonce<T>>::put(working_summary.snapshot_id);
+				<LastSnapshotBlock<T>>::put(frame_system::Pallet::<T>::current_block_number());
 				<Withdrawals<T>>::insert(working_summary.snapshot_id, withdrawal_map);
 				// The unwrap below should not fail
 				<FeesCollected<T>>::insert(working_summary.snapshot_id, working_summary.get_fees());
@@ -1080,7 +1132,8 @@ pub mod pallet {
 			Ok(())
 		}
 
-		/// Submit Snapshot Summary
+		/// Whitelists the orderbook operator key, the messages signed with any previous key are
+		/// rejected immediately.
 		#[pallet::call_index(18)]
 		#[pallet::weight(10000)]
 		pub fn whitelist_orderbook_operator(
@@ -1089,9 +1142,220 @@ pub mod pallet {
 		) -> DispatchResult {
 			T::GovernanceOrigin::ensure_origin(origin)?;
 			<OrderbookOperatorPublicKey<T>>::put(operator_public_key);
+			<PreviousOrderbookOperatorPublicKey<T>>::kill();
 			Self::deposit_event(Event::<T>::OrderbookOperatorKeyWhitelisted(operator_public_key));
 			Ok(())
 		}
+
+		/// Rotates the orderbook operator key.
+		///
+		/// The messages signed with the replaced key are still accepted for `grace_period`
+		/// blocks, so the operator can switch engines without a hard cutover.
+		///
+		/// # Parameters
+		///
+		/// * `origin`: governance origin.
+		/// * `operator_public_key`: new orderbook operator key.
+		/// * `grace_period`: number of blocks the replaced key is still accepted for.
+		#[pallet::call_index(22)]
+		#[pallet::weight(<T as Config>::WeightInfo::rotate_orderbook_operator())]
+		pub fn rotate_orderbook_operator(
+			origin: OriginFor<T>,
+			operator_public_key: sp_core::ecdsa::Public,
+			grace_period: T::BlockNumber,
+		) -> DispatchResult {
+			T::GovernanceOrigin::ensure_origin(origin)?;
+			let previous_public_key = <OrderbookOperatorPublicKey<T>>::get();
+			ensure!(
+				previous_public_key != Some(operator_public_key),
+				Error::<T>::OperatorKeyAlreadyActive
+			);
+			let accepted_until =
+				frame_system::Pallet::<T>::current_block_number().saturating_add(grace_period);
+			match previous_public_key {
+				Some(previous_public_key) => <PreviousOrderbookOperatorPublicKey<T>>::put((
+					previous_public_key,
+					accepted_until,
+				)),
+				None => <PreviousOrderbookOperatorPublicKey<T>>::kill(),
+			}
+			<OrderbookOperatorPublicKey<T>>::put(operator_public_key);
+			Self::deposit_event(Event::<T>::OrderbookOperatorKeyRotated {
+				operator_public_key,
+				previous_public_key,
+				accepted_until,
+			});
+			Ok(())
+		}
+
+		/// Withdraws assets from the Orderbook without going through the operator.
+		///
+		/// Queues a `DirectWithdrawal` ingress message which the orderbook workers must apply
+		/// and include in the next snapshot, so a user can exit even if the operator ignores
+		/// their withdrawal requests.
+		///
+		/// # Parameters
+		///
+		/// * `origin`: main account.
+		/// * `asset`: asset id to withdraw.
+		/// * `amount`: amount to withdraw.
+		/// * `do_force_withdraw`: if set to true and the free balance is lower than `amount`,
+		/// the whole free balance is withdrawn instead of skipping the request.
+		#[pallet::call_index(19)]
+		#[pallet::weight(<T as Config>::WeightInfo::direct_withdrawal(1))]
+		pub fn direct_withdrawal(
+			origin: OriginFor<T>,
+			asset: AssetId,
+			amount: BalanceOf<T>,
+			do_force_withdraw: bool,
+		) -> DispatchResult {
+			let main_account = ensure_signed(origin)?;
+			Self::withdrawal_from_orderbook(main_account, asset, amount, do_force_withdraw)?;
+			Ok(())
+		}
+
+		/// Assigns a trading fee tier to a registered main account.
+		///
+		/// # Parameters
+		///
+		/// * `origin`: governance origin.
+		/// * `main`: main account to assign the fee configuration to.
+		/// * `fee_config`: maker and taker fee fractions, both must be within `[0, 1)`.
+		#[pallet::call_index(20)]
+		#[pallet::weight(<T as Config>::WeightInfo::set_fee_config(1))]
+		pub fn set_fee_config(
+			origin: OriginFor<T>,
+			main: T::AccountId,
+			fee_config: FeeConfig,
+		) -> DispatchResult {
+			T::GovernanceOrigin::ensure_origin(origin)?;
+			ensure!(fee_config.is_valid(), Error::<T>::InvalidFeeConfig);
+			<Accounts<T>>::try_mutate(&main, |account_info| {
+				let account_info = account_info.as_mut().ok_or(Error::<T>::MainAccountNotFound)?;
+				account_info.fee_config = fee_config;
+				Ok::<_, Error<T>>(())
+			})?;
+			let current_blk = frame_system::Pallet::<T>::current_block_number();
+			<IngressMessages<T>>::mutate(current_blk, |ingress_messages| {
+				ingress_messages.push(polkadex_primitives::ingress::IngressMessages::SetFeeConfig(
+					main.clone(),
+					fee_config,
+				));
+			});
+			Self::deposit_event(Event::FeeConfigUpdated { main, fee_config });
+			Ok(())
+		}
+
+		/// Withdraws the balances of an account asset at the last snapshot while the exchange is
+		/// frozen.
+		///
+		/// The exchange is frozen once no snapshot has been accepted for `FreezeExchangeAfter`
+		/// blocks. Free and reserved balances are proven against the state root of the last
+		/// snapshot and deposits made after the last block it processed are added to them. Each
+		/// account asset can only be withdrawn once and no snapshot is accepted after the first
+		/// withdrawal.
+		///
+		/// # Parameters
+		///
+		/// * `origin`: main account.
+		/// * `proof`: balances of the main account at the last snapshot.
+		#[pallet::call_index(21)]
+		#[pallet::weight(<T as Config>::WeightInfo::escape_hatch_withdraw(proof.proof.len() as u32))]
+		pub fn escape_hatch_withdraw(origin: OriginFor<T>, proof: BalanceProof) -> DispatchResult {
+			let main_account = ensure_signed(origin)?;
+			ensure!(Self::is_exchange_frozen(), Error::<T>::ExchangeNotFrozen);
+			let proof_main = T::AccountId::decode(&mut &proof.account_asset.main.encode()[..])
+				.map_err(|_| Error::<T>::AccountIdCannotBeDecoded)?;
+			ensure!(proof_main == main_account, Error::<T>::SenderNotAuthorizedToWithdraw);
+			let asset = proof.account_asset.asset;
+			ensure!(
+				!<EscapeHatchClaims<T>>::contains_key(&main_account, asset),
+				Error::<T>::BalanceAlreadyClaimed
+			);
+			let snapshot_id = <SnapshotNonce<T>>::get();
+			ensure!(
+				snapshot_id != 0 && proof.snapshot_id == snapshot_id,
+				Error::<T>::InvalidBalanceProof
+			);
+			let summary = <Snapshots<T>>::get(snapshot_id);
+			ensure!(
+				proof.verify::<StateLayout>(&summary.state_root),
+				Error::<T>::InvalidBalanceProof
+			);
+			let processed_blk: T::BlockNumber = summary.last_processed_blk.into();
+			let unprocessed_deposits = <PendingDeposits<T>>::take(&main_account, asset)
+				.into_iter()
+				.filter(|(deposit_blk, _)| *deposit_blk > processed_blk)
+				.fold(0u128, |total, (_, amount)| {
+					total.saturating_add(amount.saturated_into::<u128>())
+				});
+			let amount = proof
+				.total()
+				.saturating_mul(Decimal::from(UNIT_BALANCE))
+				.to_u128()
+				.ok_or(Error::<T>::InvalidWithdrawalAmount)?
+				.saturating_add(unprocessed_deposits);
+			// The orderbook state does not know about this withdrawal, no snapshot can follow
+			<ExchangeFrozen<T>>::put(true);
+			<EscapeHatchClaims<T>>::insert(&main_account, asset, snapshot_id);
+			Self::transfer_asset(
+				&Self::get_pallet_account(),
+				&main_account,
+				amount.saturated_into(),
+				asset,
+			)?;
+			Self::deposit_event(Event::EscapeHatchWithdrawal {
+				main: main_account,
+				asset,
+				amount: amount.saturated_into(),
+				snapshot_id,
+			});
+			Ok(())
+		}
+
+		/// Sets the actions a proxy account is allowed to sign on behalf of its main account,
+		/// proxies are allowed every action until scoped.
+		///
+		/// # Parameters
+		///
+		/// * `origin`: main account.
+		/// * `proxy`: proxy account of the main account.
+		/// * `permissions`: actions the proxy is allowed to sign.
+		#[pallet::call_index(23)]
+		#[pallet::weight(<T as Config>::WeightInfo::set_proxy_permissions())]
+		pub fn set_proxy_permissions(
+			origin: OriginFor<T>,
+			proxy: T::AccountId,
+			permissions: ProxyPermissions,
+		) -> DispatchResult {
+			let main_account = ensure_signed(origin)?;
+			ensure!(Self::orderbook_operational_state(), Error::<T>::ExchangeNotOperational);
+			ensure!(permissions.is_valid(), Error::<T>::InvalidProxyPermissions);
+			let account_info =
+				<Accounts<T>>::get(&main_account).ok_or(Error::<T>::MainAccountNotFound)?;
+			ensure!(account_info.proxies.contains(&proxy), Error::<T>::ProxyNotFound);
+			if permissions == ProxyPermissions::ALL {
+				<ProxyPermissionsOf<T>>::remove(&main_account, &proxy);
+			} else {
+				<ProxyPermissionsOf<T>>::insert(&main_account, &proxy, permissions);
+			}
+			let current_blk = frame_system::Pallet::<T>::current_block_number();
+			<IngressMessages<T>>::mutate(current_blk, |ingress_messages| {
+				ingress_messages.push(
+					polkadex_primitives::ingress::IngressMessages::SetProxyPermissions(
+						main_account.clone(),
+						proxy.clone(),
+						permissions,
+					),
+				);
+			});
+			Self::deposit_event(Event::ProxyPermissionsUpdated {
+				main: main_account,
+				proxy,
+				permissions,
+			});
+			Ok(())
+		}
 	}
 
 	impl<T: Config> LiquidityModifier for Pallet<T> {
@@ -1108,14 +1372,13 @@ pub mod pallet {
 		}
 		fn on_withdraw(
 			account: Self::AccountId,
-			proxy_account: Self::AccountId,
+			_proxy_account: Self::AccountId,
 			asset: Self::AssetId,
 			balance: u128,
 			do_force_withdraw: bool,
 		) -> DispatchResult {
 			Self::withdrawal_from_orderbook(
 				account,
-				proxy_account,
 				asset,
 				balance.saturated_into(),
 				do_force_withdraw,
@@ -1162,6 +1425,8 @@ pub mod pallet {
 			amount: BalanceOf<T>,
 		) -> DispatchResult {
 			ensure!(Self::orderbook_operational_state(), Error::<T>::ExchangeNotOperational);
+			// Deposits would not be part of the snapshot balances are withdrawn from
+			ensure!(!Self::is_exchange_frozen(), Error::<T>::ExchangeFrozen);
 			ensure!(<AllowlistedToken<T>>::get().contains(&asset), Error::<T>::TokenNotAllowlisted);
 			// Check if account is registered
 			ensure!(<Accounts<T>>::contains_key(&user), Error::<T>::AccountNotRegistered);
@@ -1180,6 +1445,7 @@ pub mod pallet {
 				return Err(Error::<T>::AmountOverflow.into())
 			}
 			let current_blk = frame_system::Pallet::<T>::current_block_number();
+			Self::record_pending_deposit(&user, asset, current_blk, amount);
 			<IngressMessages<T>>::mutate(current_blk, |ingress_messages| {
 				ingress_messages.push(polkadex_primitives::ingress::IngressMessages::Deposit(
 					user.clone(),
@@ -1215,7 +1481,6 @@ pub mod pallet {
 
 		pub fn withdrawal_from_orderbook(
 			user: T::AccountId,
-			proxy_account: T::AccountId,
 			asset: AssetId,
 			amount: BalanceOf<T>,
 			do_force_withdraw: bool,
@@ -1232,7 +1497,7 @@ pub mod pallet {
 			<IngressMessages<T>>::mutate(current_blk, |ingress_messages| {
 				ingress_messages.push(
 					polkadex_primitives::ingress::IngressMessages::DirectWithdrawal(
-						proxy_account,
+						user.clone(),
 						asset,
 						converted_amount,
 						do_force_withdraw,
@@ -1243,6 +1508,71 @@ pub mod pallet {
 			Ok(())
 		}
 
+		/// Records a deposit until a snapshot processes its block, so it can still be withdrawn if
+		/// the exchange is frozen before.
+		///
+		/// Deposits processed by the last snapshot are dropped and deposits of the same block are
+		/// added up. Once `PendingDepositsLimit` blocks are pending, the deposit is added to the
+		/// newest entry which takes its block, so deposits are never rejected.
+		///
+		/// # Parameters
+		///
+		/// * `user`: main account.
+		/// * `asset`: deposited asset.
+		/// * `deposit_blk`: block of the deposit.
+		/// * `amount`: deposited amount.
+		fn record_pending_deposit(
+			user: &T::AccountId,
+			asset: AssetId,
+			deposit_blk: T::BlockNumber,
+			amount: BalanceOf<T>,
+		) {
+			<PendingDeposits<T>>::mutate(user, asset, |deposits| {
+				if !deposits.is_empty() {
+					let processed_blk: T::BlockNumber =
+						<Snapshots<T>>::get(<SnapshotNonce<T>>::get()).last_processed_blk.into();
+					deposits.retain(|(blk, _)| *blk > processed_blk);
+				}
+				let is_full = deposits.is_full();
+				match deposits.last_mut() {
+					Some((blk, total)) if *blk == deposit_blk || is_full => {
+						*blk = deposit_blk;
+						*total = total.saturating_add(amount);
+					},
+					// Cannot fail, the pending deposits are not full
+					_ => {
+						let _ = deposits.try_push((deposit_blk, amount));
+					},
+				}
+			})
+		}
+
+		/// Returns true if the exchange is frozen, either because no snapshot has been accepted
+		/// for `FreezeExchangeAfter` blocks or because balances have already been withdrawn with
+		/// a proof.
+		pub fn is_exchange_frozen() -> bool {
+			let current_blk = frame_system::Pallet::<T>::current_block_number();
+			<ExchangeFrozen<T>>::get() ||
+				current_blk.saturating_sub(<LastSnapshotBlock<T>>::get()) >=
+					T::FreezeExchangeAfter::get()
+		}
+
+		/// Returns the orderbook operator keys the messages are accepted from, the current key
+		/// first followed by the previous key until the end of the rotation grace period.
+		pub fn get_orderbook_operator_public_keys() -> Vec<sp_core::ecdsa::Public> {
+			let current_blk = frame_system::Pallet::<T>::current_block_number();
+			let mut keys: Vec<sp_core::ecdsa::Public> =
+				<OrderbookOperatorPublicKey<T>>::get().into_iter().collect();
+			if let Some((previous_public_key, accepted_until)) =
+				<PreviousOrderbookOperatorPublicKey<T>>::get()
+			{
+				if current_blk <= accepted_until {
+					keys.push(previous_public_key);
+				}
+			}
+			keys
+		}
+
 		fn create_withdrawal_tree(
 			pending_withdrawals: Vec<Withdrawal<T::AccountId>>,
 		) -> WithdrawalsMap<T> {
@@ -1321,6 +1651,31 @@ pub mod pallet {
 		WithdrawFromOrderbook(T::AccountId, AssetId, BalanceOf<T>),
 		/// Orderbook Operator Key Whitelisted
 		OrderbookOperatorKeyWhitelisted(sp_core::ecdsa::Public),
+		/// Orderbook operator key has been rotated, the previous key is accepted until
+		/// `accepted_until`
+		OrderbookOperatorKeyRotated {
+			operator_public_key: sp_core::ecdsa::Public,
+			previous_public_key: Option<sp_core::ecdsa::Public>,
+			accepted_until: T::BlockNumber,
+		},
+		/// Trading fee configuration of the main account has been updated
+		FeeConfigUpdated {
+			main: T::AccountId,
+			fee_config: FeeConfig,
+		},
+		/// Balances have been withdrawn with a proof while the exchange is frozen
+		EscapeHatchWithdrawal {
+			main: T::AccountId,
+			asset: AssetId,
+			amount: BalanceOf<T>,
+			snapshot_id: u64,
+		},
+		/// Actions the proxy is allowed to sign have been updated
+		ProxyPermissionsUpdated {
+			main: T::AccountId,
+			proxy: T::AccountId,
+			permissions: ProxyPermissions,
+		},
 	}
 
 	///Allowlisted tokens
@@ -1454,6 +1809,62 @@ pub mod pallet {
 	#[pallet::getter(fn get_orderbook_operator_public_key)]
 	pub(super) type OrderbookOperatorPublicKey<T: Config> =
 		StorageValue<_, sp_core::ecdsa::Public, OptionQuery>;
+
+	// Operator key replaced by the last rotation and the last block it is accepted at
+	#[pallet::storage]
+	#[pallet::getter(fn get_previous_orderbook_operator_public_key)]
+	pub(super) type PreviousOrderbookOperatorPublicKey<T: Config> =
+		StorageValue<_, (sp_core::ecdsa::Public, T::BlockNumber), OptionQuery>;
+
+	// Block at which the last snapshot was accepted
+	#[pallet::storage]
+	#[pallet::getter(fn last_snapshot_block)]
+	pub(super) type LastSnapshotBlock<T: Config> = StorageValue<_, T::BlockNumber, ValueQuery>;
+
+	// Set once balances are withdrawn with a proof, snapshots are no longer accepted
+	#[pallet::storage]
+	#[pallet::getter(fn exchange_frozen)]
+	pub(super) type ExchangeFrozen<T: Config> = StorageValue<_, bool, ValueQuery>;
+
+	// Deposits of the blocks the last snapshot may not have processed, (main, asset) => (block,
+	// amount)
+	#[pallet::storage]
+	#[pallet::getter(fn pending_deposits)]
+	pub(super) type PendingDeposits<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		Blake2_128Concat,
+		AssetId,
+		BoundedVec<(T::BlockNumber, BalanceOf<T>), PendingDepositsLimit>,
+		ValueQuery,
+	>;
+
+	// Account assets withdrawn with a proof, (main, asset) => snapshot id
+	#[pallet::storage]
+	#[pallet::getter(fn escape_hatch_claims)]
+	pub(super) type EscapeHatchClaims<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		Blake2_128Concat,
+		AssetId,
+		u64,
+		OptionQuery,
+	>;
+
+	// Actions a proxy is allowed to sign, (main, proxy) => permissions
+	#[pallet::storage]
+	#[pallet::getter(fn proxy_permissions)]
+	pub(super) type ProxyPermissionsOf<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		Blake2_128Concat,
+		T::AccountId,
+		ProxyPermissions,
+		ValueQuery,
+	>;
 }
 
 // The main implementation block for the pallet. Functions here fall into three broad
@@ -1472,6 +1883,10 @@ impl<T: Config + frame_system::offchain::SendTransactionTypes<Call<T>>> Pallet<T
 				.propagate(true)
 				.build()
 		};
+		// Balances withdrawn with a proof are not part of the orderbook state
+		if <ExchangeFrozen<T>>::get() {
+			return InvalidTransaction::Call.into()
+		}
 		// Verify Nonce/state_change_id
 		let last_snapshot_serial_number = <SnapshotNonce<T>>::get();
 		if !snapshot_summary
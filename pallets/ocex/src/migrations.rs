@@ -0,0 +1,113 @@
+// This file is part of Polkadex.
+//
+// Copyright (c) 2023 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Storage migrations of the "OCEX" pallet.
+
+use frame_support::{
+	traits::{Get, StorageVersion},
+	weights::Weight,
+};
+
+use crate::{Config, Pallet};
+
+/// Current storage version of the pallet.
+pub const STORAGE_VERSION: StorageVersion = StorageVersion::new(2);
+
+/// Applies the migrations from the on-chain storage version to the current one.
+pub fn migrate<T: Config>() -> Weight {
+	let on_chain = StorageVersion::get::<Pallet<T>>();
+	let mut weight = T::DbWeight::get().reads(1);
+	if on_chain >= STORAGE_VERSION {
+		return weight
+	}
+	if on_chain < 1 {
+		weight = weight.saturating_add(v1::migrate::<T>());
+	}
+	if on_chain < 2 {
+		weight = weight.saturating_add(v2::migrate::<T>());
+	}
+	STORAGE_VERSION.put::<Pallet<T>>();
+	weight.saturating_add(T::DbWeight::get().writes(1))
+}
+
+/// Adds the trading fees to the snapshot summaries.
+pub mod v1 {
+	use super::*;
+	use crate::Snapshots;
+	use orderbook_primitives::{SnapshotSummary, H128};
+	use parity_scale_codec::{Codec, Decode};
+	use polkadex_primitives::{withdrawal::Withdrawal, BlockNumber};
+	use sp_core::H256;
+	use sp_std::vec::Vec;
+
+	/// Snapshot summary before the trading fees were reported.
+	#[derive(Decode)]
+	struct OldSnapshotSummary<AccountId: Clone + Codec> {
+		validator_set_id: u64,
+		snapshot_id: u64,
+		state_root: H256,
+		worker_nonce: u64,
+		state_change_id: u64,
+		last_processed_blk: BlockNumber,
+		state_chunk_hashes: Vec<H128>,
+		bitflags: Vec<u128>,
+		withdrawals: Vec<Withdrawal<AccountId>>,
+		aggregate_signature: Option<bls_primitives::Signature>,
+		state_version: u16,
+	}
+
+	/// Translates the stored snapshot summaries, which didn't collect any trading fee.
+	pub fn migrate<T: Config>() -> Weight {
+		let mut translated = 0u64;
+		<Snapshots<T>>::translate::<OldSnapshotSummary<T::AccountId>, _>(|_, old| {
+			translated = translated.saturating_add(1);
+			Some(SnapshotSummary {
+				validator_set_id: old.validator_set_id,
+				snapshot_id: old.snapshot_id,
+				state_root: old.state_root,
+				worker_nonce: old.worker_nonce,
+				state_change_id: old.state_change_id,
+				last_processed_blk: old.last_processed_blk,
+				state_chunk_hashes: old.state_chunk_hashes,
+				bitflags: old.bitflags,
+				withdrawals: old.withdrawals,
+				aggregate_signature: old.aggregate_signature,
+				state_version: old.state_version,
+				trading_fees: Vec::new(),
+			})
+		});
+		log::info!(target: "ocex", "Migrated {} snapshot summaries", translated);
+		T::DbWeight::get().reads_writes(translated, translated)
+	}
+}
+
+/// Starts the delay after which the exchange is frozen at the upgrade.
+pub mod v2 {
+	use super::*;
+	use crate::LastSnapshotBlock;
+	use sp_runtime::traits::BlockNumberProvider;
+
+	/// Sets the block of the last snapshot to the current block, the exchange would otherwise be
+	/// frozen right after the upgrade as no snapshot has been recorded yet.
+	pub fn migrate<T: Config>() -> Weight {
+		let current_blk = frame_system::Pallet::<T>::current_block_number();
+		<LastSnapshotBlock<T>>::put(current_blk);
+		log::info!(target: "ocex", "Set the last snapshot block to {:?}", current_blk);
+		T::DbWeight::get().reads_writes(1, 1)
+	}
+}
@@ -120,6 +120,7 @@ parameter_types! {
 	pub const ProxyLimit: u32 = 2;
 	pub const OcexPalletId: PalletId = PalletId(*b"OCEX_LMP");
 	pub const MsPerDay: u64 = 86_400_000;
+	pub const FreezeExchangeAfter: u64 = 100;
 }
 
 impl Config for Test {
@@ -132,6 +133,7 @@ impl Config for Test {
 	type Signature = Signature;
 	type MsPerDay = MsPerDay;
 	type GovernanceOrigin = EnsureRoot<sp_runtime::AccountId32>;
+	type FreezeExchangeAfter = FreezeExchangeAfter;
 	type WeightInfo = crate::weights::WeightInfo<Test>;
 }
 
@@ -21,7 +21,8 @@
 use crate::*;
 use frame_support::{assert_noop, assert_ok, bounded_vec};
 use polkadex_primitives::{
-	assets::AssetId, ingress::IngressMessages, withdrawal::Withdrawal, UNIT_BALANCE,
+	assets::AssetId, fees::FeeConfig, ingress::IngressMessages, ocex::ProxyPermissions,
+	withdrawal::Withdrawal, UNIT_BALANCE,
 };
 use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
 use sp_std::collections::btree_map::BTreeMap;
@@ -90,6 +91,7 @@ fn test_ocex_submit_snapshot() {
 		withdrawals: vec![],
 		aggregate_signature: Some(bls_primitives::Signature(signature)),
 		state_version: 0,
+		trading_fees: vec![],
 	};
 
 	let signature: [u8; 48] = hex::decode(
@@ -116,6 +118,7 @@ fn test_ocex_submit_snapshot() {
 		withdrawals: vec![],
 		aggregate_signature: Some(bls_primitives::Signature(signature)),
 		state_version: 0,
+		trading_fees: vec![],
 	};
 
 	new_test_ext().execute_with(|| {
@@ -297,6 +300,80 @@ fn test_add_proxy_account() {
 	});
 }
 
+#[test]
+fn test_set_proxy_permissions() {
+	let account_id = create_account_id();
+	let proxy_account = create_proxy_account();
+
+	new_test_ext().execute_with(|| {
+		assert_ok!(OCEX::set_exchange_state(RuntimeOrigin::root(), true));
+		assert_ok!(OCEX::register_main_account(
+			RuntimeOrigin::signed(account_id.clone().into()),
+			account_id.clone().into()
+		));
+		assert_noop!(
+			OCEX::set_proxy_permissions(
+				RuntimeOrigin::signed(account_id.clone().into()),
+				proxy_account.clone().into(),
+				ProxyPermissions::TRADE
+			),
+			Error::<Test>::ProxyNotFound
+		);
+		assert_ok!(OCEX::add_proxy_account(
+			RuntimeOrigin::signed(account_id.clone().into()),
+			proxy_account.clone().into()
+		));
+		// Proxies are allowed every action until scoped
+		assert_eq!(
+			OCEX::proxy_permissions(account_id.clone(), proxy_account.clone()),
+			ProxyPermissions::ALL
+		);
+		assert_noop!(
+			OCEX::set_proxy_permissions(
+				RuntimeOrigin::signed(account_id.clone().into()),
+				proxy_account.clone().into(),
+				ProxyPermissions(0)
+			),
+			Error::<Test>::InvalidProxyPermissions
+		);
+		assert_ok!(OCEX::set_proxy_permissions(
+			RuntimeOrigin::signed(account_id.clone().into()),
+			proxy_account.clone().into(),
+			ProxyPermissions::TRADE
+		));
+		assert_eq!(
+			OCEX::proxy_permissions(account_id.clone(), proxy_account.clone()),
+			ProxyPermissions::TRADE
+		);
+		assert_last_event::<Test>(
+			crate::Event::ProxyPermissionsUpdated {
+				main: account_id.clone(),
+				proxy: proxy_account.clone(),
+				permissions: ProxyPermissions::TRADE,
+			}
+			.into(),
+		);
+		let blk = frame_system::Pallet::<Test>::current_block_number();
+		assert_eq!(
+			OCEX::ingress_messages(blk).last(),
+			Some(&IngressMessages::SetProxyPermissions(
+				account_id.clone(),
+				proxy_account.clone(),
+				ProxyPermissions::TRADE
+			))
+		);
+
+		assert_ok!(OCEX::remove_proxy_account(
+			RuntimeOrigin::signed(account_id.clone().into()),
+			proxy_account.clone().into()
+		));
+		assert_eq!(
+			OCEX::proxy_permissions(account_id.clone(), proxy_account.clone()),
+			ProxyPermissions::ALL
+		);
+	});
+}
+
 #[test]
 fn test_register_trading_pair_both_assets_cannot_be_same() {
 	new_test_ext().execute_with(|| {
@@ -1456,13 +1533,12 @@ fn test_collect_fees_bad_origin() {
 
 #[test]
 fn withdrawal_when_exchange_not_operational() {
-	let (alice_account_id, proxy_account_id) = get_alice_accounts();
+	let (alice_account_id, _) = get_alice_accounts();
 
 	new_test_ext().execute_with(|| {
 		assert_noop!(
 			OCEX::withdrawal_from_orderbook(
 				alice_account_id.clone(),
-				proxy_account_id,
 				AssetId::Polkadex,
 				100_u128.saturated_into(),
 				true
@@ -1474,14 +1550,13 @@ fn withdrawal_when_exchange_not_operational() {
 
 #[test]
 fn withdrawal_when_token_not_allowlisted() {
-	let (alice_main_account, alice_proxy_account) = get_alice_accounts();
+	let (alice_main_account, _) = get_alice_accounts();
 
 	new_test_ext().execute_with(|| {
 		assert_ok!(OCEX::set_exchange_state(RuntimeOrigin::root(), true));
 		assert_noop!(
 			OCEX::withdrawal_from_orderbook(
 				alice_main_account.clone(),
-				alice_proxy_account,
 				AssetId::Polkadex,
 				100_u128.saturated_into(),
 				true
@@ -1493,7 +1568,7 @@ fn withdrawal_when_token_not_allowlisted() {
 
 #[test]
 fn withdrawal_when_account_not_register() {
-	let (alice_main_account, alice_proxy_account) = get_alice_accounts();
+	let (alice_main_account, _) = get_alice_accounts();
 
 	new_test_ext().execute_with(|| {
 		assert_ok!(OCEX::set_exchange_state(RuntimeOrigin::root(), true));
@@ -1501,7 +1576,6 @@ fn withdrawal_when_account_not_register() {
 		assert_noop!(
 			OCEX::withdrawal_from_orderbook(
 				alice_main_account.clone(),
-				alice_proxy_account,
 				AssetId::Polkadex,
 				100_u128.saturated_into(),
 				true
@@ -1527,7 +1601,6 @@ fn withdrawal_with_overflow_amount() {
 		assert_noop!(
 			OCEX::withdrawal_from_orderbook(
 				alice_main_account.clone(),
-				alice_proxy_account,
 				AssetId::Polkadex,
 				(WITHDRAWAL_MAX + 1).saturated_into(),
 				true
@@ -1552,7 +1625,6 @@ fn withdrawal() {
 
 		assert_ok!(OCEX::withdrawal_from_orderbook(
 			alice_main_account.clone(),
-			alice_proxy_account.clone(),
 			AssetId::Polkadex,
 			100_u128.saturated_into(),
 			true
@@ -1562,7 +1634,7 @@ fn withdrawal() {
 		assert_eq!(
 			OCEX::ingress_messages(blk)[2],
 			IngressMessages::DirectWithdrawal(
-				alice_proxy_account,
+				alice_main_account.clone(),
 				AssetId::Polkadex,
 				Decimal::new(100, 12),
 				true
@@ -1577,6 +1649,157 @@ fn withdrawal() {
 	});
 }
 
+#[test]
+fn direct_withdrawal_queues_ingress_message_for_main_account() {
+	let (alice_main_account, alice_proxy_account) = get_alice_accounts();
+
+	new_test_ext().execute_with(|| {
+		assert_ok!(OCEX::set_exchange_state(RuntimeOrigin::root(), true));
+		allowlist_token(AssetId::Polkadex);
+
+		assert_ok!(OCEX::register_main_account(
+			RuntimeOrigin::signed(alice_main_account.clone().into()),
+			alice_proxy_account.clone().into()
+		));
+
+		assert_ok!(OCEX::direct_withdrawal(
+			RuntimeOrigin::signed(alice_main_account.clone().into()),
+			AssetId::Polkadex,
+			100_u128.saturated_into(),
+			false
+		));
+		let blk = frame_system::Pallet::<Test>::current_block_number();
+		assert_eq!(
+			OCEX::ingress_messages(blk)[2],
+			IngressMessages::DirectWithdrawal(
+				alice_main_account.clone(),
+				AssetId::Polkadex,
+				Decimal::new(100, 12),
+				false
+			)
+		);
+		assert_last_event::<Test>(
+			crate::Event::WithdrawFromOrderbook(alice_main_account, AssetId::Polkadex, 100_u128)
+				.into(),
+		);
+	});
+}
+
+#[test]
+fn direct_withdrawal_from_unregistered_account() {
+	let (alice_main_account, _) = get_alice_accounts();
+
+	new_test_ext().execute_with(|| {
+		assert_ok!(OCEX::set_exchange_state(RuntimeOrigin::root(), true));
+		allowlist_token(AssetId::Polkadex);
+		assert_noop!(
+			OCEX::direct_withdrawal(
+				RuntimeOrigin::signed(alice_main_account.into()),
+				AssetId::Polkadex,
+				100_u128.saturated_into(),
+				true
+			),
+			Error::<Test>::AccountNotRegistered
+		);
+	});
+}
+
+#[test]
+fn direct_withdrawal_with_bad_origin() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			OCEX::direct_withdrawal(
+				RuntimeOrigin::root(),
+				AssetId::Polkadex,
+				100_u128.saturated_into(),
+				true
+			),
+			BadOrigin
+		);
+	});
+}
+
+#[test]
+fn set_fee_config_updates_account_and_queues_ingress_message() {
+	let (alice_main_account, alice_proxy_account) = get_alice_accounts();
+
+	new_test_ext().execute_with(|| {
+		assert_ok!(OCEX::set_exchange_state(RuntimeOrigin::root(), true));
+		assert_ok!(OCEX::register_main_account(
+			RuntimeOrigin::signed(alice_main_account.clone().into()),
+			alice_proxy_account.clone().into()
+		));
+		let fee_config = FeeConfig::new(Decimal::new(1, 3), Decimal::new(2, 3));
+		assert_ok!(OCEX::set_fee_config(
+			RuntimeOrigin::root(),
+			alice_main_account.clone(),
+			fee_config
+		));
+		assert_eq!(<Accounts<Test>>::get(&alice_main_account).unwrap().fee_config, fee_config);
+		let blk = frame_system::Pallet::<Test>::current_block_number();
+		assert_eq!(
+			OCEX::ingress_messages(blk)[2],
+			IngressMessages::SetFeeConfig(alice_main_account.clone(), fee_config)
+		);
+		assert_last_event::<Test>(
+			crate::Event::FeeConfigUpdated { main: alice_main_account, fee_config }.into(),
+		);
+	});
+}
+
+#[test]
+fn set_fee_config_with_invalid_fractions() {
+	let (alice_main_account, alice_proxy_account) = get_alice_accounts();
+
+	new_test_ext().execute_with(|| {
+		assert_ok!(OCEX::set_exchange_state(RuntimeOrigin::root(), true));
+		assert_ok!(OCEX::register_main_account(
+			RuntimeOrigin::signed(alice_main_account.clone().into()),
+			alice_proxy_account.into()
+		));
+		assert_noop!(
+			OCEX::set_fee_config(
+				RuntimeOrigin::root(),
+				alice_main_account.clone(),
+				FeeConfig::new(Decimal::ONE, Decimal::new(2, 3))
+			),
+			Error::<Test>::InvalidFeeConfig
+		);
+		assert_noop!(
+			OCEX::set_fee_config(
+				RuntimeOrigin::root(),
+				alice_main_account,
+				FeeConfig::new(Decimal::new(1, 3), Decimal::new(-2, 3))
+			),
+			Error::<Test>::InvalidFeeConfig
+		);
+	});
+}
+
+#[test]
+fn set_fee_config_for_unregistered_account() {
+	let (alice_main_account, _) = get_alice_accounts();
+
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			OCEX::set_fee_config(
+				RuntimeOrigin::root(),
+				alice_main_account.clone(),
+				FeeConfig::default()
+			),
+			Error::<Test>::MainAccountNotFound
+		);
+		assert_noop!(
+			OCEX::set_fee_config(
+				RuntimeOrigin::signed(alice_main_account.clone().into()),
+				alice_main_account,
+				FeeConfig::default()
+			),
+			BadOrigin
+		);
+	});
+}
+
 // P.S. This was to apply a DDOS attack and see the response in the mock environment
 /* #[test]
 fn collect_fees_ddos(){
@@ -1638,6 +1861,7 @@ fn get_dummy_snapshot(
 		withdrawals,
 		aggregate_signature: None,
 		state_version: 0,
+		trading_fees: vec![],
 	};
 	let (pair, _seed) = bls_primitives::Pair::generate();
 	snapshot.aggregate_signature = Some(pair.sign(&snapshot.sign_data()));
@@ -1832,7 +2056,7 @@ pub fn test_set_balances_when_exchange_is_not_pause() {
 pub fn test_set_balances_when_exchange_is_pause() {
 	let account_id = create_account_id();
 	new_test_ext().execute_with(|| {
-		assert_ok!(OCEX::set_exchange_state(RuntimeOrigin::root(), false));
+		register_for_set_balances(account_id.clone());
 		let mut vec_of_balances: Vec<HandleBalance<AccountId32>> = vec![];
 		vec_of_balances.push(HandleBalance {
 			main_account: account_id,
@@ -1849,12 +2073,39 @@ pub fn test_set_balances_when_exchange_is_pause() {
 		);
 		let blk = frame_system::Pallet::<Test>::current_block_number();
 		assert_eq!(
-			OCEX::ingress_messages(blk)[1],
-			IngressMessages::SetFreeReserveBalanceForAccounts(bounded_vec_for_alice,)
+			OCEX::ingress_messages(blk).last(),
+			Some(&IngressMessages::SetFreeReserveBalanceForAccounts(bounded_vec_for_alice))
+		);
+	});
+}
+
+#[test]
+pub fn test_set_balances_with_unregistered_account() {
+	let account_id = create_account_id();
+	new_test_ext().execute_with(|| {
+		assert_ok!(OCEX::set_exchange_state(RuntimeOrigin::root(), false));
+		let vec_of_balances = vec![HandleBalance {
+			main_account: account_id,
+			asset_id: AssetId::Polkadex,
+			free: 100,
+			reserve: 50,
+		}];
+		let bounded_vec_for_alice: BoundedVec<HandleBalance<AccountId>, HandleBalanceLimit> =
+			BoundedVec::try_from(vec_of_balances).unwrap();
+		assert_noop!(
+			OCEX::set_balances(RuntimeOrigin::root(), bounded_vec_for_alice),
+			Error::<Test>::MainAccountNotFound
 		);
 	});
 }
 
+/// Registers the main account and pauses the exchange, as required to set balances.
+fn register_for_set_balances(account_id: AccountId32) {
+	assert_ok!(OCEX::set_exchange_state(RuntimeOrigin::root(), true));
+	assert_ok!(OCEX::register_main_account(RuntimeOrigin::signed(account_id.clone()), account_id));
+	assert_ok!(OCEX::set_exchange_state(RuntimeOrigin::root(), false));
+}
+
 #[test]
 pub fn test_set_balances_when_bounded_vec_limits_out_of_bound() {
 	let account_id = create_account_id();
@@ -1881,7 +2132,7 @@ pub fn test_set_balances_when_bounded_vec_limits_out_of_bound() {
 pub fn test_set_balances_when_bounded_vec_limits_in_bound() {
 	let account_id = create_account_id();
 	new_test_ext().execute_with(|| {
-		assert_ok!(OCEX::set_exchange_state(RuntimeOrigin::root(), false));
+		register_for_set_balances(account_id.clone());
 		let mut vec_of_balances: Vec<HandleBalance<AccountId32>> = vec![];
 		for _i in 0..1000 {
 			vec_of_balances.push(HandleBalance {
@@ -1900,6 +2151,273 @@ pub fn test_set_balances_when_bounded_vec_limits_in_bound() {
 	});
 }
 
+#[test]
+fn escape_hatch_withdraw_when_exchange_not_frozen() {
+	let account_id = create_account_id();
+	new_test_ext().execute_with(|| {
+		let (snapshot, balance_proof) = get_dummy_balance_proof(account_id.clone());
+		<Snapshots<Test>>::insert(1, snapshot);
+		<SnapshotNonce<Test>>::put(1);
+		<LastSnapshotBlock<Test>>::put(1);
+		assert_noop!(
+			OCEX::escape_hatch_withdraw(RuntimeOrigin::signed(account_id), balance_proof),
+			Error::<Test>::ExchangeNotFrozen
+		);
+	});
+}
+
+#[test]
+fn escape_hatch_withdraw() {
+	let account_id = create_account_id();
+	let custodian_account = OCEX::get_pallet_account();
+	new_test_ext().execute_with(|| {
+		mint_into_account(custodian_account.clone());
+		let (snapshot, balance_proof) = get_dummy_balance_proof(account_id.clone());
+		<Snapshots<Test>>::insert(1, snapshot);
+		<SnapshotNonce<Test>>::put(1);
+		<LastSnapshotBlock<Test>>::put(1);
+		System::set_block_number(1 + FreezeExchangeAfter::get());
+		assert!(OCEX::is_exchange_frozen());
+
+		// Balances must match the state root of the last snapshot
+		let mut tampered = balance_proof.clone();
+		tampered.free = Some(Decimal::from(100));
+		assert_noop!(
+			OCEX::escape_hatch_withdraw(RuntimeOrigin::signed(account_id.clone()), tampered),
+			Error::<Test>::InvalidBalanceProof
+		);
+		// Only the owner of the balances can withdraw them
+		assert_noop!(
+			OCEX::escape_hatch_withdraw(
+				RuntimeOrigin::signed(create_proxy_account()),
+				balance_proof.clone()
+			),
+			Error::<Test>::SenderNotAuthorizedToWithdraw
+		);
+
+		assert_ok!(OCEX::escape_hatch_withdraw(
+			RuntimeOrigin::signed(account_id.clone()),
+			balance_proof.clone()
+		));
+		assert_last_event::<Test>(
+			crate::Event::EscapeHatchWithdrawal {
+				main: account_id.clone(),
+				asset: AssetId::Polkadex,
+				amount: 15 * UNIT_BALANCE,
+				snapshot_id: 1,
+			}
+			.into(),
+		);
+		assert_eq!(
+			<Test as Config>::NativeCurrency::free_balance(account_id.clone()),
+			15 * UNIT_BALANCE
+		);
+		assert_eq!(OCEX::escape_hatch_claims(account_id.clone(), AssetId::Polkadex), Some(1));
+
+		// Balances can only be withdrawn once
+		assert_noop!(
+			OCEX::escape_hatch_withdraw(RuntimeOrigin::signed(account_id.clone()), balance_proof),
+			Error::<Test>::BalanceAlreadyClaimed
+		);
+		// The exchange stays frozen even if a snapshot is produced afterwards
+		assert_ok!(OCEX::set_exchange_state(RuntimeOrigin::root(), true));
+		let (mut snapshot, _public) = get_dummy_snapshot(0);
+		snapshot.snapshot_id = 2;
+		assert_noop!(
+			OCEX::submit_snapshot(RuntimeOrigin::none(), snapshot),
+			Error::<Test>::ExchangeFrozen
+		);
+		assert_noop!(
+			OCEX::deposit(RuntimeOrigin::signed(account_id), AssetId::Polkadex, 100_u128.into()),
+			Error::<Test>::ExchangeFrozen
+		);
+	});
+}
+
+#[test]
+fn escape_hatch_withdraw_includes_deposits_after_the_last_snapshot() {
+	let account_id = create_account_id();
+	let custodian_account = OCEX::get_pallet_account();
+	new_test_ext().execute_with(|| {
+		assert_ok!(OCEX::set_exchange_state(RuntimeOrigin::root(), true));
+		mint_into_account(account_id.clone());
+		mint_into_account(custodian_account);
+		allowlist_token(AssetId::Polkadex);
+		assert_ok!(OCEX::register_main_account(
+			RuntimeOrigin::signed(account_id.clone()),
+			account_id.clone()
+		));
+		let (mut snapshot, balance_proof) = get_dummy_balance_proof(account_id.clone());
+		snapshot.last_processed_blk = 5;
+		<Snapshots<Test>>::insert(1, snapshot);
+		<SnapshotNonce<Test>>::put(1);
+		<LastSnapshotBlock<Test>>::put(5);
+
+		// The deposit of block 5 is processed by the snapshot, the ones of block 6 are not
+		System::set_block_number(5);
+		assert_ok!(OCEX::deposit(
+			RuntimeOrigin::signed(account_id.clone()),
+			AssetId::Polkadex,
+			10 * UNIT_BALANCE
+		));
+		System::set_block_number(6);
+		assert_ok!(OCEX::deposit(
+			RuntimeOrigin::signed(account_id.clone()),
+			AssetId::Polkadex,
+			20 * UNIT_BALANCE
+		));
+		assert_ok!(OCEX::deposit(
+			RuntimeOrigin::signed(account_id.clone()),
+			AssetId::Polkadex,
+			5 * UNIT_BALANCE
+		));
+		assert_eq!(
+			OCEX::pending_deposits(account_id.clone(), AssetId::Polkadex).into_inner(),
+			vec![(6, 25 * UNIT_BALANCE)]
+		);
+
+		System::set_block_number(5 + FreezeExchangeAfter::get());
+		let free_balance = <Test as Config>::NativeCurrency::free_balance(account_id.clone());
+		assert_ok!(OCEX::escape_hatch_withdraw(
+			RuntimeOrigin::signed(account_id.clone()),
+			balance_proof
+		));
+		assert_eq!(
+			<Test as Config>::NativeCurrency::free_balance(account_id.clone()),
+			free_balance + 40 * UNIT_BALANCE
+		);
+		assert!(OCEX::pending_deposits(account_id, AssetId::Polkadex).is_empty());
+	});
+}
+
+#[test]
+fn deposits_beyond_the_pending_deposits_limit_are_added_to_the_newest_entry() {
+	let account_id = create_account_id();
+	new_test_ext().execute_with(|| {
+		assert_ok!(OCEX::set_exchange_state(RuntimeOrigin::root(), true));
+		mint_into_account(account_id.clone());
+		allowlist_token(AssetId::Polkadex);
+		assert_ok!(OCEX::register_main_account(
+			RuntimeOrigin::signed(account_id.clone()),
+			account_id.clone()
+		));
+
+		let limit = PendingDepositsLimit::get() as u64;
+		for blk in 1..=limit + 1 {
+			System::set_block_number(blk);
+			assert_ok!(OCEX::deposit(
+				RuntimeOrigin::signed(account_id.clone()),
+				AssetId::Polkadex,
+				UNIT_BALANCE
+			));
+		}
+		let deposits = OCEX::pending_deposits(account_id.clone(), AssetId::Polkadex);
+		assert_eq!(deposits.len() as u64, limit);
+		assert_eq!(deposits[limit as usize - 2], (limit - 1, UNIT_BALANCE));
+		assert_eq!(deposits[limit as usize - 1], (limit + 1, 2 * UNIT_BALANCE));
+		let total = deposits.iter().fold(0, |total, (_, amount)| total + amount);
+		assert_eq!(total, (limit as u128 + 1) * UNIT_BALANCE);
+	});
+}
+
+#[test]
+fn migration_starts_the_freeze_delay_at_the_upgrade() {
+	use frame_support::traits::StorageVersion;
+	new_test_ext().execute_with(|| {
+		StorageVersion::new(1).put::<OCEX>();
+		System::set_block_number(1000);
+		assert!(OCEX::is_exchange_frozen());
+		crate::migrations::migrate::<Test>();
+		assert_eq!(OCEX::last_snapshot_block(), 1000);
+		assert_eq!(StorageVersion::get::<OCEX>(), crate::migrations::STORAGE_VERSION);
+		assert!(!OCEX::is_exchange_frozen());
+	});
+}
+
+#[test]
+fn rotate_orderbook_operator() {
+	let old_key = sp_core::ecdsa::Public::from_raw([1; 33]);
+	let new_key = sp_core::ecdsa::Public::from_raw([2; 33]);
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			OCEX::rotate_orderbook_operator(
+				RuntimeOrigin::signed(create_account_id()),
+				new_key,
+				10
+			),
+			BadOrigin
+		);
+		assert_ok!(OCEX::whitelist_orderbook_operator(RuntimeOrigin::root(), old_key));
+		assert_eq!(OCEX::get_orderbook_operator_public_keys(), vec![old_key]);
+		assert_noop!(
+			OCEX::rotate_orderbook_operator(RuntimeOrigin::root(), old_key, 10),
+			Error::<Test>::OperatorKeyAlreadyActive
+		);
+
+		assert_ok!(OCEX::rotate_orderbook_operator(RuntimeOrigin::root(), new_key, 10));
+		assert_last_event::<Test>(
+			crate::Event::OrderbookOperatorKeyRotated {
+				operator_public_key: new_key,
+				previous_public_key: Some(old_key),
+				accepted_until: 11,
+			}
+			.into(),
+		);
+		// Both keys are accepted during the grace period
+		assert_eq!(OCEX::get_orderbook_operator_public_keys(), vec![new_key, old_key]);
+		System::set_block_number(11);
+		assert_eq!(OCEX::get_orderbook_operator_public_keys(), vec![new_key, old_key]);
+		System::set_block_number(12);
+		assert_eq!(OCEX::get_orderbook_operator_public_keys(), vec![new_key]);
+
+		// Whitelisting a key is a hard cutover
+		assert_ok!(OCEX::rotate_orderbook_operator(RuntimeOrigin::root(), old_key, 10));
+		assert_eq!(OCEX::get_orderbook_operator_public_keys(), vec![old_key, new_key]);
+		assert_ok!(OCEX::whitelist_orderbook_operator(RuntimeOrigin::root(), new_key));
+		assert_eq!(OCEX::get_orderbook_operator_public_keys(), vec![new_key]);
+	});
+}
+
+fn get_dummy_balance_proof(
+	main: AccountId32,
+) -> (SnapshotSummary<AccountId>, orderbook_primitives::proof::BalanceProof) {
+	use orderbook_primitives::types::AccountAsset;
+	use parity_scale_codec::Encode;
+	use reference_trie::{ExtensionLayout, RefHasher};
+	use trie_db::{proof::generate_proof, TrieDBMutBuilder, TrieMut};
+
+	let mut memory_db: memory_db::MemoryDB<RefHasher, memory_db::HashKey<RefHasher>, Vec<u8>> =
+		Default::default();
+	let mut state_root = Default::default();
+	let account_asset = AccountAsset::new(main, AssetId::Polkadex);
+	{
+		let mut trie =
+			TrieDBMutBuilder::<ExtensionLayout>::new(&mut memory_db, &mut state_root).build();
+		trie.insert(&account_asset.encode(), &Decimal::from(10).encode()).unwrap();
+		trie.insert(&account_asset.reserved_balance_key(), &Decimal::from(5).encode())
+			.unwrap();
+		trie.commit();
+	}
+	let keys = [account_asset.encode(), account_asset.reserved_balance_key()];
+	let proof = generate_proof::<_, ExtensionLayout, _, _>(&memory_db, &state_root, &keys)
+		.unwrap()
+		.into_iter()
+		.map(|node| node.try_into().unwrap())
+		.collect::<Vec<_>>()
+		.try_into()
+		.unwrap();
+	let (mut snapshot, _public) = get_dummy_snapshot(0);
+	snapshot.state_root = state_root.into();
+	let balance_proof = orderbook_primitives::proof::BalanceProof {
+		snapshot_id: 1,
+		account_asset,
+		free: Some(Decimal::from(10)),
+		reserved: Some(Decimal::from(5)),
+		proof,
+	};
+	(snapshot, balance_proof)
+}
+
 fn allowlist_token(token: AssetId) {
 	let mut allowlisted_token = <AllowlistedToken<Test>>::get();
 	allowlisted_token.try_insert(token).unwrap();
@@ -109,19 +109,24 @@ impl<T: frame_system::Config> super::OcexWeightInfo for WeightInfo<T> {
 			.saturating_add(T::DbWeight::get().writes(2))
 	}
 	// Storage: OCEX ExchangeState (r:1 w:0)
+	// Storage: OCEX ExchangeFrozen (r:1 w:0)
+	// Storage: OCEX LastSnapshotBlock (r:1 w:0)
 	// Storage: OCEX AllowlistedToken (r:1 w:0)
 	// Storage: OCEX Accounts (r:1 w:0)
 	// Storage: Assets Asset (r:1 w:1)
 	// Storage: Assets Account (r:2 w:2)
 	// Storage: System Account (r:1 w:1)
 	// Storage: OCEX TotalAssets (r:1 w:1)
+	// Storage: OCEX PendingDeposits (r:1 w:1)
+	// Storage: OCEX SnapshotNonce (r:1 w:0)
+	// Storage: OCEX Snapshots (r:1 w:0)
 	// Storage: OCEX IngressMessages (r:1 w:1)
 	/// The range of component `x` is `[1, 255]`.
 	fn deposit(_x: u32, ) -> Weight {
 		// Minimum execution time: 61_954 nanoseconds.
 		Weight::from_ref_time(63_987_215)
-			.saturating_add(T::DbWeight::get().reads(9))
-			.saturating_add(T::DbWeight::get().writes(6))
+			.saturating_add(T::DbWeight::get().reads(14))
+			.saturating_add(T::DbWeight::get().writes(7))
 	}
 	// Storage: OCEX ExchangeState (r:1 w:0)
 	// Storage: OCEX Accounts (r:1 w:1)
@@ -223,4 +228,59 @@ impl<T: frame_system::Config> super::OcexWeightInfo for WeightInfo<T> {
 		Weight::from_ref_time(4_741_000)
 			.saturating_add(T::DbWeight::get().writes(1))
 	}
+	// Storage: OCEX ExchangeState (r:1 w:0)
+	// Storage: OCEX AllowlistedToken (r:1 w:0)
+	// Storage: OCEX Accounts (r:1 w:0)
+	// Storage: OCEX IngressMessages (r:1 w:1)
+	/// The range of component `x` is `[1, 255]`.
+	fn direct_withdrawal(_x: u32, ) -> Weight {
+		// Minimum execution time: 27_384 nanoseconds.
+		Weight::from_ref_time(28_210_517)
+			.saturating_add(T::DbWeight::get().reads(4))
+			.saturating_add(T::DbWeight::get().writes(1))
+	}
+	// Storage: OCEX Accounts (r:1 w:1)
+	// Storage: OCEX IngressMessages (r:1 w:1)
+	/// The range of component `x` is `[1, 255]`.
+	fn set_fee_config(_x: u32, ) -> Weight {
+		// Minimum execution time: 21_712 nanoseconds.
+		Weight::from_ref_time(22_450_311)
+			.saturating_add(T::DbWeight::get().reads(2))
+			.saturating_add(T::DbWeight::get().writes(2))
+	}
+	// Storage: OCEX LastSnapshotBlock (r:1 w:0)
+	// Storage: OCEX ExchangeFrozen (r:1 w:1)
+	// Storage: OCEX EscapeHatchClaims (r:1 w:1)
+	// Storage: OCEX SnapshotNonce (r:1 w:0)
+	// Storage: OCEX Snapshots (r:1 w:0)
+	// Storage: OCEX PendingDeposits (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	// Storage: Assets Asset (r:1 w:1)
+	// Storage: Assets Account (r:2 w:2)
+	/// The range of component `x` is `[1, 128]`.
+	fn escape_hatch_withdraw(x: u32, ) -> Weight {
+		// Estimated until the benchmark is run on the reference hardware.
+		Weight::from_ref_time(70_000_000)
+			.saturating_add(Weight::from_ref_time(2_000_000).saturating_mul(x.into()))
+			.saturating_add(T::DbWeight::get().reads(10))
+			.saturating_add(T::DbWeight::get().writes(7))
+	}
+	// Storage: OCEX OrderbookOperatorPublicKey (r:1 w:1)
+	// Storage: OCEX PreviousOrderbookOperatorPublicKey (r:0 w:1)
+	fn rotate_orderbook_operator() -> Weight {
+		// Estimated until the benchmark is run on the reference hardware.
+		Weight::from_ref_time(15_000_000)
+			.saturating_add(T::DbWeight::get().reads(1))
+			.saturating_add(T::DbWeight::get().writes(2))
+	}
+	// Storage: OCEX ExchangeState (r:1 w:0)
+	// Storage: OCEX Accounts (r:1 w:0)
+	// Storage: OCEX ProxyPermissionsOf (r:0 w:1)
+	// Storage: OCEX IngressMessages (r:1 w:1)
+	fn set_proxy_permissions() -> Weight {
+		// Estimated until the benchmark is run on the reference hardware.
+		Weight::from_ref_time(25_000_000)
+			.saturating_add(T::DbWeight::get().reads(3))
+			.saturating_add(T::DbWeight::get().writes(2))
+	}
 }
@@ -27,7 +27,11 @@
 //! * keep track of egress messages;
 //! * handle validator session changes;
 
-use frame_support::{pallet_prelude::*, traits::Get, BoundedVec, Parameter};
+use frame_support::{
+	pallet_prelude::*,
+	traits::{Get, StorageVersion},
+	BoundedBTreeSet, BoundedVec, Parameter,
+};
 use frame_system::{offchain::SubmitTransaction, pallet_prelude::*};
 use parity_scale_codec::{Encode, MaxEncodedLen};
 use sp_runtime::{
@@ -35,15 +39,27 @@ use sp_runtime::{
 	transaction_validity::{InvalidTransaction, TransactionValidity, ValidTransaction},
 	RuntimeAppPublic, SaturatedConversion,
 };
-use sp_std::prelude::*;
+use sp_std::{collections::btree_set::BTreeSet, prelude::*};
 
 pub use pallet::*;
 use polkadex_primitives::utils::return_set_bits;
 use thea_primitives::{
-	types::Message, Network, ValidatorSet, GENESIS_AUTHORITY_SET_ID, NATIVE_NETWORK,
+	types::Message, Network, ValidatorSet, GENESIS_AUTHORITY_SET_ID, MAX_MESSAGES_IN_FLIGHT,
+	NATIVE_NETWORK,
 };
 
+#[cfg(test)]
+mod mock;
 mod session;
+#[cfg(test)]
+mod tests;
+
+/// The current storage version.
+const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
+
+/// Number of blocks an incoming message stays valid in the transaction pool, long enough for a
+/// message to wait for the [`MAX_MESSAGES_IN_FLIGHT`] previous nonces to be included.
+pub const INCOMING_MESSAGE_LONGEVITY: u64 = 2 * MAX_MESSAGES_IN_FLIGHT;
 
 #[frame_support::pallet]
 pub mod pallet {
@@ -75,11 +91,15 @@ pub mod pallet {
 		/// The maximum number of authorities that can be added.
 		type MaxAuthorities: Get<u32>;
 
+		/// The maximum number of networks served by one authority.
+		type MaxNetworks: Get<u32>;
+
 		/// Something that executes the payload
 		type Executor: thea_primitives::TheaIncomingExecutor;
 	}
 
 	#[pallet::pallet]
+	#[pallet::storage_version(STORAGE_VERSION)]
 	#[pallet::without_storage_info]
 	pub struct Pallet<T>(PhantomData<T>);
 
@@ -108,11 +128,20 @@ pub mod pallet {
 	pub(super) type NextAuthorities<T: Config> =
 		StorageMap<_, Identity, Network, BoundedVec<T::TheaId, T::MaxAuthorities>, ValueQuery>;
 
-	/// Authority's network preference
+	/// Authorities of the current and the queued sessions, whatever the networks they serve
+	#[pallet::storage]
+	#[pallet::getter(fn session_authorities)]
+	pub(super) type SessionAuthorities<T: Config> = StorageValue<
+		_,
+		(BoundedVec<T::TheaId, T::MaxAuthorities>, BoundedVec<T::TheaId, T::MaxAuthorities>),
+		ValueQuery,
+	>;
+
+	/// Networks served by the authority
 	#[pallet::storage]
 	#[pallet::getter(fn network_pref)]
 	pub(super) type NetworkPreference<T: Config> =
-		StorageMap<_, Identity, T::TheaId, Network, OptionQuery>;
+		StorageMap<_, Identity, T::TheaId, BoundedBTreeSet<Network, T::MaxNetworks>, ValueQuery>;
 
 	/// Outgoing messages
 	/// first key: Network
@@ -143,7 +172,7 @@ pub mod pallet {
 	#[pallet::event]
 	#[pallet::generate_deposit(pub (super) fn deposit_event)]
 	pub enum Event<T: Config> {
-		NetworkUpdated { authority: T::TheaId, network: Network },
+		NetworkUpdated { authority: T::TheaId, networks: BoundedBTreeSet<Network, T::MaxNetworks> },
 	}
 
 	#[pallet::error]
@@ -158,6 +187,15 @@ pub mod pallet {
 		NoValidatorsFound(Network),
 		/// Cannot update with older nonce
 		NonceIsAlreadyProcessed,
+		/// Native network can't be served as a foreign network
+		InvalidNetworkPreference,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<T::BlockNumber> for Pallet<T> {
+		fn on_runtime_upgrade() -> Weight {
+			Self::migrate_network_preferences()
+		}
 	}
 
 	#[pallet::validate_unsigned]
@@ -166,6 +204,8 @@ pub mod pallet {
 
 		fn validate_unsigned(_source: TransactionSource, call: &Self::Call) -> TransactionValidity {
 			match call {
+				Call::update_network_pref { authority, networks, signature } =>
+					Self::validate_network_pref(authority, networks, signature),
 				Call::incoming_message { bitmap, payload, signature } =>
 					Self::validate_incoming_message(bitmap, payload, signature),
 				_ => InvalidTransaction::Call.into(),
@@ -175,18 +215,26 @@ pub mod pallet {
 
 	#[pallet::call]
 	impl<T: Config> Pallet<T> {
-		/// Updates the network preference of a thea validator
+		/// Updates the set of networks served by a thea validator, an empty set removes the
+		/// validator from every network from the next session. The set is signed by the
+		/// authority with the current validator set id, which is verified in validate_unsigned
+		/// along with the authority being part of the current or the queued session
 		#[pallet::call_index(0)]
-		#[pallet::weight(Weight::default())]
+		#[pallet::weight(T::DbWeight::get().reads_writes(1, 1))]
 		pub fn update_network_pref(
 			origin: OriginFor<T>,
 			authority: T::TheaId,
-			network: Network,
+			networks: BoundedBTreeSet<Network, T::MaxNetworks>,
 			_signature: T::Signature,
 		) -> DispatchResult {
 			ensure_none(origin)?;
-			<NetworkPreference<T>>::insert(authority.clone(), network);
-			Self::deposit_event(Event::NetworkUpdated { authority, network });
+			ensure!(!networks.contains(&NATIVE_NETWORK), Error::<T>::InvalidNetworkPreference);
+			if networks.is_empty() {
+				<NetworkPreference<T>>::remove(&authority);
+			} else {
+				<NetworkPreference<T>>::insert(&authority, &networks);
+			}
+			Self::deposit_event(Event::NetworkUpdated { authority, networks });
 			Ok(())
 		}
 
@@ -255,8 +303,38 @@ impl<T: Config> Pallet<T> {
 		<Authorities<T>>::get(network, id).to_vec()
 	}
 
-	pub fn authority_network_pref(authority: &T::TheaId) -> Option<Network> {
-		<NetworkPreference<T>>::get(authority)
+	pub fn authority_network_pref(authority: &T::TheaId) -> BTreeSet<Network> {
+		<NetworkPreference<T>>::get(authority).into_inner()
+	}
+
+	/// Payload signed by an authority to update its network preference, it is only valid for
+	/// the current validator set id so that it can't be replayed in a later session.
+	pub fn network_pref_payload(networks: &BoundedBTreeSet<Network, T::MaxNetworks>) -> Vec<u8> {
+		(networks, Self::validator_set_id()).encode()
+	}
+
+	fn validate_network_pref(
+		authority: &T::TheaId,
+		networks: &BoundedBTreeSet<Network, T::MaxNetworks>,
+		signature: &T::Signature,
+	) -> TransactionValidity {
+		if networks.contains(&NATIVE_NETWORK) {
+			return Err(InvalidTransaction::Call.into())
+		}
+		// Authorities which serve no network yet are not part of the authorities of a network
+		let (current, queued) = <SessionAuthorities<T>>::get();
+		if !current.contains(authority) && !queued.contains(authority) {
+			return Err(InvalidTransaction::BadSigner.into())
+		}
+		let payload = Self::network_pref_payload(networks);
+		if !authority.verify(&payload, signature.into_ref()) {
+			return Err(InvalidTransaction::BadProof.into())
+		}
+		ValidTransaction::with_tag_prefix("thea_network_pref")
+			.and_provides((authority, Self::validator_set_id()))
+			.longevity(3)
+			.propagate(true)
+			.build()
 	}
 
 	fn validate_incoming_message(
@@ -264,10 +342,11 @@ impl<T: Config> Pallet<T> {
 		payload: &Message,
 		signature: &T::Signature,
 	) -> TransactionValidity {
-		// Check if this message can be processed next by checking its nonce
-		let nonce = <IncomingNonce<T>>::get(payload.network);
-		if payload.nonce != nonce.saturating_add(1) {
-			return Err(InvalidTransaction::Custom(1).into())
+		// Check if this message is not processed yet by checking its nonce, the following
+		// messages wait in the pool for the previous ones
+		let next_nonce = <IncomingNonce<T>>::get(payload.network).saturating_add(1);
+		if payload.nonce < next_nonce {
+			return Err(InvalidTransaction::Stale.into())
 		}
 
 		// Find who all signed this payload
@@ -295,11 +374,14 @@ impl<T: Config> Pallet<T> {
 			return Err(InvalidTransaction::BadSigner.into())
 		}
 
-		ValidTransaction::with_tag_prefix("thea")
+		let mut transaction = ValidTransaction::with_tag_prefix("thea")
 			.and_provides([signature])
-			.longevity(3)
-			.propagate(true)
-			.build()
+			.and_provides((payload.network, payload.nonce));
+		if payload.nonce > next_nonce {
+			transaction =
+				transaction.and_requires((payload.network, payload.nonce.saturating_sub(1)));
+		}
+		transaction.longevity(INCOMING_MESSAGE_LONGEVITY).propagate(true).build()
 	}
 
 	/// Return the current active validator set for all networks
@@ -323,6 +405,7 @@ impl<T: Config> Pallet<T> {
 		new: BoundedVec<T::TheaId, T::MaxAuthorities>,
 		queued: BoundedVec<T::TheaId, T::MaxAuthorities>,
 	) {
+		<SessionAuthorities<T>>::put((new.clone(), queued.clone()));
 		if new == queued {
 			// Don't do anything if there is not change in new and queued validators
 			return
@@ -333,7 +416,8 @@ impl<T: Config> Pallet<T> {
 		> {
 			let mut map = sp_std::collections::btree_map::BTreeMap::new();
 			for auth in list {
-				if let Some(network) = <NetworkPreference<T>>::get(auth) {
+				// TODO: Make it an offence to not provide network as part of next version
+				for network in <NetworkPreference<T>>::get(auth) {
 					map.entry(network)
 						.and_modify(|list: &mut BoundedVec<T::TheaId, T::MaxAuthorities>| {
 							// Force push is fine as the subset of network will be less than
@@ -343,8 +427,6 @@ impl<T: Config> Pallet<T> {
 						.or_insert(BoundedVec::truncate_from(sp_std::vec::Vec::from([
 							auth.clone()
 						])));
-				} else {
-					// TODO: Make it an offence to not provide network as part of next version
 				}
 			}
 			map
@@ -382,11 +464,16 @@ impl<T: Config> Pallet<T> {
 	fn initialize_authorities(authorities: &[T::TheaId]) -> Result<(), ()> {
 		let id = GENESIS_AUTHORITY_SET_ID;
 		<ValidatorSetId<T>>::put(id);
+		let session_authorities = BoundedVec::truncate_from(authorities.to_vec());
+		<SessionAuthorities<T>>::put((session_authorities.clone(), session_authorities));
 
 		<Authorities<T>>::insert(1, id, BoundedVec::truncate_from(authorities.to_vec()));
 		for auth in authorities {
 			// Everyone is assigned to one on genesis.
-			<NetworkPreference<T>>::insert(auth.clone(), 1);
+			<NetworkPreference<T>>::insert(
+				auth.clone(),
+				BoundedBTreeSet::try_from(BTreeSet::from([1])).unwrap_or_default(),
+			);
 		}
 		Ok(())
 	}
@@ -396,7 +483,53 @@ impl<T: Config> Pallet<T> {
 	}
 
 	pub fn network(auth: T::TheaId) -> Option<Network> {
-		<NetworkPreference<T>>::get(auth)
+		<NetworkPreference<T>>::get(auth).into_iter().next()
+	}
+
+	pub fn networks(auth: T::TheaId) -> Vec<Network> {
+		<NetworkPreference<T>>::get(auth).into_iter().collect()
+	}
+
+	/// Migrates the network preferences from a single network to a set of networks and seeds the
+	/// session authorities from the authorities of the networks until the next session.
+	fn migrate_network_preferences() -> Weight {
+		if StorageVersion::get::<Pallet<T>>() >= STORAGE_VERSION {
+			return T::DbWeight::get().reads(1)
+		}
+		let mut translated = 0u64;
+		<NetworkPreference<T>>::translate::<Network, _>(|_, network| {
+			translated = translated.saturating_add(1);
+			BoundedBTreeSet::try_from(BTreeSet::from([network])).ok()
+		});
+		let id = Self::validator_set_id();
+		let mut reads = translated.saturating_add(2);
+		let mut current = Vec::new();
+		for (_, set_id, authorities) in <Authorities<T>>::iter() {
+			reads = reads.saturating_add(1);
+			if set_id == id {
+				for auth in authorities {
+					if !current.contains(&auth) {
+						current.push(auth);
+					}
+				}
+			}
+		}
+		let mut queued = Vec::new();
+		for authorities in <NextAuthorities<T>>::iter_values() {
+			reads = reads.saturating_add(1);
+			for auth in authorities {
+				if !queued.contains(&auth) {
+					queued.push(auth);
+				}
+			}
+		}
+		<SessionAuthorities<T>>::put((
+			BoundedVec::truncate_from(current),
+			BoundedVec::truncate_from(queued),
+		));
+		STORAGE_VERSION.put::<Pallet<T>>();
+		frame_support::log::info!(target: "runtime::thea", "Migrated {} network preferences", translated);
+		T::DbWeight::get().reads_writes(reads, translated.saturating_add(2))
 	}
 
 	#[allow(clippy::result_unit_err)]
@@ -0,0 +1,258 @@
+// This file is part of Polkadex.
+//
+// Copyright (c) 2023 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+use crate::{
+	mock::{new_test_ext, RuntimeOrigin as Origin, Test, *},
+	Authorities, Error, IncomingNonce, NetworkPreference, NextAuthorities, SessionAuthorities,
+	ValidatorSetId, INCOMING_MESSAGE_LONGEVITY,
+};
+use frame_support::{
+	assert_noop, assert_ok, storage::unhashed, traits::StorageVersion, BoundedBTreeSet, BoundedVec,
+};
+use parity_scale_codec::Encode;
+use polkadex_primitives::utils::prepare_bitmap;
+use sp_core::Pair;
+use sp_runtime::{
+	traits::ValidateUnsigned,
+	transaction_validity::{InvalidTransaction, TransactionSource},
+};
+use sp_std::collections::btree_set::BTreeSet;
+use thea_primitives::{
+	crypto::Pair as TheaPair, AuthoritySignature, Message, Network, NATIVE_NETWORK,
+	PARACHAIN_NETWORK,
+};
+
+/// Registers three authorities for the parachain network and returns their key pairs.
+fn register_authorities() -> Vec<TheaPair> {
+	let pairs: Vec<TheaPair> = (1..=3u8).map(|seed| TheaPair::from_seed(&[seed; 32])).collect();
+	let authorities = pairs.iter().map(|pair| pair.public()).collect::<Vec<_>>();
+	<Authorities<Test>>::insert(PARACHAIN_NETWORK, 0, BoundedVec::truncate_from(authorities));
+	pairs
+}
+
+fn message(nonce: u64) -> Message {
+	Message {
+		block_no: 10,
+		nonce,
+		data: vec![1, 2, 3],
+		network: PARACHAIN_NETWORK,
+		is_key_change: false,
+		validator_set_id: 0,
+		validator_set_len: 3,
+	}
+}
+
+/// Returns the bitmap and aggregated signature of the message signed by every authority.
+fn sign(pairs: &[TheaPair], message: &Message) -> (Vec<u128>, AuthoritySignature) {
+	let payload = message.encode();
+	let signature = pairs
+		.iter()
+		.map(|pair| bls_primitives::Signature::from(pair.sign(&payload)))
+		.reduce(|aggregate, signature| aggregate.add_signature(&signature).unwrap())
+		.unwrap();
+	let bitmap = prepare_bitmap(&(0..pairs.len()).collect(), pairs.len()).unwrap();
+	(bitmap, signature.into())
+}
+
+fn incoming_message_call(pairs: &[TheaPair], nonce: u64) -> crate::Call<Test> {
+	let payload = message(nonce);
+	let (bitmap, signature) = sign(pairs, &payload);
+	crate::Call::incoming_message { bitmap, payload, signature }
+}
+
+#[test]
+fn test_incoming_messages_wait_for_the_previous_nonces() {
+	new_test_ext().execute_with(|| {
+		let pairs = register_authorities();
+
+		let first =
+			Thea::validate_unsigned(TransactionSource::External, &incoming_message_call(&pairs, 1))
+				.unwrap();
+		assert!(first.requires.is_empty());
+		assert!(first.provides.contains(&("thea", (PARACHAIN_NETWORK, 1u64)).encode()));
+		assert_eq!(first.longevity, INCOMING_MESSAGE_LONGEVITY);
+
+		// A future nonce is valid once the previous nonce is provided
+		let third =
+			Thea::validate_unsigned(TransactionSource::External, &incoming_message_call(&pairs, 3))
+				.unwrap();
+		assert_eq!(third.requires, vec![("thea", (PARACHAIN_NETWORK, 2u64)).encode()]);
+		assert!(third.provides.contains(&("thea", (PARACHAIN_NETWORK, 3u64)).encode()));
+		assert_eq!(third.longevity, INCOMING_MESSAGE_LONGEVITY);
+	});
+}
+
+#[test]
+fn test_incoming_messages_are_processed_in_the_order_of_their_nonces() {
+	new_test_ext().execute_with(|| {
+		let pairs = register_authorities();
+		let (bitmap, signature) = sign(&pairs, &message(2));
+		assert_noop!(
+			Thea::incoming_message(Origin::none(), bitmap, message(2), signature),
+			Error::<Test>::MessageNonce
+		);
+
+		for nonce in 1..=2 {
+			let (bitmap, signature) = sign(&pairs, &message(nonce));
+			assert_ok!(Thea::incoming_message(Origin::none(), bitmap, message(nonce), signature));
+		}
+		assert_eq!(<IncomingNonce<Test>>::get(PARACHAIN_NETWORK), 2);
+
+		// Processed nonces are stale
+		assert_eq!(
+			Thea::validate_unsigned(TransactionSource::External, &incoming_message_call(&pairs, 2)),
+			Err(InvalidTransaction::Stale.into())
+		);
+		assert!(Thea::validate_unsigned(
+			TransactionSource::External,
+			&incoming_message_call(&pairs, 3)
+		)
+		.is_ok());
+	});
+}
+
+#[test]
+fn test_incoming_messages_require_the_authorities_signature() {
+	new_test_ext().execute_with(|| {
+		let pairs = register_authorities();
+		let (bitmap, signature) = sign(&pairs, &message(2));
+		let call = crate::Call::incoming_message { bitmap, payload: message(1), signature };
+		assert_eq!(
+			Thea::validate_unsigned(TransactionSource::External, &call),
+			Err(InvalidTransaction::BadSigner.into())
+		);
+	});
+}
+
+/// Returns the update of the networks of the authority signed by the given key pair.
+fn network_pref_call(
+	authority: &TheaPair,
+	signer: &TheaPair,
+	networks: &[Network],
+) -> crate::Call<Test> {
+	let networks =
+		BoundedBTreeSet::try_from(BTreeSet::from_iter(networks.iter().copied())).unwrap();
+	let signature = signer.sign(&Thea::network_pref_payload(&networks));
+	crate::Call::update_network_pref { authority: authority.public(), networks, signature }
+}
+
+#[test]
+fn test_network_preferences_are_accepted_from_the_session_authorities() {
+	new_test_ext().execute_with(|| {
+		let pairs: Vec<TheaPair> = (1..=3u8).map(|seed| TheaPair::from_seed(&[seed; 32])).collect();
+		<SessionAuthorities<Test>>::put((
+			BoundedVec::truncate_from(vec![pairs[0].public()]),
+			BoundedVec::truncate_from(vec![pairs[1].public()]),
+		));
+
+		// Authorities of the current and the queued sessions, even without network yet
+		for pair in &pairs[..2] {
+			let valid = Thea::validate_unsigned(
+				TransactionSource::External,
+				&network_pref_call(pair, pair, &[PARACHAIN_NETWORK]),
+			)
+			.unwrap();
+			assert_eq!(valid.provides, vec![("thea_network_pref", (pair.public(), 0u64)).encode()]);
+		}
+		assert_eq!(
+			Thea::validate_unsigned(
+				TransactionSource::External,
+				&network_pref_call(&pairs[2], &pairs[2], &[PARACHAIN_NETWORK])
+			),
+			Err(InvalidTransaction::BadSigner.into())
+		);
+	});
+}
+
+#[test]
+fn test_network_preferences_require_a_foreign_network_and_the_authority_signature() {
+	new_test_ext().execute_with(|| {
+		let pairs: Vec<TheaPair> = (1..=2u8).map(|seed| TheaPair::from_seed(&[seed; 32])).collect();
+		<SessionAuthorities<Test>>::put((
+			BoundedVec::truncate_from(pairs.iter().map(|pair| pair.public()).collect()),
+			BoundedVec::default(),
+		));
+		assert_eq!(
+			Thea::validate_unsigned(
+				TransactionSource::External,
+				&network_pref_call(&pairs[0], &pairs[0], &[PARACHAIN_NETWORK, NATIVE_NETWORK])
+			),
+			Err(InvalidTransaction::Call.into())
+		);
+		assert_eq!(
+			Thea::validate_unsigned(
+				TransactionSource::External,
+				&network_pref_call(&pairs[0], &pairs[1], &[PARACHAIN_NETWORK])
+			),
+			Err(InvalidTransaction::BadProof.into())
+		);
+
+		// The signature of a previous validator set can't be replayed
+		let call = network_pref_call(&pairs[0], &pairs[0], &[PARACHAIN_NETWORK]);
+		assert!(Thea::validate_unsigned(TransactionSource::External, &call).is_ok());
+		<ValidatorSetId<Test>>::put(1);
+		assert_eq!(
+			Thea::validate_unsigned(TransactionSource::External, &call),
+			Err(InvalidTransaction::BadProof.into())
+		);
+	});
+}
+
+#[test]
+fn test_network_preferences_are_migrated_to_sets_of_networks() {
+	new_test_ext().execute_with(|| {
+		let pairs: Vec<TheaPair> = (1..=3u8).map(|seed| TheaPair::from_seed(&[seed; 32])).collect();
+		StorageVersion::new(0).put::<Thea>();
+		// Before the migration an authority served a single network
+		for pair in &pairs[..2] {
+			unhashed::put(
+				&<NetworkPreference<Test>>::hashed_key_for(pair.public()),
+				&PARACHAIN_NETWORK,
+			);
+		}
+		<ValidatorSetId<Test>>::put(1);
+		<Authorities<Test>>::insert(
+			PARACHAIN_NETWORK,
+			0,
+			BoundedVec::truncate_from(vec![pairs[2].public()]),
+		);
+		<Authorities<Test>>::insert(
+			PARACHAIN_NETWORK,
+			1,
+			BoundedVec::truncate_from(vec![pairs[0].public(), pairs[1].public()]),
+		);
+		<NextAuthorities<Test>>::insert(
+			PARACHAIN_NETWORK,
+			BoundedVec::truncate_from(vec![pairs[1].public()]),
+		);
+
+		Thea::migrate_network_preferences();
+		assert_eq!(StorageVersion::get::<Thea>(), StorageVersion::new(1));
+		for pair in &pairs[..2] {
+			assert_eq!(Thea::networks(pair.public()), vec![PARACHAIN_NETWORK]);
+		}
+		assert!(Thea::networks(pairs[2].public()).is_empty());
+		let (current, queued) = <SessionAuthorities<Test>>::get();
+		assert_eq!(current.into_inner(), vec![pairs[0].public(), pairs[1].public()]);
+		assert_eq!(queued.into_inner(), vec![pairs[1].public()]);
+
+		// The migration only runs once
+		<NetworkPreference<Test>>::remove(pairs[0].public());
+		Thea::migrate_network_preferences();
+		assert!(Thea::networks(pairs[0].public()).is_empty());
+	});
+}
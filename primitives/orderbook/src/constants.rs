@@ -24,6 +24,15 @@ use polkadex_primitives::Balance;
 pub const POLKADEX_MAINNET_SS58: u16 = 88;
 
 pub const MAX_WITHDRAWALS_PER_SNAPSHOT: u8 = 20;
+/// Prefix of the reserved balance keys in the orderbook state.
+pub const RESERVED_BALANCE_PREFIX: &[u8; 24] = b"OrderbookReservedBalance";
+/// Prefix of the keys of the reserved balance part held by open orders in the orderbook state.
+pub const ORDER_RESERVED_BALANCE_PREFIX: &[u8; 29] = b"OrderbookOrderReservedBalance";
+/// Prefix of the open order keys in the orderbook state, followed by the trading pair and the
+/// order identifier.
+pub const OPEN_ORDER_PREFIX: &[u8; 18] = b"OrderbookOpenOrder";
+/// Prefix of the last trade price keys of the trading pairs in the orderbook state.
+pub const LAST_TRADE_PRICE_PREFIX: &[u8; 23] = b"OrderbookLastTradePrice";
 pub const UNIT_BALANCE: Balance = 1_000_000_000_000_u128;
 /// Range of QTY: 0.00000001 to 10,000,000 UNITs
 pub const MIN_QTY: Balance = UNIT_BALANCE / 10000000;
@@ -0,0 +1,330 @@
+// This file is part of Polkadex.
+//
+// Copyright (c) 2023 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Layout of the orderbook state trie.
+//!
+//! The orderbook client stores its state with `reference_trie::ExtensionLayout`, which is only
+//! available with std. [`StateLayout`] hashes and encodes the trie nodes the same way, so the
+//! runtime can verify proofs of the orderbook state.
+
+use parity_scale_codec::{Compact, Decode, Encode, Error as CodecError, Input};
+use sp_core::H256;
+use sp_std::{borrow::Borrow, marker::PhantomData, ops::Range, vec, vec::Vec};
+use trie_db::{
+	nibble_ops,
+	node::{NibbleSlicePlan, NodeHandlePlan, NodePlan, Value, ValuePlan},
+	ChildReference, Hasher, NodeCodec, TrieLayout,
+};
+
+const EMPTY_TRIE: u8 = 0;
+const LEAF_NODE_OFFSET: u8 = 1;
+const EXTENSION_NODE_OFFSET: u8 = 128;
+const BRANCH_NODE_NO_VALUE: u8 = 254;
+const BRANCH_NODE_WITH_VALUE: u8 = 255;
+const LEAF_NODE_LAST: u8 = EXTENSION_NODE_OFFSET - 1;
+const EXTENSION_NODE_LAST: u8 = BRANCH_NODE_NO_VALUE - 1;
+const BITMAP_LENGTH: usize = 2;
+
+/// Keccak-256 hasher of the orderbook state trie nodes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct StateHasher;
+
+impl Hasher for StateHasher {
+	type Out = H256;
+	type StdHasher = hash256_std_hasher::Hash256StdHasher;
+	const LENGTH: usize = 32;
+
+	fn hash(data: &[u8]) -> H256 {
+		sp_io::hashing::keccak_256(data).into()
+	}
+}
+
+/// Layout of the orderbook state trie, with extension nodes and inline values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct StateLayout;
+
+impl TrieLayout for StateLayout {
+	const USE_EXTENSION: bool = true;
+	const ALLOW_EMPTY: bool = false;
+	const MAX_INLINE_VALUE: Option<u32> = None;
+
+	type Hash = StateHasher;
+	type Codec = StateNodeCodec<StateHasher>;
+}
+
+/// Codec of the orderbook state trie nodes.
+///
+/// Nodes start with a one byte header: `0` for the empty node, `1..=127` for a leaf and
+/// `128..=253` for an extension, offset by the number of nibbles of their partial key, and
+/// `254`/`255` for a branch without/with a value, followed by a bitmap of its children.
+#[derive(Default, Clone)]
+pub struct StateNodeCodec<H>(PhantomData<H>);
+
+/// Header of an encoded node.
+enum NodeHeader {
+	Null,
+	Branch(bool),
+	Extension(usize),
+	Leaf(usize),
+}
+
+impl Decode for NodeHeader {
+	fn decode<I: Input>(input: &mut I) -> Result<Self, CodecError> {
+		Ok(match input.read_byte()? {
+			EMPTY_TRIE => NodeHeader::Null,
+			BRANCH_NODE_NO_VALUE => NodeHeader::Branch(false),
+			BRANCH_NODE_WITH_VALUE => NodeHeader::Branch(true),
+			i @ LEAF_NODE_OFFSET..=LEAF_NODE_LAST =>
+				NodeHeader::Leaf((i - LEAF_NODE_OFFSET) as usize),
+			i @ EXTENSION_NODE_OFFSET..=EXTENSION_NODE_LAST =>
+				NodeHeader::Extension((i - EXTENSION_NODE_OFFSET) as usize),
+		})
+	}
+}
+
+/// Input over an encoded node, keeping track of the ranges read.
+struct ByteSliceInput<'a> {
+	data: &'a [u8],
+	offset: usize,
+}
+
+impl<'a> ByteSliceInput<'a> {
+	fn new(data: &'a [u8]) -> Self {
+		ByteSliceInput { data, offset: 0 }
+	}
+
+	fn take(&mut self, count: usize) -> Result<Range<usize>, CodecError> {
+		let end = self
+			.offset
+			.checked_add(count)
+			.filter(|end| *end <= self.data.len())
+			.ok_or("out of data")?;
+		let range = self.offset..end;
+		self.offset = end;
+		Ok(range)
+	}
+}
+
+impl<'a> Input for ByteSliceInput<'a> {
+	fn remaining_len(&mut self) -> Result<Option<usize>, CodecError> {
+		Ok(Some(self.data.len().saturating_sub(self.offset)))
+	}
+
+	fn read(&mut self, into: &mut [u8]) -> Result<(), CodecError> {
+		let range = self.take(into.len())?;
+		into.copy_from_slice(&self.data[range]);
+		Ok(())
+	}
+
+	fn read_byte(&mut self) -> Result<u8, CodecError> {
+		let range = self.take(1)?;
+		Ok(self.data[range.start])
+	}
+}
+
+/// Encodes the header of a leaf or an extension followed by its partial key.
+fn partial_to_key(partial: impl Iterator<Item = u8>, nibble_count: usize, offset: u8) -> Vec<u8> {
+	let mut output = Vec::with_capacity(1 + nibble_count / nibble_ops::NIBBLE_PER_BYTE);
+	output.push(offset + nibble_count as u8);
+	output.extend(partial);
+	output
+}
+
+/// Decodes the partial key of a leaf or an extension.
+fn decode_partial(
+	input: &mut ByteSliceInput,
+	nibble_count: usize,
+) -> Result<NibbleSlicePlan, CodecError> {
+	let partial = input.take(nibble_count.div_ceil(nibble_ops::NIBBLE_PER_BYTE))?;
+	Ok(NibbleSlicePlan::new(partial, nibble_ops::number_padding(nibble_count)))
+}
+
+/// Decodes a child reference, which is a hash or an inlined node.
+fn decode_child<H: Hasher>(input: &mut ByteSliceInput) -> Result<NodeHandlePlan, CodecError> {
+	let count = <Compact<u32>>::decode(input)?.0 as usize;
+	let range = input.take(count)?;
+	Ok(if count == H::LENGTH { NodeHandlePlan::Hash(range) } else { NodeHandlePlan::Inline(range) })
+}
+
+/// Encodes a child reference.
+fn encode_child<HO: AsRef<[u8]>>(child: &ChildReference<HO>, output: &mut Vec<u8>) {
+	match child {
+		ChildReference::Hash(hash) => hash.as_ref().encode_to(output),
+		ChildReference::Inline(inline_data, len) => inline_data.as_ref()[..*len].encode_to(output),
+	}
+}
+
+impl<H: Hasher> NodeCodec for StateNodeCodec<H> {
+	type Error = CodecError;
+	type HashOut = H::Out;
+
+	fn hashed_null_node() -> H::Out {
+		H::hash(<Self as NodeCodec>::empty_node())
+	}
+
+	fn decode_plan(data: &[u8]) -> Result<NodePlan, Self::Error> {
+		let mut input = ByteSliceInput::new(data);
+		match NodeHeader::decode(&mut input)? {
+			NodeHeader::Null => Ok(NodePlan::Empty),
+			NodeHeader::Branch(has_value) => {
+				let bitmap_range = input.take(BITMAP_LENGTH)?;
+				let bitmap =
+					u16::from_le_bytes([data[bitmap_range.start], data[bitmap_range.start + 1]]);
+				let value = if has_value {
+					let count = <Compact<u32>>::decode(&mut input)?.0 as usize;
+					Some(ValuePlan::Inline(input.take(count)?))
+				} else {
+					None
+				};
+				let mut children: [Option<NodeHandlePlan>; nibble_ops::NIBBLE_LENGTH] =
+					Default::default();
+				for (index, child) in children.iter_mut().enumerate() {
+					if bitmap & (1 << index) != 0 {
+						*child = Some(decode_child::<H>(&mut input)?);
+					}
+				}
+				Ok(NodePlan::Branch { value, children })
+			},
+			NodeHeader::Extension(nibble_count) => {
+				let partial = decode_partial(&mut input, nibble_count)?;
+				let child = decode_child::<H>(&mut input)?;
+				Ok(NodePlan::Extension { partial, child })
+			},
+			NodeHeader::Leaf(nibble_count) => {
+				let partial = decode_partial(&mut input, nibble_count)?;
+				let count = <Compact<u32>>::decode(&mut input)?.0 as usize;
+				Ok(NodePlan::Leaf { partial, value: ValuePlan::Inline(input.take(count)?) })
+			},
+		}
+	}
+
+	fn is_empty_node(data: &[u8]) -> bool {
+		data == <Self as NodeCodec>::empty_node()
+	}
+
+	fn empty_node() -> &'static [u8] {
+		&[EMPTY_TRIE]
+	}
+
+	fn leaf_node(partial: impl Iterator<Item = u8>, number_nibble: usize, value: Value) -> Vec<u8> {
+		let mut output = partial_to_key(partial, number_nibble, LEAF_NODE_OFFSET);
+		match value {
+			Value::Inline(value) => value.encode_to(&mut output),
+			// Values are always inlined, `MAX_INLINE_VALUE` is not set
+			Value::Node(hash) => hash.encode_to(&mut output),
+		}
+		output
+	}
+
+	fn extension_node(
+		partial: impl Iterator<Item = u8>,
+		number_nibble: usize,
+		child: ChildReference<Self::HashOut>,
+	) -> Vec<u8> {
+		let mut output = partial_to_key(partial, number_nibble, EXTENSION_NODE_OFFSET);
+		encode_child(&child, &mut output);
+		output
+	}
+
+	fn branch_node(
+		children: impl Iterator<Item = impl Borrow<Option<ChildReference<Self::HashOut>>>>,
+		value: Option<Value>,
+	) -> Vec<u8> {
+		let mut output = vec![0; BITMAP_LENGTH + 1];
+		output[0] = match value {
+			Some(Value::Inline(value)) | Some(Value::Node(value)) => {
+				value.encode_to(&mut output);
+				BRANCH_NODE_WITH_VALUE
+			},
+			None => BRANCH_NODE_NO_VALUE,
+		};
+		let mut bitmap = 0u16;
+		for (index, child) in children.enumerate() {
+			if let Some(child) = child.borrow() {
+				encode_child(child, &mut output);
+				bitmap |= 1 << index;
+			}
+		}
+		output[1..=BITMAP_LENGTH].copy_from_slice(&bitmap.to_le_bytes());
+		output
+	}
+
+	fn branch_node_nibbled(
+		_partial: impl Iterator<Item = u8>,
+		_number_nibble: usize,
+		_children: impl Iterator<Item = impl Borrow<Option<ChildReference<Self::HashOut>>>>,
+		_value: Option<Value>,
+	) -> Vec<u8> {
+		unreachable!("Branches have no partial key with extension nodes")
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::layout::{StateHasher, StateLayout};
+	use memory_db::{HashKey, MemoryDB};
+	use reference_trie::{ExtensionLayout, RefHasher};
+	use trie_db::{
+		proof::{generate_proof, verify_proof},
+		TrieDBMutBuilder, TrieMut,
+	};
+
+	#[test]
+	pub fn test_state_layout_matches_reference_layout() {
+		let mut memory_db: MemoryDB<RefHasher, HashKey<RefHasher>, Vec<u8>> = Default::default();
+		let mut reference_root = Default::default();
+		let mut state_db: MemoryDB<StateHasher, HashKey<StateHasher>, Vec<u8>> = Default::default();
+		let mut state_root = Default::default();
+		// Keys sharing prefixes of odd and even lengths, with short and long values
+		let entries: Vec<(Vec<u8>, Vec<u8>)> = vec![
+			(vec![1, 2, 3], vec![1]),
+			(vec![1, 2, 4], vec![2; 40]),
+			(vec![1, 0x23], vec![3]),
+			(vec![1, 0x24, 5], vec![4; 20]),
+			(vec![2; 40], vec![5; 100]),
+			(vec![2, 2, 2], vec![6]),
+		];
+		{
+			let mut reference =
+				TrieDBMutBuilder::<ExtensionLayout>::new(&mut memory_db, &mut reference_root)
+					.build();
+			let mut state =
+				TrieDBMutBuilder::<StateLayout>::new(&mut state_db, &mut state_root).build();
+			for (key, value) in &entries {
+				reference.insert(key, value).unwrap();
+				state.insert(key, value).unwrap();
+			}
+			reference.commit();
+			state.commit();
+		}
+		assert_eq!(state_root.0, reference_root);
+
+		// Proofs generated by the client are verified with the state layout
+		let keys = [vec![1, 2, 4], vec![2, 2, 2], vec![1, 2, 5]];
+		let proof =
+			generate_proof::<_, ExtensionLayout, _, _>(&memory_db, &reference_root, &keys).unwrap();
+		let mut items = vec![
+			(keys[0].clone(), Some(vec![2; 40])),
+			(keys[1].clone(), Some(vec![6])),
+			(keys[2].clone(), None),
+		];
+		assert!(verify_proof::<StateLayout, _, _, _>(&state_root, &proof, &items).is_ok());
+		items[1].1 = Some(vec![7]);
+		assert!(verify_proof::<StateLayout, _, _, _>(&state_root, &proof, &items).is_err());
+	}
+}
@@ -43,6 +43,8 @@ use sp_runtime::traits::IdentifyAccount;
 use sp_std::vec::Vec;
 
 pub mod constants;
+pub mod layout;
+pub mod proof;
 pub mod types;
 
 #[cfg(feature = "std")]
@@ -179,6 +181,8 @@ pub struct SnapshotSummary<AccountId: Clone + Codec> {
 	/// Aggregated signature.
 	pub aggregate_signature: Option<bls_primitives::Signature>,
 	pub state_version: u16,
+	/// Trading fees collected since the previous snapshot.
+	pub trading_fees: Vec<Fees>,
 }
 
 impl<AccountId: Clone + Codec> Default for SnapshotSummary<AccountId> {
@@ -195,6 +199,7 @@ impl<AccountId: Clone + Codec> Default for SnapshotSummary<AccountId> {
 			withdrawals: Vec::new(),
 			aggregate_signature: None,
 			state_version: 0,
+			trading_fees: Vec::new(),
 		}
 	}
 }
@@ -212,12 +217,13 @@ impl<AccountId: Clone + Codec> SnapshotSummary<AccountId> {
 		Ok(())
 	}
 
-	/// Collects and returns the collection of fees fro for all withdrawals.
+	/// Collects and returns the collection of fees for all withdrawals and trades.
 	pub fn get_fees(&self) -> Vec<Fees> {
 		let mut fees = Vec::new();
 		for withdrawal in &self.withdrawals {
 			fees.push(Fees { asset: withdrawal.asset, amount: withdrawal.fees });
 		}
+		fees.extend(self.trading_fees.iter().cloned());
 		fees
 	}
 
@@ -248,14 +254,17 @@ impl<AccountId: Clone + Codec> SnapshotSummary<AccountId> {
 			self.worker_nonce,
 			self.state_chunk_hashes.clone(),
 			self.withdrawals.clone(),
+			self.trading_fees.clone(),
 		);
-
 		sp_io::hashing::blake2_256(&data.encode())
 	}
 }
 
 sp_api::decl_runtime_apis! {
 	/// APIs necessary for Orderbook.
+	///
+	/// Version 2 adds `get_orderbook_operator_keys`.
+	#[api_version(2)]
 	pub trait ObApi
 	{
 		/// Return the current active Orderbook validator set.
@@ -283,6 +292,10 @@ sp_api::decl_runtime_apis! {
 		/// Returns Public Key of Whitelisted Orderbook Operator.
 		fn get_orderbook_opearator_key() -> Option<sp_core::ecdsa::Public>;
 
+		/// Returns the Public Keys the Orderbook Operator messages are accepted from, the
+		/// current key first followed by the previous key during a rotation grace period.
+		fn get_orderbook_operator_keys() -> Vec<sp_core::ecdsa::Public>;
+
 		/// Returns snapshot generation intervals.
 		fn get_snapshot_generation_intervals() -> (u64,BlockNumber);
 
@@ -0,0 +1,146 @@
+// This file is part of Polkadex.
+//
+// Copyright (c) 2023 Polkadex oü.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Merkle proofs of the orderbook state against the `state_root` of a snapshot.
+//!
+//! Proofs are generated by the orderbook client in the `trie_db` compact format. The verifier is
+//! generic over the trie layout, the runtime verifies them with `layout::StateLayout` and the
+//! client with `reference_trie::ExtensionLayout`.
+
+use crate::types::AccountAsset;
+use parity_scale_codec::{Decode, Encode};
+use rust_decimal::Decimal;
+use scale_info::TypeInfo;
+use sp_core::ConstU32;
+use sp_runtime::BoundedVec;
+use sp_std::vec::Vec;
+use trie_db::{proof::verify_proof, TrieHash, TrieLayout};
+
+/// Maximum number of trie nodes of a balance proof.
+pub const MAX_BALANCE_PROOF_NODES: u32 = 128;
+
+/// Maximum size of an encoded trie node of a balance proof, a branch with sixteen hashed children
+/// takes 531 bytes.
+pub const MAX_BALANCE_PROOF_NODE_SIZE: u32 = 1024;
+
+/// Trie nodes of a balance proof.
+pub type BalanceProofNodes = BoundedVec<
+	BoundedVec<u8, ConstU32<MAX_BALANCE_PROOF_NODE_SIZE>>,
+	ConstU32<MAX_BALANCE_PROOF_NODES>,
+>;
+
+/// Proof of the balances of an account asset at a snapshot.
+#[derive(Clone, Debug, Encode, Decode, TypeInfo, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+pub struct BalanceProof {
+	/// Snapshot the proof is generated at.
+	pub snapshot_id: u64,
+	/// Account asset.
+	pub account_asset: AccountAsset,
+	/// Free balance, `None` if the state has no entry for it.
+	pub free: Option<Decimal>,
+	/// Balance reserved by open orders, `None` if the state has no entry for it.
+	pub reserved: Option<Decimal>,
+	/// Trie nodes proving the free and reserved balance entries.
+	pub proof: BalanceProofNodes,
+}
+
+impl BalanceProof {
+	/// Returns the free and reserved balances.
+	pub fn total(&self) -> Decimal {
+		self.free.unwrap_or_default().saturating_add(self.reserved.unwrap_or_default())
+	}
+
+	/// Verifies the proof against the state root of the snapshot.
+	///
+	/// # Parameters
+	///
+	/// * `state_root`: State root of the snapshot, taken from the signed snapshot summary.
+	///
+	/// # Returns
+	///
+	/// * `bool`: Whether the balances are proven.
+	pub fn verify<L: TrieLayout>(&self, state_root: &TrieHash<L>) -> bool {
+		let items = [
+			(self.account_asset.encode(), self.free.map(|balance| balance.encode())),
+			(
+				self.account_asset.reserved_balance_key(),
+				self.reserved.map(|balance| balance.encode()),
+			),
+		];
+		let proof: Vec<Vec<u8>> = self.proof.iter().map(|node| node.to_vec()).collect();
+		verify_proof::<L, _, _, _>(state_root, &proof, &items).is_ok()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{layout::StateLayout, proof::BalanceProof, types::AccountAsset};
+	use memory_db::{HashKey, MemoryDB};
+	use parity_scale_codec::Encode;
+	use polkadex_primitives::{AccountId, AssetId};
+	use reference_trie::{ExtensionLayout, RefHasher};
+	use rust_decimal::Decimal;
+	use trie_db::{proof::generate_proof, TrieDBMutBuilder, TrieMut};
+
+	#[test]
+	pub fn test_balance_proof_is_verified_against_state_root() {
+		let mut memory_db: MemoryDB<RefHasher, HashKey<RefHasher>, Vec<u8>> = Default::default();
+		let mut state_root = Default::default();
+		let account_asset = AccountAsset::new(AccountId::new([1; 32]), AssetId::Polkadex);
+		let other = AccountAsset::new(AccountId::new([2; 32]), AssetId::Polkadex);
+		{
+			let mut trie =
+				TrieDBMutBuilder::<ExtensionLayout>::new(&mut memory_db, &mut state_root).build();
+			trie.insert(&account_asset.encode(), &Decimal::from(10).encode()).unwrap();
+			trie.insert(&other.encode(), &Decimal::from(5).encode()).unwrap();
+			trie.insert(&other.reserved_balance_key(), &Decimal::from(1).encode()).unwrap();
+			trie.commit();
+		}
+		let keys = [account_asset.encode(), account_asset.reserved_balance_key()];
+		let proof = generate_proof::<_, ExtensionLayout, _, _>(&memory_db, &state_root, &keys)
+			.unwrap()
+			.into_iter()
+			.map(|node| node.try_into().unwrap())
+			.collect::<Vec<_>>()
+			.try_into()
+			.unwrap();
+		let mut balance_proof = BalanceProof {
+			snapshot_id: 1,
+			account_asset,
+			free: Some(Decimal::from(10)),
+			reserved: None,
+			proof,
+		};
+		assert!(balance_proof.verify::<ExtensionLayout>(&state_root));
+		assert!(balance_proof.verify::<StateLayout>(&state_root.into()));
+		assert_eq!(balance_proof.total(), Decimal::from(10));
+
+		// Tampered balances are rejected
+		balance_proof.free = Some(Decimal::from(11));
+		assert!(!balance_proof.verify::<ExtensionLayout>(&state_root));
+		assert!(!balance_proof.verify::<StateLayout>(&state_root.into()));
+		balance_proof.free = Some(Decimal::from(10));
+		balance_proof.reserved = Some(Decimal::from(1));
+		assert!(!balance_proof.verify::<ExtensionLayout>(&state_root));
+
+		// The proof does not hold against another state root
+		balance_proof.reserved = None;
+		assert!(!balance_proof.verify::<ExtensionLayout>(&[0; 32]));
+	}
+}
@@ -16,9 +16,9 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::types::AccountAsset;
+use crate::types::{AccountAsset, Order};
 use parity_scale_codec::{Decode, Encode};
-use polkadex_primitives::{AccountId, BlockNumber};
+use polkadex_primitives::{ocex::ProxyPermissions, AccountId, BlockNumber};
 use rust_decimal::Decimal;
 use serde_with::{json::JsonString, serde_as};
 use std::collections::BTreeMap;
@@ -43,4 +43,15 @@ pub struct ObRecoveryState {
 	pub worker_nonce: u64,
 	/// State version
 	pub state_version: u16,
+	/// A `BTreeMap` that maps `AccountAsset`s to reserved `Decimal` balances.
+	#[serde_as(as = "JsonString<Vec<(JsonString, _)>>")]
+	#[serde(default)]
+	pub reserved_balances: BTreeMap<AccountAsset, Decimal>,
+	/// Open orders of every trading pair, in the order they were opened.
+	#[serde(default)]
+	pub open_orders: Vec<Order>,
+	/// Scoped permissions of the proxies of each main account, unscoped proxies are omitted.
+	#[serde_as(as = "JsonString<Vec<(JsonString, JsonString<Vec<(JsonString, _)>>)>>")]
+	#[serde(default)]
+	pub proxy_permissions: BTreeMap<AccountId, BTreeMap<AccountId, ProxyPermissions>>,
 }
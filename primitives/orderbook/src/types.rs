@@ -19,14 +19,17 @@
 //! In this module defined "Orderbook" specific operations and types.
 
 use crate::constants::*;
-use parity_scale_codec::{Decode, Encode};
+use parity_scale_codec::{Decode, DecodeAll, Encode};
 use polkadex_primitives::{
-	ocex::TradingPairConfig, withdrawal::Withdrawal, AccountId, AssetId, Signature,
+	ocex::{ProxyPermissions, TradingPairConfig},
+	withdrawal::Withdrawal,
+	AccountId, AssetId, Signature,
 };
 use rust_decimal::{prelude::Zero, Decimal, RoundingStrategy};
+use scale_info::TypeInfo;
 use sp_core::H256;
 use sp_runtime::traits::Verify;
-use sp_std::cmp::Ordering;
+use sp_std::{cmp::Ordering, collections::btree_map::BTreeMap};
 
 #[cfg(not(feature = "std"))]
 use sp_std::vec::Vec;
@@ -46,10 +49,46 @@ pub type OrderId = H256;
 pub struct AccountInfo {
 	/// Collection of the proxy accounts.
 	pub proxies: Vec<AccountId>,
+	/// Scoped permissions of the proxies, proxies without an entry are allowed every action.
+	#[cfg_attr(feature = "std", serde(default))]
+	pub permissions: BTreeMap<AccountId, ProxyPermissions>,
+}
+
+impl AccountInfo {
+	/// Constructor of an account whose proxies are allowed every action.
+	///
+	/// # Parameters
+	///
+	/// * `proxies`: Collection of the proxy accounts.
+	pub fn new(proxies: Vec<AccountId>) -> Self {
+		AccountInfo { proxies, permissions: BTreeMap::new() }
+	}
+
+	/// Returns the permissions of a registered proxy.
+	///
+	/// # Parameters
+	///
+	/// * `proxy`: Proxy account identifier.
+	pub fn proxy_permissions(&self, proxy: &AccountId) -> Option<ProxyPermissions> {
+		if !self.proxies.contains(proxy) {
+			return None
+		}
+		Some(self.permissions.get(proxy).copied().unwrap_or_default())
+	}
+
+	/// Decodes an account stored before the proxy permissions were introduced, when only the
+	/// proxies were encoded.
+	///
+	/// # Parameters
+	///
+	/// * `data`: Encoded account.
+	pub fn decode_legacy(data: &[u8]) -> Result<Self, parity_scale_codec::Error> {
+		Ok(AccountInfo::new(Vec::<AccountId>::decode_all(&mut &data[..])?))
+	}
 }
 
 /// Defines account to asset map DTO to be used in the "Orderbook" client.
-#[derive(Clone, Debug, Encode, Decode, Ord, PartialOrd, PartialEq, Eq)]
+#[derive(Clone, Debug, Encode, Decode, TypeInfo, Ord, PartialOrd, PartialEq, Eq)]
 #[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
 pub struct AccountAsset {
 	/// Main account identifier.
@@ -68,6 +107,19 @@ impl AccountAsset {
 	pub fn new(main: AccountId, asset: AssetId) -> Self {
 		AccountAsset { main, asset }
 	}
+
+	/// Returns the key of the reserved balance in the orderbook state.
+	///
+	/// The free balance is stored under the encoded account asset itself.
+	pub fn reserved_balance_key(&self) -> Vec<u8> {
+		(RESERVED_BALANCE_PREFIX, self).encode()
+	}
+
+	/// Returns the key of the part of the reserved balance held by open orders in the orderbook
+	/// state.
+	pub fn order_reserved_balance_key(&self) -> Vec<u8> {
+		(ORDER_RESERVED_BALANCE_PREFIX, self).encode()
+	}
 }
 
 /// Defines trade related structure DTO.
@@ -160,7 +212,16 @@ impl Trade {
             self.taker.verify_signature() &
             // Verify pair configs
             self.maker.verify_config(&config) &
-            self.taker.verify_config(&config)
+            self.taker.verify_config(&config) &
+            // Verify order types
+            self.verify_order_types()
+	}
+
+	/// Verifies that the order types allow the orders to be matched as maker and taker.
+	///
+	/// Only orders resting in the book can be makers and post only orders can't be takers.
+	pub fn verify_order_types(&self) -> bool {
+		self.maker.is_resting() && self.taker.order_type != OrderType::POST_ONLY
 	}
 }
 
@@ -217,6 +278,18 @@ impl ObMessage {
 		}
 	}
 
+	/// Verifies itself against a set of public keys, it is valid if signed by any of them.
+	///
+	/// # Parameters
+	///
+	/// * `public_keys`: Accepted ecdsa public keys.
+	pub fn verify_with_any(&self, public_keys: &[sp_core::ecdsa::Public]) -> bool {
+		match self.signature.recover_prehashed(&self.sign_data()) {
+			None => false,
+			Some(recovered_pubk) => public_keys.contains(&recovered_pubk),
+		}
+	}
+
 	/// Signs itself.
 	pub fn sign_data(&self) -> [u8; 32] {
 		let mut cloned_self = self.clone();
@@ -251,6 +324,10 @@ pub enum UserActions {
 	/// Block import requested.
 	BlockImport(u32),
 	Reset,
+	/// Order placed by a user, to be matched by validators running the matching engine.
+	PlaceOrder(Order),
+	/// Order cancelled by a user (trading pair, order identifier).
+	CancelOrder(TradingPair, OrderId),
 }
 
 /// Defines withdraw request DTO.
@@ -349,6 +426,7 @@ impl TryFrom<String> for OrderSide {
 }
 
 /// Defines possible order types variants.
+#[allow(non_camel_case_types)]
 #[derive(Encode, Decode, Copy, Clone, Hash, Debug, Eq, PartialEq)]
 #[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
 pub enum OrderType {
@@ -356,6 +434,10 @@ pub enum OrderType {
 	LIMIT,
 	/// Order market type.
 	MARKET,
+	/// Limit order activated once the last trade price reaches its trigger price.
+	STOP_LIMIT,
+	/// Limit order which is rejected instead of taking liquidity from the book.
+	POST_ONLY,
 }
 
 #[cfg(feature = "std")]
@@ -366,11 +448,40 @@ impl TryFrom<String> for OrderType {
 		match value.as_str() {
 			"LIMIT" => Ok(OrderType::LIMIT),
 			"MARKET" => Ok(OrderType::MARKET),
+			"STOP_LIMIT" => Ok(OrderType::STOP_LIMIT),
+			"POST_ONLY" => Ok(OrderType::POST_ONLY),
 			_ => Err(anyhow::Error::msg("Unknown ot variant")),
 		}
 	}
 }
 
+/// Defines possible time in force variants, how long an order remains active.
+#[derive(Encode, Decode, Copy, Clone, Hash, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+pub enum TimeInForce {
+	/// Good till cancelled, the unfilled part of the order rests in the book.
+	#[default]
+	GTC,
+	/// Immediate or cancel, the unfilled part of the order is cancelled.
+	IOC,
+	/// Fill or kill, the order is cancelled unless it is entirely filled at once.
+	FOK,
+}
+
+#[cfg(feature = "std")]
+impl TryFrom<String> for TimeInForce {
+	type Error = anyhow::Error;
+
+	fn try_from(value: String) -> Result<Self, Self::Error> {
+		match value.as_str() {
+			"GTC" => Ok(TimeInForce::GTC),
+			"IOC" => Ok(TimeInForce::IOC),
+			"FOK" => Ok(TimeInForce::FOK),
+			_ => Err(anyhow::Error::msg("Unknown time in force variant")),
+		}
+	}
+}
+
 /// Defines possible order statuses variants.
 #[derive(Encode, Decode, Copy, Clone, Hash, Debug, Eq, PartialEq)]
 #[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
@@ -456,6 +567,16 @@ impl TradingPair {
 		TradingPair { base, quote }
 	}
 
+	/// Returns the prefix of the open order keys of the trading pair in the orderbook state.
+	pub fn open_orders_prefix(&self) -> Vec<u8> {
+		(OPEN_ORDER_PREFIX, self).encode()
+	}
+
+	/// Returns the key of the last trade price in the orderbook state.
+	pub fn last_trade_price_key(&self) -> Vec<u8> {
+		(LAST_TRADE_PRICE_PREFIX, self).encode()
+	}
+
 	/// Defines if provided asset is a quote asset of the current trading pair.
 	///
 	/// # Parameters
@@ -565,6 +686,12 @@ pub struct Order {
 	pub overall_unreserved_volume: Decimal,
 	/// Signature.
 	pub signature: Signature,
+	/// Trigger price of a STOP_LIMIT order, zero for the other order types.
+	#[cfg_attr(feature = "std", serde(default))]
+	pub trigger_price: Decimal,
+	/// Time in force.
+	#[cfg_attr(feature = "std", serde(default))]
+	pub time_in_force: TimeInForce,
 }
 
 #[cfg(feature = "std")]
@@ -578,14 +705,18 @@ impl Order {
 		let is_market_same =
 			self.pair.base == config.base_asset && self.pair.quote == config.quote_asset;
 		let result = match self.order_type {
-			OrderType::LIMIT =>
+			OrderType::LIMIT => is_market_same && self.verify_limit_config(config),
+			// A post only order never takes liquidity, it has to rest in the book
+			OrderType::POST_ONLY =>
 				is_market_same &&
-					self.price >= config.min_price &&
-					self.price <= config.max_price &&
-					self.qty >= config.min_qty &&
-					self.qty <= config.max_qty &&
-					self.price.rem(config.price_tick_size).is_zero() &&
-					self.qty.rem(config.qty_step_size).is_zero(),
+					self.verify_limit_config(config) &&
+					self.time_in_force == TimeInForce::GTC,
+			OrderType::STOP_LIMIT =>
+				is_market_same &&
+					self.verify_limit_config(config) &&
+					self.trigger_price >= config.min_price &&
+					self.trigger_price <= config.max_price &&
+					self.trigger_price.rem(config.price_tick_size).is_zero(),
 			OrderType::MARKET =>
 				if self.side == OrderSide::Ask {
 					// for ask order we are checking base order qty
@@ -607,10 +738,35 @@ impl Order {
 		result
 	}
 
+	/// Verifies price and quantity of the order against the limits of the trading pair
+	/// configuration.
+	///
+	/// # Parameters
+	///
+	/// * `config`: Trading pair configuration reference.
+	fn verify_limit_config(&self, config: &TradingPairConfig) -> bool {
+		self.price >= config.min_price &&
+			self.price <= config.max_price &&
+			self.qty >= config.min_qty &&
+			self.qty <= config.max_qty &&
+			self.price.rem(config.price_tick_size).is_zero() &&
+			self.qty.rem(config.qty_step_size).is_zero()
+	}
+
+	/// Checks if the unfilled part of the order rests in the book.
+	///
+	/// MARKET orders and orders which are not good till cancelled never rest in the book.
+	pub fn is_resting(&self) -> bool {
+		self.order_type != OrderType::MARKET && self.time_in_force == TimeInForce::GTC
+	}
+
 	/// Verifies signature.
 	pub fn verify_signature(&self) -> bool {
 		let payload: OrderPayload = self.clone().into();
-		let result = self.signature.verify(&payload.encode()[..], &self.user);
+		let result = payload
+			.signed_encodings()
+			.iter()
+			.any(|encoded| self.signature.verify(&encoded[..], &self.user));
 		if !result {
 			log::error!(target:"orderbook","Order signature check failed");
 		}
@@ -700,6 +856,16 @@ impl Ord for Order {
 
 #[cfg(feature = "std")]
 impl Order {
+	/// Returns the key of the open order in the orderbook state.
+	///
+	/// # Parameters
+	///
+	/// * `pair`: Trading pair of the order.
+	/// * `id`: Order identifier.
+	pub fn open_order_key(pair: &TradingPair, id: &OrderId) -> Vec<u8> {
+		(OPEN_ORDER_PREFIX, pair, id).encode()
+	}
+
 	/// Computes the new avg_price and adds qty to filled_qty. If returned is false - then underflow
 	/// occurred during division.
 	///
@@ -791,6 +957,8 @@ impl Order {
 			timestamp: 1,
 			overall_unreserved_volume: Decimal::zero(),
 			signature: Signature::Sr25519(sp_core::sr25519::Signature::from_raw([0; 64])),
+			trigger_price: Decimal::zero(),
+			time_in_force: TimeInForce::GTC,
 		}
 	}
 }
@@ -830,6 +998,13 @@ pub struct OrderPayload {
 	pub price: String,
 	/// Creation timestamp.
 	pub timestamp: i64,
+	/// Trigger price of a STOP_LIMIT order, "0" for the other order types.
+	/// Trigger price is defined in quote asset per unit base asset.
+	#[serde(default)]
+	pub trigger_price: String,
+	/// Time in force.
+	#[serde(default)]
+	pub time_in_force: TimeInForce,
 }
 
 #[cfg(feature = "std")]
@@ -846,7 +1021,45 @@ impl From<Order> for OrderPayload {
 			qty: value.qty.to_string(),
 			price: value.price.to_string(),
 			timestamp: value.timestamp,
+			trigger_price: value.trigger_price.to_string(),
+			time_in_force: value.time_in_force,
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl OrderPayload {
+	/// Returns the encodings of the payload a client may have signed.
+	///
+	/// Clients may leave the trigger price empty, which is read as zero, and clients that predate
+	/// STOP_LIMIT orders and time in force sign a payload without both fields. These encodings
+	/// are only accepted for orders with a zero trigger price, the legacy one for good till
+	/// cancelled orders only.
+	pub fn signed_encodings(&self) -> Vec<Vec<u8>> {
+		let mut encodings = vec![self.encode()];
+		if self.trigger_price == Decimal::zero().to_string() {
+			let mut payload = self.clone();
+			payload.trigger_price = String::new();
+			encodings.push(payload.encode());
+			if self.time_in_force == TimeInForce::GTC {
+				encodings.push(
+					(
+						&self.client_order_id,
+						&self.user,
+						&self.main_account,
+						&self.pair,
+						&self.side,
+						&self.order_type,
+						&self.quote_order_quantity,
+						&self.qty,
+						&self.price,
+						self.timestamp,
+					)
+						.encode(),
+				);
+			}
 		}
+		encodings
 	}
 }
 
@@ -855,6 +1068,17 @@ impl TryFrom<OrderDetails> for Order {
 	type Error = anyhow::Error;
 	fn try_from(details: OrderDetails) -> Result<Self, anyhow::Error> {
 		let payload = details.payload;
+		// Orders without trigger price are not STOP_LIMIT orders
+		let trigger_price = if payload.trigger_price.is_empty() {
+			Decimal::zero()
+		} else {
+			payload
+				.trigger_price
+				.parse::<f64>()
+				.ok()
+				.and_then(Decimal::from_f64)
+				.ok_or(anyhow::Error::msg("Trigger price couldn't be parsed".to_string()))?
+		};
 		if let Ok(qty) = payload.qty.parse::<f64>() {
 			if let Ok(price) = payload.price.parse::<f64>() {
 				return if let Some(qty) = Decimal::from_f64(qty) {
@@ -881,6 +1105,8 @@ impl TryFrom<OrderDetails> for Order {
 										timestamp: payload.timestamp,
 										overall_unreserved_volume: Decimal::zero(),
 										signature: details.signature,
+										trigger_price: trigger_price.round_dp(8),
+										time_in_force: payload.time_in_force,
 									})
 								} else {
 									Err(anyhow::Error::msg(
@@ -929,7 +1155,113 @@ pub struct WithdrawalDetails {
 
 #[cfg(test)]
 mod tests {
-	use crate::types::{ObMessage, UserActions};
+	use crate::types::{
+		AccountInfo, ObMessage, Order, OrderDetails, OrderPayload, OrderSide, OrderType,
+		TimeInForce, Trade, TradingPair, UserActions,
+	};
+	use parity_scale_codec::{Decode, DecodeAll, Encode};
+	use polkadex_primitives::{
+		ocex::{ProxyPermissions, TradingPairConfig},
+		AccountId, AssetId, Signature,
+	};
+	use rust_decimal::Decimal;
+	use sp_core::{sr25519, Pair, H256};
+
+	#[test]
+	pub fn test_verify_signature_of_payload_without_trigger_price() {
+		let pair = sr25519::Pair::from_seed(&[1; 32]);
+		let payload = OrderPayload {
+			client_order_id: H256([1; 32]),
+			user: AccountId::from(pair.public().0),
+			main_account: AccountId::new([2; 32]),
+			pair: "PDEX-1".to_string(),
+			side: OrderSide::Bid,
+			order_type: OrderType::LIMIT,
+			quote_order_quantity: "0".to_string(),
+			qty: "1".to_string(),
+			price: "10".to_string(),
+			timestamp: 1,
+			trigger_price: String::new(),
+			time_in_force: TimeInForce::GTC,
+		};
+		let signature = Signature::Sr25519(pair.sign(&payload.encode()));
+		let order: Order = OrderDetails { payload: payload.clone(), signature }.try_into().unwrap();
+		assert_eq!(order.trigger_price, Decimal::ZERO);
+		assert!(order.verify_signature());
+
+		// Clients predating STOP_LIMIT orders sign a payload without trigger price and time in
+		// force
+		let legacy = (
+			&payload.client_order_id,
+			&payload.user,
+			&payload.main_account,
+			&payload.pair,
+			&payload.side,
+			&payload.order_type,
+			&payload.quote_order_quantity,
+			&payload.qty,
+			&payload.price,
+			payload.timestamp,
+		)
+			.encode();
+		let signature = Signature::Sr25519(pair.sign(&legacy));
+		let mut order: Order =
+			OrderDetails { payload: payload.clone(), signature }.try_into().unwrap();
+		assert!(order.verify_signature());
+		// The legacy encoding does not cover the time in force
+		order.time_in_force = TimeInForce::IOC;
+		assert!(!order.verify_signature());
+
+		let mut stop_limit = payload;
+		stop_limit.order_type = OrderType::STOP_LIMIT;
+		stop_limit.trigger_price = "9".to_string();
+		let signature = Signature::Sr25519(pair.sign(&stop_limit.encode()));
+		let mut order: Order = OrderDetails { payload: stop_limit, signature }.try_into().unwrap();
+		assert!(order.verify_signature());
+		order.trigger_price = Decimal::from(8);
+		assert!(!order.verify_signature());
+	}
+
+	#[test]
+	pub fn test_verify_config_of_stop_limit_and_post_only_orders() {
+		let pair = TradingPair { base: AssetId::Polkadex, quote: AssetId::Asset(1) };
+		let config = TradingPairConfig::default(AssetId::Polkadex, AssetId::Asset(1));
+		let mut order = Order::random_order_for_testing(pair, OrderSide::Bid, OrderType::LIMIT);
+		order.price = Decimal::ONE;
+		order.qty = Decimal::ONE;
+		assert!(order.verify_config(&config));
+
+		order.order_type = OrderType::STOP_LIMIT;
+		// Trigger price is out of the trading pair price range
+		assert!(!order.verify_config(&config));
+		order.trigger_price = Decimal::ONE;
+		assert!(order.verify_config(&config));
+
+		order.order_type = OrderType::POST_ONLY;
+		assert!(order.verify_config(&config));
+		order.time_in_force = TimeInForce::IOC;
+		assert!(!order.verify_config(&config));
+	}
+
+	#[test]
+	pub fn test_verify_order_types_of_trade() {
+		let pair = TradingPair { base: AssetId::Polkadex, quote: AssetId::Asset(1) };
+		let mut maker = Order::random_order_for_testing(pair, OrderSide::Bid, OrderType::LIMIT);
+		let mut taker = Order::random_order_for_testing(pair, OrderSide::Ask, OrderType::LIMIT);
+		let trade = Trade::new(maker.clone(), taker.clone(), Decimal::ONE, Decimal::ONE);
+		assert!(trade.verify_order_types());
+
+		// Post only orders can't take liquidity
+		taker.order_type = OrderType::POST_ONLY;
+		let trade = Trade::new(maker.clone(), taker.clone(), Decimal::ONE, Decimal::ONE);
+		assert!(!trade.verify_order_types());
+
+		// Immediate or cancel orders never rest in the book
+		taker.order_type = OrderType::LIMIT;
+		maker.time_in_force = TimeInForce::IOC;
+		let trade = Trade::new(maker, taker, Decimal::ONE, Decimal::ONE);
+		assert!(!trade.verify_order_types());
+	}
 
 	#[test]
 	pub fn test_ob_message() {
@@ -944,4 +1276,23 @@ mod tests {
 
 		println!("OBMessage: {:?}", serde_json::to_string(&msg).unwrap());
 	}
+
+	#[test]
+	pub fn test_account_info_encoding_with_permissions() {
+		let main = AccountId::new([1; 32]);
+		let bot = AccountId::new([2; 32]);
+		// Accounts stored before the permissions were introduced only encoded their proxies
+		let legacy = vec![main.clone()].encode();
+		assert!(AccountInfo::decode_all(&mut &legacy[..]).is_err());
+		let mut account_info = AccountInfo::decode_legacy(&legacy).unwrap();
+		assert_eq!(account_info.proxies, vec![main.clone()]);
+		assert!(account_info.permissions.is_empty());
+
+		account_info.proxies.push(bot.clone());
+		account_info.permissions.insert(bot.clone(), ProxyPermissions::TRADE);
+		let decoded = AccountInfo::decode(&mut &account_info.encode()[..]).unwrap();
+		assert_eq!(decoded.proxy_permissions(&main), Some(ProxyPermissions::ALL));
+		assert_eq!(decoded.proxy_permissions(&bot), Some(ProxyPermissions::TRADE));
+		assert_eq!(decoded.proxy_permissions(&AccountId::new([3; 32])), None);
+	}
 }
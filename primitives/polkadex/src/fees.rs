@@ -18,7 +18,7 @@
 
 //! In this module defined operations fee related types.
 
-use codec::{Decode, Encode};
+use codec::{Decode, Encode, MaxEncodedLen};
 use rust_decimal::{prelude::Zero, Decimal};
 use scale_info::TypeInfo;
 
@@ -26,13 +26,49 @@ use scale_info::TypeInfo;
 use serde::{Deserialize, Serialize};
 
 /// Defines structure of the fee configuration.
-#[derive(Copy, Clone, Encode, Decode, PartialEq, Debug, TypeInfo)]
+#[derive(Copy, Clone, Encode, Decode, MaxEncodedLen, PartialEq, Eq, Debug, TypeInfo)]
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 pub struct FeeConfig {
 	/// Market fee fraction.
-	pub(crate) maker_fraction: Decimal,
+	pub maker_fraction: Decimal,
 	/// Trade fee fraction.
-	pub(crate) taker_fraction: Decimal,
+	pub taker_fraction: Decimal,
+}
+
+impl FeeConfig {
+	/// Constructor.
+	///
+	/// # Parameters
+	///
+	/// * `maker_fraction`: Fraction of the credited amount charged to makers.
+	/// * `taker_fraction`: Fraction of the credited amount charged to takers.
+	pub fn new(maker_fraction: Decimal, taker_fraction: Decimal) -> Self {
+		Self { maker_fraction, taker_fraction }
+	}
+
+	/// Checks that both fractions are within `[0, 1)`.
+	pub fn is_valid(&self) -> bool {
+		let range = Decimal::zero()..Decimal::ONE;
+		range.contains(&self.maker_fraction) && range.contains(&self.taker_fraction)
+	}
+
+	/// Calculates the fee charged to a maker receiving `amount`.
+	///
+	/// # Parameters
+	///
+	/// * `amount`: Amount credited to the maker.
+	pub fn maker_fee(&self, amount: Decimal) -> Decimal {
+		amount.saturating_mul(self.maker_fraction)
+	}
+
+	/// Calculates the fee charged to a taker receiving `amount`.
+	///
+	/// # Parameters
+	///
+	/// * `amount`: Amount credited to the taker.
+	pub fn taker_fee(&self, amount: Decimal) -> Decimal {
+		amount.saturating_mul(self.taker_fraction)
+	}
 }
 
 impl Default for FeeConfig {
@@ -18,7 +18,11 @@
 
 //! In this module defined ingress messages related types.
 
-use crate::{ocex::TradingPairConfig, AssetId};
+use crate::{
+	fees::FeeConfig,
+	ocex::{ProxyPermissions, TradingPairConfig},
+	AssetId,
+};
 #[cfg(feature = "std")]
 use serde::{Deserialize, Serialize};
 
@@ -49,8 +53,15 @@ pub enum IngressMessages<AccountId> {
 	SetFreeReserveBalanceForAccounts(BoundedVec<HandleBalance<AccountId>, HandleBalanceLimit>),
 	/// Changing the exchange state in order-book.
 	SetExchangeState(bool),
-	/// Withdrawal from Chain to OrderBook.
+	/// Withdrawal from Chain to OrderBook (main, asset, amount, do_force_withdraw).
+	///
+	/// If `do_force_withdraw` is set and the free balance is lower than the amount, the whole
+	/// free balance is withdrawn instead.
 	DirectWithdrawal(AccountId, AssetId, Decimal, bool),
+	/// Main Acc, trading fee configuration assigned to it.
+	SetFeeConfig(AccountId, FeeConfig),
+	/// Main Acc, Proxy Account, actions the proxy is allowed to sign.
+	SetProxyPermissions(AccountId, AccountId, ProxyPermissions),
 }
 
 /// Defines the structure of handle balance data which used to set account balance.
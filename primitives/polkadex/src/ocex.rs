@@ -88,6 +88,40 @@ impl<Account: PartialEq, ProxyLimit: Get<u32>> AccountInfo<Account, ProxyLimit>
 	}
 }
 
+/// Bitmask of the actions a proxy account is allowed to sign on behalf of its main account.
+#[derive(Clone, Copy, Encode, Decode, MaxEncodedLen, TypeInfo, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct ProxyPermissions(pub u8);
+
+impl ProxyPermissions {
+	/// Placing, cancelling and trading orders.
+	pub const TRADE: Self = Self(0b01);
+	/// Requesting withdrawals.
+	pub const WITHDRAW: Self = Self(0b10);
+	/// Every action, the scope of proxies registered without explicit permissions.
+	pub const ALL: Self = Self(0b11);
+
+	/// Checks if all the permissions of `other` are granted.
+	///
+	/// # Parameters
+	///
+	/// * `other`: Permissions to check.
+	pub fn contains(&self, other: Self) -> bool {
+		self.0 & other.0 == other.0
+	}
+
+	/// Checks if the permissions only contain known bits and grant at least one action.
+	pub fn is_valid(&self) -> bool {
+		self.0 != 0 && self.0 & !Self::ALL.0 == 0
+	}
+}
+
+impl Default for ProxyPermissions {
+	fn default() -> Self {
+		Self::ALL
+	}
+}
+
 /// Trading pair configuration structure definition.
 #[derive(Clone, Encode, Decode, MaxEncodedLen, TypeInfo, Debug, Eq, PartialEq)]
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
@@ -139,11 +139,23 @@ pub type Network = u8;
 /// Native network id.
 pub const NATIVE_NETWORK: Network = 0;
 
+/// Parachain network id.
+pub const PARACHAIN_NETWORK: Network = 1;
+
+/// Ethereum network id, bridged through the Thea bridge contract.
+pub const ETHEREUM_NETWORK: Network = 2;
+
 /// TTL of the cached message.
 pub const MESSAGE_CACHE_DURATION_IN_SECS: u64 = 60;
 
+/// Maximum number of consecutive messages of a network processed by Thea at once.
+pub const MAX_MESSAGES_IN_FLIGHT: u64 = 16;
+
 sp_api::decl_runtime_apis! {
 	/// APIs necessary for Thea.
+	///
+	/// Version 2 adds `networks`.
+	#[api_version(2)]
 	pub trait TheaApi
 	{
 		/// Return the current active Thea validator set for all networks.
@@ -154,6 +166,8 @@ sp_api::decl_runtime_apis! {
 		fn outgoing_messages(network: Network, nonce: u64) -> Option<Message>;
 		/// Get Thea network associated with Validator.
 		fn network(auth: AuthorityId) -> Option<Network>;
+		/// Get Thea networks associated with Validator.
+		fn networks(auth: AuthorityId) -> Vec<Network>;
 		/// Incoming messages.
 		#[allow(clippy::result_unit_err)]
 		fn incoming_message(message: Message, bitmap: Vec<u128>, signature: AuthoritySignature) -> Result<(),()>;
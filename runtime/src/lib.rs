@@ -119,10 +119,10 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	// and set impl_version to 0. If only runtime
 	// implementation changes and behavior does not, then leave spec_version as
 	// is and increment impl_version.
-	spec_version: 283,
+	spec_version: 285,
 	impl_version: 0,
 	apis: RUNTIME_API_VERSIONS,
-	transaction_version: 2,
+	transaction_version: 3,
 	state_version: 0,
 };
 
@@ -1275,6 +1275,7 @@ parameter_types! {
 	pub const ProxyLimit: u32 = 3;
 	pub const OcexPalletId: PalletId = PalletId(*b"OCEX_LMP");
 	pub const MsPerDay: u64 = 86_400_000;
+	pub const FreezeExchangeAfter: BlockNumber = 7 * DAYS;
 }
 
 impl pallet_ocex_lmp::Config for Runtime {
@@ -1287,6 +1288,7 @@ impl pallet_ocex_lmp::Config for Runtime {
 	type Signature = Signature;
 	type MsPerDay = MsPerDay;
 	type GovernanceOrigin = EnsureRootOrHalfOrderbookCouncil;
+	type FreezeExchangeAfter = FreezeExchangeAfter;
 	type WeightInfo = pallet_ocex_lmp::weights::WeightInfo<Runtime>;
 }
 
@@ -1358,6 +1360,7 @@ impl thea::pallet::Config for Runtime {
 	type TheaId = thea_primitives::AuthorityId;
 	type Signature = thea_primitives::AuthoritySignature;
 	type MaxAuthorities = MaxAuthorities;
+	type MaxNetworks = ConstU32<8>;
 	type Executor = TheaExecutor;
 }
 
@@ -1599,6 +1602,10 @@ impl_runtime_apis! {
 		fn get_orderbook_opearator_key() -> Option<sp_core::ecdsa::Public>{
 			OCEX::get_orderbook_operator_public_key()
 		}
+
+		fn get_orderbook_operator_keys() -> Vec<sp_core::ecdsa::Public>{
+			OCEX::get_orderbook_operator_public_keys()
+		}
 	}
 
 	impl thea_primitives::TheaApi<Block> for Runtime {
@@ -1618,6 +1625,10 @@ impl_runtime_apis! {
 		fn network(auth: thea_primitives::AuthorityId) -> Option<thea_primitives::Network>{
 			Thea::network(auth)
 		}
+		/// Get Thea networks associated with Validator
+		fn networks(auth: thea_primitives::AuthorityId) -> Vec<thea_primitives::Network>{
+			Thea::networks(auth)
+		}
 		/// Incoming messages
 		fn incoming_message(message: thea_primitives::Message, bitmap: Vec<u128>, signature: thea_primitives::AuthoritySignature) -> Result<(),()>{
 			Thea::submit_incoming_message(message,bitmap,signature)